@@ -1,36 +1,90 @@
 use core::fmt;
-use drawckt::schematic::{LayerStyle, LayerStyles};
+use drawckt::schematic::{Font, Layer, LayerStyle, LayerStyles, LineCap, LineJoin};
 use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+struct FontFmt<'a>(&'a Font);
+impl<'a> fmt::Display for FontFmt<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Font::Other(s) => write!(f, "drawckt::schematic::Font::Other({s:?}.to_string())"),
+            font => write!(f, "drawckt::schematic::Font::{font:?}"),
+        }
+    }
+}
+struct LayerFmt<'a>(&'a Layer);
+impl<'a> fmt::Display for LayerFmt<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Layer::Custom(s) => write!(f, "drawckt::schematic::Layer::Custom({s:?}.to_string())"),
+            layer => write!(f, "drawckt::schematic::Layer::{layer:?}"),
+        }
+    }
+}
+struct LineJoinFmt(Option<LineJoin>);
+impl fmt::Display for LineJoinFmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(line_join) => write!(f, "Some(drawckt::schematic::LineJoin::{line_join:?})"),
+            None => write!(f, "None"),
+        }
+    }
+}
+struct LineCapFmt(Option<LineCap>);
+impl fmt::Display for LineCapFmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(line_cap) => write!(f, "Some(drawckt::schematic::LineCap::{line_cap:?})"),
+            None => write!(f, "None"),
+        }
+    }
+}
 struct LayerStyleFmt<'a>(&'a LayerStyle);
 impl<'a> fmt::Display for LayerStyleFmt<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "drawckt::schematic::LayerStyle::new({:?}, {:?}, {:?}, {:?}, {:?}, {:?}, {:?})",
+            "drawckt::schematic::LayerStyle::new({:?}, {:?}, {:?}, {:?}, {:?}, {}, {:?}, {:?}, {:?}, {}, {}, {:?}, {:?}, {:?}, {:?}, {:?})",
             self.0.stroke_color,
             self.0.stroke_width,
+            self.0.fill_color,
             self.0.text_color,
             self.0.font_zoom,
-            self.0.font_family,
+            FontFmt(&self.0.font_family),
             self.0.label_sch_visible,
             self.0.shape_sch_visible,
+            self.0.opacity,
+            LineJoinFmt(self.0.line_join),
+            LineCapFmt(self.0.line_cap),
+            self.0.label_spacing,
+            self.0.label_baseline_adjust,
+            self.0.wrap_width,
+            self.0.label_background,
+            self.0.label_border,
         )
     }
 }
+struct OptionFmt<T>(Option<T>);
+impl<T: fmt::Display> fmt::Display for OptionFmt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(value) => write!(f, "Some({value})"),
+            None => write!(f, "None"),
+        }
+    }
+}
 struct LayerStylesFmt(LayerStyles);
 impl fmt::Display for LayerStylesFmt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "LayerStyles {{")?;
+        write!(f, "LayerStyles {{version: {:?}, ", self.0.version)?;
         write!(f, "layer_order: [")?;
         for layer in &self.0.layer_order {
-            write!(f, "drawckt::schematic::Layer::{layer:?}, ")?;
+            write!(f, "{}, ", LayerFmt(layer))?;
         }
         write!(
             f,
-            "], device: {}, instance: {}, wire: {}, annotate: {}, pin: {}, text: {}, wire_intersection_scale: {:?}, wire_show_intersection: {:?}}}",
+            "], device: {}, instance: {}, wire: {}, annotate: {}, pin: {}, text: {}, wire_intersection_scale: {:?}, wire_show_intersection: {:?}, wire_intersection: {}, wire_intersection_filled: {:?}, font_families: [",
             LayerStyleFmt(&self.0.device),
             LayerStyleFmt(&self.0.instance),
             LayerStyleFmt(&self.0.wire),
@@ -38,7 +92,46 @@ impl fmt::Display for LayerStylesFmt {
             LayerStyleFmt(&self.0.pin),
             LayerStyleFmt(&self.0.text),
             self.0.wire_intersection_scale,
-            self.0.wire_show_intersection
+            self.0.wire_show_intersection,
+            OptionFmt(self.0.wire_intersection.as_ref().map(LayerStyleFmt)),
+            self.0.wire_intersection_filled
+        )?;
+        for (font, family) in &self.0.font_families {
+            write!(f, "({}, {family:?}.to_string()), ", FontFmt(font))?;
+        }
+        write!(f, "].into_iter().collect(), custom_fonts: [")?;
+        for (name, url) in &self.0.custom_fonts {
+            write!(f, "({name:?}.to_string(), {url:?}.to_string()), ")?;
+        }
+        write!(f, "].into_iter().collect(), net_colors: [")?;
+        for (net, color) in &self.0.net_colors {
+            write!(f, "({net:?}.to_string(), {color:?}.to_string()), ")?;
+        }
+        write!(f, "].into_iter().collect(), layers_enabled: ")?;
+        match &self.0.layers_enabled {
+            Some(layers) => {
+                write!(f, "Some([")?;
+                for layer in layers {
+                    write!(f, "{}, ", LayerFmt(layer))?;
+                }
+                write!(f, "].into_iter().collect())")?;
+            }
+            None => write!(f, "None")?,
+        }
+        write!(f, ", custom_layers: [")?;
+        for (name, style) in &self.0.custom_layers {
+            write!(f, "({name:?}.to_string(), {}), ", LayerStyleFmt(style))?;
+        }
+        write!(
+            f,
+            "].into_iter().collect(), show_instance_params: {:?}, wire_open_end_color: {:?}.into(), pin_label_offset: {:?}, pin_label_height: {:?}, layer_legend_corner: drawckt::schematic::LegendCorner::{:?}, min_font_size: {:?}, max_font_size: {:?}}}",
+            self.0.show_instance_params,
+            self.0.wire_open_end_color,
+            self.0.pin_label_offset,
+            self.0.pin_label_height,
+            self.0.layer_legend_corner,
+            self.0.min_font_size,
+            self.0.max_font_size
         )
     }
 }
@@ -135,7 +228,7 @@ pub fn init_styles() -> IndexMap<String, LayerStyles> {
     for (name, path) in &style_entries {
         let json_content = fs::read_to_string(path)
             .expect(&format!("Failed to read style file: {}", path.display()));
-        let style: LayerStyles = serde_json::from_str(&json_content)
+        let style = LayerStyles::from_json(&json_content)
             .expect(&format!("Failed to parse JSON: {}", path.display()));
         // Generate code for each style
         write!(
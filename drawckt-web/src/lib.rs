@@ -21,11 +21,19 @@ include!(concat!(env!("OUT_DIR"), "/demo_files.rs"));
 // Initialize styles from static/style JSON files (auto-generated by build.rs)
 include!(concat!(env!("OUT_DIR"), "/styles.rs"));
 
-// Initialize panic hook and logger
+#[wasm_bindgen]
+extern "C" {
+    // Random UUID of the browser, drawckt is built without its `uuid` feature
+    #[wasm_bindgen(js_namespace = crypto, js_name = randomUUID)]
+    fn random_uuid() -> String;
+}
+
+// Initialize panic hook, logger and cell ids
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
     console_log::init_with_level(log::Level::Info).expect("Failed to initialize logger");
+    drawrs::xml_base::set_id_generator(Some(random_uuid));
 }
 
 // State management - store data in WASM
@@ -594,7 +602,7 @@ pub fn process_schematic_zip_with_filename(
         {
             if let Some(content) = find_file(&mut file_contents, &base_path, name) {
                 let style_json = String::from_utf8(content)?;
-                let layer_styles: LayerStyles = serde_json::from_str(&style_json)?;
+                let layer_styles = LayerStyles::from_json(&style_json)?;
                 all_styles.insert(name_without_ext.to_owned(), layer_styles);
             }
         }
@@ -613,7 +621,7 @@ pub fn process_schematic_zip_with_filename(
         {
             if let Some(content) = find_file(&mut file_contents, &base_path, &name) {
                 let style_json = String::from_utf8(content)?;
-                let layer_styles: LayerStyles = serde_json::from_str(&style_json)?;
+                let layer_styles = LayerStyles::from_json(&style_json)?;
                 let current_name = name_without_ext.to_owned();
                 styles_state.current = layer_styles.clone();
                 styles_state.current_name = current_name.clone();
@@ -629,7 +637,7 @@ pub fn process_schematic_zip_with_filename(
     if styles_state.all.is_empty() {
         if let Some(layers_data) = find_file(&mut file_contents, &base_path, "layers.json") {
             let layers_json = String::from_utf8(layers_data)?;
-            let layer_styles: LayerStyles = serde_json::from_str(&layers_json)?;
+            let layer_styles = LayerStyles::from_json(&layers_json)?;
             styles_state.current = layer_styles.clone();
             styles_state.current_name = "custom".to_owned();
             styles_state.all.insert("custom".to_owned(), layer_styles);
@@ -796,7 +804,7 @@ pub fn get_all_symbols() -> Result<JsValue, AppError> {
 pub fn update_layer_styles(styles_json: &str) -> Result<JsValue, AppError> {
     log::debug!("Updating layer styles");
 
-    let new_styles: LayerStyles = serde_json::from_str(styles_json)?;
+    let new_styles = LayerStyles::from_json(styles_json)?;
 
     AppState::with_state_mut(|state| -> Result<(), AppError> {
         state.apply_style_update(&new_styles)?;
@@ -831,11 +839,7 @@ pub fn get_current_fixed_layer_styles() -> Result<JsValue, AppError> {
 pub fn update_symbol_content(lib: &str, cell: &str, content: String) -> Result<JsValue, AppError> {
     log::info!("Updating symbol content: {}/{}", lib, cell);
 
-    let symbol_mapping = once(DesignId {
-        lib: Cow::Borrowed(lib),
-        cell: Cow::Borrowed(cell),
-    })
-    .collect::<IndexSet<_>>();
+    let symbol_mapping = once(DesignId::new(lib, cell)).collect::<IndexSet<_>>();
 
     AppState::with_state_mut(|state| -> Result<(), AppError> {
         let schematic_state = state
@@ -911,8 +915,10 @@ pub fn undo_symbol(lib: &str, cell: &str) -> Result<JsValue, AppError> {
         if let Some(ref schematic_state) = state.schematic {
             log::info!("Re-rendering symbol and schematic after undo");
             let symbol_contents = state.symbol_contents()?;
+            // The symbol may come from before a style change, only warn
             let renderer =
-                Renderer::new(&schematic_state.schematic, state.styles.get_current_style());
+                Renderer::new(&schematic_state.schematic, state.styles.get_current_style())
+                    .with_allow_stale_symbols(true);
 
             let schematic_content = renderer
                 .render_schematic_file(&symbol_contents)
@@ -967,8 +973,10 @@ pub fn redo_symbol(lib: &str, cell: &str) -> Result<JsValue, AppError> {
         if let Some(ref schematic_state) = state.schematic {
             log::info!("Re-rendering symbol and schematic after redo");
             let symbol_contents = state.symbol_contents()?;
+            // The symbol may come from before a style change, only warn
             let renderer =
-                Renderer::new(&schematic_state.schematic, state.styles.get_current_style());
+                Renderer::new(&schematic_state.schematic, state.styles.get_current_style())
+                    .with_allow_stale_symbols(true);
 
             let schematic_content = renderer
                 .render_schematic_file(&symbol_contents)
@@ -1180,7 +1188,7 @@ pub fn set_current_style(name: &str) -> Result<JsValue, AppError> {
 
 #[wasm_bindgen]
 pub fn add_style(name: &str, styles_json: &str) -> Result<JsValue, AppError> {
-    let styles: LayerStyles = serde_json::from_str(styles_json).map_err(AppError::SerdeJson)?;
+    let styles = LayerStyles::from_json(styles_json)?;
 
     AppState::with_state_mut(|state| -> Result<(), AppError> {
         state.styles.add_style(name.to_owned(), styles.clone());
@@ -1264,26 +1272,14 @@ pub fn route_embedded(path: &str) -> Result<JsValue, AppError> {
             without_prefix
         };
 
-        // Split by '/' to get lib and cell
-        let parts: Vec<&str> = without_suffix.split('/').collect();
-
-        log::debug!("Path parts: {:?}, count: {}", parts, parts.len());
-
-        if parts.len() == 2 {
-            let lib = parts[0];
-            let cell = parts[1];
-            log::debug!("Getting symbol: lib={}, cell={}", lib, cell);
-            return get_symbol_content(lib, cell);
-        } else {
-            log::warn!("Invalid path format: expected 2 parts, got {}", parts.len());
-            return Err(AppError::Message(
-                format!(
-                    "Invalid path format: expected lib/cell, got {} parts",
-                    parts.len()
-                )
-                .into(),
-            ));
-        }
+        let symbol_id: DesignId = without_suffix.parse().map_err(|_| {
+            log::warn!("Invalid path format: expected lib/cell, got {without_suffix}");
+            AppError::Message(
+                format!("Invalid path format: expected lib/cell, got {without_suffix}").into(),
+            )
+        })?;
+        log::debug!("Getting symbol: {symbol_id}");
+        return get_symbol_content(&symbol_id.lib, &symbol_id.cell);
     }
 
     Err(AppError::Message(format!("Unknown path: {path}").into()))
@@ -28,15 +28,25 @@ export interface ProcessResult {
 export interface LayerStyle {
   stroke_color: string;
   stroke_width: number;
+  fill_color?: string;
   text_color: string;
   font_family: string;
   font_zoom: number;
   priority: number;
   label_sch_visible: boolean;
   shape_sch_visible: boolean;
+  opacity?: number;
+  line_join?: "round" | "miter" | "bevel";
+  line_cap?: "flat" | "round" | "square";
+  label_spacing: number;
+  label_baseline_adjust: number;
+  wrap_width?: number;
+  label_background?: string;
+  label_border?: string;
 }
 
 export interface LayerStyles {
+  version?: number;
   layer_order: string[];
   instance: LayerStyle;
   device: LayerStyle;
@@ -45,7 +55,21 @@ export interface LayerStyles {
   wire: LayerStyle;
   wire_show_intersection: boolean;
   wire_intersection_scale: number;
+  wire_intersection?: LayerStyle | null;
+  wire_intersection_filled?: boolean;
   text: LayerStyle;
+  font_families?: Record<string, string>;
+  custom_fonts?: Record<string, string>;
+  net_colors?: Record<string, string>;
+  layers_enabled?: string[] | null;
+  custom_layers?: Record<string, LayerStyle>;
+  show_instance_params?: boolean;
+  wire_open_end_color?: string;
+  pin_label_offset?: number;
+  pin_label_height?: number;
+  layer_legend_corner?: "topLeft" | "topRight" | "bottomLeft" | "bottomRight";
+  min_font_size?: number | null;
+  max_font_size?: number | null;
 }
 
 export const wasmAPI = {
@@ -0,0 +1,102 @@
+//! `Renderer::parse_drawio_file` on a large file, against the former
+//! approach of re-serializing every cell and parsing it again with
+//! `parse_xml_to_object`.
+//!
+//! Run with `cargo bench -p drawckt --bench parse_drawio`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use drawckt::renderer::Renderer;
+use drawrs::{DiagramObject, XMLBase, parse_xml_to_object};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::hint::black_box;
+
+const PAGES: usize = 50;
+
+/// The schematic test file with its page repeated [`PAGES`] times
+fn large_file() -> String {
+    let content = include_str!("../tests/test_parse_drawio/schematic.drawio");
+    let start = content.find("<diagram").unwrap();
+    let end = content.rfind("</diagram>").unwrap() + "</diagram>".len();
+    let page = &content[start..end];
+    let mut file = content[..start].to_string();
+    for i in 0..PAGES {
+        file.push_str(&page.replacen(
+            r#"name="DCDC_BUCK/current_sense_amp""#,
+            &format!(r#"name="DCDC_BUCK/current_sense_amp_{i}""#),
+            1,
+        ));
+    }
+    file.push_str(&content[end..]);
+    file
+}
+
+fn push_tag(xml: &mut String, e: &BytesStart, self_closing: bool) {
+    xml.push_str(&format!("<{}", String::from_utf8_lossy(e.name().as_ref())));
+    for attr in e.attributes().flatten() {
+        let val = attr.unescape_value().unwrap_or_default();
+        xml.push_str(&format!(
+            r#" {}="{}""#,
+            String::from_utf8_lossy(attr.key.as_ref()),
+            XMLBase::xml_ify(&val)
+        ));
+    }
+    xml.push_str(if self_closing { " />" } else { ">" });
+}
+
+/// Every cell of the root collected as a string, then parsed again
+fn reparse_cells(content: &str) -> Vec<DiagramObject> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut objects = Vec::new();
+    let mut in_root = false;
+    let mut depth = 0;
+    let mut xml = String::new();
+    loop {
+        match reader.read_event_into(&mut buf).unwrap() {
+            Event::Start(e) if depth > 0 => {
+                push_tag(&mut xml, &e, false);
+                depth += 1;
+            }
+            Event::Empty(e) if depth > 0 => push_tag(&mut xml, &e, true),
+            Event::End(e) if depth > 0 => {
+                xml.push_str(&format!(
+                    "</{}>",
+                    String::from_utf8_lossy(e.name().as_ref())
+                ));
+                depth -= 1;
+                if depth == 0 {
+                    objects.push(parse_xml_to_object(&xml).unwrap());
+                    xml.clear();
+                }
+            }
+            Event::Start(e) if e.name().as_ref() == b"root" => in_root = true,
+            Event::Start(e) if in_root => {
+                push_tag(&mut xml, &e, false);
+                depth = 1;
+            }
+            Event::End(e) if e.name().as_ref() == b"root" => in_root = false,
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    objects
+}
+
+fn bench_parse_drawio(c: &mut Criterion) {
+    let content = large_file();
+    let mut group = c.benchmark_group("parse_drawio");
+    group.sample_size(20);
+    group.bench_function("single_pass", |b| {
+        b.iter(|| Renderer::parse_drawio_file(black_box(&content)).unwrap())
+    });
+    group.bench_function("reparse_cells", |b| {
+        b.iter(|| reparse_cells(black_box(&content)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_drawio);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use drawckt::renderer::Renderer;
+use drawckt::schematic::{FillStyleKind, Layer, LayerStyles};
+use drawckt::{DrawcktResult, SchematicBuilder, SymbolBuilder};
+use drawrs::Orient;
+use drawrs::diagram::text_format::{Justify, JustifyX, JustifyY};
+use std::fs;
+
+fn main() -> DrawcktResult<()> {
+    // Triangle with an inversion bubble, input on the left and output on the right
+    let inverter = SymbolBuilder::new("logic", "inv")
+        .polygon(Layer::Device, &[[-0.25, 0.25], [-0.25, -0.25], [0.15, 0.0]])
+        .ellipse(Layer::Device, [0.15, -0.05], [0.25, 0.05])
+        .line(Layer::Device, &[[-0.5, 0.0], [-0.25, 0.0]])
+        .line(Layer::Device, &[[0.25, 0.0], [0.5, 0.0]])
+        .with_fill_style(FillStyleKind::Filled)
+        .rect(Layer::Pin, [-0.525, -0.025], [-0.475, 0.025])
+        .rect(Layer::Pin, [0.475, -0.025], [0.525, 0.025])
+        .label(
+            Layer::Instance,
+            "inv",
+            [0.0, 0.3],
+            0.0625,
+            Justify::new()
+                .with_x(JustifyX::Center)
+                .with_y(JustifyY::Bottom),
+        )
+        .pin("A", "input", -0.5, 0.0)
+        .pin("Y", "output", 0.5, 0.0);
+
+    // Two inverters in a chain
+    let schematic = SchematicBuilder::new("logic", "buf")
+        .add_symbol(inverter)
+        .add_instance("I0", "logic", "inv", 1.0, 0.0, Orient::R0)
+        .add_instance("I1", "logic", "inv", 2.5, 0.0, Orient::R0)
+        .add_wire("in", &[[0.0, 0.0], [0.5, 0.0]])
+        .add_wire("mid", &[[1.5, 0.0], [2.0, 0.0]])
+        .add_wire("out", &[[3.0, 0.0], [3.5, 0.0]])
+        .add_pin("in", "input", 0.0, 0.0)
+        .add_pin("out", "output", 3.5, 0.0)
+        .build()?;
+
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file()?;
+    let schematic_file = renderer.render_schematic_file(&symbols)?;
+
+    let output_file = "buf.drawio";
+    fs::write(output_file, schematic_file)?;
+    println!("Schematic written to: {}", output_file);
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use drawckt::DrawcktResult;
+use drawckt::schematic::{LayerStyles, Schematic};
+use env_logger::{Builder, Env};
+use log::{info, warn};
+use std::fs;
+
+fn main() -> DrawcktResult<()> {
+    Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let schema = match args.get(1).map(String::as_str) {
+        Some("schematic") => Schematic::json_schema(),
+        Some("style") => LayerStyles::json_schema(),
+        _ => {
+            warn!("Usage: {} <schematic|style> [output_file]", args[0]);
+            warn!("  schematic: JSON Schema of the schematic input files");
+            warn!("  style: JSON Schema of the style.json files");
+            warn!("  output_file: Output file (default: stdout)");
+            return Ok(());
+        }
+    };
+    let json = serde_json::to_string_pretty(&schema)?;
+    match args.get(2) {
+        Some(output_file) => {
+            fs::write(output_file, json)?;
+            info!("Schema written to: {output_file}");
+        }
+        None => println!("{json}"),
+    }
+    Ok(())
+}
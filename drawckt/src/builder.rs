@@ -0,0 +1,285 @@
+//! Build a [`Schematic`] in Rust instead of deserializing JSON.
+//!
+//! The `add_*` methods check their own arguments and record the problems,
+//! [`SchematicBuilder::build`] reports them together with the ones found by
+//! [`Schematic::validate`].
+
+use crate::error::{DrawcktError, DrawcktResult};
+use crate::schematic::{
+    DesignId, FillStyleKind, Font, Instance, Layer, Pin, Schematic, SchematicIssue, Shape, Symbol,
+    TemplatePin, Units, Wire,
+};
+use drawrs::Orient;
+use drawrs::diagram::text_format::Justify;
+use indexmap::{IndexMap, IndexSet};
+use ordered_float::OrderedFloat;
+
+fn point([x, y]: [f64; 2]) -> [OrderedFloat<f64>; 2] {
+    [OrderedFloat(x), OrderedFloat(y)]
+}
+
+fn points(points: &[[f64; 2]]) -> Vec<[OrderedFloat<f64>; 2]> {
+    points.iter().copied().map(point).collect()
+}
+
+fn design_id(lib: &str, cell: &str) -> DesignId<'static> {
+    DesignId::new(lib.to_string(), cell.to_string())
+}
+
+fn check_name(name: &str, item: &str, issues: &mut Vec<SchematicIssue>) {
+    if name.is_empty() {
+        issues.push(SchematicIssue::EmptyName {
+            item: item.to_string(),
+        });
+    }
+}
+
+/// Builder of a [`Symbol`], the shapes are drawn in symbol coordinates
+#[derive(Debug, Clone)]
+pub struct SymbolBuilder {
+    symbol: Symbol,
+    fill_style: FillStyleKind,
+    font: Font,
+    issues: Vec<SchematicIssue>,
+}
+
+impl SymbolBuilder {
+    pub fn new(lib: &str, cell: &str) -> Self {
+        let mut issues = Vec::new();
+        check_name(lib, "symbol lib", &mut issues);
+        check_name(cell, "symbol cell", &mut issues);
+        Self {
+            symbol: Symbol {
+                id: design_id(lib, cell),
+                shapes: IndexSet::new(),
+                pins: Vec::new(),
+            },
+            fill_style: FillStyleKind::Outline,
+            font: Font::Stick,
+            issues,
+        }
+    }
+
+    /// Fill style of the rects, ellipses and polygons added after this call
+    pub fn with_fill_style(mut self, fill_style: FillStyleKind) -> Self {
+        self.fill_style = fill_style;
+        self
+    }
+
+    /// Font of the labels added after this call
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    fn shape(mut self, shape: Shape) -> Self {
+        self.symbol.shapes.insert(shape);
+        self
+    }
+
+    /// Rectangle from two opposite corners
+    pub fn rect(self, layer: Layer, from: [f64; 2], to: [f64; 2]) -> Self {
+        let fill_style = self.fill_style;
+        self.shape(Shape::Rect {
+            layer,
+            fill_style,
+            b_box: [point(from), point(to)],
+        })
+    }
+
+    /// Ellipse inscribed in the box of two opposite corners
+    pub fn ellipse(self, layer: Layer, from: [f64; 2], to: [f64; 2]) -> Self {
+        let fill_style = self.fill_style;
+        self.shape(Shape::Ellipse {
+            layer,
+            fill_style,
+            b_box: [point(from), point(to)],
+        })
+    }
+
+    pub fn line(mut self, layer: Layer, line: &[[f64; 2]]) -> Self {
+        if line.len() < 2 {
+            self.issues.push(SchematicIssue::TooFewPoints {
+                item: format!("symbol {} line", self.symbol.id),
+                points: line.len(),
+                min: 2,
+            });
+            return self;
+        }
+        self.shape(Shape::Line {
+            layer,
+            points: points(line),
+        })
+    }
+
+    pub fn polygon(mut self, layer: Layer, polygon: &[[f64; 2]]) -> Self {
+        if polygon.len() < 3 {
+            self.issues.push(SchematicIssue::TooFewPoints {
+                item: format!("symbol {} polygon", self.symbol.id),
+                points: polygon.len(),
+                min: 3,
+            });
+            return self;
+        }
+        let fill_style = self.fill_style;
+        self.shape(Shape::Polygon {
+            layer,
+            fill_style,
+            points: points(polygon),
+            holes: Vec::new(),
+            rounded: false,
+        })
+    }
+
+    /// Label anchored at `xy` with the justification of the anchor
+    pub fn label(
+        self,
+        layer: Layer,
+        text: &str,
+        xy: [f64; 2],
+        height: f64,
+        justify: Justify,
+    ) -> Self {
+        let font = self.font.clone();
+        self.shape(Shape::Label {
+            layer,
+            text: text.to_string(),
+            xy: point(xy),
+            orient: "R0".to_string(),
+            height: OrderedFloat(height),
+            justify,
+            font,
+        })
+    }
+
+    /// Terminal of the symbol, instances connect to it at `[x, y]`
+    pub fn pin(mut self, name: &str, direction: &str, x: f64, y: f64) -> Self {
+        check_name(
+            name,
+            &format!("symbol {} pin", self.symbol.id),
+            &mut self.issues,
+        );
+        self.symbol.pins.push(TemplatePin {
+            name: name.to_string(),
+            direction: direction.to_string(),
+            x,
+            y,
+        });
+        self
+    }
+}
+
+/// Builder of a [`Schematic`], see the [module](self) documentation
+#[derive(Debug, Clone)]
+pub struct SchematicBuilder {
+    schematic: Schematic,
+    issues: Vec<SchematicIssue>,
+}
+
+impl SchematicBuilder {
+    pub fn new(lib: &str, cell: &str) -> Self {
+        Self {
+            schematic: Schematic {
+                design: design_id(lib, cell),
+                instances: Vec::new(),
+                wires: Vec::new(),
+                pins: Vec::new(),
+                symbols: Vec::new(),
+                labels: Vec::new(),
+                shapes: Vec::new(),
+                units: None,
+                units_per_drawio_pixel: None,
+            },
+            issues: Vec::new(),
+        }
+    }
+
+    /// Unit of the coordinates and heights, see [`Schematic::scale`]
+    pub fn with_units(mut self, units: Units) -> Self {
+        self.schematic.units = Some(units);
+        self
+    }
+
+    pub fn add_symbol(mut self, symbol: SymbolBuilder) -> Self {
+        self.issues.extend(symbol.issues);
+        self.schematic.symbols.push(symbol.symbol);
+        self
+    }
+
+    /// Instance of the symbol `lib/cell` placed at `[x, y]`
+    pub fn add_instance(
+        mut self,
+        name: &str,
+        lib: &str,
+        cell: &str,
+        x: f64,
+        y: f64,
+        orient: Orient,
+    ) -> Self {
+        check_name(
+            name,
+            &format!("instance #{}", self.schematic.instances.len()),
+            &mut self.issues,
+        );
+        self.schematic.instances.push(Instance {
+            name: name.to_string(),
+            symbol_id: design_id(lib, cell),
+            x,
+            y,
+            orient,
+            params: IndexMap::new(),
+        });
+        self
+    }
+
+    pub fn add_wire(mut self, net: &str, wire: &[[f64; 2]]) -> Self {
+        let index = self.schematic.wires.len();
+        check_name(net, &format!("wire #{index}"), &mut self.issues);
+        if wire.len() < 2 {
+            self.issues.push(SchematicIssue::ShortWire {
+                index,
+                net: net.to_string(),
+                points: wire.len(),
+            });
+            return self;
+        }
+        self.schematic.wires.push(Wire {
+            net: net.to_string(),
+            points: points(wire),
+        });
+        self
+    }
+
+    /// Top-level port of the design
+    pub fn add_pin(mut self, name: &str, direction: &str, x: f64, y: f64) -> Self {
+        check_name(
+            name,
+            &format!("pin #{}", self.schematic.pins.len()),
+            &mut self.issues,
+        );
+        self.schematic.pins.push(Pin {
+            name: name.to_string(),
+            direction: direction.to_string(),
+            x,
+            y,
+        });
+        self
+    }
+
+    pub fn add_shape(mut self, shape: Shape) -> Self {
+        self.schematic.shapes.push(shape);
+        self
+    }
+
+    /// The schematic, failing with all the recorded and validation issues
+    pub fn build(mut self) -> DrawcktResult<Schematic> {
+        if let Err(issues) = self.schematic.validate() {
+            self.issues.extend(issues);
+        }
+        if self.issues.is_empty() {
+            Ok(self.schematic)
+        } else {
+            Err(DrawcktError::InvalidSchematic(self.issues))
+        }
+    }
+}
@@ -0,0 +1,676 @@
+//! The `drawckt` command line, one binary with a subcommand per task:
+//!
+//! - `symbols`: render the symbol files of a schematic
+//! - `schematic`: render a schematic with its symbol files
+//! - `update-style`: restyle rendered files in place
+//! - `check`: validate a schematic and render it in memory
+//! - `stats`: count the items of a schematic
+//!
+//! The subcommands reading a schematic share the `--format`, `--style` and
+//! `--options` files and the flags of [`RenderOptions::FLAGS_USAGE`]. With
+//! the `watch` feature, `symbols` and `schematic` take `--watch` to render
+//! again each time their inputs change. With the `raster` feature,
+//! `schematic` also writes a PNG preview with `--png`.
+
+use crate::error::{DrawcktError, DrawcktResult};
+use crate::options::RenderOptions;
+use crate::renderer::{Renderer, SymbolContexts};
+use crate::schematic::{InputFormat, LayerStyles, Schematic};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use env_logger::{Builder, Env};
+use log::{error, info, warn};
+#[cfg(feature = "watch")]
+use notify::{Event, RecursiveMode, Watcher};
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+#[cfg(feature = "watch")]
+use std::path::PathBuf;
+use std::process::ExitCode;
+#[cfg(feature = "watch")]
+use std::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// Exit code of a wrong command line, e.g. a missing argument or an invalid
+/// flag value
+pub const EXIT_USAGE: u8 = 2;
+/// Exit code of a schematic or layer styles failing their validation
+pub const EXIT_INVALID: u8 = 3;
+/// Exit code of a file that cannot be read or written
+pub const EXIT_IO: u8 = 4;
+
+const EXIT_CODES: &str = "Exit codes:
+  0  success
+  1  rendering or parsing error
+  2  wrong command line
+  3  invalid schematic or layer styles
+  4  file not read or written";
+
+// Quiet time of `--watch` after the last change before rendering, so that
+// saving several files renders once
+#[cfg(feature = "watch")]
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Exit code of an error, any other than [`EXIT_USAGE`], [`EXIT_INVALID`]
+/// and [`EXIT_IO`] exits with 1
+pub fn exit_code(error: &DrawcktError) -> u8 {
+    match error.root_cause() {
+        DrawcktError::InvalidOption { .. } => EXIT_USAGE,
+        DrawcktError::InvalidSchematic(_) | DrawcktError::InvalidLayerStyles(_) => EXIT_INVALID,
+        DrawcktError::Io(_) => EXIT_IO,
+        _ => 1,
+    }
+}
+
+/// Log to stderr with the source location, `info` unless `RUST_LOG` is set
+pub fn init_logger() {
+    // A second call, e.g. from a wrapper binary, keeps the first logger
+    let _ = Builder::from_env(Env::default().default_filter_or("info"))
+        .format(|buf, record| {
+            let file_name = record.file().unwrap_or("<unknown>");
+            let line = record.line().unwrap_or(0);
+            writeln!(
+                buf,
+                "{} {}:{}: {}",
+                record.level(),
+                file_name,
+                line,
+                record.args()
+            )
+        })
+        .try_init();
+}
+
+/// The `drawckt` binary
+pub fn main() -> ExitCode {
+    init_logger();
+    run(std::env::args_os())
+}
+
+/// Run the command line, the first argument is the binary name. The errors
+/// are logged and turned into the exit code.
+pub fn run<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = match command().try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(e) => {
+            // `--help` and `--version` are printed to stdout and exit with 0
+            let _ = e.print();
+            return ExitCode::from(e.exit_code() as u8);
+        }
+    };
+    match run_matches(&matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+/// Arguments of `drawckt <subcommand>` from the ones of the deprecated
+/// `render_symbols` and `render_schematic` binaries: the flags are kept and
+/// the positional arguments after the schematic file become `options`, e.g.
+/// `["--style", "--output"]`
+pub fn legacy_args(
+    subcommand: &str,
+    args: impl IntoIterator<Item = String>,
+    options: &[&str],
+) -> Vec<String> {
+    let mut args = args.into_iter().skip(1);
+    let mut converted = vec!["drawckt".to_string(), subcommand.to_string()];
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        let takes_value = RenderOptions::flag_takes_value(&arg)
+            || matches!(
+                arg.as_str(),
+                "--options" | "--format" | "--single-file" | "--report" | "--stats"
+            );
+        if takes_value {
+            converted.push(arg);
+            converted.extend(args.next());
+        } else if arg.starts_with("--") {
+            converted.push(arg);
+        } else {
+            positional.push(arg);
+        }
+    }
+    let mut positional = positional.into_iter();
+    converted.extend(positional.next());
+    for (option, value) in options.iter().zip(positional) {
+        converted.push(option.to_string());
+        converted.push(value);
+    }
+    converted
+}
+
+/// The `drawckt` command and its subcommands
+pub fn command() -> Command {
+    Command::new("drawckt")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Render schematics as draw.io files")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .after_help(EXIT_CODES)
+        .subcommand(
+            watch_args(render_args(schematic_args(
+                Command::new("symbols").about("Render the symbol files of a schematic"),
+            )))
+            .arg(force_arg())
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("DIR")
+                    .default_value("./symbols")
+                    .help("Output directory of the {lib}/{cell}.drawio symbol files"),
+            )
+            .arg(
+                Arg::new("single-file")
+                    .long("single-file")
+                    .value_name("FILE")
+                    .conflicts_with("output")
+                    .help("Write all symbols as pages of one .drawio file instead"),
+            ),
+        )
+        .subcommand(
+            watch_args(render_args(schematic_args(
+                Command::new("schematic").about("Render a schematic with its symbol files"),
+            )))
+            .arg(force_arg())
+            .arg(symbols_arg().default_value("./symbols"))
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("FILE")
+                    .default_value("schematic.drawio")
+                    .help("Output schematic file"),
+            )
+            .arg(
+                Arg::new("svg")
+                    .long("svg")
+                    .action(ArgAction::SetTrue)
+                    .help("Also write an SVG preview next to the output file"),
+            )
+            .args(png_args())
+            .arg(
+                Arg::new("report")
+                    .long("report")
+                    .value_name("FILE")
+                    .help("Also write the schematic item of each cell id as JSON"),
+            )
+            .arg(
+                Arg::new("stats")
+                    .long("stats")
+                    .value_name("FILE")
+                    .help("Also write the counts of the schematic items as JSON"),
+            ),
+        )
+        .subcommand(
+            Command::new("update-style")
+                .about("Restyle rendered symbol or schematic files in place")
+                .arg(
+                    Arg::new("files")
+                        .value_name("FILE")
+                        .required(true)
+                        .num_args(1..)
+                        .help("Rendered .drawio files"),
+                )
+                .arg(
+                    Arg::new("old-style")
+                        .long("old-style")
+                        .value_name("FILE")
+                        .help("Style file the files were rendered with (default: built-in)"),
+                )
+                .arg(style_arg().help("New style file (default: built-in)")),
+        )
+        .subcommand(
+            render_args(schematic_args(Command::new("check").about(
+                "Validate a schematic and render it in memory, writing nothing",
+            )))
+            .arg(
+                symbols_arg()
+                    .help("Symbols directory or single .drawio file (default: rendered in memory)"),
+            ),
+        )
+        .subcommand(
+            schematic_args(Command::new("stats").about("Count the items of a schematic")).arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("FILE")
+                    .help("Output JSON file (default: stdout)"),
+            ),
+        )
+}
+
+// The schematic file and its format
+fn schematic_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("schematic")
+                .value_name("SCHEMATIC")
+                .required(true)
+                .help("Input schematic file, JSON, YAML or TOML"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(["json", "yaml", "toml"])
+                .help("Format of the schematic file (default: from its extension)"),
+        )
+}
+
+// The style file and the render options
+fn render_args(command: Command) -> Command {
+    let command =
+        command.arg(style_arg().help("Style file, JSON, YAML or TOML (default: built-in)"));
+    render_flags().fold(command, |command, (flag, value_name, help)| {
+        let arg = Arg::new(flag)
+            .long(flag)
+            .help(help)
+            .help_heading("Render options");
+        command.arg(match value_name {
+            Some(value_name) => arg.value_name(value_name),
+            None => arg.action(ArgAction::SetTrue),
+        })
+    })
+}
+
+// `--watch`, without the `watch` feature nothing
+fn watch_args(command: Command) -> Command {
+    #[cfg(feature = "watch")]
+    let command = command.arg(
+        Arg::new("watch")
+            .long("watch")
+            .action(ArgAction::SetTrue)
+            .help("Render again each time the schematic, style, options or symbols change"),
+    );
+    command
+}
+
+// `--png` and its `--dpi`, without the `raster` feature nothing
+fn png_args() -> Vec<Arg> {
+    #[cfg(feature = "raster")]
+    return vec![
+        Arg::new("png")
+            .long("png")
+            .value_name("FILE")
+            .help("Also write a PNG preview, texts in the system or custom fonts"),
+        Arg::new("dpi")
+            .long("dpi")
+            .value_name("DPI")
+            .value_parser(clap::value_parser!(f64))
+            .default_value("96")
+            .requires("png")
+            .help("Resolution of the PNG preview"),
+    ];
+    #[cfg(not(feature = "raster"))]
+    Vec::new()
+}
+
+fn style_arg() -> Arg {
+    Arg::new("style").long("style").value_name("FILE")
+}
+
+fn symbols_arg() -> Arg {
+    Arg::new("symbols")
+        .long("symbols")
+        .value_name("PATH")
+        .help("Symbols directory or single .drawio file")
+}
+
+fn force_arg() -> Arg {
+    Arg::new("force")
+        .long("force")
+        .action(ArgAction::SetTrue)
+        .help("Render even if the schematic has validation issues")
+}
+
+// Name, value name and help of the flags of `RenderOptions::FLAGS_USAGE`,
+// e.g. `  --snap-grid <pixels>: Round the coordinates to a grid`
+fn render_flags() -> impl Iterator<Item = (&'static str, Option<&'static str>, &'static str)> {
+    RenderOptions::FLAGS_USAGE.iter().filter_map(|usage| {
+        let (flag, help) = usage.trim().split_once(": ")?;
+        let mut parts = flag.split_whitespace();
+        let name = parts.next()?.strip_prefix("--")?;
+        let value_name = parts.next().map(|value| value.trim_matches(['<', '>']));
+        Some((name, value_name, help))
+    })
+}
+
+fn run_matches(matches: &ArgMatches) -> DrawcktResult<()> {
+    match matches.subcommand() {
+        Some(("symbols", matches)) => symbols(matches),
+        Some(("schematic", matches)) => schematic(matches),
+        Some(("update-style", matches)) => update_style(matches),
+        Some(("check", matches)) => check(matches),
+        Some(("stats", matches)) => stats(matches),
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+fn symbols(matches: &ArgMatches) -> DrawcktResult<()> {
+    #[cfg(feature = "watch")]
+    if matches.get_flag("watch") {
+        watch(&input_paths(matches), |_| render_symbols(matches));
+    }
+    render_symbols(matches)
+}
+
+fn render_symbols(matches: &ArgMatches) -> DrawcktResult<()> {
+    let options = render_options(matches)?;
+    options.scoped(|| {
+        let schematic = load_schematic(matches)?;
+        validate(&schematic, matches.get_flag("force"))?;
+        log::info!(
+            "Scale: {} pixels per {:?}",
+            schematic.scale(),
+            schematic.units.unwrap_or_default()
+        );
+        let layer_styles = load_style(matches.get_one::<String>("style"))?;
+
+        let renderer = Renderer::new(&schematic, &layer_styles).with_options(&options);
+        let symbol_contexts = renderer.render_symbols_file()?;
+        if let Some(single_file) = matches.get_one::<String>("single-file") {
+            // Write symbols as pages of one file
+            fs::write(single_file, symbol_contexts.to_single_file()?)?;
+            info!("Symbols rendered to: {:?}", single_file);
+        } else {
+            // Write symbols to directory structure
+            symbol_contexts.write_to_dir(required(matches, "output"))?;
+        }
+        Ok(())
+    })
+}
+
+fn schematic(matches: &ArgMatches) -> DrawcktResult<()> {
+    let symbols_path = required(matches, "symbols");
+    #[cfg(feature = "watch")]
+    if matches.get_flag("watch") {
+        let mut paths = input_paths(matches);
+        paths.push(symbols_path.into());
+        // A symbols directory is read once, then only its changed files
+        let mut symbol_contexts: Option<SymbolContexts> = None;
+        watch(&paths, |changed| {
+            match symbol_contexts.as_mut() {
+                Some(contexts) if Path::new(symbols_path).is_dir() => {
+                    contexts.reload_changed(changed)?;
+                }
+                _ => symbol_contexts = Some(load_symbols(symbols_path)?),
+            }
+            let symbol_contexts = symbol_contexts.as_ref().expect("symbols are loaded");
+            render_schematic(matches, symbol_contexts)
+        });
+    }
+    render_schematic(matches, &load_symbols(symbols_path)?)
+}
+
+fn render_schematic(matches: &ArgMatches, symbol_contexts: &SymbolContexts) -> DrawcktResult<()> {
+    let options = render_options(matches)?;
+    options.scoped(|| {
+        let schematic = load_schematic(matches)?;
+        validate(&schematic, matches.get_flag("force"))?;
+        let stats = schematic.stats();
+        info!("Schematic: {stats}");
+        if let Some(stats_file) = matches.get_one::<String>("stats") {
+            fs::write(stats_file, stats.to_json()?)?;
+            info!("Stats written to: {:?}", stats_file);
+        }
+        log::info!(
+            "Scale: {} pixels per {:?}",
+            schematic.scale(),
+            schematic.units.unwrap_or_default()
+        );
+        let layer_styles = load_style(matches.get_one::<String>("style"))?;
+
+        let (schematic_page, report) = Renderer::new(&schematic, &layer_styles)
+            .with_options(&options)
+            .render_schematic_page_with_report(symbol_contexts)?;
+        if let Some(report_file) = matches.get_one::<String>("report") {
+            fs::write(report_file, report.to_json()?)?;
+            info!("Report written to: {:?}", report_file);
+        }
+        let output_file = required(matches, "output");
+        if matches.get_flag("svg") {
+            let svg_file = Path::new(output_file).with_extension("svg");
+            fs::write(&svg_file, schematic_page.to_svg())?;
+            info!("SVG preview rendered to: {:?}", svg_file);
+        }
+        #[cfg(feature = "raster")]
+        if let Some(png_file) = matches.get_one::<String>("png") {
+            let dpi = *matches.get_one::<f64>("dpi").expect("dpi has a default");
+            let png = schematic_page.to_png_with_fonts(dpi, &png_fonts(&layer_styles))?;
+            fs::write(png_file, png)?;
+            info!("PNG preview rendered to: {:?}", png_file);
+        }
+        let mut schematic_file = layer_styles.draw_file();
+        schematic_file.add_page(schematic_page);
+        fs::write(output_file, schematic_file.xml()?.to_string())?;
+        info!("Schematic rendered to: {:?}", output_file);
+        Ok(())
+    })
+}
+
+// Fonts of the PNG preview: the system fonts and the custom fonts of the
+// styles that are local font files, the other families fall back to the
+// system fonts
+#[cfg(feature = "raster")]
+fn png_fonts(layer_styles: &LayerStyles) -> drawrs::raster::Fonts {
+    let mut fonts = drawrs::raster::Fonts::system();
+    for (family, url) in &layer_styles.custom_fonts {
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        if Path::new(path).is_file() {
+            match fonts.load_font_file(path) {
+                Ok(loaded) => {
+                    if let Some(loaded) = loaded.into_iter().next()
+                        && loaded != *family
+                    {
+                        fonts.set_alias(family.as_str(), loaded);
+                    }
+                }
+                Err(e) => warn!("font {family} cannot be loaded from {path}: {e}"),
+            }
+        } else if !fonts.has_family(family) {
+            warn!("font {family} is not installed, the PNG preview draws it with a fallback font");
+        }
+    }
+    fonts
+}
+
+fn update_style(matches: &ArgMatches) -> DrawcktResult<()> {
+    let old_style = load_style(matches.get_one::<String>("old-style"))?;
+    let new_style = load_style(matches.get_one::<String>("style"))?;
+    for file in matches.get_many::<String>("files").into_iter().flatten() {
+        let content = fs::read_to_string(file)?;
+        let updated = Renderer::update_schematic_style(&content, &old_style, &new_style)?;
+        if updated == content {
+            info!("Unchanged: {:?}", file);
+        } else {
+            fs::write(file, updated)?;
+            info!("Style updated: {:?}", file);
+        }
+    }
+    Ok(())
+}
+
+fn check(matches: &ArgMatches) -> DrawcktResult<()> {
+    let options = render_options(matches)?;
+    options.scoped(|| {
+        let schematic = load_schematic(matches)?;
+        validate(&schematic, false)?;
+        let layer_styles = load_style(matches.get_one::<String>("style"))?;
+        let renderer = Renderer::new(&schematic, &layer_styles).with_options(&options);
+        match matches.get_one::<String>("symbols") {
+            Some(path) => renderer.check(&load_symbols(path)?)?,
+            None => renderer.check(&renderer.render_symbols_file()?)?,
+        }
+        info!("No issues found in: {:?}", required(matches, "schematic"));
+        Ok(())
+    })
+}
+
+fn stats(matches: &ArgMatches) -> DrawcktResult<()> {
+    let stats = load_schematic(matches)?.stats();
+    info!("Schematic: {stats}");
+    match matches.get_one::<String>("output") {
+        Some(output_file) => {
+            fs::write(output_file, stats.to_json()?)?;
+            info!("Stats written to: {:?}", output_file);
+        }
+        None => println!("{}", stats.to_json()?),
+    }
+    Ok(())
+}
+
+// Value of an argument that is required or has a default
+fn required<'m>(matches: &'m ArgMatches, id: &str) -> &'m str {
+    matches
+        .get_one::<String>(id)
+        .unwrap_or_else(|| panic!("argument {id} has a value"))
+}
+
+fn render_options(matches: &ArgMatches) -> DrawcktResult<RenderOptions> {
+    // Defaults < options file < command line flags
+    let mut options = match matches.get_one::<String>("options") {
+        Some(path) => RenderOptions::load(path)?,
+        None => RenderOptions::default(),
+    };
+    for (name, value_name, _) in render_flags().filter(|(name, ..)| *name != "options") {
+        let flag = format!("--{name}");
+        if value_name.is_some() {
+            if let Some(value) = matches.get_one::<String>(name) {
+                options.apply_flag(&flag, Some(value))?;
+            }
+        } else if matches.get_flag(name) {
+            options.apply_flag(&flag, None)?;
+        }
+    }
+    Ok(options)
+}
+
+fn load_schematic(matches: &ArgMatches) -> DrawcktResult<Schematic> {
+    let path = required(matches, "schematic");
+    // The format flag overrides the extension
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => format.parse()?,
+        None => InputFormat::from_path(path).unwrap_or_default(),
+    };
+    Schematic::from_reader(format, fs::File::open(path)?)
+}
+
+fn load_style(path: Option<&String>) -> DrawcktResult<LayerStyles> {
+    match path {
+        Some(path) => LayerStyles::load(path),
+        None => Ok(LayerStyles::default()),
+    }
+}
+
+// Symbols from a single multi-page file, or from the directory structure
+// `{path}/{lib}/{cell}.drawio`
+fn load_symbols(path: &str) -> DrawcktResult<SymbolContexts<'static>> {
+    if Path::new(path).is_file() {
+        SymbolContexts::from_single_file(&fs::read_to_string(path)?)
+    } else {
+        SymbolContexts::load_from_dir(path)
+    }
+}
+
+fn validate(schematic: &Schematic, force: bool) -> DrawcktResult<()> {
+    if let Err(issues) = schematic.validate() {
+        for issue in &issues {
+            error!("{issue}");
+        }
+        if !force {
+            return Err(DrawcktError::InvalidSchematic(issues));
+        }
+        warn!("Rendering anyway because of --force");
+    }
+    Ok(())
+}
+
+// The schematic, style and options files
+#[cfg(feature = "watch")]
+fn input_paths(matches: &ArgMatches) -> Vec<PathBuf> {
+    ["schematic", "style", "options"]
+        .into_iter()
+        .filter_map(|id| matches.get_one::<String>(id))
+        .map(PathBuf::from)
+        .collect()
+}
+
+// Run `cycle`, then again with the changed files each time the files under
+// `paths` change, until the process is stopped. The errors of a cycle are
+// logged and the watch goes on, a watch that cannot start exits.
+#[cfg(feature = "watch")]
+fn watch(paths: &[PathBuf], mut cycle: impl FnMut(&[PathBuf]) -> DrawcktResult<()>) -> ! {
+    let mut run = |changed: &[PathBuf]| {
+        let start = Instant::now();
+        match cycle(changed) {
+            Ok(()) => info!("Rendered in {:.0?}", start.elapsed()),
+            Err(e) => error!("{e}"),
+        }
+    };
+    run(&[]);
+
+    // Directories are watched recursively, files through their directory
+    // so that a file replaced by an editor is still watched
+    let paths: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| std::path::absolute(path).unwrap_or_else(|_| path.clone()))
+        .collect();
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).unwrap_or_else(|e| {
+        error!("Cannot watch the inputs: {e}");
+        std::process::exit(EXIT_IO.into())
+    });
+    for path in &paths {
+        let (watched, mode) = if path.is_dir() {
+            (path.as_path(), RecursiveMode::Recursive)
+        } else {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            (dir, RecursiveMode::NonRecursive)
+        };
+        if let Err(e) = watcher.watch(watched, mode) {
+            error!("Cannot watch {watched:?}: {e}");
+            std::process::exit(EXIT_IO.into());
+        }
+    }
+    info!("Watching {:?}, stop with Ctrl-C", paths);
+
+    // The files read by a cycle are accessed, only the other events are
+    // changes
+    let mut changed: Vec<PathBuf> = Vec::new();
+    let add_changes = |event: notify::Result<Event>, changed: &mut Vec<PathBuf>| match event {
+        Ok(event) if !event.kind.is_access() => {
+            for event_path in event.paths {
+                let input = paths.iter().any(|path| {
+                    event_path == *path || (path.is_dir() && event_path.starts_with(path))
+                });
+                if input && !changed.contains(&event_path) {
+                    changed.push(event_path);
+                }
+            }
+        }
+        Ok(_) => {}
+        Err(e) => error!("Watch error: {e}"),
+    };
+    loop {
+        let event = receiver.recv().expect("the watcher is alive");
+        add_changes(event, &mut changed);
+        while let Ok(event) = receiver.recv_timeout(WATCH_DEBOUNCE) {
+            add_changes(event, &mut changed);
+        }
+        if !changed.is_empty() {
+            run(&std::mem::take(&mut changed));
+        }
+    }
+}
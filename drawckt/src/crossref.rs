@@ -0,0 +1,235 @@
+//! Off-page connectors of the nets drawn on several pages, see [`CrossRefs`]
+//! and [`Renderer::add_cross_refs`].
+//!
+//! [`Renderer::add_cross_refs`]: crate::renderer::Renderer::add_cross_refs
+
+use crate::error::DrawcktResult;
+use crate::report::SOURCE_ATTRIBUTE;
+use crate::schematic::{Layer, LayerStyles};
+use drawrs::xml_base::sanitize_id;
+use drawrs::{BoundingBox, DiagramObject, Object, Page};
+use indexmap::IndexMap;
+
+// `SOURCE_ATTRIBUTE` of the connectors
+const CROSS_REF_KIND: &str = "crossRef";
+
+/// Where the connector of a net goes on each of its pages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrossRefPlacement {
+    /// Column right of the page content, one row per net in net order
+    #[default]
+    Margin,
+    /// Next to the wire end of the net nearest to the border of the page
+    /// content, where the net is cut, pointing out of the page
+    WireEnd,
+}
+
+/// Placement and size of the connectors, their colors and font are the ones
+/// of the wire layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossRefStyle {
+    pub placement: CrossRefPlacement,
+    /// Size of a connector in draw.io pixels
+    pub width: f64,
+    pub height: f64,
+    /// Gap between the page content, or the wire end, and the connectors
+    pub gap: f64,
+    pub font_size: f64,
+}
+
+impl Default for CrossRefStyle {
+    fn default() -> Self {
+        Self {
+            placement: CrossRefPlacement::default(),
+            width: 80.0,
+            height: 20.0,
+            gap: 20.0,
+            font_size: 10.0,
+        }
+    }
+}
+
+/// Pages touched by each net, numbered from 1 in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrossRefs {
+    pub nets: IndexMap<String, Vec<usize>>,
+}
+
+impl CrossRefs {
+    /// Record the nets of the wires of each page, from their `net` attribute
+    pub fn collect<'a>(pages: impl IntoIterator<Item = &'a Page>) -> Self {
+        let mut nets: IndexMap<String, Vec<usize>> = IndexMap::new();
+        for (i, page) in pages.into_iter().enumerate() {
+            for (net, _) in wires(page) {
+                let pages = nets.entry(net.to_string()).or_default();
+                if pages.last() != Some(&(i + 1)) {
+                    pages.push(i + 1);
+                }
+            }
+        }
+        Self { nets }
+    }
+
+    /// The other pages of `net`, empty when it is drawn on `page` only
+    pub fn other_pages(&self, net: &str, page: usize) -> Vec<usize> {
+        self.nets
+            .get(net)
+            .filter(|pages| pages.contains(&page))
+            .map(|pages| pages.iter().copied().filter(|&p| p != page).collect())
+            .unwrap_or_default()
+    }
+
+    /// Text of the connector of `net` on `page`, e.g. `VREF →2,3`
+    pub fn label(&self, net: &str, page: usize) -> Option<String> {
+        let others = self.other_pages(net, page);
+        if others.is_empty() {
+            return None;
+        }
+        let others: Vec<String> = others.iter().map(usize::to_string).collect();
+        Some(format!("{net} →{}", others.join(",")))
+    }
+
+    /// Add a connector with id `xref-{net}-{page}-{n}`, `n` numbering the
+    /// connectors of the page from 1, on the wire label layer of each page
+    /// for each net it shares with other pages, `pages` in the order given
+    /// to [`Self::collect`]. The connectors are marked with the
+    /// [`SOURCE_ATTRIBUTE`] `crossRef`, see [`is_connector`]. Returns the
+    /// number of connectors, fails on a font family of the wire layer that
+    /// does not pass [`LayerStyles::validate`].
+    pub fn add_connectors(
+        &self,
+        pages: &mut [Page],
+        layer_styles: &LayerStyles,
+        style: &CrossRefStyle,
+    ) -> DrawcktResult<usize> {
+        let mut count = 0;
+        for (i, page) in pages.iter_mut().enumerate() {
+            let page_num = i + 1;
+            let Some(content) = content_box(page) else {
+                continue;
+            };
+            let mut ends: IndexMap<String, Vec<[f64; 2]>> = IndexMap::new();
+            for (net, points) in wires(page) {
+                ends.entry(net.to_string()).or_default().extend(points);
+            }
+            let mut row = 0;
+            let mut n = 0;
+            for (net, points) in ends {
+                let Some(label) = self.label(&net, page_num) else {
+                    continue;
+                };
+                let (position, direction) = match style.placement {
+                    CrossRefPlacement::Margin => {
+                        let y = content.min_y() + row as f64 * (style.height + style.gap / 2.0);
+                        row += 1;
+                        ([content.max_x() + style.gap, y], "east")
+                    }
+                    CrossRefPlacement::WireEnd => wire_end_position(&content, &points, style),
+                };
+                n += 1;
+                let mut connector = connector(layer_styles, style, label, position, direction)?;
+                connector.set_id(format!("xref-{}-{page_num}-{n}", sanitize_id(&net)));
+                connector.set_attribute(SOURCE_ATTRIBUTE, CROSS_REF_KIND)?;
+                page.add_object(connector.into());
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Whether the object is a connector added by [`CrossRefs::add_connectors`]
+pub fn is_connector(obj: &DiagramObject) -> bool {
+    obj.attribute(SOURCE_ATTRIBUTE)
+        .is_some_and(|kind| kind == CROSS_REF_KIND)
+}
+
+// Nets of the wires of the page with their end points
+fn wires(page: &Page) -> impl Iterator<Item = (&str, Vec<[f64; 2]>)> {
+    page.objects().iter().filter_map(|obj| match obj {
+        DiagramObject::Edge(edge) => {
+            let net = edge.base().attributes.get("net")?;
+            let geometry = edge.geometry_ref();
+            let ends = [geometry.source_point(), geometry.target_point()];
+            Some((net.as_str(), ends.into_iter().flatten().collect()))
+        }
+        _ => None,
+    })
+}
+
+// Box of the objects and wire points of the page, without the connectors
+fn content_box(page: &Page) -> Option<BoundingBox> {
+    let points = wires(page).flat_map(|(_, points)| points);
+    BoundingBox::union(
+        page.objects()
+            .iter()
+            .filter(|obj| !is_connector(obj))
+            .filter_map(DiagramObject::bounding_box)
+            .chain(points.map(|[x, y]| BoundingBox::new(x, y, 0.0, 0.0))),
+    )
+}
+
+// Top-left corner and direction of the connector at the wire end nearest
+// to the border of the content
+fn wire_end_position(
+    content: &BoundingBox,
+    points: &[[f64; 2]],
+    style: &CrossRefStyle,
+) -> ([f64; 2], &'static str) {
+    let (width, height, gap) = (style.width, style.height, style.gap);
+    points
+        .iter()
+        .flat_map(|&[x, y]| {
+            [
+                (
+                    x - content.min_x(),
+                    [x - gap - width, y - height / 2.0],
+                    "west",
+                ),
+                (content.max_x() - x, [x + gap, y - height / 2.0], "east"),
+                (
+                    y - content.min_y(),
+                    [x - width / 2.0, y - gap - height],
+                    "north",
+                ),
+                (content.max_y() - y, [x - width / 2.0, y + gap], "south"),
+            ]
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, position, direction)| (position, direction))
+        .unwrap_or(([content.max_x() + gap, content.min_y()], "east"))
+}
+
+// Pentagon pointing to `direction` with the label inside
+fn connector(
+    layer_styles: &LayerStyles,
+    style: &CrossRefStyle,
+    label: String,
+    [x, y]: [f64; 2],
+    direction: &str,
+) -> DrawcktResult<Object> {
+    let wire_style = layer_styles.layer_style(&Layer::Wire);
+    let (width, height) = match direction {
+        "north" | "south" => (style.height.max(style.width / 2.0), style.width),
+        _ => (style.width, style.height),
+    };
+    let mut object = Object::new(None);
+    object.parse_and_set_style(&format!(
+        "shape=mxgraph.arrows2.arrow;dy=0;dx={};notch=0;direction={direction};whiteSpace=wrap;html=1;",
+        style.height / 2.0
+    ));
+    object.set_value(label);
+    object.set_position([x, y]);
+    object.set_width(width);
+    object.set_height(height);
+    object.set_fill_color(Some("none".to_string()));
+    object.set_stroke_color(Some(wire_style.stroke_color.to_string()));
+    object.set_stroke_width(Some(wire_style.stroke_width));
+    object.set_font_color(Some(wire_style.text_color.to_string()));
+    object.set_font_family(Some(
+        layer_styles.css_family(&wire_style.font_family).to_string(),
+    ))?;
+    object.set_font_size(Some(style.font_size));
+    object.set_xml_parent(Some(Layer::Wire.id_label()));
+    Ok(object)
+}
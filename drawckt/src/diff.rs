@@ -0,0 +1,157 @@
+use crate::schematic::{Instance, Pin, Schematic, Symbol, Wire};
+use indexmap::{IndexMap, IndexSet};
+use ordered_float::OrderedFloat;
+
+/// An instance with the same name in both schematics but a different placement
+#[derive(Debug, Clone)]
+pub struct MovedInstance {
+    pub old: Instance,
+    pub new: Instance,
+}
+
+/// A pin with the same name in both schematics but a different direction or position
+#[derive(Debug, Clone)]
+pub struct ChangedPin {
+    pub old: Pin,
+    pub new: Pin,
+}
+
+/// Delta between two schematics, see [`diff_schematics`]
+#[derive(Debug, Clone, Default)]
+pub struct SchematicDiff {
+    pub added_instances: Vec<Instance>,
+    pub removed_instances: Vec<Instance>,
+    pub moved_instances: Vec<MovedInstance>,
+    pub added_wires: Vec<Wire>,
+    pub removed_wires: Vec<Wire>,
+    pub added_pins: Vec<Pin>,
+    pub removed_pins: Vec<Pin>,
+    pub changed_pins: Vec<ChangedPin>,
+    /// Symbols of the old schematic used by removed instances and missing
+    /// from the new one, to draw the removed instances
+    pub removed_symbols: Vec<Symbol>,
+}
+
+impl SchematicDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_instances.is_empty()
+            && self.removed_instances.is_empty()
+            && self.moved_instances.is_empty()
+            && self.added_wires.is_empty()
+            && self.removed_wires.is_empty()
+            && self.added_pins.is_empty()
+            && self.removed_pins.is_empty()
+            && self.changed_pins.is_empty()
+    }
+}
+
+type WireKey<'a> = (&'a str, Vec<[OrderedFloat<f64>; 2]>);
+
+// Net and geometry, independent of the drawing direction
+fn wire_key(wire: &Wire) -> WireKey<'_> {
+    let reversed: Vec<_> = wire.points.iter().rev().copied().collect();
+    (wire.net.as_str(), wire.points.clone().min(reversed))
+}
+
+/// Compare two schematics.
+///
+/// Instances and pins are keyed by name, an instance whose symbol changed is
+/// reported as removed and added. Wires are keyed by net and geometry, so a
+/// renamed net shows up as removed and added wires.
+pub fn diff_schematics(old: &Schematic, new: &Schematic) -> SchematicDiff {
+    let mut diff = SchematicDiff::default();
+
+    let old_instances: IndexMap<&str, &Instance> = old
+        .instances
+        .iter()
+        .map(|inst| (inst.name.as_str(), inst))
+        .collect();
+    let new_instances: IndexMap<&str, &Instance> = new
+        .instances
+        .iter()
+        .map(|inst| (inst.name.as_str(), inst))
+        .collect();
+    for (name, old_inst) in &old_instances {
+        match new_instances.get(name) {
+            Some(new_inst) if new_inst.symbol_id == old_inst.symbol_id => {
+                if new_inst.x != old_inst.x
+                    || new_inst.y != old_inst.y
+                    || new_inst.orient != old_inst.orient
+                {
+                    diff.moved_instances.push(MovedInstance {
+                        old: (*old_inst).clone(),
+                        new: (*new_inst).clone(),
+                    });
+                }
+            }
+            Some(new_inst) => {
+                diff.removed_instances.push((*old_inst).clone());
+                diff.added_instances.push((*new_inst).clone());
+            }
+            None => diff.removed_instances.push((*old_inst).clone()),
+        }
+    }
+    for (name, new_inst) in &new_instances {
+        if !old_instances.contains_key(name) {
+            diff.added_instances.push((*new_inst).clone());
+        }
+    }
+    for instance in &diff.removed_instances {
+        let id = &instance.symbol_id;
+        if new.symbols.iter().all(|symbol| symbol.id != *id)
+            && diff.removed_symbols.iter().all(|symbol| symbol.id != *id)
+            && let Some(symbol) = old.symbols.iter().find(|symbol| symbol.id == *id)
+        {
+            diff.removed_symbols.push(symbol.clone());
+        }
+    }
+
+    let old_wires: IndexSet<WireKey> = old.wires.iter().map(wire_key).collect();
+    let new_wires: IndexSet<WireKey> = new.wires.iter().map(wire_key).collect();
+    diff.removed_wires = old
+        .wires
+        .iter()
+        .filter(|wire| !new_wires.contains(&wire_key(wire)))
+        .cloned()
+        .collect();
+    diff.added_wires = new
+        .wires
+        .iter()
+        .filter(|wire| !old_wires.contains(&wire_key(wire)))
+        .cloned()
+        .collect();
+
+    let old_pins: IndexMap<&str, &Pin> = old
+        .pins
+        .iter()
+        .map(|pin| (pin.name.as_str(), pin))
+        .collect();
+    let new_pins: IndexMap<&str, &Pin> = new
+        .pins
+        .iter()
+        .map(|pin| (pin.name.as_str(), pin))
+        .collect();
+    for (name, old_pin) in &old_pins {
+        match new_pins.get(name) {
+            Some(new_pin) => {
+                if new_pin.direction != old_pin.direction
+                    || new_pin.x != old_pin.x
+                    || new_pin.y != old_pin.y
+                {
+                    diff.changed_pins.push(ChangedPin {
+                        old: (*old_pin).clone(),
+                        new: (*new_pin).clone(),
+                    });
+                }
+            }
+            None => diff.removed_pins.push((*old_pin).clone()),
+        }
+    }
+    for (name, new_pin) in &new_pins {
+        if !old_pins.contains_key(name) {
+            diff.added_pins.push((*new_pin).clone());
+        }
+    }
+
+    diff
+}
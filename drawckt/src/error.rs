@@ -1,7 +1,7 @@
 use drawrs::DrawrsError;
 use thiserror::Error;
 
-use crate::schematic::Layer;
+use crate::schematic::{InputFormat, LayerStyleIssue, SchematicIssue};
 
 /// Main error type for drawckt crate
 #[derive(Error, Debug)]
@@ -21,14 +21,127 @@ pub enum DrawcktError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    #[error("TOML parsing error: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[cfg(feature = "yaml")]
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("{0} input is not enabled, build drawckt with the `{0}` feature")]
+    FormatNotEnabled(InputFormat),
+
+    #[error("Invalid value {value:?} for option {option}")]
+    InvalidOption { option: String, value: String },
+
     #[error("Symbol page '{0}' not found in symbols.drawio")]
     SymbolNotFound(String),
 
     #[error("No page found")]
     NoPage,
 
-    #[error("Repeat layer: {0}")]
-    RepeatLayer(Layer),
+    #[error("Layer cell '{0}' not found, the file was rendered without it")]
+    LayerCellNotFound(String),
+
+    #[error("Invalid symbol page name '{0}', expected lib/cell")]
+    InvalidPageName(String),
+
+    #[error("Symbol {lib}/{cell} is stale: {reason}")]
+    StaleSymbol {
+        lib: String,
+        cell: String,
+        reason: String,
+    },
+
+    #[error("Terminal {0} is not connected")]
+    UnconnectedTerminal(String),
+
+    #[error("Invalid layer styles: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidLayerStyles(Vec<LayerStyleIssue>),
+
+    #[error("Invalid schematic: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidSchematic(Vec<SchematicIssue>),
+
+    #[error("instance {name} ({symbol}): {source}")]
+    InInstance {
+        name: String,
+        symbol: String,
+        source: Box<DrawcktError>,
+    },
+
+    #[error("symbol {symbol}: {source}")]
+    InSymbol {
+        symbol: String,
+        source: Box<DrawcktError>,
+    },
+
+    #[error("shape #{index}: {source}")]
+    InShape {
+        index: usize,
+        source: Box<DrawcktError>,
+    },
+
+    #[error("pin {name}: {source}")]
+    InPin {
+        name: String,
+        source: Box<DrawcktError>,
+    },
+
+    #[error("label #{index}: {source}")]
+    InLabel {
+        index: usize,
+        source: Box<DrawcktError>,
+    },
+}
+
+impl DrawcktError {
+    pub fn in_instance(self, name: &str, symbol: impl ToString) -> Self {
+        Self::InInstance {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            source: Box::new(self),
+        }
+    }
+
+    pub fn in_symbol(self, symbol: impl ToString) -> Self {
+        Self::InSymbol {
+            symbol: symbol.to_string(),
+            source: Box::new(self),
+        }
+    }
+
+    pub fn in_shape(self, index: usize) -> Self {
+        Self::InShape {
+            index,
+            source: Box::new(self),
+        }
+    }
+
+    pub fn in_pin(self, name: &str) -> Self {
+        Self::InPin {
+            name: name.to_string(),
+            source: Box::new(self),
+        }
+    }
+
+    pub fn in_label(self, index: usize) -> Self {
+        Self::InLabel {
+            index,
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost error, without the context wrappers
+    pub fn root_cause(&self) -> &DrawcktError {
+        match self {
+            Self::InInstance { source, .. }
+            | Self::InSymbol { source, .. }
+            | Self::InShape { source, .. }
+            | Self::InPin { source, .. }
+            | Self::InLabel { source, .. } => source.root_cause(),
+            _ => self,
+        }
+    }
 }
 
 /// Convenience type alias for Result
@@ -0,0 +1,196 @@
+//! Connectivity of polylines, e.g. the wires of a net: merging the lines
+//! that meet end to end, counting the line ends at each point and splitting
+//! the lines at their junctions. The renderer merges the wires of each net
+//! and the lines of each symbol layer with [`merge_polylines`].
+
+use log::debug;
+use ordered_float::OrderedFloat;
+use std::collections::{HashMap, HashSet};
+
+/// A point of a polyline, compared exactly so that it can key maps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point(pub [OrderedFloat<f64>; 2]);
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self([OrderedFloat(x), OrderedFloat(y)])
+    }
+
+    pub fn x(&self) -> f64 {
+        *self.0[0]
+    }
+
+    pub fn y(&self) -> f64 {
+        *self.0[1]
+    }
+}
+
+impl From<[OrderedFloat<f64>; 2]> for Point {
+    fn from(point: [OrderedFloat<f64>; 2]) -> Self {
+        Self(point)
+    }
+}
+
+impl From<[f64; 2]> for Point {
+    fn from([x, y]: [f64; 2]) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Point> for [OrderedFloat<f64>; 2] {
+    fn from(point: Point) -> Self {
+        point.0
+    }
+}
+
+/// Merge the lines that meet end to end, where exactly two line ends share a
+/// point. Zero-length lines and repeated lines, in either direction, are
+/// dropped. The merged lines are in order of their first line.
+pub fn merge_polylines<L: AsRef<[Point]>>(lines: &[L]) -> Vec<Vec<Point>> {
+    merge_polylines_with_breakpoints(lines, &HashSet::new())
+}
+
+/// [`merge_polylines`] that never merges two lines at one of the
+/// `breakpoints`, e.g. the pins of the instances, and splits the lines
+/// running through them first, so every breakpoint on a line is one of its
+/// ends
+pub fn merge_polylines_with_breakpoints<L: AsRef<[Point]>>(
+    lines: &[L],
+    breakpoints: &HashSet<Point>,
+) -> Vec<Vec<Point>> {
+    let split = split_where(lines, |point| breakpoints.contains(point));
+    // Zero-length lines and repeated lines would be stacked cells
+    let mut seen = HashSet::new();
+    let count = split.len();
+    let lines: Vec<&[Point]> = split
+        .iter()
+        .map(Vec::as_slice)
+        .filter(|line| {
+            let reversed: Vec<_> = line.iter().rev().copied().collect();
+            line.iter().any(|point| *point != line[0])
+                && seen.insert(std::cmp::min(line.to_vec(), reversed))
+        })
+        .collect();
+    if lines.len() < count {
+        debug!(
+            "merge lines: removed {} zero-length or duplicate lines",
+            count - lines.len()
+        );
+    }
+    let mut merged: Vec<Vec<Point>> = Vec::new();
+    let mut processed = vec![false; lines.len()];
+    let ends = |line: &[Point]| [line[0], line[line.len() - 1]];
+
+    for i in 0..lines.len() {
+        if processed[i] {
+            continue;
+        }
+        processed[i] = true;
+        let mut current = lines[i].to_vec();
+        // Keep merging until no line continues the current one
+        loop {
+            let mut merged_this_round = false;
+            for j in 0..lines.len() {
+                if processed[j] {
+                    continue;
+                }
+                let other = lines[j];
+                let [start, end] = ends(&current);
+                let [other_start, other_end] = ends(other);
+                let shared = if start == other_start || start == other_end {
+                    start
+                } else if end == other_start || end == other_end {
+                    end
+                } else {
+                    continue;
+                };
+                // Only two lines may meet at the shared point, counting the
+                // unprocessed lines and the lines already merged
+                let connected = (0..lines.len())
+                    .filter(|&k| k != j && !processed[k])
+                    .map(|k| lines[k])
+                    .chain(merged.iter().map(Vec::as_slice))
+                    .any(|line| ends(line).contains(&shared));
+                if connected || breakpoints.contains(&shared) {
+                    continue;
+                }
+                if shared == start && other_end == shared {
+                    // The other line comes first
+                    current = [other, &current[1..]].concat();
+                } else {
+                    if shared == start {
+                        current.reverse();
+                    }
+                    // The other line goes on from the shared point
+                    current.pop();
+                    if other_start == shared {
+                        current.extend_from_slice(other);
+                    } else {
+                        current.extend(other.iter().rev());
+                    }
+                }
+                processed[j] = true;
+                merged_this_round = true;
+            }
+            if !merged_this_round {
+                break;
+            }
+        }
+        merged.push(current);
+    }
+    merged
+}
+
+/// Number of line ends at each end point of the lines, a closed line counts
+/// twice at its end point. Lines of fewer than two points have no ends.
+pub fn endpoint_degrees<L: AsRef<[Point]>>(lines: &[L]) -> HashMap<Point, usize> {
+    let mut degrees = HashMap::new();
+    for line in lines.iter().map(AsRef::as_ref) {
+        if line.len() < 2 {
+            continue;
+        }
+        for end in [line[0], line[line.len() - 1]] {
+            *degrees.entry(end).or_default() += 1;
+        }
+    }
+    degrees
+}
+
+/// Split the lines at their inner points where three or more segments meet,
+/// e.g. where another line ends, so that every line runs between two ends or
+/// junctions. The inverse of [`merge_polylines`]; lines of fewer than two
+/// points are dropped.
+pub fn split_at_junctions<L: AsRef<[Point]>>(lines: &[L]) -> Vec<Vec<Point>> {
+    // Segments at each point: one at a line end, two at an inner point
+    let mut degrees: HashMap<Point, usize> = HashMap::new();
+    for line in lines.iter().map(AsRef::as_ref) {
+        for (i, point) in line.iter().enumerate() {
+            let segments = if i == 0 || i == line.len() - 1 { 1 } else { 2 };
+            *degrees.entry(*point).or_default() += segments;
+        }
+    }
+    split_where(lines, |point| degrees[point] >= 3)
+}
+
+// The lines of at least two points, split at their inner points where
+// `is_break` holds
+fn split_where<L: AsRef<[Point]>>(
+    lines: &[L],
+    is_break: impl Fn(&Point) -> bool,
+) -> Vec<Vec<Point>> {
+    let mut split = Vec::new();
+    for line in lines.iter().map(AsRef::as_ref) {
+        if line.len() < 2 {
+            continue;
+        }
+        let mut start = 0;
+        for i in 1..line.len() - 1 {
+            if is_break(&line[i]) {
+                split.push(line[start..=i].to_vec());
+                start = i;
+            }
+        }
+        split.push(line[start..].to_vec());
+    }
+    split
+}
@@ -1,9 +1,25 @@
+pub mod builder;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod crossref;
+pub mod diff;
 pub mod error;
+pub mod geometry;
+pub mod netlist;
+pub mod options;
 pub mod renderer;
+pub mod report;
 pub mod schematic;
+pub mod stats;
 #[cfg(test)]
 mod tests;
 
+pub use builder::{SchematicBuilder, SymbolBuilder};
+pub use crossref::{CrossRefPlacement, CrossRefStyle, CrossRefs};
 pub use error::{DrawcktError, DrawcktResult};
+pub use geometry::Point;
+pub use options::RenderOptions;
 pub use renderer::SymbolPageData;
+pub use report::{CellSource, RenderReport, SOURCE_ATTRIBUTE};
 pub use schematic::DesignId;
+pub use stats::SchematicStats;
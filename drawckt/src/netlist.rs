@@ -0,0 +1,136 @@
+use crate::error::{DrawcktError, DrawcktResult};
+use crate::schematic::{Instance, Schematic, Symbol};
+use drawrs::Orient;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Distance in user units below which a terminal and a wire endpoint are
+/// connected, see [`Schematic::snap_tolerance`]
+pub(crate) const SNAP_TOLERANCE: f64 = 1e-6;
+
+/// How [`Schematic::to_netlist_with`] handles a terminal without a net
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unconnected {
+    /// Fail with [`DrawcktError::UnconnectedTerminal`]
+    #[default]
+    Error,
+    /// Give each terminal its own `UNCONNECTED_{n}` net
+    Name,
+}
+
+// Symbol pin offset placed by an orientation, in schematic coordinates (y up)
+fn orient_point(orient: Orient, [x, y]: [f64; 2]) -> [f64; 2] {
+    match orient {
+        Orient::R0 => [x, y],
+        Orient::R90 => [-y, x],
+        Orient::R180 => [-x, -y],
+        Orient::R270 => [y, -x],
+        Orient::MY => [-x, y],
+        Orient::MX => [x, -y],
+        Orient::MYR90 => [-y, -x],
+        Orient::MXR90 => [y, x],
+    }
+}
+
+/// Whether two points are within `tolerance` of each other
+pub(crate) fn snapped(a: [f64; 2], b: [f64; 2], tolerance: f64) -> bool {
+    (a[0] - b[0]).abs() < tolerance && (a[1] - b[1]).abs() < tolerance
+}
+
+impl Instance {
+    /// Position of each symbol pin of the instance, in the symbol's pin order
+    pub fn terminal_points(&self, symbol: &Symbol) -> Vec<[f64; 2]> {
+        symbol
+            .pins
+            .iter()
+            .map(|pin| {
+                let [dx, dy] = orient_point(self.orient, [pin.x, pin.y]);
+                [self.x + dx, self.y + dy]
+            })
+            .collect()
+    }
+}
+
+impl Schematic {
+    /// SPICE-like netlist, failing on unconnected terminals
+    pub fn to_netlist(&self) -> DrawcktResult<String> {
+        self.to_netlist_with(Unconnected::Error)
+    }
+
+    /// SPICE-like netlist: the ports of the design from `pins`, then one
+    /// `X{name} {nets} {lib}_{cell}` line per instance.
+    ///
+    /// A terminal takes the net of the wire ending on it, or the name of the
+    /// top-level pin it sits on. Instances placed on a top-level pin are pin
+    /// glyphs and are left out.
+    pub fn to_netlist_with(&self, unconnected: Unconnected) -> DrawcktResult<String> {
+        let symbols: HashMap<_, _> = self.symbols.iter().map(|s| (&s.id, s)).collect();
+        let wire_ends: Vec<([f64; 2], &str)> = self
+            .wires
+            .iter()
+            .flat_map(|wire| {
+                [wire.points.first(), wire.points.last()]
+                    .into_iter()
+                    .flatten()
+                    .map(|[x, y]| ([x.0, y.0], wire.net.as_str()))
+            })
+            .collect();
+        let tolerance = self.snap_tolerance();
+        let pin_at = |point: [f64; 2]| {
+            self.pins
+                .iter()
+                .find(|pin| snapped([pin.x, pin.y], point, tolerance))
+                .map(|pin| pin.name.as_str())
+        };
+        let net_at = |point: [f64; 2]| {
+            wire_ends
+                .iter()
+                .find(|(end, _)| snapped(*end, point, tolerance))
+                .map(|(_, net)| *net)
+                .or_else(|| pin_at(point))
+        };
+
+        let mut netlist = String::new();
+        let _ = writeln!(netlist, "* {}", self.design);
+        let _ = write!(netlist, ".SUBCKT {}", self.design.cell);
+        for pin in &self.pins {
+            let _ = write!(netlist, " {}", pin.name);
+        }
+        netlist.push('\n');
+
+        let mut unconnected_count = 0;
+        for instance in &self.instances {
+            let Some(symbol) = symbols.get(&instance.symbol_id) else {
+                return Err(DrawcktError::SymbolNotFound(instance.symbol_id.to_string())
+                    .in_instance(&instance.name, &instance.symbol_id));
+            };
+            if pin_at([instance.x, instance.y]).is_some() {
+                continue;
+            }
+            let points = instance.terminal_points(symbol);
+            let _ = write!(netlist, "X{}", instance.name);
+            for (pin, point) in symbol.pins.iter().zip(points) {
+                match (net_at(point), unconnected) {
+                    (Some(net), _) => {
+                        let _ = write!(netlist, " {net}");
+                    }
+                    (None, Unconnected::Name) => {
+                        unconnected_count += 1;
+                        let _ = write!(netlist, " UNCONNECTED_{unconnected_count}");
+                    }
+                    (None, Unconnected::Error) => {
+                        return Err(DrawcktError::UnconnectedTerminal(pin.name.clone())
+                            .in_instance(&instance.name, &instance.symbol_id));
+                    }
+                }
+            }
+            let _ = writeln!(
+                netlist,
+                " {}_{}",
+                instance.symbol_id.lib, instance.symbol_id.cell
+            );
+        }
+        netlist.push_str(".ENDS\n");
+        Ok(netlist)
+    }
+}
@@ -0,0 +1,301 @@
+//! Renderer options that are not visual style, loaded from a JSON or TOML
+//! config file.
+//!
+//! The binaries merge the options in this order, later ones win:
+//! 1. the built-in defaults of [`RenderOptions::default`]
+//! 2. the fields present in the `--options` file
+//! 3. the explicit command line flags
+//!
+//! Visual settings (colors, fonts, layer visibility) stay in
+//! [`LayerStyles`](crate::schematic::LayerStyles).
+
+use crate::error::{DrawcktError, DrawcktResult};
+use crate::renderer::{DEFAULT_LABEL_MAX_DISPLACEMENT, DEFAULT_SIMPLIFY_EPSILON, Renderer};
+use crate::schematic::{DesignId, IdScheme, Layer};
+use indexmap::IndexMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    /// See [`Renderer::with_id_scheme`]
+    pub id_scheme: IdScheme,
+    /// See [`Renderer::with_flatten_instances`]
+    pub flatten_instances: bool,
+    /// See [`Renderer::with_cell_tags`]
+    pub cell_tags: bool,
+    /// See [`Renderer::with_snap_grid`]
+    pub snap_grid: Option<f64>,
+    /// See [`Renderer::with_fallback_layer`]
+    pub fallback_layer: Layer,
+    /// Maximum number of decimals in the written files, see
+    /// [`drawrs::DrawFile::float_precision`] and [`Self::scoped`]
+    pub float_precision: Option<usize>,
+    /// See [`Renderer::with_allow_stale_symbols`]
+    pub allow_stale_symbols: bool,
+    /// See [`Renderer::with_connection_points`]
+    pub connection_points: bool,
+    /// See [`Renderer::with_simplify_epsilon`]
+    pub simplify_epsilon: Option<f64>,
+    /// See [`Renderer::with_orthogonalize_tolerance`]
+    pub orthogonalize_tolerance: Option<f64>,
+    /// See [`Renderer::with_deterministic_ids`]
+    pub deterministic_ids: bool,
+    /// See [`Renderer::with_instance_link_template`]
+    pub instance_link_template: Option<String>,
+    /// See [`Renderer::with_instance_hit_boxes`]
+    pub instance_hit_boxes: bool,
+    /// See [`Renderer::with_group_instances`]
+    pub group_instances: bool,
+    /// See [`Renderer::with_strict_text`]
+    pub strict_text: bool,
+    /// See [`Renderer::with_labels_autosize`]
+    pub labels_autosize: bool,
+    /// See [`Renderer::with_split_wires_at_pins`]
+    pub split_wires_at_pins: bool,
+    /// See [`Renderer::with_mark_open_ends`]
+    pub mark_open_ends: bool,
+    /// See [`Renderer::with_avoid_label_overlap`]
+    pub avoid_label_overlap: bool,
+    /// See [`Renderer::with_label_max_displacement`]
+    pub label_max_displacement: f64,
+    /// See [`Renderer::with_pin_symbols`], e.g.
+    /// `{"input": {"lib": "basic", "cell": "ipin"}}`
+    pub pin_symbols: IndexMap<String, DesignId<'static>>,
+    /// See [`Renderer::with_layer_legend`]
+    pub layer_legend: bool,
+    /// Fixed `modified` attribute of the written files, see
+    /// [`drawrs::file::set_fixed_timestamp`] and [`Self::scoped`]
+    pub timestamp: Option<String>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            id_scheme: IdScheme::default(),
+            flatten_instances: false,
+            cell_tags: false,
+            snap_grid: None,
+            fallback_layer: Layer::Annotate,
+            float_precision: None,
+            allow_stale_symbols: false,
+            connection_points: false,
+            simplify_epsilon: Some(DEFAULT_SIMPLIFY_EPSILON),
+            orthogonalize_tolerance: None,
+            deterministic_ids: false,
+            instance_link_template: None,
+            instance_hit_boxes: false,
+            group_instances: false,
+            strict_text: false,
+            labels_autosize: false,
+            split_wires_at_pins: false,
+            mark_open_ends: false,
+            avoid_label_overlap: false,
+            label_max_displacement: DEFAULT_LABEL_MAX_DISPLACEMENT,
+            pin_symbols: IndexMap::new(),
+            layer_legend: false,
+            timestamp: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Options from a JSON document, missing fields keep their defaults and
+    /// unknown fields are ignored with a warning
+    pub fn from_json(content: &str) -> DrawcktResult<Self> {
+        let deserializer = &mut serde_json::Deserializer::from_str(content);
+        let options = serde_ignored::deserialize(deserializer, warn_unknown)?;
+        Ok(options)
+    }
+
+    /// Options from a TOML document, see [`Self::from_json`]
+    pub fn from_toml(content: &str) -> DrawcktResult<Self> {
+        let deserializer = toml::Deserializer::new(content);
+        let options = serde_ignored::deserialize(deserializer, warn_unknown)?;
+        Ok(options)
+    }
+
+    /// Options from a `.toml` file, any other extension is read as JSON
+    #[cfg(feature = "std-fs")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> DrawcktResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            Self::from_toml(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+
+    /// Run `f` with the [`Self::float_precision`] and [`Self::timestamp`] of
+    /// the files written on this thread, the previous settings are restored
+    /// afterwards so they do not leak into the next render
+    pub fn scoped<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _restore = ThreadOutput::save();
+        if let Some(decimals) = self.float_precision {
+            drawrs::xml_base::set_float_precision(decimals);
+        }
+        if self.timestamp.is_some() {
+            drawrs::file::set_fixed_timestamp(self.timestamp.clone());
+        }
+        f()
+    }
+
+    /// Usage of the command line flags of [`Self::apply_flag`]
+    pub const FLAGS_USAGE: [&str; 23] = [
+        "  --options <file>: Render options from a JSON or .toml file",
+        "  --id-scheme <index|content>: Ids of the objects inside the symbols",
+        "  --flatten-instances: Emit the instance objects as plain cells",
+        "  --cell-tags: Tag the instance objects with their cell name",
+        "  --snap-grid <pixels>: Round the coordinates to a grid",
+        "  --float-precision <decimals>: Maximum number of decimals in the output",
+        "  --allow-stale-symbols: Warn instead of failing on outdated symbol files",
+        "  --connection-points: Let new wires snap to the instance terminals in draw.io",
+        "  --simplify-epsilon <units>: Tolerance of the collinear points dropped from the lines",
+        "  --orthogonalize <units>: Straighten the wire segments this close to an axis",
+        "  --deterministic-ids: Number the anonymous objects instead of random ids",
+        "  --instance-link <template>: Link the instance shapes, e.g. https://pdk.example.com/{lib}/{cell}",
+        "  --instance-hit-boxes: Cover each instance with one box to click or select",
+        "  --group-instances: Wrap each instance in a draw.io group to move it as a unit",
+        "  --strict-text: Fail on texts with characters not allowed in XML instead of dropping them",
+        "  --labels-autosize: Let draw.io size the label boxes from their text",
+        "  --split-wires-at-pins: End the merged wires at every instance terminal and pin",
+        "  --mark-open-ends: Mark the dangling wire ends with a small square",
+        "  --avoid-label-overlap: Nudge overlapping pin names and net labels apart",
+        "  --label-max-displacement <units>: Largest distance a label is nudged by",
+        "  --pin-symbols <symbols>: Symbols at the top-level pins, e.g. input=basic/ipin,output=basic/opin",
+        "  --layer-legend: Add a legend of the enabled layers to the schematic page",
+        "  --timestamp <time>: Fixed modified time of the output, e.g. 2024-01-01T00:00:00",
+    ];
+
+    /// Whether the command line flag is followed by a value
+    pub fn flag_takes_value(flag: &str) -> bool {
+        matches!(
+            flag,
+            "--id-scheme"
+                | "--snap-grid"
+                | "--float-precision"
+                | "--simplify-epsilon"
+                | "--orthogonalize"
+                | "--label-max-displacement"
+                | "--pin-symbols"
+                | "--instance-link"
+                | "--timestamp"
+        )
+    }
+
+    /// Override an option from a command line flag, e.g. `--snap-grid 10`
+    pub fn apply_flag(&mut self, flag: &str, value: Option<&str>) -> DrawcktResult<()> {
+        let invalid = || DrawcktError::InvalidOption {
+            option: flag.to_string(),
+            value: value.unwrap_or_default().to_string(),
+        };
+        match flag {
+            "--flatten-instances" => self.flatten_instances = true,
+            "--cell-tags" => self.cell_tags = true,
+            "--allow-stale-symbols" => self.allow_stale_symbols = true,
+            "--connection-points" => self.connection_points = true,
+            "--deterministic-ids" => self.deterministic_ids = true,
+            "--instance-hit-boxes" => self.instance_hit_boxes = true,
+            "--group-instances" => self.group_instances = true,
+            "--strict-text" => self.strict_text = true,
+            "--labels-autosize" => self.labels_autosize = true,
+            "--split-wires-at-pins" => self.split_wires_at_pins = true,
+            "--mark-open-ends" => self.mark_open_ends = true,
+            "--avoid-label-overlap" => self.avoid_label_overlap = true,
+            "--layer-legend" => self.layer_legend = true,
+            "--timestamp" => self.timestamp = Some(value.ok_or_else(invalid)?.to_string()),
+            "--instance-link" => {
+                self.instance_link_template = Some(value.ok_or_else(invalid)?.to_string())
+            }
+            "--id-scheme" => {
+                self.id_scheme = match value {
+                    Some("index") => IdScheme::Index,
+                    Some("content") => IdScheme::Content,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--snap-grid" => {
+                self.snap_grid = Some(value.and_then(|v| v.parse().ok()).ok_or_else(invalid)?)
+            }
+            "--simplify-epsilon" => {
+                self.simplify_epsilon =
+                    Some(value.and_then(|v| v.parse().ok()).ok_or_else(invalid)?)
+            }
+            "--orthogonalize" => {
+                self.orthogonalize_tolerance =
+                    Some(value.and_then(|v| v.parse().ok()).ok_or_else(invalid)?)
+            }
+            "--label-max-displacement" => {
+                self.label_max_displacement =
+                    value.and_then(|v| v.parse().ok()).ok_or_else(invalid)?
+            }
+            "--pin-symbols" => {
+                for symbol in value.ok_or_else(invalid)?.split(',') {
+                    let (direction, id) = symbol.split_once('=').ok_or_else(invalid)?;
+                    let id = id.parse().map_err(|_| invalid())?;
+                    self.pin_symbols.insert(direction.to_string(), id);
+                }
+            }
+            "--float-precision" => {
+                self.float_precision = Some(value.and_then(|v| v.parse().ok()).ok_or_else(invalid)?)
+            }
+            _ => return Err(invalid()),
+        }
+        Ok(())
+    }
+}
+
+// The output settings of the thread, restored when dropped
+struct ThreadOutput {
+    float_precision: usize,
+    timestamp: Option<String>,
+}
+
+impl ThreadOutput {
+    fn save() -> Self {
+        Self {
+            float_precision: drawrs::xml_base::float_precision(),
+            timestamp: drawrs::file::fixed_timestamp(),
+        }
+    }
+}
+
+impl Drop for ThreadOutput {
+    fn drop(&mut self) {
+        drawrs::xml_base::set_float_precision(self.float_precision);
+        drawrs::file::set_fixed_timestamp(self.timestamp.take());
+    }
+}
+
+fn warn_unknown(path: serde_ignored::Path) {
+    warn!("unknown render option {path} is ignored");
+}
+
+impl<'a> Renderer<'a> {
+    /// Apply all the renderer knobs of `options`
+    pub fn with_options(self, options: &RenderOptions) -> Self {
+        self.with_id_scheme(options.id_scheme)
+            .with_flatten_instances(options.flatten_instances)
+            .with_cell_tags(options.cell_tags)
+            .with_snap_grid(options.snap_grid)
+            .with_fallback_layer(options.fallback_layer.clone())
+            .with_allow_stale_symbols(options.allow_stale_symbols)
+            .with_connection_points(options.connection_points)
+            .with_simplify_epsilon(options.simplify_epsilon)
+            .with_orthogonalize_tolerance(options.orthogonalize_tolerance)
+            .with_deterministic_ids(options.deterministic_ids)
+            .with_instance_link_template(options.instance_link_template.clone())
+            .with_instance_hit_boxes(options.instance_hit_boxes)
+            .with_group_instances(options.group_instances)
+            .with_strict_text(options.strict_text)
+            .with_labels_autosize(options.labels_autosize)
+            .with_split_wires_at_pins(options.split_wires_at_pins)
+            .with_mark_open_ends(options.mark_open_ends)
+            .with_avoid_label_overlap(options.avoid_label_overlap)
+            .with_label_max_displacement(options.label_max_displacement)
+            .with_pin_symbols(options.pin_symbols.clone())
+            .with_layer_legend(options.layer_legend)
+    }
+}
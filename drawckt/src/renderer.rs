@@ -1,38 +1,196 @@
+use crate::crossref::{CrossRefStyle, CrossRefs, is_connector};
+use crate::diff::SchematicDiff;
 use crate::error::{DrawcktError, DrawcktResult};
+use crate::geometry::{Point, merge_polylines, merge_polylines_with_breakpoints};
+use crate::netlist::snapped;
+use crate::report::{CellSource, RenderReport, SOURCE_ATTRIBUTE};
 use crate::schematic::*;
 use drawrs::FillStyle;
 use drawrs::diagram::text_format::{Justify, JustifyX, JustifyY};
-use drawrs::xml_base::XMLBase;
+use drawrs::utils::Stencil;
+use drawrs::xml_base::{XMLBase, disambiguate_id, sanitize_id, sanitize_tag};
+use drawrs::xml_parser::attr_value;
 use drawrs::{
-    BoundingBox, DiagramObject, DrawFile, Edge, GroupTransform, Object, Page, parse_xml_to_object,
+    BoundingBox, CellBuilder, DiagramObject, DrawFile, Edge, GroupTransform, Object, Orient, Page,
+    RawXml, StyleClass, Transform2D,
 };
 use indexmap::{IndexMap, IndexSet};
-use log::info;
+use log::{debug, warn};
 use ordered_float::OrderedFloat;
 use quick_xml::Reader;
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
-use std::fs;
-use std::path::Path;
 
-// Scale factor to convert from schematic units to Draw.io pixels
-const SCALE: f64 = 200.0;
+/// Default tolerance of [`Renderer::with_simplify_epsilon`], in user units
+pub const DEFAULT_SIMPLIFY_EPSILON: f64 = 1e-6;
+/// Default of [`Renderer::with_label_max_displacement`], in user units
+pub const DEFAULT_LABEL_MAX_DISPLACEMENT: f64 = 0.5;
+/// Layer of the click targets of [`Renderer::with_instance_hit_boxes`]
+pub const INSTANCE_HIT_LAYER: &str = "layer-instance-hit";
+// Text height of the instance parameters, in user units
+const PARAM_TEXT_HEIGHT: f64 = 0.0625;
+// Size of the open wire end marks before `wire_intersection_scale`, in user units
+const OPEN_END_SIZE: f64 = 0.0625;
+// Highlight boxes of `render_diff`, padded around the changed items
+const DIFF_LAYER_ID: &str = "layer-diff";
+const DIFF_PADDING: f64 = 5.0;
+const DIFF_OPACITY: i32 = 30;
+const DIFF_ADDED_COLOR: &str = "#00CC00";
+const DIFF_REMOVED_COLOR: &str = "#FF0000";
+const DIFF_MOVED_COLOR: &str = "#FF9900";
+// Difference in pixels between the box of a symbol page and the one of its
+// shapes above which the page is reported, the grid snapping comes on top
+const SYMBOL_BOX_TOLERANCE: f64 = 1.0;
+// Padding in pixels between a label and its box, see `LayerStyle::label_background`
+const LABEL_BOX_PADDING: f64 = 4.0;
+
+// Estimated width of a (multi-line) text, half the font height per character
+pub(crate) fn text_width(font_height: f64, text: &str) -> f64 {
+    let chars = text.lines().map(|line| line.chars().count()).max();
+    font_height * chars.unwrap_or(0) as f64 / 2.0
+}
+
+/// Lines of a label, soft-wrapped at word boundaries after `max_chars`
+/// characters. Words longer than a line are kept whole.
+fn wrap_lines(text: &str, max_chars: Option<usize>) -> Vec<String> {
+    let Some(max_chars) = max_chars else {
+        return text.lines().map(str::to_string).collect();
+    };
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_chars
+            {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+/// Ring with its points in clockwise (y down) or counterclockwise order
+fn wound(mut ring: Vec<[f64; 2]>, clockwise: bool) -> Vec<[f64; 2]> {
+    let area: f64 = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum();
+    if (area > 0.0) != clockwise {
+        ring.reverse();
+    }
+    ring
+}
+
+/// Bounding box with the corners reordered to `[min, max]`
+fn normalize_b_box(b_box: &[[OrderedFloat<f64>; 2]; 2]) -> [[OrderedFloat<f64>; 2]; 2] {
+    let [[x0, y0], [x1, y1]] = *b_box;
+    [[x0.min(x1), y0.min(y1)], [x0.max(x1), y0.max(y1)]]
+}
+
+/// Box of an object, or of the points of an edge
+fn extent(obj: &DiagramObject) -> Option<BoundingBox> {
+    match obj {
+        DiagramObject::Edge(edge) => {
+            let geometry = edge.geometry_ref();
+            let points = geometry
+                .source_point()
+                .into_iter()
+                .chain(geometry.target_point())
+                .chain(geometry.intermediate_points().iter().copied());
+            BoundingBox::union(points.map(|[x, y]| BoundingBox::new(x, y, 0.0, 0.0)))
+        }
+        _ => obj.bounding_box(),
+    }
+}
+
+// Corner and size of a box for the messages
+fn box_str(bbox: &BoundingBox) -> String {
+    format!(
+        "({}, {}) {}x{}",
+        bbox.min_x(),
+        bbox.min_y(),
+        bbox.width(),
+        bbox.height()
+    )
+}
 
 // Structure to hold parsed symbol page data
 #[derive(Debug, Clone)]
 pub struct SymbolPageData {
-    objects: Vec<drawrs::page::DiagramObject>, // Parsed drawrs objects (Object or Edge)
+    pub(crate) objects: Vec<drawrs::page::DiagramObject>, // Parsed drawrs objects (Object or Edge)
     origin_bounding_box: BoundingBox,
+    // `styleHash` and `symbolHash` of the generated symbol pages
+    style_hash: Option<String>,
+    symbol_hash: Option<String>,
+    // Id of the `<diagram>` and its layer cells, kept apart from the objects
+    page_id: Option<String>,
+    layer_cells: Vec<XMLBase>,
 }
 
 impl LayerStyle {
+    // Text fields of the labels: the text color, font family and opacity,
+    // never the stroke or fill. `update_label` changes these fields one by
+    // one, only when the old and new styles differ, keep both in step.
+    fn set_text_format(&self, object: &mut Object, styles: &LayerStyles) -> DrawcktResult<()> {
+        object.set_font_color(Some(self.text_color.clone().into_owned()));
+        object.set_font_family(Some(styles.css_family(&self.font_family).to_string()))?;
+        object.set_opacity(self.opacity);
+        Ok(())
+    }
+
+    // Box of the text labels: the layer colors or none, rounded when drawn
+    fn set_label_box(&self, object: &mut Object) {
+        let color =
+            |color: &Option<Cow<'static, str>>| color.as_deref().unwrap_or("none").to_string();
+        object.set_fill_color(Some(color(&self.label_background)));
+        object.set_stroke_color(Some(color(&self.label_border)));
+        object.set_rounded(self.has_label_box().then_some(true));
+    }
+
+    fn label_padding(&self) -> f64 {
+        if self.has_label_box() {
+            LABEL_BOX_PADDING
+        } else {
+            0.0
+        }
+    }
+
+    // Whether the object is drawn with the box of the layer labels
+    fn is_label_box(&self, object: &Object) -> bool {
+        let same = |color: Option<&str>, expected: &Option<Cow<'static, str>>| {
+            color.is_some_and(|c| c.eq_ignore_ascii_case(expected.as_deref().unwrap_or("none")))
+        };
+        same(
+            object.fill_color().map(String::as_str),
+            &self.label_background,
+        ) && same(
+            object.stroke_color().map(String::as_str),
+            &self.label_border,
+        )
+    }
+
     fn update_label(
         obj: &mut DiagramObject,
-        old_style: &Self,
-        new_style: &Self,
+        old_styles: &LayerStyles,
+        new_styles: &LayerStyles,
+        layer: &Layer,
     ) -> DrawcktResult<()> {
+        let old_style = old_styles.layer_style(layer);
+        let new_style = new_styles.layer_style(layer);
         if let Some(object) = obj.as_object_mut() {
+            // Pure text labels, bare or in the box of the layer, were sized
+            // from their text length
+            let bare = object.fill_color().is_some_and(|c| c == "none")
+                && object.stroke_color().is_some_and(|c| c == "none");
+            let in_box = old_style.has_label_box() && old_style.is_label_box(object);
+            let old_padding = if in_box { LABEL_BOX_PADDING } else { 0.0 };
             // Update font color
             if old_style.text_color != new_style.text_color {
                 object.set_font_color(Some(new_style.text_color.clone().into_owned()));
@@ -41,27 +199,100 @@ impl LayerStyle {
             // Update font size based on font_zoom ratio
             if let Some(current_font_size) = object.font_size() {
                 if old_style.font_zoom != new_style.font_zoom && old_style.font_zoom > 0.0 {
-                    let new_font_size =
-                        current_font_size * (new_style.font_zoom / old_style.font_zoom);
+                    let new_font_size = new_styles.clamp_font_size(
+                        current_font_size * (new_style.font_zoom / old_style.font_zoom),
+                        object.value().map_or("", String::as_str),
+                        layer,
+                    );
                     object.set_font_size(Some(new_font_size));
 
-                    // Update width proportionally if it was calculated from text length
-                    if let Some(text) = object.value() {
+                    // Resize pure text labels, whose box was calculated from the
+                    // text length, other captioned objects keep their geometry
+                    // and draw.io fits the autosize ones
+                    let is_text = (bare || in_box) && !object.autosize();
+                    if is_text && let Some(text) = object.value() {
                         let font_height = new_font_size;
-                        let font_width = font_height * text.len() as f64 / 2.0;
-                        object.set_width(font_width);
-                        object.set_height(font_height);
+                        let text = text.replace("<br>", "\n");
+                        let font_width = text_width(font_height, &text);
+                        let lines = text.lines().count().max(1);
+                        object.set_width(font_width + 2.0 * old_padding);
+                        object.set_height(font_height * lines as f64 + 2.0 * old_padding);
                     }
                 }
             }
 
             // Update font family
-            if old_style.font_family != new_style.font_family {
-                object.set_font_family(Some(new_style.font_family.clone().into_owned()));
+            let new_family = new_styles.css_family(&new_style.font_family);
+            if old_styles.css_family(&old_style.font_family) != new_family {
+                object.set_font_family(Some(new_family.to_string()))?;
+            }
+
+            if old_style.opacity != new_style.opacity {
+                object.set_opacity(new_style.opacity);
+            }
+
+            // Text shapes, the labels with a spacing, get or lose the box
+            // around their text, which keeps its place
+            let mut padding = old_padding;
+            if (bare || in_box)
+                && object.style_property("spacing").is_some()
+                && (old_style.label_background != new_style.label_background
+                    || old_style.label_border != new_style.label_border)
+            {
+                padding = new_style.label_padding();
+                let delta = padding - old_padding;
+                let [x, y] = object.position();
+                object.set_position([x - delta, y - delta]);
+                object.set_width(object.width() + 2.0 * delta);
+                object.set_height(object.height() + 2.0 * delta);
+                new_style.set_label_box(object);
+            }
+            if (old_style.label_spacing != new_style.label_spacing || padding != old_padding)
+                && object.style_property("spacing").is_some()
+            {
+                object.set_spacing(new_style.label_spacing + padding);
+            }
+            // The baseline adjustment of bottom-justified labels follows the font height
+            if object.style_property("spacingBottom").is_some()
+                && let Some(font_size) = object.font_size()
+            {
+                object.set_spacing_bottom(new_style.label_baseline_adjust * font_size);
+            }
+        } else if let Some(edge) = obj.as_edge_mut() {
+            // Edge labels only carry the text format
+            if old_style.text_color != new_style.text_color {
+                edge.set_font_color(Some(new_style.text_color.clone().into_owned()));
+            }
+            if let Some(current_font_size) = edge.font_size()
+                && old_style.font_zoom != new_style.font_zoom
+                && old_style.font_zoom > 0.0
+            {
+                let new_font_size = new_styles.clamp_font_size(
+                    current_font_size * (new_style.font_zoom / old_style.font_zoom),
+                    edge.label().map_or("", String::as_str),
+                    layer,
+                );
+                edge.set_font_size(Some(new_font_size));
+            }
+            let new_family = new_styles.css_family(&new_style.font_family);
+            if old_styles.css_family(&old_style.font_family) != new_family {
+                edge.set_font_family(Some(new_family.to_string()))?;
             }
         }
         Ok(())
     }
+    // Turn an outlined intersection dot into a filled one, the open wire end
+    // marks on the same layer are left alone
+    fn fill_intersection(obj: &mut DiagramObject, style: &Self) {
+        if let Some(object) = obj.as_object_mut()
+            && object.style_property("shape") == Some("ellipse")
+        {
+            object.set_stroke_color(Some("none".to_string()));
+            object.set_fill_color(Some(style.fill_color().to_string()));
+            object.set_fill_style(None);
+        }
+    }
+
     fn update_shape(
         obj: &mut DiagramObject,
         old_style: &Self,
@@ -75,6 +306,16 @@ impl LayerStyle {
                 if old_style.stroke_width != new_style.stroke_width {
                     edge.set_stroke_width(Some(new_style.stroke_width));
                 }
+                if old_style.opacity != new_style.opacity {
+                    edge.set_opacity(new_style.opacity);
+                }
+                if old_style.line_join != new_style.line_join {
+                    edge.set_line_join(new_style.line_join);
+                }
+                if old_style.line_cap != new_style.line_cap {
+                    edge.set_start_cap(new_style.line_cap);
+                    edge.set_end_cap(new_style.line_cap);
+                }
             }
             DiagramObject::Object(object) => {
                 if let Some(color) = object.stroke_color()
@@ -83,14 +324,22 @@ impl LayerStyle {
                     object.set_stroke_width(Some(new_style.stroke_width));
                     object.set_stroke_color(Some(new_style.stroke_color.clone().into_owned()));
                 }
+                // Filled shapes are rendered with the layer fill color, a
+                // different fill was chosen by hand and is kept
                 if let Some(color) = object.fill_color()
-                    && color != "none"
+                    && color.eq_ignore_ascii_case(old_style.fill_color())
                 {
-                    object.set_fill_color(Some(new_style.stroke_color.clone().into_owned()));
+                    object.set_fill_color(Some(new_style.fill_color().to_string()));
+                }
+                if old_style.opacity != new_style.opacity {
+                    object.set_opacity(new_style.opacity);
+                }
+                if old_style.line_join != new_style.line_join {
+                    object.set_line_join(new_style.line_join);
                 }
             }
-            DiagramObject::XmlBase(_) => {
-                // XmlBase objects don't need style updates
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => {
+                // XmlBase objects and raw XML don't need style updates
             }
         }
         Ok(())
@@ -98,75 +347,67 @@ impl LayerStyle {
 }
 
 impl SymbolPageData {
+    fn new(
+        objects: Vec<DiagramObject>,
+        style_hash: Option<String>,
+        symbol_hash: Option<String>,
+        page_id: Option<String>,
+        layer_cells: Vec<XMLBase>,
+    ) -> Self {
+        let origin_bounding_box = BoundingBox::union(objects.iter().filter_map(extent))
+            .unwrap_or_else(|| BoundingBox::new(0.0, 0.0, 0.0, 0.0));
+        Self {
+            objects,
+            origin_bounding_box,
+            style_hash,
+            symbol_hash,
+            page_id,
+            layer_cells,
+        }
+    }
+
+    pub fn objects(&self) -> &[DiagramObject] {
+        &self.objects
+    }
+
     pub fn update_style(
         self,
         old_style: &LayerStyles,
         new_style: &LayerStyles,
     ) -> impl Iterator<Item = DrawcktResult<Option<DiagramObject>>> {
         self.objects.into_iter().map(|mut obj| {
-            match obj.xml_parent() {
-                Some("layer-instance-label") => {
-                    LayerStyle::update_label(&mut obj, &old_style.instance, &new_style.instance)?
-                }
-                Some("layer-instance-shape") => {
-                    LayerStyle::update_shape(&mut obj, &old_style.instance, &new_style.instance)?
-                }
-                Some("layer-annotate-label") => {
-                    LayerStyle::update_label(&mut obj, &old_style.annotate, &new_style.annotate)?
-                }
-                Some("layer-annotate-shape") => {
-                    LayerStyle::update_shape(&mut obj, &old_style.annotate, &new_style.annotate)?
-                }
-                Some("layer-pin-label") => {
-                    LayerStyle::update_label(&mut obj, &old_style.pin, &new_style.pin)?
-                }
-                Some("layer-pin-shape") => {
-                    LayerStyle::update_shape(&mut obj, &old_style.pin, &new_style.pin)?
-                }
-                Some("layer-device-label") => {
-                    LayerStyle::update_label(&mut obj, &old_style.device, &new_style.device)?
-                }
-                Some("layer-device-shape") => {
-                    LayerStyle::update_shape(&mut obj, &old_style.device, &new_style.device)?
-                }
-                Some("layer-wire-label") => {
-                    LayerStyle::update_label(&mut obj, &old_style.wire, &new_style.wire)?
-                }
-                Some("layer-wire-shape") => {
-                    LayerStyle::update_shape(&mut obj, &old_style.wire, &new_style.wire)?
+            // Objects of grouped instances keep their layer cell as a tag
+            let layer = obj
+                .xml_parent()
+                .and_then(Layer::parse_id)
+                .or_else(|| obj.tags().find_map(Layer::parse_id));
+            match layer {
+                Some((layer, LayerCell::Label)) => {
+                    LayerStyle::update_label(&mut obj, old_style, new_style, &layer)?
                 }
-                Some("layer-wire-intersection") => {
+                Some((layer, LayerCell::Shape)) => LayerStyle::update_shape(
+                    &mut obj,
+                    old_style.layer_style(&layer),
+                    new_style.layer_style(&layer),
+                )?,
+                Some((layer, LayerCell::Intersection)) => {
                     // update bounding box based on wire_intersection_scale change
                     if let Some((bbox, _)) = obj.mut_box() {
                         let old_scale = old_style.wire_intersection_scale;
                         let new_scale = new_style.wire_intersection_scale;
 
                         if (old_scale - new_scale).abs() > f64::EPSILON && old_scale > 0.0 {
-                            // Calculate center point
-                            let center_x = bbox.min_x + bbox.width / 2.0;
-                            let center_y = bbox.min_y + bbox.height / 2.0;
-
-                            // Calculate relative scale factor
-                            let scale_ratio = new_scale / old_scale;
-
-                            // Scale width and height
-                            let new_width = bbox.width * scale_ratio;
-                            let new_height = bbox.height * scale_ratio;
-
-                            // Update bounding box while keeping center point unchanged
-                            bbox.min_x = center_x - new_width / 2.0;
-                            bbox.min_y = center_y - new_height / 2.0;
-                            bbox.width = new_width;
-                            bbox.height = new_height;
+                            bbox.scale_about_center(new_scale / old_scale);
                         }
                     }
-                    LayerStyle::update_shape(&mut obj, &old_style.wire, &new_style.wire)?
-                }
-                Some("layer-text-label") => {
-                    LayerStyle::update_label(&mut obj, &old_style.text, &new_style.text)?
-                }
-                Some("layer-text-shape") => {
-                    LayerStyle::update_shape(&mut obj, &old_style.text, &new_style.text)?
+                    if new_style.wire_intersection_filled && !old_style.wire_intersection_filled {
+                        LayerStyle::fill_intersection(&mut obj, new_style.intersection_style());
+                    }
+                    LayerStyle::update_shape(
+                        &mut obj,
+                        old_style.shape_style(&layer, true),
+                        new_style.shape_style(&layer, true),
+                    )?
                 }
                 _ => {}
             }
@@ -177,9 +418,181 @@ impl SymbolPageData {
 
 pub struct SymbolContexts<'a>(pub IndexMap<DesignId<'a>, Cow<'a, str>>);
 
+// A `<diagram>` element: its decoded attributes and the source range after the start tag
+struct DiagramSpan {
+    attributes: Vec<(String, String)>,
+    body: std::ops::Range<usize>,
+}
+
+fn diagram_spans(content: &str) -> DrawcktResult<Vec<DiagramSpan>> {
+    let mut reader = Reader::from_str(content);
+    let mut buf = Vec::new();
+    let mut spans = Vec::new();
+    let mut current: Option<(Vec<(String, String)>, usize)> = None;
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) if e.name().as_ref() == b"diagram" => {
+                let attributes = e
+                    .attributes()
+                    .flatten()
+                    .map(|attr| {
+                        (
+                            String::from_utf8_lossy(attr.key.as_ref()).to_string(),
+                            attr_value(&attr),
+                        )
+                    })
+                    .collect();
+                current = Some((attributes, reader.buffer_position()));
+            }
+            Event::End(e) if e.name().as_ref() == b"diagram" => {
+                if let Some((attributes, start)) = current.take() {
+                    spans.push(DiagramSpan {
+                        attributes,
+                        body: start..reader.buffer_position(),
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(spans)
+}
+
+// `modified` attribute of the `<mxfile>` element
+fn file_modified(content: &str) -> Option<String> {
+    let mut reader = Reader::from_str(content);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).ok()? {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"mxfile" => {
+                return e
+                    .attributes()
+                    .flatten()
+                    .find(|attr| attr.key.as_ref() == b"modified")
+                    .map(|attr| attr_value(&attr));
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+// XML of the update of `content`: an update that changes nothing keeps the
+// `modified` attribute, so the file is left byte for byte as is
+fn updated_xml(content: &str, mut file: DrawFile) -> DrawcktResult<String> {
+    if file.timestamp.is_none() {
+        file.timestamp = file_modified(content);
+        let xml = file.xml()?.to_string();
+        if xml == content {
+            return Ok(xml);
+        }
+        file.timestamp = None;
+    }
+    Ok(file.xml()?.to_string())
+}
+
+// Rewrite the `visible` attribute of the layer cells `id` on every page, the
+// rest of the file is kept byte for byte
+fn set_layer_cell_visible(content: &str, id: &str, visible: bool) -> DrawcktResult<String> {
+    let mut reader = Reader::from_str(content);
+    let mut buf = Vec::new();
+    let mut tags = Vec::new();
+    loop {
+        let start = reader.buffer_position();
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e)
+                if e.name().as_ref() == b"mxCell"
+                    && e.attributes()
+                        .flatten()
+                        .any(|attr| attr.key.as_ref() == b"id" && attr_value(&attr) == id) =>
+            {
+                tags.push(start..reader.buffer_position());
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    if tags.is_empty() {
+        return Err(DrawcktError::LayerCellNotFound(id.to_string()));
+    }
+    let value = if visible { "1" } else { "0" };
+    let mut rewritten = String::with_capacity(content.len());
+    let mut last = 0;
+    for tag in tags {
+        rewritten.push_str(&content[last..tag.start]);
+        let tag_str = &content[tag.clone()];
+        if let Some(pos) = tag_str.find(r#" visible=""#) {
+            let value_start = pos + r#" visible=""#.len();
+            let value_end = value_start + tag_str[value_start..].find('"').unwrap_or(0);
+            rewritten.push_str(&tag_str[..value_start]);
+            rewritten.push_str(value);
+            rewritten.push_str(&tag_str[value_end..]);
+        } else if visible {
+            // Layers without the attribute are visible
+            rewritten.push_str(tag_str);
+        } else {
+            let close = tag_str.len() - if tag_str.ends_with("/>") { 2 } else { 1 };
+            rewritten.push_str(&tag_str[..close]);
+            rewritten.push_str(r#" visible="0""#);
+            rewritten.push_str(&tag_str[close..]);
+        }
+        last = tag.end;
+    }
+    rewritten.push_str(&content[last..]);
+    Ok(rewritten)
+}
+
+// Start tag of a diagram with the name replaced, attribute values are escaped again
+fn diagram_start_tag(attributes: &[(String, String)], name: &str) -> String {
+    let mut tag = format!(r#"<diagram name="{}""#, XMLBase::xml_ify(name));
+    for (key, value) in attributes {
+        if key != "name" {
+            tag.push_str(&format!(r#" {key}="{}""#, XMLBase::xml_ify(value)));
+        }
+    }
+    tag.push('>');
+    tag
+}
+
+fn mxfile(diagrams: &[String]) -> String {
+    let file = DrawFile::new();
+    let mut content = format!(
+        r#"<mxfile host="{}" modified="{}" agent="{}" version="{}" pages="{}">"#,
+        file.host,
+        file.modified(),
+        file.agent(),
+        file.version,
+        diagrams.len()
+    );
+    for diagram in diagrams {
+        content.push_str("\n  ");
+        content.push_str(diagram);
+    }
+    content.push_str("\n</mxfile>");
+    content
+}
+
 impl<'a> SymbolContexts<'a> {
+    /// No symbols, see [`Self::insert`]
+    pub fn new() -> Self {
+        Self(IndexMap::new())
+    }
+
+    /// Add the drawio `content` of the symbol `id`, e.g. a file the user
+    /// uploaded, replacing any previous one
+    pub fn insert(&mut self, id: DesignId<'a>, content: impl Into<Cow<'a, str>>) {
+        self.0.insert(id, content.into());
+    }
+
     /// Write all symbols to directory structure: {dir}/{lib}/{cell}.drawio
-    pub fn write_to_dir(&self, dir: impl AsRef<Path>) -> DrawcktResult<()> {
+    #[cfg(feature = "std-fs")]
+    pub fn write_to_dir(&self, dir: impl AsRef<std::path::Path>) -> DrawcktResult<()> {
+        use std::fs;
+
         let output_path = dir.as_ref();
         fs::create_dir_all(output_path)?;
 
@@ -188,14 +601,56 @@ impl<'a> SymbolContexts<'a> {
             fs::create_dir_all(&lib_dir)?;
             let cell_file = lib_dir.join(format!("{}.drawio", symbol_id.cell));
             fs::write(&cell_file, content.as_ref())?;
-            info!("Symbol rendered to: {:?}", cell_file);
+            log::info!("Symbol rendered to: {:?}", cell_file);
         }
 
         Ok(())
     }
 
+    /// Combine all symbols into one multi-page document, one page per `{lib}/{cell}`
+    pub fn to_single_file(&self) -> DrawcktResult<String> {
+        let mut diagrams = Vec::new();
+        for (symbol_id, content) in &self.0 {
+            let mut spans = diagram_spans(content).map_err(|e| e.in_symbol(symbol_id))?;
+            if spans.len() != 1 {
+                return Err(DrawcktError::NoPage.in_symbol(symbol_id));
+            }
+            let span = spans.remove(0);
+            diagrams.push(format!(
+                "{}{}",
+                diagram_start_tag(&span.attributes, &symbol_id.to_string()),
+                &content[span.body]
+            ));
+        }
+        Ok(mxfile(&diagrams))
+    }
+
+    /// Split a multi-page document written by [`Self::to_single_file`] into one
+    /// context per symbol, the page names must be `{lib}/{cell}`
+    pub fn from_single_file(content: &str) -> DrawcktResult<SymbolContexts<'static>> {
+        let mut contexts = IndexMap::new();
+        for span in diagram_spans(content)? {
+            let name = span
+                .attributes
+                .iter()
+                .find(|(key, _)| key == "name")
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            let symbol_id: DesignId<'static> = name.parse()?;
+            let diagram = format!(
+                "{}{}",
+                diagram_start_tag(&span.attributes, &name),
+                &content[span.body]
+            );
+            contexts.insert(symbol_id, mxfile(&[diagram]).into());
+        }
+        Ok(SymbolContexts(contexts))
+    }
+
     /// Load symbols from directory structure: {dir}/{lib}/{cell}.drawio
-    pub fn load_from_dir(dir: impl AsRef<Path>) -> DrawcktResult<Self> {
+    #[cfg(feature = "std-fs")]
+    pub fn load_from_dir(dir: impl AsRef<std::path::Path>) -> DrawcktResult<Self> {
+        use std::fs;
         let symbols_path = dir.as_ref();
         let mut symbol_contexts = IndexMap::new();
 
@@ -232,10 +687,8 @@ impl<'a> SymbolContexts<'a> {
                                 })?;
 
                             let content = fs::read_to_string(&cell_path)?;
-                            let symbol_id = DesignId {
-                                lib: lib_name.to_string().into(),
-                                cell: cell_name.to_string().into(),
-                            };
+                            let symbol_id =
+                                DesignId::new(lib_name.to_string(), cell_name.to_string());
                             symbol_contexts.insert(symbol_id, content.to_string().into());
                         }
                     }
@@ -249,194 +702,820 @@ impl<'a> SymbolContexts<'a> {
             )))
         }
     }
+
+    /// Reload the `{lib}/{cell}.drawio` files among `paths`, e.g. the files
+    /// of a [`Self::load_from_dir`] directory that changed: an existing file
+    /// replaces its symbol, a deleted one removes it, other paths are
+    /// ignored. Returns the number of symbols reloaded or removed.
+    #[cfg(feature = "std-fs")]
+    pub fn reload_changed<P: AsRef<std::path::Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> DrawcktResult<usize> {
+        let mut count = 0;
+        for path in paths {
+            let path = path.as_ref();
+            if path.extension().and_then(|s| s.to_str()) != Some("drawio") {
+                continue;
+            }
+            let lib = path.parent().and_then(|lib| lib.file_name());
+            let cell = path.file_stem();
+            let (Some(lib), Some(cell)) =
+                (lib.and_then(|n| n.to_str()), cell.and_then(|n| n.to_str()))
+            else {
+                continue;
+            };
+            let symbol_id = DesignId::new(lib.to_string(), cell.to_string());
+            if path.is_file() {
+                let content = std::fs::read_to_string(path)?;
+                self.0.insert(symbol_id, content.into());
+                log::info!("Symbol reloaded: {:?}", path);
+            } else if self.0.shift_remove(&symbol_id).is_some() {
+                log::info!("Symbol removed: {:?}", path);
+            } else {
+                continue;
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl Default for SymbolContexts<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// In-memory symbols, e.g. `[(DesignId::new("lib", "cell"), content)]`
+impl<'a, C: Into<Cow<'a, str>>> FromIterator<(DesignId<'a>, C)> for SymbolContexts<'a> {
+    fn from_iter<I: IntoIterator<Item = (DesignId<'a>, C)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(id, content)| (id, content.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Schematic items drawn by [`Renderer::render_overlay`], combine them with
+/// `|`, e.g. `OverlayKinds::WIRES | OverlayKinds::PINS`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OverlayKinds {
+    pub instances: bool,
+    pub wires: bool,
+    pub pins: bool,
+    pub labels: bool,
+    pub shapes: bool,
+}
+
+impl OverlayKinds {
+    pub const NONE: Self = Self {
+        instances: false,
+        wires: false,
+        pins: false,
+        labels: false,
+        shapes: false,
+    };
+    pub const INSTANCES: Self = Self {
+        instances: true,
+        ..Self::NONE
+    };
+    pub const WIRES: Self = Self {
+        wires: true,
+        ..Self::NONE
+    };
+    pub const PINS: Self = Self {
+        pins: true,
+        ..Self::NONE
+    };
+    pub const LABELS: Self = Self {
+        labels: true,
+        ..Self::NONE
+    };
+    pub const SHAPES: Self = Self {
+        shapes: true,
+        ..Self::NONE
+    };
+    pub const ALL: Self = Self {
+        instances: true,
+        wires: true,
+        pins: true,
+        labels: true,
+        shapes: true,
+    };
+}
+
+impl std::ops::BitOr for OverlayKinds {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self {
+            instances: self.instances || other.instances,
+            wires: self.wires || other.wires,
+            pins: self.pins || other.pins,
+            labels: self.labels || other.labels,
+            shapes: self.shapes || other.shapes,
+        }
+    }
 }
 
 pub struct Renderer<'a> {
     schematic: &'a Schematic,
     layer_styles: &'a LayerStyles,
+    id_scheme: IdScheme,
+    flatten_instances: bool,
+    cell_tags: bool,
+    snap_grid: Option<f64>,
+    fallback_layer: Layer,
+    allow_stale_symbols: bool,
+    connection_points: bool,
+    simplify_epsilon: Option<f64>,
+    orthogonalize_tolerance: Option<f64>,
+    deterministic_ids: bool,
+    tag_sanitizer: fn(&str) -> String,
+    instance_link_template: Option<String>,
+    instance_hit_boxes: bool,
+    group_instances: bool,
+    strict_text: bool,
+    labels_autosize: bool,
+    split_wires_at_pins: bool,
+    mark_open_ends: bool,
+    avoid_label_overlap: bool,
+    label_max_displacement: f64,
+    pin_symbols: IndexMap<String, DesignId<'static>>,
+    layer_legend: bool,
+    layer_style_issues: Vec<LayerStyleIssue>,
 }
 
 impl<'a> Renderer<'a> {
+    /// The layer styles are validated here, see [`LayerStyles::validate`]:
+    /// every render fails with their issues until they are fixed
     pub fn new(schematic: &'a Schematic, layer_styles: &'a LayerStyles) -> Self {
+        let layer_style_issues = layer_styles.validate().err().unwrap_or_default();
+        for issue in &layer_style_issues {
+            warn!("{issue}");
+        }
         Self {
             schematic,
             layer_styles,
+            id_scheme: IdScheme::default(),
+            flatten_instances: false,
+            cell_tags: false,
+            snap_grid: None,
+            fallback_layer: Layer::Annotate,
+            allow_stale_symbols: false,
+            connection_points: false,
+            simplify_epsilon: Some(DEFAULT_SIMPLIFY_EPSILON),
+            orthogonalize_tolerance: None,
+            deterministic_ids: false,
+            tag_sanitizer: sanitize_tag,
+            instance_link_template: None,
+            instance_hit_boxes: false,
+            group_instances: false,
+            strict_text: false,
+            labels_autosize: false,
+            split_wires_at_pins: false,
+            mark_open_ends: false,
+            avoid_label_overlap: false,
+            label_max_displacement: DEFAULT_LABEL_MAX_DISPLACEMENT,
+            pin_symbols: IndexMap::new(),
+            layer_legend: false,
+            layer_style_issues,
         }
     }
 
-    // Generate ID for wire: wire-{net}-{counter} or wire-{uuid}
-    fn gen_wire_id(net: &str, counter: usize) -> String {
-        if !net.is_empty() {
-            let safe_net = net.replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
-            format!("wire-{}-{}", safe_net, counter)
+    // The issues of the layer styles found by `new`
+    fn check_layer_styles(&self) -> DrawcktResult<()> {
+        if self.layer_style_issues.is_empty() {
+            Ok(())
         } else {
-            format!("wire-{}", uuid::Uuid::new_v4().to_string())
+            Err(DrawcktError::InvalidLayerStyles(
+                self.layer_style_issues.clone(),
+            ))
         }
     }
 
-    // Convert wires to HashMap grouped by net, with each wire as a Shape::Line
-    fn wires_to_shapes_by_net(&self) -> HashMap<String, Vec<&Vec<[OrderedFloat<f64>; 2]>>> {
-        let mut shapes_by_net = HashMap::new();
-        for wire in &self.schematic.wires {
-            if wire.points.len() >= 2 {
-                _ = shapes_by_net
-                    .entry(wire.net.clone())
-                    .or_insert_with(Vec::new)
-                    .push(&wire.points);
-            }
-        }
-        shapes_by_net
+    /// Id scheme of the objects inside the symbols
+    pub fn with_id_scheme(mut self, id_scheme: IdScheme) -> Self {
+        self.id_scheme = id_scheme;
+        self
     }
 
-    // Merge lines that share endpoints (same net, same endpoint, only two lines at that point)
-    pub(crate) fn merge_lines(
-        lines: Vec<&Vec<[OrderedFloat<f64>; 2]>>,
-    ) -> Vec<Vec<[OrderedFloat<f64>; 2]>> {
-        if lines.is_empty() {
-            return Vec::new();
-        }
+    /// Emit the instance objects as plain cells, without the instance tag,
+    /// the `lib`/`cell`/`net` data attributes and the `UserObject` wrapper
+    pub fn with_flatten_instances(mut self, flatten_instances: bool) -> Self {
+        self.flatten_instances = flatten_instances;
+        self
+    }
 
-        let mut merged: Vec<Vec<[OrderedFloat<f64>; 2]>> = Vec::new();
-        let mut processed = vec![false; lines.len()];
+    /// Tag the instance objects with the symbol cell name next to the
+    /// instance name, so draw.io can hide all instances of a cell
+    pub fn with_cell_tags(mut self, cell_tags: bool) -> Self {
+        self.cell_tags = cell_tags;
+        self
+    }
 
-        for i in 0..lines.len() {
-            if processed[i] {
-                continue;
-            }
+    /// Turn the instance and cell names into single draw.io tags, by default
+    /// [`sanitize_tag`] replaces the whitespace by `_`
+    pub fn with_tag_sanitizer(mut self, tag_sanitizer: fn(&str) -> String) -> Self {
+        self.tag_sanitizer = tag_sanitizer;
+        self
+    }
 
-            let mut current_line = lines[i].clone();
+    /// Link the device shapes of each instance to e.g. its page in a symbol
+    /// library browser, `{lib}`, `{cell}` and `{name}` of the template are
+    /// replaced by the ones of the instance:
+    /// `https://pdk.example.com/{lib}/{cell}`. The link is emitted on the
+    /// `UserObject` wrapper, also for flattened instances.
+    pub fn with_instance_link_template(mut self, instance_link_template: Option<String>) -> Self {
+        self.instance_link_template = instance_link_template;
+        self
+    }
 
-            if current_line.is_empty() {
-                processed[i] = true;
-                continue;
-            }
+    /// Cover each instance with a nearly transparent box on the
+    /// [`INSTANCE_HIT_LAYER`] layer above the other layers, so a click selects the
+    /// whole instance in draw.io. The box carries the tag, tooltip, link and
+    /// data attributes of the instance.
+    pub fn with_instance_hit_boxes(mut self, instance_hit_boxes: bool) -> Self {
+        self.instance_hit_boxes = instance_hit_boxes;
+        self
+    }
 
-            // Keep trying to merge until no more merges are possible
-            loop {
-                let mut merged_this_round = false;
+    /// Wrap the objects of each instance in a draw.io group cell named after
+    /// the instance, see [`GroupTransform::group_cell`], so the instance moves
+    /// as a unit. The grouped objects leave their layer cells: hiding a layer
+    /// no longer hides them, they are tagged with it instead.
+    pub fn with_group_instances(mut self, group_instances: bool) -> Self {
+        self.group_instances = group_instances;
+        self
+    }
 
-                // Try to merge with all other unprocessed lines
-                for j in 0..lines.len() {
-                    if i == j || processed[j] {
-                        continue;
-                    }
+    /// Fail with [`drawrs::DrawrsError::InvalidText`] on the labels and other
+    /// texts holding characters XML 1.0 does not allow, e.g. a vertical tab,
+    /// instead of dropping them from the written files
+    pub fn with_strict_text(mut self, strict_text: bool) -> Self {
+        self.strict_text = strict_text;
+        self
+    }
 
-                    let other_line = lines[j];
+    /// Let draw.io size the boxes of the labels from their text, see
+    /// [`Object::set_autosize`], instead of the width estimated from the
+    /// number of characters. The estimated box is still written so the file
+    /// renders before draw.io fits it.
+    pub fn with_labels_autosize(mut self, labels_autosize: bool) -> Self {
+        self.labels_autosize = labels_autosize;
+        self
+    }
 
-                    if other_line.is_empty() {
-                        continue;
-                    }
+    /// Never merge two wires where they meet on an instance terminal or a
+    /// top-level pin, and split the wires running through one, so every
+    /// drawn wire ends on the pins it touches
+    pub fn with_split_wires_at_pins(mut self, split_wires_at_pins: bool) -> Self {
+        self.split_wires_at_pins = split_wires_at_pins;
+        self
+    }
 
-                    let current_start = &current_line[0];
-                    let current_end = &current_line[current_line.len() - 1];
-                    let other_start = &other_line[0];
-                    let other_end = &other_line[other_line.len() - 1];
+    /// Mark dangling wire ends with a small square on the wire intersection
+    /// layer, filled with [`LayerStyles::wire_open_end_color`]
+    pub fn with_mark_open_ends(mut self, mark_open_ends: bool) -> Self {
+        self.mark_open_ends = mark_open_ends;
+        self
+    }
 
-                    // Check if lines share an endpoint
-                    let share_start_start = current_start == other_start;
-                    let share_start_end = current_start == other_end;
-                    let share_end_start = current_end == other_start;
-                    let share_end_end = current_end == other_end;
+    /// Nudge overlapping pin names and net labels apart after placement, by
+    /// at most [`Self::with_label_max_displacement`]
+    pub fn with_avoid_label_overlap(mut self, avoid_label_overlap: bool) -> Self {
+        self.avoid_label_overlap = avoid_label_overlap;
+        self
+    }
 
-                    if !share_start_start && !share_start_end && !share_end_start && !share_end_end
-                    {
-                        continue;
-                    }
+    /// Largest distance a label is nudged by [`Self::with_avoid_label_overlap`],
+    /// in user units, [`DEFAULT_LABEL_MAX_DISPLACEMENT`] by default
+    pub fn with_label_max_displacement(mut self, label_max_displacement: f64) -> Self {
+        self.label_max_displacement = label_max_displacement;
+        self
+    }
 
-                    // Determine the shared point
-                    let shared_point = if share_start_start {
-                        Some(current_start)
-                    } else if share_start_end {
-                        Some(current_start)
-                    } else if share_end_start {
-                        Some(current_end)
-                    } else {
-                        Some(current_end)
-                    };
+    /// Symbol drawn at the top-level pins of each direction, e.g.
+    /// `"input"` to `basic/ipin`, facing away from the wire. The symbols are
+    /// looked up in the symbol files of the render.
+    pub fn with_pin_symbols(mut self, pin_symbols: IndexMap<String, DesignId<'static>>) -> Self {
+        self.pin_symbols = pin_symbols;
+        self
+    }
 
-                    if let Some(shared) = shared_point {
-                        // Count how many lines connect at this point
-                        // We need to check:
-                        // 1. Unprocessed lines (besides i and j)
-                        // 2. Already merged lines that are kept separate (in merged array)
-                        let mut connection_count = 0;
-
-                        // Check unprocessed lines
-                        for k in 0..lines.len() {
-                            if k == i || k == j || processed[k] {
-                                continue;
-                            }
-                            let points = &lines[k];
-                            if !points.is_empty() {
-                                let line_start = &points[0];
-                                let line_end = &points[points.len() - 1];
-                                if line_start == shared || line_end == shared {
-                                    connection_count += 1;
-                                }
-                            }
-                        }
+    /// Add a legend of the enabled layers and their stroke colors to the
+    /// schematic page, on the annotate label layer, at
+    /// [`LayerStyles::layer_legend_corner`]
+    pub fn with_layer_legend(mut self, layer_legend: bool) -> Self {
+        self.layer_legend = layer_legend;
+        self
+    }
 
-                        // Check already merged lines that are kept separate
-                        for merged_line in &merged {
-                            if !merged_line.is_empty() {
-                                let line_start = &merged_line[0];
-                                let line_end = &merged_line[merged_line.len() - 1];
-                                if line_start == shared || line_end == shared {
-                                    connection_count += 1;
-                                }
-                            }
-                        }
+    // XML of a rendered file, its texts are checked first in strict mode
+    fn file_xml(&self, file: &DrawFile) -> DrawcktResult<String> {
+        if self.strict_text {
+            file.check_text()?;
+        }
+        Ok(file.xml()?.to_string())
+    }
 
-                        // Only merge if no other lines connect at this point
-                        // This ensures that we only merge when exactly two lines meet at a point
-                        if connection_count == 0 {
-                            // Merge the lines
-                            let mut new_points = current_line.clone();
-
-                            if share_start_start {
-                                // Reverse current line and append other line
-                                new_points.reverse();
-                                new_points.pop(); // Remove duplicate point
-                                new_points.extend_from_slice(other_line);
-                            } else if share_start_end {
-                                // Prepend other line (reversed) to current line
-                                // current_start == other_end, so we reverse other_line and remove its first point
-                                let mut other_reversed = other_line.clone();
-                                _ = other_reversed.pop(); // Remove duplicate point (first point after reverse)
-                                other_reversed.reverse();
-                                other_reversed.extend_from_slice(&new_points);
-                                new_points = other_reversed;
-                            } else if share_end_start {
-                                // Append other line to current
-                                new_points.pop(); // Remove duplicate point
-                                new_points.extend_from_slice(other_line);
-                            } else if share_end_end {
-                                // Append reversed other line to current
-                                let mut other_reversed = other_line.clone();
-                                other_reversed.reverse();
-                                new_points.pop(); // Remove duplicate point
-                                new_points.extend_from_slice(&other_reversed);
-                            }
+    // Link of the instance from `instance_link_template`
+    pub(crate) fn instance_link(&self, instance: &Instance) -> Option<String> {
+        let template = self.instance_link_template.as_ref()?;
+        Some(
+            template
+                .replace("{lib}", instance.symbol_id.lib.as_ref())
+                .replace("{cell}", instance.symbol_id.cell.as_ref())
+                .replace("{name}", &instance.name),
+        )
+    }
 
-                            current_line = new_points;
-                            processed[j] = true;
-                            merged_this_round = true;
-                            // Continue to try merging with remaining lines
-                        }
+    /// Layer of the symbol objects whose `layer-*` parent is not a layer of
+    /// the schematic page, e.g. a layer renamed inside a symbol file.
+    /// Annotate by default.
+    pub fn with_fallback_layer(mut self, fallback_layer: Layer) -> Self {
+        self.fallback_layer = fallback_layer;
+        self
+    }
+
+    /// Only warn about symbol pages generated from other symbol shapes or
+    /// styles than the renderer's, instead of failing with
+    /// [`DrawcktError::StaleSymbol`]
+    pub fn with_allow_stale_symbols(mut self, allow_stale_symbols: bool) -> Self {
+        self.allow_stale_symbols = allow_stale_symbols;
+        self
+    }
+
+    /// Cover each instance with an invisible box whose connection points
+    /// are the terminals of its symbol, so the wires drawn by hand in draw.io
+    /// snap to them
+    pub fn with_connection_points(mut self, connection_points: bool) -> Self {
+        self.connection_points = connection_points;
+        self
+    }
+
+    /// Drop the interior points of the lines and wires that are within
+    /// `simplify_epsilon` user units of the segment between their kept
+    /// neighbours, see [`Self::simplify_polyline`]. `None` keeps every point.
+    pub fn with_simplify_epsilon(mut self, simplify_epsilon: Option<f64>) -> Self {
+        self.simplify_epsilon = simplify_epsilon;
+        self
+    }
+
+    /// Straighten the wire segments that are less than
+    /// `orthogonalize_tolerance` user units off an axis, before the wires
+    /// are merged, see [`Self::orthogonalize`]. Off by default.
+    pub fn with_orthogonalize_tolerance(mut self, orthogonalize_tolerance: Option<f64>) -> Self {
+        self.orthogonalize_tolerance = orthogonalize_tolerance.filter(|tolerance| *tolerance > 0.0);
+        self
+    }
+
+    /// Number the objects that have no name to derive their id from (e.g.
+    /// the wires of anonymous nets) instead of giving them random UUIDs, so
+    /// two renders of the same input are identical. See also
+    /// [`drawrs::file::set_fixed_timestamp`].
+    pub fn with_deterministic_ids(mut self, deterministic_ids: bool) -> Self {
+        self.deterministic_ids = deterministic_ids;
+        self
+    }
+
+    // Run a render with the ids counted from 0, unless a render is already
+    // counting. Fails with the issues of the layer styles found by `new`.
+    fn with_ids<R>(&self, f: impl FnOnce() -> DrawcktResult<R>) -> DrawcktResult<R> {
+        self.check_layer_styles()?;
+        if self.deterministic_ids && drawrs::xml_base::id_counter().is_none() {
+            drawrs::xml_base::with_id_counter(0, f)
+        } else {
+            f()
+        }
+    }
+
+    /// Round every emitted coordinate and size to the nearest multiple of
+    /// `snap_grid`, in Draw.io pixels. Wires are snapped before they are merged,
+    /// so endpoints that land on the same grid point still connect.
+    pub fn with_snap_grid(mut self, snap_grid: Option<f64>) -> Self {
+        self.snap_grid = snap_grid.filter(|grid| *grid > 0.0);
+        self
+    }
+
+    // Snap a page coordinate to the grid
+    fn snap(&self, value: f64) -> f64 {
+        match self.snap_grid {
+            Some(grid) => (value / grid).round() * grid,
+            None => value,
+        }
+    }
+
+    // Snap the points and the box of an emitted object to the grid
+    fn snap_object(&self, obj: &mut DiagramObject) {
+        if self.snap_grid.is_none() {
+            return;
+        }
+        for point in obj.mut_points() {
+            *point = point.map(|v| self.snap(v));
+        }
+        if let Some((bbox, _)) = obj.mut_box() {
+            *bbox = BoundingBox::new(
+                self.snap(bbox.min_x()),
+                self.snap(bbox.min_y()),
+                self.snap(bbox.width()),
+                self.snap(bbox.height()),
+            );
+        }
+        if let DiagramObject::Edge(edge) = obj {
+            let geometry = edge.geometry();
+            let (width, height) = (geometry.width(), geometry.height());
+            geometry.set_width(self.snap(width));
+            geometry.set_height(self.snap(height));
+        }
+    }
+
+    fn symbol_obj_id(
+        &self,
+        template: &Symbol,
+        shape: &Shape,
+        idx: usize,
+        used: &mut HashSet<String>,
+    ) -> String {
+        match self.id_scheme {
+            IdScheme::Index => template.gen_obj_id(shape.layer(), idx),
+            IdScheme::Content => template.gen_obj_content_id(shape, used),
+        }
+    }
+
+    // Generate ID for wire: wire-{net}-{counter} or wire-{new_id}
+    fn gen_wire_id(net: &str, counter: usize) -> String {
+        if !net.is_empty() {
+            let safe_net = net.replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
+            format!("wire-{}-{}", disambiguate_id(net, safe_net), counter)
+        } else {
+            format!("wire-{}", drawrs::xml_base::new_id())
+        }
+    }
+
+    // Nudge the labels added from `first` on apart, along their free axis
+    // (vertically for horizontal text) by multiples of their height
+    fn avoid_label_overlap(&self, page: &mut Page, first: usize) {
+        let mut labels: Vec<(usize, BoundingBox, bool)> = page.objects()[first..]
+            .iter()
+            .enumerate()
+            .filter(|(_, obj)| {
+                obj.xml_parent()
+                    .and_then(Layer::parse_id)
+                    .is_some_and(|(_, cell)| cell == LayerCell::Label)
+            })
+            .filter_map(|(i, obj)| {
+                let bbox = Self::visual_bounding_box(obj)?;
+                let rotated = obj
+                    .bounding_box()
+                    .is_some_and(|raw| raw.width() != bbox.width());
+                Some((first + i, bbox, !rotated))
+            })
+            .collect();
+        // Sweep from left to right, earlier labels keep their place
+        labels.sort_by(|a, b| a.1.min_x().total_cmp(&b.1.min_x()));
+
+        let max_displacement = self.label_max_displacement * self.schematic.user_unit_scale();
+        let mut placed: Vec<BoundingBox> = Vec::new();
+        for (index, bbox, vertical) in labels {
+            let active: Vec<&BoundingBox> = placed
+                .iter()
+                .filter(|other| other.max_x() > bbox.min_x())
+                .collect();
+            let clear =
+                |candidate: &BoundingBox| !active.iter().any(|other| candidate.intersects(other));
+            if clear(&bbox) {
+                placed.push(bbox);
+                continue;
+            }
+            let step = if vertical {
+                bbox.height()
+            } else {
+                bbox.width()
+            };
+            let mut moved = None;
+            for n in 1.. {
+                let distance = n as f64 * step;
+                if step <= 0.0 || distance > max_displacement {
+                    break;
+                }
+                let found = [distance, -distance].into_iter().find_map(|delta| {
+                    let (dx, dy) = if vertical { (0.0, delta) } else { (delta, 0.0) };
+                    let mut candidate = bbox;
+                    candidate.translate(dx, dy);
+                    clear(&candidate).then_some((candidate, (dx, dy)))
+                });
+                if found.is_some() {
+                    moved = found;
+                    break;
+                }
+            }
+            let obj = &mut page.objects_mut()[index];
+            match moved {
+                Some((candidate, (dx, dy))) => {
+                    if let Some((obj_bbox, _)) = obj.mut_box() {
+                        obj_bbox.translate(dx, dy);
                     }
+                    placed.push(candidate);
+                }
+                None => {
+                    warn!(
+                        "label {} still overlaps after moving it by up to {max_displacement}",
+                        obj.id()
+                    );
+                    placed.push(bbox);
                 }
+            }
+        }
+    }
 
-                // If no merge happened this round, we're done with this line
-                if !merged_this_round {
-                    break;
+    // Instance terminals and top-level pins, snapped as the wire points
+    fn pin_points(&self) -> Vec<[f64; 2]> {
+        let scale = self.schematic.scale();
+        let snap = |v: f64| self.snap(v * scale) / scale;
+        let symbols: HashMap<_, _> = self.schematic.symbols.iter().map(|s| (&s.id, s)).collect();
+        self.schematic
+            .instances
+            .iter()
+            .filter_map(|instance| {
+                Some(instance.terminal_points(symbols.get(&instance.symbol_id)?))
+            })
+            .flatten()
+            .chain(self.schematic.pins.iter().map(|pin| [pin.x, pin.y]))
+            .map(|point| point.map(snap))
+            .collect()
+    }
+
+    // Wire endpoints touching no other wire, instance terminal or top-level pin
+    fn open_wire_ends(&self) -> Vec<(&str, [f64; 2])> {
+        let tolerance = self.schematic.snap_tolerance();
+        // Whether the point is on a segment of the wire, including its ends
+        let on_wire = |wire: &Wire, [x, y]: [f64; 2]| {
+            wire.points.windows(2).any(|segment| {
+                let ([x0, y0], [x1, y1]) = (segment[0].map(|v| v.0), segment[1].map(|v| v.0));
+                let cross = (x1 - x0) * (y - y0) - (y1 - y0) * (x - x0);
+                let length = (x1 - x0).hypot(y1 - y0);
+                cross.abs() <= tolerance * length.max(1.0)
+                    && x >= x0.min(x1) - tolerance
+                    && x <= x0.max(x1) + tolerance
+                    && y >= y0.min(y1) - tolerance
+                    && y <= y0.max(y1) + tolerance
+            })
+        };
+        let symbols: HashMap<_, _> = self.schematic.symbols.iter().map(|s| (&s.id, s)).collect();
+        let terminals: Vec<[f64; 2]> = self
+            .schematic
+            .instances
+            .iter()
+            .filter_map(|instance| {
+                Some(instance.terminal_points(symbols.get(&instance.symbol_id)?))
+            })
+            .flatten()
+            .chain(self.schematic.pins.iter().map(|pin| [pin.x, pin.y]))
+            .collect();
+
+        let mut open_ends = Vec::new();
+        for (i, wire) in self.schematic.wires.iter().enumerate() {
+            if wire.points.len() < 2 {
+                continue;
+            }
+            for end in [wire.points[0], wire.points[wire.points.len() - 1]] {
+                let end = end.map(|v| v.0);
+                let connected = self
+                    .schematic
+                    .wires
+                    .iter()
+                    .enumerate()
+                    .any(|(j, other)| j != i && on_wire(other, end))
+                    || terminals
+                        .iter()
+                        .any(|terminal| snapped(*terminal, end, tolerance));
+                if !connected {
+                    open_ends.push((wire.net.as_str(), end));
                 }
             }
+        }
+        open_ends
+    }
 
-            merged.push(current_line);
-            processed[i] = true;
+    // Place the symbol of the pin direction at a top-level pin, rotated so the
+    // wire leaves it on its right as in R0
+    fn render_pin_symbol(
+        &self,
+        index: usize,
+        pin: &Pin,
+        wire_direction: Option<[f64; 2]>,
+        symbol_pages: &IndexMap<&DesignId, SymbolPageData>,
+        page: &mut Page,
+    ) -> DrawcktResult<()> {
+        if self.pin_symbols.is_empty() {
+            return Ok(());
+        }
+        let Some(symbol_id) = self.pin_symbols.get(&pin.direction) else {
+            warn!(
+                "pin {}: no pin symbol for direction {:?}, only the name is drawn",
+                pin.name, pin.direction
+            );
+            return Ok(());
+        };
+        if !symbol_pages.contains_key(symbol_id) {
+            warn!(
+                "pin {}: pin symbol {symbol_id} not found, only the name is drawn",
+                pin.name
+            );
+            return Ok(());
         }
+        let orient = match wire_direction {
+            Some([x, y]) if x.abs() >= y.abs() && x < 0.0 => Orient::MY,
+            Some([x, y]) if y.abs() > x.abs() && y > 0.0 => Orient::R90,
+            Some([x, y]) if y.abs() > x.abs() => Orient::R270,
+            _ => Orient::R0,
+        };
+        let glyph = Instance {
+            name: format!("pin-{index}"),
+            symbol_id: symbol_id.clone(),
+            x: pin.x,
+            y: pin.y,
+            orient,
+            params: IndexMap::new(),
+        };
+        self.render_instance(&glyph, symbol_pages, page)?;
+        Ok(())
+    }
 
-        merged
+    // Direction in which the first wire ending on the pin leaves it
+    fn pin_wire_direction(&self, pin: &Pin) -> Option<[f64; 2]> {
+        let at = [pin.x, pin.y];
+        let tolerance = self.schematic.snap_tolerance();
+        self.schematic.wires.iter().find_map(|wire| {
+            let points: Vec<[f64; 2]> = wire.points.iter().map(|p| p.map(|v| v.0)).collect();
+            let ends = [
+                (points.first()?, points.get(1)?),
+                (points.last()?, points.get(points.len().checked_sub(2)?)?),
+            ];
+            ends.into_iter().find_map(|(end, next)| {
+                let direction = [next[0] - end[0], next[1] - end[1]];
+                (snapped(*end, at, tolerance) && !snapped(*end, *next, tolerance))
+                    .then_some(direction)
+            })
+        })
+    }
+
+    // Small squares on the wire intersection layer at the open wire ends, with their net
+    fn render_open_ends(&self) -> Vec<(&str, DiagramObject)> {
+        let scale = self.schematic.scale();
+        let size = OPEN_END_SIZE
+            * self.layer_styles.wire_intersection_scale
+            * self.schematic.user_unit_scale();
+        let mut counters: HashMap<&str, usize> = HashMap::new();
+        let mut marks = Vec::new();
+        for (net, [x, y]) in self.open_wire_ends() {
+            let counter = counters.entry(net).or_default();
+            *counter += 1;
+            let safe_net = net.replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
+            let mut obj = Object::new(Some(format!("openend-{safe_net}-{counter}")));
+            obj.set_position([x * scale - size / 2.0, -y * scale - size / 2.0]);
+            obj.set_width(size);
+            obj.set_height(size);
+            obj.set_fill_color(Some(self.layer_styles.wire_open_end_color.to_string()));
+            obj.set_stroke_color(Some("none".to_string()));
+            obj.set_xml_parent(Some(Layer::Wire.id_shape(true)));
+            let mut obj = DiagramObject::Object(obj);
+            self.snap_object(&mut obj);
+            marks.push((net, obj));
+        }
+        marks
+    }
+
+    // Convert wires to IndexMap grouped by net in order of first wire, with each wire as a Shape::Line.
+    // The points are snapped here so that merge_polylines sees the final endpoints.
+    // Each wire keeps its index in the schematic.
+    #[allow(clippy::type_complexity)]
+    fn wires_to_shapes_by_net(
+        &self,
+    ) -> IndexMap<String, Vec<(usize, Vec<[OrderedFloat<f64>; 2]>)>> {
+        let scale = self.schematic.scale();
+        let snap = |v: OrderedFloat<f64>| OrderedFloat(self.snap(*v * scale) / scale);
+        let mut points: Vec<Vec<[OrderedFloat<f64>; 2]>> = self
+            .schematic
+            .wires
+            .iter()
+            .map(|wire| wire.points.clone())
+            .collect();
+        if let Some(tolerance) = self.orthogonalize_tolerance {
+            let mut lines: Vec<_> = points.iter_mut().filter(|line| line.len() >= 2).collect();
+            Self::orthogonalize(&mut lines, tolerance);
+        }
+        self.schematic
+            .wires_by_net()
+            .into_iter()
+            .map(|(net, indices)| {
+                let lines = indices
+                    .into_iter()
+                    .map(|i| {
+                        let line = std::mem::take(&mut points[i]);
+                        (i, line.into_iter().map(|point| point.map(snap)).collect())
+                    })
+                    .collect();
+                (net.to_string(), lines)
+            })
+            .collect()
+    }
+
+    /// Make the segments whose x (or y) delta is below `tolerance` exactly
+    /// vertical (or horizontal). The endpoints of such segments take the
+    /// average coordinate of all the points chained to them by near-axis
+    /// segments, so a point shared by several lines moves the same way in
+    /// each of them. Diagonal segments are left alone.
+    pub fn orthogonalize(lines: &mut [&mut Vec<[OrderedFloat<f64>; 2]>], tolerance: f64) {
+        let mut index: HashMap<[OrderedFloat<f64>; 2], usize> = HashMap::new();
+        let mut points = Vec::new();
+        for point in lines.iter().flat_map(|line| line.iter()) {
+            index.entry(*point).or_insert_with(|| {
+                points.push(*point);
+                points.len() - 1
+            });
+        }
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        // One union-find per axis: `parent[axis]` joins the points that must
+        // share their coordinate on `axis`
+        let mut parent = [
+            (0..points.len()).collect::<Vec<_>>(),
+            (0..points.len()).collect::<Vec<_>>(),
+        ];
+        for line in lines.iter() {
+            for segment in line.windows(2) {
+                let (a, b) = (index[&segment[0]], index[&segment[1]]);
+                for (axis, parent) in parent.iter_mut().enumerate() {
+                    if (*segment[0][axis] - *segment[1][axis]).abs() < tolerance {
+                        let (root_a, root_b) = (find(parent, a), find(parent, b));
+                        parent[root_a] = root_b;
+                    }
+                }
+            }
+        }
+        let mut snapped = points.clone();
+        for (axis, parent) in parent.iter_mut().enumerate() {
+            let mut sums: HashMap<usize, (f64, usize)> = HashMap::new();
+            for (i, point) in points.iter().enumerate() {
+                let sum = sums.entry(find(parent, i)).or_default();
+                sum.0 += *point[axis];
+                sum.1 += 1;
+            }
+            for (i, point) in snapped.iter_mut().enumerate() {
+                let (sum, count) = sums[&find(parent, i)];
+                point[axis] = OrderedFloat(sum / count as f64);
+            }
+        }
+        for line in lines.iter_mut() {
+            for point in line.iter_mut() {
+                *point = snapped[index[point]];
+            }
+        }
+    }
+
+    /// The polyline without the interior points that lie within `epsilon` of
+    /// the segment between the previous kept point and a later point, e.g.
+    /// the joints of a straight run chopped into segments. The ends and the
+    /// corners are kept.
+    pub fn simplify_polyline(
+        points: &[[OrderedFloat<f64>; 2]],
+        epsilon: f64,
+    ) -> Vec<[OrderedFloat<f64>; 2]> {
+        if points.len() <= 2 {
+            return points.to_vec();
+        }
+        let xy = |i: usize| [*points[i][0], *points[i][1]];
+        // Whether `p` projects inside the segment `a`-`b` within `epsilon` of it
+        let on_segment = |p: [f64; 2], a: [f64; 2], b: [f64; 2]| {
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let length2 = dx * dx + dy * dy;
+            let t = if length2 > 0.0 {
+                ((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / length2
+            } else {
+                0.0
+            };
+            let (px, py) = (a[0] + t * dx - p[0], a[1] + t * dy - p[1]);
+            (0.0..=1.0).contains(&t) && (px * px + py * py).sqrt() <= epsilon
+        };
+        let mut kept = vec![points[0]];
+        let mut anchor = 0;
+        for end in 2..points.len() {
+            if !(anchor + 1..end).all(|i| on_segment(xy(i), xy(anchor), xy(end))) {
+                anchor = end - 1;
+                kept.push(points[anchor]);
+            }
+        }
+        kept.push(points[points.len() - 1]);
+        kept
     }
 
     // Apply fill style to an Object based on fillStyle value (0-5)
@@ -446,68 +1525,833 @@ impl<'a> Renderer<'a> {
     // 3: Filled with an X pattern (filled + special pattern)
     // 4: Filled with a pattern (filled + dashed pattern)
     // 5: Filled with a pattern and outlined (filled + dashed pattern + outline)
-    fn apply_fill_style(&self, obj: &mut Object, fill_style: u8, layer_style: &LayerStyle) {
-        let normalized_style = if fill_style == 0 { 1 } else { fill_style };
-
-        match normalized_style {
-            1 => {
-                // Not filled, only outlined
-                obj.set_stroke_color(Some(layer_style.stroke_color.clone().into_owned()));
-                obj.set_stroke_width(Some(layer_style.stroke_width));
-                obj.set_fill_color(Some("none".to_string()));
-            }
-            2 => {
-                // Filled with color
-                obj.set_stroke_color(Some("none".to_string()));
-                obj.set_stroke_width(Some(layer_style.stroke_width));
-                obj.set_fill_color(Some(layer_style.stroke_color.clone().into_owned()));
-            }
-            3 => {
-                // Filled with an X pattern
-                obj.set_stroke_color(Some("none".to_string()));
-                obj.set_stroke_width(Some(layer_style.stroke_width));
-                obj.set_fill_color(Some(layer_style.stroke_color.clone().into_owned()));
+    fn apply_fill_style(obj: &mut Object, fill_style: FillStyleKind, layer_style: &LayerStyle) {
+        let stroke = Some(layer_style.stroke_color.clone().into_owned());
+        let fill = Some(layer_style.fill_color().to_string());
+        let none = || Some("none".to_string());
+        obj.set_stroke_width(Some(layer_style.stroke_width));
+        match fill_style {
+            FillStyleKind::Outline => {
+                obj.set_stroke_color(stroke);
+                obj.set_fill_color(none());
+            }
+            FillStyleKind::Filled => {
+                obj.set_stroke_color(none());
+                obj.set_fill_color(fill);
+            }
+            FillStyleKind::FilledX => {
+                obj.set_stroke_color(none());
+                obj.set_fill_color(fill);
                 obj.set_fill_style(Some(FillStyle::CrossHatch));
             }
-            4 => {
-                // Filled with a pattern
-                obj.set_stroke_color(Some("none".to_string()));
-                obj.set_stroke_width(Some(layer_style.stroke_width));
-                obj.set_fill_color(Some(layer_style.stroke_color.clone().into_owned()));
+            FillStyleKind::FilledPattern => {
+                obj.set_stroke_color(none());
+                obj.set_fill_color(fill);
                 obj.set_fill_style(Some(FillStyle::Hatch));
             }
-            5 => {
-                // Filled with pattern and outlined
-                obj.set_stroke_color(Some(layer_style.stroke_color.clone().into_owned()));
-                obj.set_stroke_width(Some(layer_style.stroke_width));
-                obj.set_fill_color(Some(layer_style.stroke_color.clone().into_owned()));
+            FillStyleKind::FilledPatternOutline => {
+                obj.set_stroke_color(stroke);
+                obj.set_fill_color(fill);
                 obj.set_fill_style(Some(FillStyle::Hatch));
             }
-            _ => {
-                // Fallback to not filled
-                obj.set_stroke_color(Some(layer_style.stroke_color.clone().into_owned()));
-                obj.set_stroke_width(Some(layer_style.stroke_width));
-                obj.set_fill_color(Some("none".to_string()));
-            }
         }
+        obj.set_opacity(layer_style.opacity);
+        obj.set_line_join(layer_style.line_join);
     }
 
     pub fn render_symbols_file<'b>(&'b self) -> DrawcktResult<SymbolContexts<'b>> {
-        let contexts = self
+        self.with_ids(|| {
+            let contexts = self
+                .schematic
+                .symbols
+                .iter()
+                .map(|template| {
+                    let name = template.id.to_string();
+                    let mut symbol_page = Page::new(Some(name.clone()), false);
+                    symbol_page.set_name(name);
+                    // Provenance checked by `render_schematic_page`
+                    symbol_page.set_attribute(
+                        "styleHash".to_string(),
+                        format!("{:08x}", self.layer_styles.symbol_hash()),
+                    )?;
+                    symbol_page.set_attribute(
+                        "symbolHash".to_string(),
+                        format!("{:08x}", template.content_hash()),
+                    )?;
+                    self.render_symbol(&mut symbol_page, template)
+                        .map_err(|e| e.in_symbol(&template.id))?;
+                    let mut symbol_file = self.layer_styles.draw_file();
+                    symbol_file.add_page(symbol_page);
+                    let xml = self
+                        .file_xml(&symbol_file)
+                        .map_err(|e| e.in_symbol(&template.id))?;
+                    Ok((template.id.refs(), xml.into()))
+                })
+                .collect::<Result<_, DrawcktError>>()?;
+            Ok(SymbolContexts(contexts))
+        })
+    }
+
+    // Compare the provenance of the generated symbol pages with the schematic
+    // symbols and the styles, hand-made pages without it are trusted
+    fn check_stale_symbols(
+        &self,
+        symbol_pages: &IndexMap<&DesignId, SymbolPageData>,
+    ) -> DrawcktResult<()> {
+        let style_hash = format!("{:08x}", self.layer_styles.symbol_hash());
+        for symbol in &self.schematic.symbols {
+            let Some(page_data) = symbol_pages.get(&symbol.id) else {
+                continue;
+            };
+            let reason = if page_data
+                .symbol_hash
+                .as_ref()
+                .is_some_and(|hash| *hash != format!("{:08x}", symbol.content_hash()))
+            {
+                "the symbol shapes or pins changed since it was rendered"
+            } else if page_data
+                .style_hash
+                .as_ref()
+                .is_some_and(|hash| *hash != style_hash)
+            {
+                "the layer styles changed since it was rendered"
+            } else {
+                continue;
+            };
+            if self.allow_stale_symbols {
+                warn!("symbol {} is stale: {reason}", symbol.id);
+            } else {
+                return Err(DrawcktError::StaleSymbol {
+                    lib: symbol.id.lib.to_string(),
+                    cell: symbol.id.cell.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Warn about the symbol pages whose box, the labels left out, is not the
+    // one of the symbol shapes, e.g. a page edited by hand: the instances
+    // are still placed, by the box of the page
+    fn check_symbol_boxes(&self, symbol_pages: &IndexMap<&DesignId, SymbolPageData>) {
+        let tolerance = SYMBOL_BOX_TOLERANCE + self.snap_grid.unwrap_or(0.0);
+        for symbol in &self.schematic.symbols {
+            let Some(page_data) = symbol_pages.get(&symbol.id) else {
+                continue;
+            };
+            let page_box = BoundingBox::union(
+                page_data
+                    .objects
+                    .iter()
+                    .filter(|obj| {
+                        !obj.xml_parent()
+                            .and_then(Layer::parse_id)
+                            .is_some_and(|(_, cell)| cell == LayerCell::Label)
+                    })
+                    .filter_map(extent),
+            );
+            let (Some(page_box), Some(shapes_box)) = (page_box, self.shapes_box(symbol)) else {
+                continue;
+            };
+            if !page_box.approx_eq(&shapes_box, tolerance) {
+                warn!(
+                    "symbol {}: the box of its page {} differs from the one of its shapes {}, \
+                     its instances may be misplaced",
+                    symbol.id,
+                    box_str(&page_box),
+                    box_str(&shapes_box)
+                );
+            }
+        }
+    }
+
+    // Box in page pixels of the shapes of a symbol on the enabled layers, the
+    // labels left out
+    fn shapes_box(&self, symbol: &Symbol) -> Option<BoundingBox> {
+        let scale = self.schematic.scale();
+        let point_box =
+            |[x, y]: [OrderedFloat<f64>; 2]| BoundingBox::new(*x * scale, -*y * scale, 0.0, 0.0);
+        let boxes = symbol
+            .shapes
+            .iter()
+            .filter(|shape| self.layer_styles.layer_enabled(shape.layer()))
+            .filter_map(|shape| match shape {
+                Shape::Rect { b_box, .. }
+                | Shape::Ellipse { b_box, .. }
+                | Shape::Arc { b_box, .. } => {
+                    BoundingBox::union(b_box.iter().copied().map(point_box))
+                }
+                Shape::Polygon { points, .. } if points.len() >= 3 => {
+                    BoundingBox::union(points.iter().copied().map(point_box))
+                }
+                Shape::Line { points, .. } if points.len() >= 2 => {
+                    BoundingBox::union(points.iter().copied().map(point_box))
+                }
+                _ => None,
+            });
+        BoundingBox::union(boxes)
+    }
+
+    // Parse symbol contexts to extract pages
+    fn parse_symbol_pages<'b>(
+        symbols_content: &'b SymbolContexts,
+    ) -> DrawcktResult<IndexMap<&'b DesignId<'b>, SymbolPageData>> {
+        let mut symbol_pages = IndexMap::new();
+        for (symbol_id, content) in &symbols_content.0 {
+            let mut pages = Self::parse_drawio_file(content).map_err(|e| e.in_symbol(symbol_id))?;
+            // Each symbol file should have only one page
+            if let Some((_, page_data)) = pages.pop() {
+                symbol_pages.insert(symbol_id, page_data);
+            } else {
+                return Err(DrawcktError::SymbolNotFound(symbol_id.to_string()));
+            }
+        }
+        Ok(symbol_pages)
+    }
+
+    /// Draw the symbol `lib/cell` of the schematic on an existing page, with
+    /// its origin at `(x, y)` in page pixels and `scale` times its schematic size.
+    /// The objects are parented to the page root "1", see [`Self::place_symbol_in`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_symbol(
+        &self,
+        page: &mut Page,
+        lib: &str,
+        cell: &str,
+        x: f64,
+        y: f64,
+        orient: Orient,
+        scale: f64,
+    ) -> DrawcktResult<()> {
+        self.place_symbol_in(page, "1", lib, cell, x, y, orient, scale)
+    }
+
+    /// [`Self::place_symbol`] with the objects parented to `parent`
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_symbol_in(
+        &self,
+        page: &mut Page,
+        parent: &str,
+        lib: &str,
+        cell: &str,
+        x: f64,
+        y: f64,
+        orient: Orient,
+        scale: f64,
+    ) -> DrawcktResult<()> {
+        self.check_layer_styles()?;
+        let Some(template) = self
             .schematic
             .symbols
             .iter()
-            .map(|template| {
-                let name = template.id.to_string();
-                let mut symbol_page = Page::new(Some(name.clone()), false);
-                symbol_page.set_name(name);
-                self.render_symbol(&mut symbol_page, template)?;
-                let mut symbol_file = DrawFile::new();
-                symbol_file.add_page(symbol_page);
-                Ok((template.id.refs(), symbol_file.xml().to_string().into()))
+            .find(|symbol| symbol.id == DesignId::new(lib, cell))
+        else {
+            return Err(DrawcktError::SymbolNotFound(format!("{lib}/{cell}")));
+        };
+        let symbol_page_data = self.render_symbol_page_data(template)?;
+
+        // Each placement gets its own id prefix
+        let name = (1..)
+            .map(|n| format!("{lib}-{cell}-{n}"))
+            .find(|name| {
+                let prefix = format!("{name}-");
+                !page
+                    .objects()
+                    .iter()
+                    .any(|obj| obj.id().starts_with(&prefix))
             })
-            .collect::<Result<_, DrawcktError>>()?;
-        Ok(SymbolContexts(contexts))
+            .unwrap();
+        let group_transform = GroupTransform::new(
+            symbol_page_data.origin_bounding_box,
+            x,
+            y,
+            orient,
+            &name,
+            cell,
+        );
+        // Scale about the symbol origin
+        let scaling = Transform2D::translation(-x, -y)
+            .then(Transform2D::scale(scale, scale))
+            .then(Transform2D::translation(x, y));
+        for (i, obj) in symbol_page_data.objects.iter().enumerate() {
+            if obj
+                .xml_parent()
+                .is_some_and(|parent| self.layer_styles.is_disabled_layer_id(parent))
+            {
+                continue;
+            }
+            let mut new_obj = group_transform
+                .new_obj(obj)
+                .map_err(|e| DrawcktError::from(e).in_shape(i).in_symbol(&template.id))?;
+            for point in new_obj.mut_points() {
+                *point = scaling.apply(*point);
+            }
+            if let Some((bbox, _)) = new_obj.mut_box() {
+                let [x, y] = scaling.apply([bbox.min_x(), bbox.min_y()]);
+                *bbox = BoundingBox::new(x, y, bbox.width() * scale, bbox.height() * scale);
+            }
+            if let Some(object) = new_obj.as_object_mut()
+                && let Some(font_size) = object.font_size()
+            {
+                object.set_font_size(Some(font_size * scale));
+            }
+            new_obj.set_xml_parent(Some(parent.to_string()));
+            self.snap_object(&mut new_obj);
+            page.add_object(new_obj);
+        }
+        Ok(())
+    }
+
+    // Symbol page of a template rendered in memory, as parsed from a symbols file
+    fn render_symbol_page_data(&self, template: &Symbol) -> DrawcktResult<SymbolPageData> {
+        let mut symbol_page = Page::new(None, false);
+        self.render_symbol(&mut symbol_page, template)
+            .map_err(|e| e.in_symbol(&template.id))?;
+        let mut symbol_file = DrawFile::new();
+        symbol_file.add_page(symbol_page);
+        let Some((_, symbol_page_data)) =
+            Self::parse_drawio_file(&symbol_file.xml()?.to_string())?.pop()
+        else {
+            return Err(DrawcktError::NoPage);
+        };
+        Ok(symbol_page_data)
+    }
+
+    // Placement of the symbol objects of the instance on the page
+    fn group_transform<'i>(
+        &self,
+        instance: &'i Instance,
+        symbol_page_data: &SymbolPageData,
+    ) -> GroupTransform<'i> {
+        GroupTransform::new(
+            symbol_page_data.origin_bounding_box,
+            instance.x * self.schematic.scale(),
+            -instance.y * self.schematic.scale(),
+            instance.orient,
+            &instance.name,
+            instance.symbol_id.cell.as_ref(),
+        )
+        .with_params(instance.params.clone())
+        .with_cell_tag(self.cell_tags)
+        .with_tag_sanitizer(self.tag_sanitizer)
+    }
+
+    // Place the symbol objects of an instance, returns their visual bounding boxes
+    fn render_instance(
+        &self,
+        instance: &Instance,
+        symbol_pages: &IndexMap<&DesignId, SymbolPageData>,
+        page: &mut Page,
+    ) -> DrawcktResult<Vec<BoundingBox>> {
+        let Some(symbol_page_data) = symbol_pages.get(&instance.symbol_id) else {
+            return Err(DrawcktError::SymbolNotFound(instance.symbol_id.to_string())
+                .in_instance(&instance.name, &instance.symbol_id));
+        };
+        let group_transform = self.group_transform(instance, symbol_page_data);
+        let layer_ids: HashSet<String> = self
+            .layer_styles
+            .layer_cells()
+            .into_iter()
+            .map(|(_, id, _)| id)
+            .collect();
+        let link = self.instance_link(instance);
+        let mut instance_bboxes = Vec::new();
+        let mut new_objs = Vec::new();
+        for (i, obj) in symbol_page_data.objects.iter().enumerate() {
+            if obj
+                .xml_parent()
+                .is_some_and(|parent| self.layer_styles.is_disabled_layer_id(parent))
+            {
+                continue;
+            }
+            if let DiagramObject::Raw(raw) = obj {
+                warn!(
+                    "instance {} ({}): raw XML cell {} cannot be transformed, skipped",
+                    instance.name,
+                    instance.symbol_id,
+                    raw.id()
+                );
+                continue;
+            }
+            // Get the new group bounding box
+            let mut new_obj = group_transform.new_obj(obj).map_err(|e| {
+                DrawcktError::from(e)
+                    .in_shape(i)
+                    .in_instance(&instance.name, &instance.symbol_id)
+            })?;
+            if self.flatten_instances {
+                new_obj.set_tag(None);
+            } else {
+                new_obj.set_attribute("lib", instance.symbol_id.lib.as_ref())?;
+                new_obj.set_attribute("cell", instance.symbol_id.cell.as_ref())?;
+            }
+            if let Some(parent) = new_obj.xml_parent()
+                && parent.starts_with("layer-")
+                && !layer_ids.contains(parent)
+            {
+                let fallback = match Layer::parse_id(parent) {
+                    Some((_, LayerCell::Label)) => self.fallback_layer.id_label(),
+                    Some((_, LayerCell::User)) => self.fallback_layer.id_user(),
+                    _ => self.fallback_layer.id_shape(false),
+                };
+                if !layer_ids.contains(&fallback) {
+                    debug!(
+                        "skip {}: fallback layer {} is disabled",
+                        new_obj.id(),
+                        self.fallback_layer
+                    );
+                    continue;
+                }
+                warn!(
+                    "instance {} ({}): unknown parent {parent} of {}, moved to {fallback}",
+                    instance.name,
+                    instance.symbol_id,
+                    new_obj.id()
+                );
+                new_obj.set_xml_parent(Some(fallback));
+            }
+            if let Some(link) = &link
+                && new_obj
+                    .xml_parent()
+                    .and_then(Layer::parse_id)
+                    .is_some_and(|(layer, _)| layer == Layer::Device)
+            {
+                new_obj.set_link(Some(link.clone()));
+            }
+            self.snap_object(&mut new_obj);
+            instance_bboxes.extend(Self::visual_bounding_box(&new_obj));
+            new_objs.push(new_obj);
+        }
+        if self.group_instances && !new_objs.is_empty() {
+            let parent = [
+                Layer::Instance.id_shape(false),
+                self.fallback_layer.id_shape(false),
+            ]
+            .into_iter()
+            .find(|id| layer_ids.contains(id))
+            .unwrap_or_else(|| "1".to_string());
+            let group = group_transform.group_cell(&parent).map_err(|e| {
+                DrawcktError::from(e).in_instance(&instance.name, &instance.symbol_id)
+            })?;
+            for new_obj in &mut new_objs {
+                GroupTransform::move_into_group(new_obj, &group);
+            }
+            page.add_object(DiagramObject::XmlBase(group));
+        }
+        for new_obj in new_objs {
+            page.add_object(new_obj);
+        }
+        Ok(instance_bboxes)
+    }
+
+    /// Render the new schematic with the delta of `diff` highlighted on a diff layer:
+    /// green for added, red for removed (with ghosts of the old instances) and
+    /// orange for moved or changed items
+    pub fn render_diff(
+        &self,
+        diff: &SchematicDiff,
+        symbols_content: &SymbolContexts,
+    ) -> DrawcktResult<Page> {
+        self.with_ids(|| {
+            let symbol_pages = Self::parse_symbol_pages(symbols_content)?;
+            let scale = self.schematic.scale();
+            let mut page = self.render_schematic_page(symbols_content)?;
+            page.add_layer_cell(true, DIFF_LAYER_ID.to_string(), "diff".to_string());
+
+            let mut marks = Vec::new();
+            // Removed instances are drawn as ghosts of the old data, the
+            // symbols removed with them are rendered from the old schematic
+            let removed_symbol_pages = diff
+                .removed_symbols
+                .iter()
+                .filter(|template| !symbol_pages.contains_key(&template.id))
+                .map(|template| Ok((&template.id, self.render_symbol_page_data(template)?)))
+                .collect::<DrawcktResult<IndexMap<_, _>>>()?;
+            for instance in &diff.removed_instances {
+                let mut ghost = Page::new(None, false);
+                let ghost_symbol_pages = if symbol_pages.contains_key(&instance.symbol_id) {
+                    &symbol_pages
+                } else {
+                    &removed_symbol_pages
+                };
+                let bboxes = self.render_instance(instance, ghost_symbol_pages, &mut ghost)?;
+                for obj in ghost.objects() {
+                    let mut obj = obj.clone();
+                    obj.set_id(format!("diff-ghost-{}", obj.id()));
+                    obj.set_xml_parent(Some(DIFF_LAYER_ID.to_string()));
+                    match &mut obj {
+                        DiagramObject::Object(o) => o.set_opacity(Some(DIFF_OPACITY)),
+                        DiagramObject::Edge(e) => e.set_opacity(Some(DIFF_OPACITY)),
+                        DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => {}
+                    }
+                    page.add_object(obj);
+                }
+                marks.extend(
+                    BoundingBox::union(bboxes.into_iter()).map(|bbox| (bbox, DIFF_REMOVED_COLOR)),
+                );
+            }
+            for instance in &diff.added_instances {
+                let bboxes =
+                    self.render_instance(instance, &symbol_pages, &mut Page::new(None, false))?;
+                marks.extend(
+                    BoundingBox::union(bboxes.into_iter()).map(|bbox| (bbox, DIFF_ADDED_COLOR)),
+                );
+            }
+            for moved in &diff.moved_instances {
+                let bboxes =
+                    self.render_instance(&moved.new, &symbol_pages, &mut Page::new(None, false))?;
+                marks.extend(
+                    BoundingBox::union(bboxes.into_iter()).map(|bbox| (bbox, DIFF_MOVED_COLOR)),
+                );
+            }
+            for wire in &diff.removed_wires {
+                marks.extend(
+                    Self::points_bounding_box(&wire.points, scale)
+                        .map(|bbox| (bbox, DIFF_REMOVED_COLOR)),
+                );
+            }
+            for wire in &diff.added_wires {
+                marks.extend(
+                    Self::points_bounding_box(&wire.points, scale)
+                        .map(|bbox| (bbox, DIFF_ADDED_COLOR)),
+                );
+            }
+            let pin_points = |pin: &Pin| vec![[OrderedFloat(pin.x), OrderedFloat(pin.y)]];
+            for pin in &diff.removed_pins {
+                marks.extend(
+                    Self::points_bounding_box(&pin_points(pin), scale)
+                        .map(|bbox| (bbox, DIFF_REMOVED_COLOR)),
+                );
+            }
+            for pin in &diff.added_pins {
+                marks.extend(
+                    Self::points_bounding_box(&pin_points(pin), scale)
+                        .map(|bbox| (bbox, DIFF_ADDED_COLOR)),
+                );
+            }
+            for changed in &diff.changed_pins {
+                marks.extend(
+                    Self::points_bounding_box(&pin_points(&changed.new), scale)
+                        .map(|bbox| (bbox, DIFF_MOVED_COLOR)),
+                );
+            }
+
+            for (i, (mut bbox, color)) in marks.into_iter().enumerate() {
+                bbox.expand(DIFF_PADDING);
+                let mut obj = Object::new(Some(format!("diff-{i}")));
+                obj.set_position([bbox.min_x(), bbox.min_y()]);
+                obj.set_width(bbox.width());
+                obj.set_height(bbox.height());
+                obj.set_fill_color(Some(color.to_string()));
+                obj.set_stroke_color(Some(color.to_string()));
+                obj.set_opacity(Some(DIFF_OPACITY));
+                obj.set_xml_parent(Some(DIFF_LAYER_ID.to_string()));
+                page.add_object(DiagramObject::from(obj));
+            }
+            Ok(page)
+        })
+    }
+
+    // Page bounding box of schematic points
+    fn points_bounding_box(points: &[[OrderedFloat<f64>; 2]], scale: f64) -> Option<BoundingBox> {
+        BoundingBox::union(
+            points
+                .iter()
+                .map(|[x, y]| BoundingBox::new(x.0 * scale, -y.0 * scale, 0.0, 0.0)),
+        )
+    }
+
+    // Bounding box of an object as displayed, taking its rotation into account
+    fn visual_bounding_box(obj: &DiagramObject) -> Option<BoundingBox> {
+        let mut bbox = obj.bounding_box()?;
+        let rotation = match obj {
+            DiagramObject::Object(object) => object.geometry_ref().flip_rotation().rotation(),
+            _ => None,
+        };
+        if rotation.is_some_and(|r| (r.rem_euclid(180.0) - 90.0).abs() < f64::EPSILON) {
+            let [center_x, center_y] = bbox.center();
+            let (width, height) = (bbox.height(), bbox.width());
+            bbox = BoundingBox::new(
+                center_x - width / 2.0,
+                center_y - height / 2.0,
+                width,
+                height,
+            );
+        }
+        Some(bbox)
+    }
+
+    // Nearly transparent box over the transformed symbol bounding box
+    fn render_instance_hit_box(
+        &self,
+        instance: &Instance,
+        symbol_pages: &IndexMap<&DesignId, SymbolPageData>,
+        page: &mut Page,
+    ) -> DrawcktResult<()> {
+        let Some(symbol_page_data) = symbol_pages.get(&instance.symbol_id) else {
+            return Err(DrawcktError::SymbolNotFound(instance.symbol_id.to_string())
+                .in_instance(&instance.name, &instance.symbol_id));
+        };
+        let bbox = self
+            .group_transform(instance, symbol_page_data)
+            .bounding_box()
+            .map_err(|e| DrawcktError::from(e).in_instance(&instance.name, &instance.symbol_id))?;
+        let mut obj = Object::new(Some(format!("{}-hit", sanitize_id(&instance.name))));
+        obj.set_position([bbox.min_x(), bbox.min_y()]);
+        obj.set_width(bbox.width());
+        obj.set_height(bbox.height());
+        // draw.io does not select fully transparent shapes
+        obj.set_fill_color(Some("#FFFFFF".to_string()));
+        obj.set_stroke_color(Some("none".to_string()));
+        obj.set_opacity(Some(2));
+        obj.set_xml_parent(Some(INSTANCE_HIT_LAYER.to_string()));
+        obj.set_link(self.instance_link(instance));
+        let mut obj = DiagramObject::from(obj);
+        if !self.flatten_instances {
+            obj.set_tag(Some((self.tag_sanitizer)(&instance.name)));
+            if self.cell_tags {
+                obj.add_tag(&(self.tag_sanitizer)(instance.symbol_id.cell.as_ref()));
+            }
+            obj.base_mut().tooltip = Some(format!("{} ({})", instance.name, instance.symbol_id));
+            obj.set_attribute("lib", instance.symbol_id.lib.as_ref())?;
+            obj.set_attribute("cell", instance.symbol_id.cell.as_ref())?;
+        }
+        self.snap_object(&mut obj);
+        page.add_object(obj);
+        Ok(())
+    }
+
+    // Invisible box over the instance and its terminals, with a connection
+    // point at each terminal of the symbol
+    fn render_instance_terminals(
+        &self,
+        instance: &Instance,
+        instance_bboxes: &[BoundingBox],
+        page: &mut Page,
+    ) {
+        let layer = Layer::Instance;
+        let Some(symbol) = self
+            .schematic
+            .symbols
+            .iter()
+            .find(|symbol| symbol.id == instance.symbol_id)
+        else {
+            debug!(
+                "skip terminals of {}: symbol is not in the schematic",
+                instance.name
+            );
+            return;
+        };
+        if symbol.pins.is_empty() || !self.layer_styles.layer_enabled(&layer) {
+            return;
+        }
+        let scale = self.schematic.scale();
+        let terminals: Vec<[f64; 2]> = instance
+            .terminal_points(symbol)
+            .into_iter()
+            .map(|[x, y]| [self.snap(x * scale), self.snap(-y * scale)])
+            .collect();
+        let terminal_bboxes = terminals
+            .iter()
+            .map(|&[x, y]| BoundingBox::new(x, y, 0.0, 0.0));
+        let Some(bbox) = BoundingBox::union(instance_bboxes.iter().copied().chain(terminal_bboxes))
+        else {
+            return;
+        };
+        let normalize = |value: f64, min: f64, size: f64| {
+            if size > 0.0 {
+                (value - min) / size
+            } else {
+                0.5
+            }
+        };
+        let mut obj = Object::new(Some(format!("{}-terminals", sanitize_id(&instance.name))));
+        obj.set_position([bbox.min_x(), bbox.min_y()]);
+        obj.set_width(bbox.width());
+        obj.set_height(bbox.height());
+        obj.set_fill_color(Some("none".to_string()));
+        obj.set_stroke_color(Some("none".to_string()));
+        obj.set_connection_points(
+            terminals
+                .iter()
+                .map(|&[x, y]| {
+                    [
+                        normalize(x, bbox.min_x(), bbox.width()),
+                        normalize(y, bbox.min_y(), bbox.height()),
+                    ]
+                })
+                .collect(),
+        );
+        obj.set_xml_parent(Some(layer.id_shape(false)));
+        let mut obj = DiagramObject::from(obj);
+        if !self.flatten_instances {
+            obj.set_tag(Some((self.tag_sanitizer)(&instance.name)));
+            if self.cell_tags {
+                obj.add_tag(&(self.tag_sanitizer)(instance.symbol_id.cell.as_ref()));
+            }
+        }
+        page.add_object(obj);
+    }
+
+    // Legend of the enabled layers with their stroke colors, from the top
+    // layer down, at the chosen corner outside of the content
+    fn render_layer_legend(&self, page: &mut Page) {
+        const MARGIN: f64 = 20.0;
+        let label_layer = Layer::Annotate;
+        if !self.layer_styles.layer_enabled(&label_layer) {
+            debug!("skip layer legend: layer {label_layer} is disabled");
+            return;
+        }
+        let mapping = self
+            .layer_styles
+            .stacked_layers()
+            .into_iter()
+            .rev()
+            .filter(|layer| self.layer_styles.layer_enabled(layer))
+            .map(|layer| {
+                let color = self
+                    .layer_styles
+                    .layer_style(&layer)
+                    .stroke_color
+                    .to_string();
+                (layer.to_string(), color)
+            });
+        let Ok(mut legend) = drawrs::Legend::new(mapping) else {
+            return;
+        };
+        legend.set_title(Some("Layers".to_string()));
+        legend.set_border(true);
+
+        let scale = self.schematic.scale();
+        let content = BoundingBox::union(
+            page.objects()
+                .iter()
+                .filter_map(Self::visual_bounding_box)
+                .chain(
+                    self.schematic
+                        .wires
+                        .iter()
+                        .filter_map(|wire| Self::points_bounding_box(&wire.points, scale)),
+                ),
+        )
+        .unwrap_or(BoundingBox::new(0.0, 0.0, 0.0, 0.0));
+        let [width, height] = legend.size();
+        let x = match self.layer_styles.layer_legend_corner {
+            LegendCorner::TopLeft | LegendCorner::BottomLeft => content.min_x(),
+            LegendCorner::TopRight | LegendCorner::BottomRight => content.max_x() - width,
+        };
+        let y = match self.layer_styles.layer_legend_corner {
+            LegendCorner::TopLeft | LegendCorner::TopRight => content.min_y() - MARGIN - height,
+            LegendCorner::BottomLeft | LegendCorner::BottomRight => content.max_y() + MARGIN,
+        };
+        legend.move_to([x, y]);
+
+        for (i, mut obj) in legend.into_objects().into_iter().enumerate() {
+            obj.set_id(format!("legend-{i}"));
+            obj.set_xml_parent(Some(label_layer.id_label()));
+            let mut obj = DiagramObject::from(obj);
+            self.snap_object(&mut obj);
+            page.add_object(obj);
+        }
+    }
+
+    // Multi-line `key=value` label just below the instance bounding box
+    fn render_instance_params(
+        &self,
+        instance: &Instance,
+        bbox: BoundingBox,
+        page: &mut Page,
+    ) -> DrawcktResult<()> {
+        let layer = Layer::Annotate;
+        if instance.params.is_empty() || !self.layer_styles.layer_enabled(&layer) {
+            return Ok(());
+        }
+        let layer_style = self.layer_styles.layer_style(&layer);
+        let text = instance
+            .params
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let font_height =
+            1.2 * PARAM_TEXT_HEIGHT * self.schematic.user_unit_scale() * layer_style.font_zoom;
+        let width = text_width(font_height, &text);
+        let height = font_height * instance.params.len() as f64;
+
+        let mut obj = Object::new(Some(format!("{}-params", sanitize_id(&instance.name))));
+        obj.set_value(text);
+        obj.set_position([bbox.center()[0] - width / 2.0, bbox.max_y()]);
+        obj.set_width(width);
+        obj.set_height(height);
+        obj.set_fill_color(Some("none".to_string()));
+        obj.set_stroke_color(Some("none".to_string()));
+        layer_style.set_text_format(&mut obj, self.layer_styles)?;
+        obj.set_font_size(Some(font_height));
+        obj.set_justify(Justify {
+            x: JustifyX::Center,
+            y: JustifyY::Top,
+        });
+        obj.set_xml_parent(Some(layer.id_label()));
+        let mut obj = DiagramObject::from(obj);
+        if !self.flatten_instances {
+            obj.set_tag(Some((self.tag_sanitizer)(&instance.name)));
+            if self.cell_tags {
+                obj.add_tag(&(self.tag_sanitizer)(instance.symbol_id.cell.as_ref()));
+            }
+        }
+        self.snap_object(&mut obj);
+        page.add_object(obj);
+        Ok(())
+    }
+
+    // Build the edge of a line shape, `points` has at least two points
+    fn line_edge(
+        &self,
+        layer: &Layer,
+        points: &[[OrderedFloat<f64>; 2]],
+        obj_id: String,
+        is_intersection: bool,
+    ) -> Edge {
+        let points = match self.simplify_epsilon {
+            Some(epsilon) => Cow::Owned(Self::simplify_polyline(points, epsilon)),
+            None => Cow::Borrowed(points),
+        };
+        let scale = self.schematic.scale();
+        let source = &points[0];
+        let target = &points[points.len() - 1];
+        let intermediate = if points.len() > 2 {
+            points[1..points.len() - 1].to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let width = (target[0] - source[0]).abs() * scale;
+        let height = (target[1] - source[1]).abs() * scale;
+
+        let source_x = source[0] * scale;
+        let source_y = -source[1] * scale;
+        let target_x = target[0] * scale;
+        let target_y = -target[1] * scale;
+
+        // The stroke comes from the line class of the layer, see `init_layers`
+        let mut edge = Edge::new_wire(Some(obj_id));
+        edge.set_style_class(Some(self.layer_styles.line_class(layer, is_intersection)));
+        edge.set_xml_parent(Some(layer.id_shape(is_intersection)));
+        edge.geometry().set_width(width);
+        edge.geometry().set_height(height);
+        edge.geometry().set_relative(Some(true));
+        edge.geometry()
+            .set_source_point(Some([*source_x, *source_y]));
+        edge.geometry()
+            .set_target_point(Some([*target_x, *target_y]));
+
+        for point in &intermediate {
+            let point_x = point[0] * scale;
+            let point_y = -point[1] * scale;
+            edge.geometry().add_intermediate_point([*point_x, *point_y]);
+        }
+        edge
     }
 
     // Unified function to render a single Shape
@@ -518,64 +2362,43 @@ impl<'a> Renderer<'a> {
         obj_id: String,
         is_intersection: bool,
     ) -> DrawcktResult<()> {
+        if !self.layer_styles.layer_enabled(shape.layer()) {
+            debug!("skip {obj_id}: layer {} is disabled", shape.layer());
+            return Ok(());
+        }
+        let scale = self.schematic.scale();
+        if !self.layer_styles.has_layer(shape.layer()) {
+            return Err(DrawcktError::UnknownLayer(shape.layer().to_string()));
+        }
+        let first = page.objects().len();
         match shape {
             Shape::Rect {
                 layer,
                 fill_style,
                 b_box,
             } => {
-                let x = b_box[0][0] * SCALE;
-                let y = -b_box[1][1] * SCALE;
-                let width = (b_box[1][0] - b_box[0][0]) * SCALE;
-                let height = (b_box[1][1] - b_box[0][1]) * SCALE;
-                let layer_style = self.layer_styles.layer_style(layer);
+                let b_box = normalize_b_box(b_box);
+                let x = b_box[0][0] * scale;
+                let y = -b_box[1][1] * scale;
+                let width = (b_box[1][0] - b_box[0][0]) * scale;
+                let height = (b_box[1][1] - b_box[0][1]) * scale;
 
                 let mut obj = Object::new(Some(obj_id));
                 obj.set_position([*x, *y]);
-                obj.set_width(width.abs());
-                obj.set_height(height.abs());
-                self.apply_fill_style(&mut obj, *fill_style, layer_style);
+                obj.set_width(*width);
+                obj.set_height(*height);
+                // The paint comes from the fill class of the layer, see `init_layers`
+                obj.set_style_class(Some(self.layer_styles.fill_class(
+                    layer,
+                    is_intersection,
+                    *fill_style,
+                )));
                 obj.set_xml_parent(Some(layer.id_shape(is_intersection)));
                 page.add_object(DiagramObject::Object(obj));
             }
             Shape::Line { layer, points } => {
                 if points.len() >= 2 {
-                    let source = &points[0];
-                    let target = &points[points.len() - 1];
-                    let intermediate = if points.len() > 2 {
-                        points[1..points.len() - 1].to_vec()
-                    } else {
-                        Vec::new()
-                    };
-
-                    let width = (target[0] - source[0]).abs() * SCALE;
-                    let height = (target[1] - source[1]).abs() * SCALE;
-
-                    let source_x = source[0] * SCALE;
-                    let source_y = -source[1] * SCALE;
-                    let target_x = target[0] * SCALE;
-                    let target_y = -target[1] * SCALE;
-
-                    let layer_style = self.layer_styles.layer_style(layer);
-
-                    let mut edge = Edge::new(Some(obj_id));
-                    edge.set_stroke_width(Some(layer_style.stroke_width));
-                    edge.set_stroke_color(Some(layer_style.stroke_color.clone().into_owned()));
-                    edge.set_xml_parent(Some(layer.id_shape(is_intersection)));
-                    edge.geometry().set_width(width);
-                    edge.geometry().set_height(height);
-                    edge.geometry().set_relative(Some(true));
-                    edge.geometry()
-                        .set_source_point(Some([*source_x, *source_y]));
-                    edge.geometry()
-                        .set_target_point(Some([*target_x, *target_y]));
-
-                    for point in &intermediate {
-                        let point_x = point[0] * SCALE;
-                        let point_y = -point[1] * SCALE;
-                        edge.geometry().add_intermediate_point([*point_x, *point_y]);
-                    }
-
+                    let edge = self.line_edge(layer, points, obj_id, is_intersection);
                     page.add_object(DiagramObject::Edge(edge));
                 }
             }
@@ -589,11 +2412,24 @@ impl<'a> Renderer<'a> {
                 font,
             } => {
                 let layer_style = self.layer_styles.layer_style(layer);
-                let mut x = xy[0] * SCALE;
-                let mut y = -xy[1] * SCALE;
-                let font_height = 1.2 * height.as_ref() * SCALE * layer_style.font_zoom;
-                let font_width = font_height * text.len() as f64 / 2.0;
+                let mut x = xy[0] * scale;
+                let mut y = -xy[1] * scale;
+                let font_height = self.layer_styles.clamp_font_size(
+                    1.2 * height.as_ref() * scale * layer_style.font_zoom,
+                    text,
+                    layer,
+                );
+                // Characters are half as wide as high, see `text_width`
+                let max_chars = layer_style
+                    .wrap_width
+                    .map(|width| ((2.0 * width / font_height) as usize).max(1));
+                let lines = wrap_lines(text, max_chars);
+                let font_width = text_width(font_height, &lines.join("\n"));
+                let block_height = font_height * lines.len().max(1) as f64;
+                // The box is padded around the text, which keeps its place
+                let padding = layer_style.label_padding();
                 let mut obj = Object::new(Some(obj_id));
+                obj.set_spacing(layer_style.label_spacing + padding);
                 {
                     // Adjust x based on JustifyX
                     match justify.x {
@@ -613,35 +2449,46 @@ impl<'a> Renderer<'a> {
                             // y is already at the top edge, no adjustment needed
                         }
                         JustifyY::Middle => {
-                            y -= font_height / 2.0;
+                            y -= block_height / 2.0;
                         }
                         JustifyY::Bottom => {
-                            y -= font_height;
-                            obj.apply_style_property("spacing", "0");
-                            obj.apply_style_property("spacingBottom", "-2");
+                            y -= block_height;
+                            obj.set_spacing_bottom(layer_style.label_baseline_adjust * font_height);
                         }
                     }
                 }
 
-                obj.set_value(text.clone());
-                obj.set_position([*x, *y]);
-                obj.set_width(font_width);
-                obj.set_height(font_height);
-                obj.set_fill_color(Some("none".to_string()));
-                obj.set_stroke_color(Some("none".to_string()));
-                obj.set_font_color(Some(layer_style.text_color.clone().into_owned()));
+                if lines.len() > 1 {
+                    // Note blocks, the lines are joined with HTML line breaks
+                    let html_lines: Vec<String> =
+                        lines.iter().map(|line| XMLBase::xml_ify(line)).collect();
+                    obj.set_value(html_lines.join("<br>"));
+                    obj.set_white_space(Some("wrap".to_string()));
+                    obj.set_html(true);
+                } else {
+                    obj.set_value(text.clone());
+                }
+                obj.set_position([*x - padding, *y - padding]);
+                obj.set_width(font_width + 2.0 * padding);
+                obj.set_height(block_height + 2.0 * padding);
+                layer_style.set_label_box(&mut obj);
+                layer_style.set_text_format(&mut obj, self.layer_styles)?;
                 obj.set_font_size(Some(font_height));
-                obj.set_font_family(Some(layer_style.font_family.clone().into_owned()));
                 obj.set_xml_parent(Some(layer.id_label()));
                 obj.set_justify(*justify);
+                obj.set_autosize(self.labels_autosize);
                 page.add_object(obj.into());
             }
             Shape::Polygon {
                 layer,
                 fill_style,
                 points,
+                holes,
+                rounded,
             } => {
-                if points.len() >= 3 {
+                if points.len() < 3 {
+                    warn!("skip {obj_id}: polygon with {} points", points.len());
+                } else {
                     // Calculate bounding box for the polygon
                     let mut min_x = points[0][0];
                     let mut min_y_local = points[0][1];
@@ -655,47 +2502,80 @@ impl<'a> Renderer<'a> {
                         max_y_local = max_y_local.max(point[1]);
                     }
 
-                    let x = min_x * SCALE;
-                    let y = -max_y_local * SCALE;
-                    let width = (max_x - min_x) * SCALE;
-                    let height = (max_y_local - min_y_local) * SCALE;
+                    let x = min_x * scale;
+                    let y = -max_y_local * scale;
+                    let width = (max_x - min_x) * scale;
+                    let height = (max_y_local - min_y_local) * scale;
 
-                    // Convert points to normalized coordinates (0-1) within the bounding box
-                    // Draw.io polygon uses polyCoords in format "[[x1,y1],[x2,y2],...]"
-                    // Coordinates are normalized (0-1) relative to the bounding box
-                    // Note: Y coordinates in polyCoords are also flipped (1 - norm_y)
-                    let bbox_width = max_x - min_x;
-                    let bbox_height = max_y_local - min_y_local;
-
-                    let poly_coords: Vec<[f64; 2]> = points
-                        .iter()
-                        .map(|p| {
-                            let norm_x = if *bbox_width > 0.0 {
-                                (p[0].as_ref() - min_x.as_ref()) / bbox_width.as_ref()
-                            } else {
-                                0.0
-                            };
-                            // Flip Y coordinate: norm_y_flipped = 1 - norm_y
-                            let norm_y = if *bbox_height > 0.0 {
-                                (p[1].as_ref() - min_y_local.as_ref()) / bbox_height.as_ref()
-                            } else {
-                                0.0
-                            };
-                            let norm_y_flipped = 1.0 - norm_y;
-                            [norm_x, norm_y_flipped]
-                        })
-                        .collect();
-
-                    let layer_style = self.layer_styles.layer_style(layer);
-
-                    let mut obj = Object::new(Some(obj_id));
+                    let mut obj = Object::new(Some(obj_id.clone()));
                     obj.set_position([*x, *y]);
                     obj.set_width(width.abs());
                     obj.set_height(height.abs());
-                    self.apply_fill_style(&mut obj, *fill_style, layer_style);
+                    obj.set_style_class(Some(self.layer_styles.fill_class(
+                        layer,
+                        is_intersection,
+                        *fill_style,
+                    )));
                     obj.set_xml_parent(Some(layer.id_shape(is_intersection)));
-                    obj.set_shape("mxgraph.basic.polygon".to_string());
-                    obj.set_poly_coords(poly_coords);
+
+                    let holes: Vec<_> = holes
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, hole)| {
+                            if hole.len() < 3 {
+                                warn!("{obj_id}: skip hole #{i} with {} points", hole.len());
+                            }
+                            hole.len() >= 3
+                        })
+                        .map(|(_, hole)| hole)
+                        .collect();
+                    if holes.is_empty() {
+                        // Convert points to normalized coordinates (0-1) within the bounding box
+                        // Draw.io polygon uses polyCoords in format "[[x1,y1],[x2,y2],...]"
+                        // Coordinates are normalized (0-1) relative to the bounding box
+                        // Note: Y coordinates in polyCoords are also flipped (1 - norm_y)
+                        let bbox_width = max_x - min_x;
+                        let bbox_height = max_y_local - min_y_local;
+
+                        let poly_coords: Vec<[f64; 2]> = points
+                            .iter()
+                            .map(|p| {
+                                let norm_x = if *bbox_width > 0.0 {
+                                    (p[0].as_ref() - min_x.as_ref()) / bbox_width.as_ref()
+                                } else {
+                                    0.0
+                                };
+                                // Flip Y coordinate: norm_y_flipped = 1 - norm_y
+                                let norm_y = if *bbox_height > 0.0 {
+                                    (p[1].as_ref() - min_y_local.as_ref()) / bbox_height.as_ref()
+                                } else {
+                                    0.0
+                                };
+                                let norm_y_flipped = 1.0 - norm_y;
+                                [norm_x, norm_y_flipped]
+                            })
+                            .collect();
+                        obj.set_shape("mxgraph.basic.polygon".to_string());
+                        obj.set_poly_coords(poly_coords);
+                    } else {
+                        // polyCoords has a single ring, holes need a stencil path
+                        // in page units from the top-left corner
+                        let local = |ring: &Vec<[OrderedFloat<f64>; 2]>| -> Vec<[f64; 2]> {
+                            ring.iter()
+                                .map(|p| {
+                                    [*((p[0] - min_x) * scale), *((max_y_local - p[1]) * scale)]
+                                })
+                                .collect()
+                        };
+                        let mut rings = vec![wound(local(points), true)];
+                        rings.extend(holes.into_iter().map(|hole| wound(local(hole), false)));
+                        let mut stencil = Stencil::new(*width, *height);
+                        stencil.add_path(&rings);
+                        obj.set_stencil(&stencil);
+                    }
+                    if *rounded {
+                        obj.set_rounded(Some(true));
+                    }
                     page.add_object(DiagramObject::Object(obj));
                 }
             }
@@ -704,18 +2584,21 @@ impl<'a> Renderer<'a> {
                 fill_style,
                 b_box,
             } => {
-                let x = b_box[0][0] * SCALE;
-                let y = -b_box[1][1] * SCALE;
-                let width = (b_box[1][0] - b_box[0][0]) * SCALE;
-                let height = (b_box[1][1] - b_box[0][1]) * SCALE;
-
-                let layer_style = self.layer_styles.layer_style(layer);
+                let b_box = normalize_b_box(b_box);
+                let x = b_box[0][0] * scale;
+                let y = -b_box[1][1] * scale;
+                let width = (b_box[1][0] - b_box[0][0]) * scale;
+                let height = (b_box[1][1] - b_box[0][1]) * scale;
 
                 let mut obj = Object::new(Some(obj_id));
                 obj.set_position([*x, *y]);
-                obj.set_width(width.abs());
-                obj.set_height(height.abs());
-                self.apply_fill_style(&mut obj, *fill_style, layer_style);
+                obj.set_width(*width);
+                obj.set_height(*height);
+                obj.set_style_class(Some(self.layer_styles.fill_class(
+                    layer,
+                    is_intersection,
+                    *fill_style,
+                )));
                 obj.set_xml_parent(Some(layer.id_shape(is_intersection)));
                 obj.set_shape("ellipse".to_string());
                 page.add_object(DiagramObject::Object(obj));
@@ -727,18 +2610,21 @@ impl<'a> Renderer<'a> {
                 start_angle,
                 stop_angle,
             } => {
-                let x = b_box[0][0] * SCALE;
-                let y = -b_box[1][1] * SCALE;
-                let width = (b_box[1][0] - b_box[0][0]) * SCALE;
-                let height = (b_box[1][1] - b_box[0][1]) * SCALE;
-
-                let layer_style = self.layer_styles.layer_style(layer);
+                let b_box = normalize_b_box(b_box);
+                let x = b_box[0][0] * scale;
+                let y = -b_box[1][1] * scale;
+                let width = (b_box[1][0] - b_box[0][0]) * scale;
+                let height = (b_box[1][1] - b_box[0][1]) * scale;
 
                 let mut obj = Object::new(Some(obj_id));
                 obj.set_position([*x, *y]);
-                obj.set_width(width.abs());
-                obj.set_height(height.abs());
-                self.apply_fill_style(&mut obj, *fill_style, layer_style);
+                obj.set_width(*width);
+                obj.set_height(*height);
+                obj.set_style_class(Some(self.layer_styles.fill_class(
+                    layer,
+                    is_intersection,
+                    *fill_style,
+                )));
                 obj.set_xml_parent(Some(layer.id_shape(is_intersection)));
                 obj.set_shape("mxgraph.basic.arc".to_string());
                 fn to_drawio_angle(radian_counterclockwise_x_begin: &f64) -> f64 {
@@ -760,49 +2646,55 @@ impl<'a> Renderer<'a> {
                 page.add_object(DiagramObject::Object(obj));
             }
         }
+        for obj in &mut page.objects_mut()[first..] {
+            self.snap_object(obj);
+        }
         Ok(())
     }
 
     fn render_symbol(&self, page: &mut Page, template: &Symbol) -> DrawcktResult<()> {
-        self.layer_styles.init_layers(page)?;
-
-        let mut lines_wire = Vec::new();
-        let mut lines_instance = Vec::new();
-        let mut lines_annotate = Vec::new();
-        let mut lines_pin = Vec::new();
-        let mut lines_device = Vec::new();
-        let mut lines_text = Vec::new();
+        self.layer_styles.init_layers(page);
+
+        // Lines are merged per layer, built-in layers first
+        let mut lines_by_layer: IndexMap<Layer, Vec<Vec<Point>>> = [
+            Layer::Wire,
+            Layer::Instance,
+            Layer::Annotate,
+            Layer::Pin,
+            Layer::Device,
+            Layer::Text,
+        ]
+        .into_iter()
+        .map(|layer| (layer, Vec::new()))
+        .collect();
         let mut idx = 0;
+        let mut used_ids = HashSet::new();
         for shape in &template.shapes {
+            if !self.layer_styles.layer_enabled(shape.layer()) {
+                debug!("skip {}: layer {} is disabled", template.id, shape.layer());
+                continue;
+            }
             if let Shape::Line { layer, points } = shape {
-                match layer {
-                    Layer::Wire => lines_wire.push(points),
-                    Layer::Instance => lines_instance.push(points),
-                    Layer::Annotate => lines_annotate.push(points),
-                    Layer::Pin => lines_pin.push(points),
-                    Layer::Device => lines_device.push(points),
-                    Layer::Text => lines_text.push(points),
-                }
+                lines_by_layer
+                    .entry(layer.clone())
+                    .or_default()
+                    .push(points.iter().copied().map(Point::from).collect());
             } else {
-                self.render_shape(shape, page, template.gen_obj_id(shape.layer(), idx), false)?;
+                let obj_id = self.symbol_obj_id(template, shape, idx, &mut used_ids);
+                self.render_shape(shape, page, obj_id, false)
+                    .map_err(|e| e.in_shape(idx))?;
                 idx += 1;
             }
         }
-        for (layer, lines) in [
-            (Layer::Wire, lines_wire),
-            (Layer::Instance, lines_instance),
-            (Layer::Annotate, lines_annotate),
-            (Layer::Pin, lines_pin),
-            (Layer::Device, lines_device),
-            (Layer::Text, lines_text),
-        ] {
-            for points in Self::merge_lines(lines) {
-                self.render_shape(
-                    &Shape::Line { layer, points },
-                    page,
-                    template.gen_obj_id(&layer, idx),
-                    false,
-                )?;
+        for (layer, lines) in lines_by_layer {
+            for points in merge_polylines(&lines) {
+                let shape = Shape::Line {
+                    layer: layer.clone(),
+                    points: points.into_iter().map(Point::into).collect(),
+                };
+                let obj_id = self.symbol_obj_id(template, &shape, idx, &mut used_ids);
+                self.render_shape(&shape, page, obj_id, false)
+                    .map_err(|e| e.in_shape(idx))?;
                 idx += 1;
             }
         }
@@ -810,103 +2702,338 @@ impl<'a> Renderer<'a> {
     }
 
     pub fn render_schematic_file(&self, symbols_content: &SymbolContexts) -> DrawcktResult<String> {
-        // Parse symbol contexts to extract pages
-        let mut symbol_pages = IndexMap::new();
-        for (symbol_id, content) in &symbols_content.0 {
-            let mut pages = Self::parse_drawio_file(content)?;
-            // Each symbol file should have only one page
-            if let Some((_, page_data)) = pages.pop() {
-                symbol_pages.insert(symbol_id, page_data);
-            } else {
-                return Err(DrawcktError::SymbolNotFound(symbol_id.to_string()));
+        Ok(self.render_schematic_file_with_report(symbols_content)?.0)
+    }
+
+    /// [`Self::render_schematic_file`] with the schematic item of each cell
+    pub fn render_schematic_file_with_report(
+        &self,
+        symbols_content: &SymbolContexts,
+    ) -> DrawcktResult<(String, RenderReport)> {
+        self.with_ids(|| {
+            let (page, report) = self.render_schematic_page_with_report(symbols_content)?;
+            let mut schematic_file = self.layer_styles.draw_file();
+            schematic_file.add_page(page);
+            Ok((self.file_xml(&schematic_file)?, report))
+        })
+    }
+
+    /// Render the schematic page, e.g. for [`Page::to_svg`]
+    pub fn render_schematic_page(&self, symbols_content: &SymbolContexts) -> DrawcktResult<Page> {
+        Ok(self.render_schematic_page_with_report(symbols_content)?.0)
+    }
+
+    /// Render the schematic in memory and drop it, to find the errors of the
+    /// inputs without writing anything. The warnings, e.g. of the edited
+    /// symbols, are logged.
+    pub fn check(&self, symbols_content: &SymbolContexts) -> DrawcktResult<()> {
+        self.check_layer_styles()?;
+        self.render_schematic_page(symbols_content)?;
+        Ok(())
+    }
+
+    /// [`Self::render_schematic_page`] with the schematic item of each cell
+    pub fn render_schematic_page_with_report(
+        &self,
+        symbols_content: &SymbolContexts,
+    ) -> DrawcktResult<(Page, RenderReport)> {
+        self.with_ids(|| {
+            let symbol_pages = Self::parse_symbol_pages(symbols_content)?;
+            self.check_stale_symbols(&symbol_pages)?;
+            self.check_symbol_boxes(&symbol_pages);
+
+            // Set page name to "{lib}/{cell}"
+            let page_name = self.schematic.design.to_string();
+            let mut schematic_page = Page::new(Some(page_name.clone()), false);
+            schematic_page.set_name(page_name);
+            self.layer_styles.init_layers(&mut schematic_page);
+            // Source of each page object, extended after each item
+            let mut sources = Vec::new();
+            let mark = |sources: &mut Vec<CellSource>, page: &Page, source: CellSource| {
+                sources.resize(page.objects().len(), source);
+            };
+            mark(&mut sources, &schematic_page, CellSource::Root);
+
+            self.render_items(
+                &symbol_pages,
+                &mut schematic_page,
+                OverlayKinds::ALL,
+                &mut sources,
+            )?;
+            if self.layer_legend {
+                self.render_layer_legend(&mut schematic_page);
+                mark(&mut sources, &schematic_page, CellSource::Legend);
             }
-        }
 
-        // Create schematic.drawio canvas
-        let mut schematic_file = DrawFile::new();
+            if self.flatten_instances {
+                let renamed = schematic_page.dedup_ids();
+                if renamed > 0 {
+                    debug!("renamed {renamed} duplicated object ids");
+                }
+            }
 
-        // Set page name to "{lib}/{cell}"
-        let page_name = self.schematic.design.to_string();
-        let mut schematic_page = Page::new(Some(page_name.clone()), false);
-        schematic_page.set_name(page_name);
-        self.layer_styles.init_layers(&mut schematic_page)?;
+            let report = RenderReport {
+                cells: schematic_page
+                    .objects()
+                    .iter()
+                    .map(|obj| obj.id().to_string())
+                    .zip(sources)
+                    .collect(),
+            };
+            Ok((schematic_page, report))
+        })
+    }
 
-        // Process each instance
-        for instance in &self.schematic.instances {
-            if let Some(symbol_page_data) = symbol_pages.get(&instance.symbol_id) {
-                // Create GroupTransform using origin_bounding_box from SymbolPageData
-                let group_transform = GroupTransform::new(
-                    symbol_page_data.origin_bounding_box,
-                    instance.x * SCALE,
-                    -instance.y * SCALE,
-                    instance.orient,
-                    &instance.name,
-                    instance.symbol_id.cell.as_ref(),
+    // Render the selected schematic items onto `page`, extending `sources`
+    // with the source of each new object
+    fn render_items(
+        &self,
+        symbol_pages: &IndexMap<&DesignId, SymbolPageData>,
+        schematic_page: &mut Page,
+        what: OverlayKinds,
+        sources: &mut Vec<CellSource>,
+    ) -> DrawcktResult<()> {
+        let mark = |sources: &mut Vec<CellSource>, page: &Page, source: CellSource| {
+            sources.resize(page.objects().len(), source);
+        };
+        let first = schematic_page.objects().len();
+        mark(sources, schematic_page, CellSource::Root);
+        if what.instances {
+            if self.instance_hit_boxes
+                && schematic_page
+                    .objects()
+                    .iter()
+                    .all(|obj| obj.id() != INSTANCE_HIT_LAYER)
+            {
+                schematic_page.add_layer_cell(
+                    true,
+                    INSTANCE_HIT_LAYER.to_string(),
+                    "instance-hit".to_string(),
                 );
-                for obj in &symbol_page_data.objects {
-                    // Get the new group bounding box
-                    schematic_page.add_object(group_transform.new_obj(obj)?);
+                mark(sources, schematic_page, CellSource::Root);
+            }
+            // Process each instance
+            for (index, instance) in self.schematic.instances.iter().enumerate() {
+                let instance_bboxes =
+                    self.render_instance(instance, symbol_pages, schematic_page)?;
+                mark(
+                    sources,
+                    schematic_page,
+                    CellSource::Instance {
+                        name: instance.name.clone(),
+                        index,
+                        lib: instance.symbol_id.lib.to_string(),
+                        cell: instance.symbol_id.cell.to_string(),
+                    },
+                );
+                if self.instance_hit_boxes {
+                    self.render_instance_hit_box(instance, symbol_pages, schematic_page)?;
+                    mark(
+                        sources,
+                        schematic_page,
+                        CellSource::InstanceHit {
+                            name: instance.name.clone(),
+                            index,
+                        },
+                    );
+                }
+                if self.connection_points {
+                    self.render_instance_terminals(instance, &instance_bboxes, schematic_page);
+                    mark(
+                        sources,
+                        schematic_page,
+                        CellSource::Terminals {
+                            name: instance.name.clone(),
+                            index,
+                        },
+                    );
+                }
+                if self.layer_styles.show_instance_params
+                    && let Some(bbox) = BoundingBox::union(instance_bboxes.into_iter())
+                {
+                    self.render_instance_params(instance, bbox, schematic_page)?;
+                    mark(
+                        sources,
+                        schematic_page,
+                        CellSource::InstanceParams {
+                            name: instance.name.clone(),
+                            index,
+                        },
+                    );
                 }
-            } else {
-                return Err(DrawcktError::SymbolNotFound(instance.symbol_id.to_string()));
             }
         }
 
-        // Render wires in wire layer
-        // Convert wires to HashMap grouped by net, then merge lines and render using Shape::Line
-        let wires_by_net = self.wires_to_shapes_by_net();
-        let mut wire_counter = 0;
+        if what.wires {
+            // Render wires in wire layer
+            // Convert wires to IndexMap grouped by net, then merge lines and render using Shape::Line
+            // Wires are always on the wire layer, skip the whole pass when it is disabled
+            let wires_by_net = if self.layer_styles.layer_enabled(&Layer::Wire) {
+                self.wires_to_shapes_by_net()
+            } else {
+                debug!("skip wires: layer {} is disabled", Layer::Wire);
+                IndexMap::new()
+            };
+            let tolerance = self.schematic.snap_tolerance();
+            let pin_points = if self.split_wires_at_pins {
+                self.pin_points()
+            } else {
+                Vec::new()
+            };
+            let mut wire_counter = 0;
+
+            for (net_name, lines) in wires_by_net {
+                // Merge lines that share endpoints
+                let polylines: Vec<Vec<Point>> = lines
+                    .iter()
+                    .map(|(_, line)| line.iter().copied().map(Point::from).collect())
+                    .collect();
+                let breakpoints: HashSet<Point> = polylines
+                    .iter()
+                    .flatten()
+                    .filter(|point| {
+                        pin_points
+                            .iter()
+                            .any(|pin| snapped(*pin, [point.x(), point.y()], tolerance))
+                    })
+                    .copied()
+                    .collect();
+                let merged_lines = merge_polylines_with_breakpoints(&polylines, &breakpoints);
+
+                let net_color = self.layer_styles.net_color(&net_name);
+                for line in merged_lines {
+                    let line: Vec<_> = line.into_iter().map(Point::into).collect();
+                    wire_counter += 1;
+                    let mut edge = self.line_edge(
+                        &Layer::Wire,
+                        &line,
+                        Self::gen_wire_id(&net_name, wire_counter),
+                        false,
+                    );
+                    if let Some(color) = net_color {
+                        edge.set_stroke_color(Some(color.to_string()));
+                    }
+                    if !self.flatten_instances {
+                        edge.set_attribute("net", net_name.as_str())?;
+                    }
+                    let mut edge = DiagramObject::Edge(edge);
+                    self.snap_object(&mut edge);
+                    schematic_page.add_object(edge);
+                    // The wires whose points are all on the merged line
+                    let indices = lines
+                        .iter()
+                        .filter(|(_, points)| points.iter().all(|point| line.contains(point)))
+                        .map(|(i, _)| *i)
+                        .collect();
+                    mark(
+                        sources,
+                        schematic_page,
+                        CellSource::Wire {
+                            net: net_name.clone(),
+                            indices,
+                        },
+                    );
+                }
+            }
 
-        for (net_name, lines) in wires_by_net {
-            // Merge lines that share endpoints
-            let merged_lines = Self::merge_lines(lines);
+            if self.mark_open_ends && self.layer_styles.layer_enabled(&Layer::Wire) {
+                for (net, obj) in self.render_open_ends() {
+                    schematic_page.add_object(obj);
+                    mark(
+                        sources,
+                        schematic_page,
+                        CellSource::OpenEnd {
+                            net: net.to_string(),
+                        },
+                    );
+                }
+            }
+        }
 
-            // Render each merged line using render_shape
-            for line in merged_lines {
-                wire_counter += 1;
+        let first_label = schematic_page.objects().len();
+        if what.pins {
+            // Render pins in pin layer, the label sizes are in user units
+            let per_user_unit = self.schematic.units.unwrap_or_default().per_user_unit();
+            let offset = self.layer_styles.pin_label_offset * per_user_unit;
+            for (i, pin) in self.schematic.pins.iter().enumerate() {
+                let direction = self.pin_wire_direction(pin);
+                self.render_pin_symbol(i, pin, direction, symbol_pages, schematic_page)
+                    .map_err(|e| e.in_pin(&pin.name))?;
+                // The name goes on the side of the pin opposite to its wire
+                let (dx, dy, justify) = match direction {
+                    Some([x, y]) if x.abs() >= y.abs() && x < 0.0 => (offset, 0.0, JustifyX::Left),
+                    Some([x, y]) if y.abs() > x.abs() => {
+                        (0.0, -offset * y.signum(), JustifyX::Center)
+                    }
+                    _ => (-offset, 0.0, JustifyX::Right),
+                };
+                let justify = Justify {
+                    x: justify,
+                    y: match dy {
+                        dy if dy > 0.0 => JustifyY::Bottom,
+                        dy if dy < 0.0 => JustifyY::Top,
+                        _ => JustifyY::Middle,
+                    },
+                };
                 self.render_shape(
-                    &Shape::Line {
-                        points: line,
-                        layer: Layer::Wire,
+                    &Shape::Label {
+                        layer: Layer::Pin,
+                        text: pin.name.clone(),
+                        xy: [
+                            ordered_float::OrderedFloat(pin.x + dx),
+                            ordered_float::OrderedFloat(pin.y + dy),
+                        ],
+                        orient: "".to_string(),
+                        height: ordered_float::OrderedFloat(
+                            self.layer_styles.pin_label_height * per_user_unit,
+                        ),
+                        justify,
+                        font: Font::Stick,
                     },
-                    &mut schematic_page,
-                    Self::gen_wire_id(&net_name, wire_counter),
+                    schematic_page,
+                    format!("pin-{i}"),
                     false,
-                )?;
-            }
-        }
-
-        // Render pins in pin layer
-        for (i, pin) in self.schematic.pins.iter().enumerate() {
-            self.render_shape(
-                &Shape::Label {
-                    layer: Layer::Pin,
-                    text: pin.name.clone(),
-                    xy: [
-                        ordered_float::OrderedFloat(pin.x - 0.175),
-                        ordered_float::OrderedFloat(pin.y),
-                    ],
-                    orient: "".to_string(),
-                    height: ordered_float::OrderedFloat(0.1),
-                    justify: Justify {
-                        x: JustifyX::Right,
-                        y: JustifyY::Middle,
+                )
+                .map_err(|e| e.in_pin(&pin.name))?;
+                mark(
+                    sources,
+                    schematic_page,
+                    CellSource::Pin {
+                        name: pin.name.clone(),
+                        index: i,
                     },
-                    font: Font::Stick,
-                },
-                &mut schematic_page,
-                format!("pin-{i}"),
-                false,
-            )?;
+                );
+            }
         }
 
-        // Render labels
+        // Render labels, the exact duplicates of an earlier label or shape are skipped
+        let mut seen_shapes = HashSet::new();
+        let mut duplicates = 0;
         for (i, label) in self.schematic.labels.iter().enumerate() {
-            self.render_shape(label, &mut schematic_page, format!("label-{i}"), false)?;
+            if !seen_shapes.insert(label) {
+                duplicates += 1;
+                continue;
+            }
+            if !what.labels {
+                continue;
+            }
+            self.render_shape(label, schematic_page, format!("label-{i}"), false)
+                .map_err(|e| e.in_label(i))?;
+            mark(sources, schematic_page, CellSource::Label { index: i });
+        }
+        if self.avoid_label_overlap && (what.pins || what.labels) {
+            self.avoid_label_overlap(schematic_page, first_label);
         }
 
         // Render shapes (with wire_show_intersection check)
         for (i, shape) in self.schematic.shapes.iter().enumerate() {
+            if !what.shapes {
+                break;
+            }
+            if !seen_shapes.insert(shape) {
+                duplicates += 1;
+                continue;
+            }
             // Skip wire layer shapes if wire_show_intersection is false
             if shape.layer().eq(&Layer::Wire) {
                 if let Shape::Ellipse {
@@ -916,6 +3043,7 @@ impl<'a> Renderer<'a> {
                 } = shape
                 {
                     // Scale the bounding box while keeping the center point unchanged
+                    let b_box = normalize_b_box(b_box);
                     let scale = self.layer_styles.wire_intersection_scale;
                     let center_x = (b_box[0][0] + b_box[1][0]) / 2.0;
                     let center_y = (b_box[0][1] + b_box[1][1]) / 2.0;
@@ -927,26 +3055,109 @@ impl<'a> Renderer<'a> {
                         [center_x - new_width / 2.0, center_y - new_height / 2.0],
                         [center_x + new_width / 2.0, center_y + new_height / 2.0],
                     ];
+                    let fill_style = if self.layer_styles.wire_intersection_filled {
+                        FillStyleKind::Filled
+                    } else {
+                        *fill_style
+                    };
                     self.render_shape(
                         &Shape::Ellipse {
-                            layer: *layer,
-                            fill_style: *fill_style,
+                            layer: layer.clone(),
+                            fill_style,
                             b_box: scaled_b_box,
                         },
-                        &mut schematic_page,
+                        schematic_page,
                         format!("shape-{i}"),
                         true,
-                    )?;
+                    )
+                    .map_err(|e| e.in_shape(i))?;
                 } else {
-                    self.render_shape(shape, &mut schematic_page, format!("shape-{i}"), false)?;
+                    self.render_shape(shape, schematic_page, format!("shape-{i}"), false)
+                        .map_err(|e| e.in_shape(i))?;
                 }
             } else {
-                self.render_shape(shape, &mut schematic_page, format!("shape-{i}"), false)?;
+                self.render_shape(shape, schematic_page, format!("shape-{i}"), false)
+                    .map_err(|e| e.in_shape(i))?;
+            }
+            mark(sources, schematic_page, CellSource::Shape { index: i });
+        }
+        if duplicates > 0 {
+            debug!("skipped {duplicates} duplicate labels and shapes");
+        }
+
+        if !self.flatten_instances {
+            let objects = &mut schematic_page.objects_mut()[first..];
+            for (obj, source) in objects.iter_mut().zip(&sources[first..]) {
+                if *source != CellSource::Root {
+                    obj.set_attribute(SOURCE_ATTRIBUTE, source.kind())?;
+                }
             }
         }
+        Ok(())
+    }
+
+    /// Render only the `what` items of the schematic onto an existing page,
+    /// e.g. a hand-annotated page rebuilt from the objects of
+    /// [`Self::parse_drawio_file`], to regenerate the wires after a netlist
+    /// change. The objects the selected kinds of items rendered before, found
+    /// by their [`SOURCE_ATTRIBUTE`], are removed first, so overlaying twice
+    /// replaces rather than duplicates, and the instances removed from the
+    /// schematic leave no objects. The objects of a page rendered with
+    /// [`Self::with_flatten_instances`] have no such mark and are kept.
+    /// Missing layer cells are added.
+    pub fn render_overlay(
+        &self,
+        symbols_content: &SymbolContexts,
+        page: &mut Page,
+        what: OverlayKinds,
+    ) -> DrawcktResult<()> {
+        self.with_ids(|| {
+            let symbol_pages = Self::parse_symbol_pages(symbols_content)?;
+            if what.instances || what.pins {
+                self.check_stale_symbols(&symbol_pages)?;
+            }
+            self.layer_styles.init_layers(page);
+            let kinds = Self::overlay_source_kinds(what);
+            page.retain_objects(|obj| {
+                obj.attribute(SOURCE_ATTRIBUTE)
+                    .is_none_or(|kind| !kinds.contains(&kind.as_str()))
+            });
+            self.render_items(&symbol_pages, page, what, &mut Vec::new())?;
+            if self.flatten_instances {
+                let renamed = page.dedup_ids();
+                if renamed > 0 {
+                    debug!("renamed {renamed} duplicated object ids");
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // Source kinds of the objects rendered for the `what` items
+    fn overlay_source_kinds(what: OverlayKinds) -> Vec<&'static str> {
+        let mut kinds = Vec::new();
+        if what.instances {
+            kinds.extend(["instance", "terminals", "instanceHit", "instanceParams"]);
+        }
+        if what.wires {
+            kinds.extend(["wire", "openEnd"]);
+        }
+        if what.pins {
+            kinds.push("pin");
+        }
+        if what.labels {
+            kinds.push("label");
+        }
+        if what.shapes {
+            kinds.push("shape");
+        }
+        kinds
+    }
 
-        schematic_file.add_page(schematic_page);
-        Ok(schematic_file.xml().to_string())
+    /// Show or hide the wire intersection layer of a rendered file on all of
+    /// its pages, without re-rendering it as `wire_show_intersection` would
+    pub fn set_wire_intersection_visible(content: &str, visible: bool) -> DrawcktResult<String> {
+        set_layer_cell_visible(content, &Layer::Wire.id_shape(true), visible)
     }
 
     // Parse symbols.drawio file to extract pages
@@ -958,212 +3169,111 @@ impl<'a> Renderer<'a> {
         let mut buf = Vec::new();
 
         let mut current_page_name: Option<String> = None;
-        let mut current_layer_names: Vec<String> = Vec::new();
-        let mut current_layer_set: std::collections::HashSet<String> =
-            std::collections::HashSet::new();
         let mut current_objects: Vec<drawrs::page::DiagramObject> = Vec::new();
+        let mut current_style_hash = None;
+        let mut current_symbol_hash = None;
+        let mut current_page_id = None;
+        let mut current_layer_cells = Vec::new();
+        // The cell being read, its events go straight to the builder
+        let mut current_cell: Option<CellBuilder> = None;
 
         let mut in_diagram = false;
         let mut in_root = false;
-        let mut in_object = false;
-        let mut current_object_xml = String::new();
 
         loop {
-            match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(e)) => {
-                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-
-                    if name == "diagram" {
+            let event_start = reader.buffer_position();
+            let event = match reader.read_event_into(&mut buf) {
+                Ok(Event::Eof) => break,
+                Ok(event) => event,
+                Err(e) => return Err(DrawcktError::XmlParsing(e)),
+            };
+            if let Some(cell) = current_cell.as_mut() {
+                if cell.push_event(&event) {
+                    current_objects.push(current_cell.take().unwrap().finish());
+                }
+                buf.clear();
+                continue;
+            }
+            match &event {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"diagram" => {
                         // Save previous page if exists
                         if let Some(prev_page_name) = current_page_name.take() {
-                            let objects = std::mem::take(&mut current_objects);
-                            let origin_bounding_box = BoundingBox::union(
-                                objects.iter().filter_map(DiagramObject::bounding_box),
-                            )
-                            .unwrap_or_else(|| BoundingBox::new(0.0, 0.0, 0.0, 0.0));
                             pages.insert(
-                                prev_page_name.clone(),
-                                SymbolPageData {
-                                    objects,
-                                    origin_bounding_box,
-                                },
+                                prev_page_name,
+                                SymbolPageData::new(
+                                    std::mem::take(&mut current_objects),
+                                    current_style_hash.take(),
+                                    current_symbol_hash.take(),
+                                    current_page_id.take(),
+                                    std::mem::take(&mut current_layer_cells),
+                                ),
                             );
-                            current_layer_set.clear();
                         }
-
-                        // Start new page (already cleared by take above)
                         in_diagram = false;
 
-                        // Get name attribute
-                        for attr in e.attributes().flatten() {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            if key == "name" {
-                                let value = String::from_utf8_lossy(&attr.value).to_string();
-                                current_page_name = Some(value);
-                                in_diagram = true;
-                                break;
-                            }
-                        }
-                    } else if name == "root" && in_diagram {
-                        in_root = true;
-                    } else if name == "UserObject" && in_root {
-                        // Parse UserObject to extract tag (layer name) and start capturing XML
-                        let mut tag_value: Option<String> = None;
-                        let mut label_value: Option<String> = None;
+                        // Get name and provenance attributes
                         for attr in e.attributes().flatten() {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            let val = String::from_utf8_lossy(&attr.value).to_string();
-                            if key == "tags" {
-                                tag_value = Some(val.clone());
-                                // Extract layer name from tag
-                                if Self::parse_layer_name(&val).is_ok() {
-                                    if !current_layer_set.contains(&val) {
-                                        current_layer_names.push(val.clone());
-                                        current_layer_set.insert(val.clone());
-                                    }
+                            match attr.key.as_ref() {
+                                b"name" => {
+                                    current_page_name = Some(attr_value(&attr));
+                                    in_diagram = true;
                                 }
-                            } else if key == "label" {
-                                label_value = Some(val);
-                            }
-                        }
-                        // Start capturing XML for UserObject
-                        in_object = true;
-                        if let Some(ref tag) = tag_value {
-                            if let Some(ref label) = label_value {
-                                let label_escaped = XMLBase::xml_ify(label);
-                                current_object_xml = format!(
-                                    r#"<UserObject label="{}" tags="{}""#,
-                                    label_escaped,
-                                    XMLBase::xml_ify(tag)
-                                );
-                            } else {
-                                current_object_xml = format!(
-                                    r#"<UserObject label="" tags="{}""#,
-                                    XMLBase::xml_ify(tag)
-                                );
-                            }
-                        } else {
-                            if let Some(ref label) = label_value {
-                                let label_escaped = XMLBase::xml_ify(label);
-                                current_object_xml =
-                                    format!(r#"<UserObject label="{}""#, label_escaped);
-                            } else {
-                                current_object_xml = format!(r#"<UserObject label="""#);
+                                b"id" => current_page_id = Some(attr_value(&attr)),
+                                b"styleHash" => current_style_hash = Some(attr_value(&attr)),
+                                b"symbolHash" => current_symbol_hash = Some(attr_value(&attr)),
+                                _ => {}
                             }
                         }
-                        for attr in e.attributes().flatten() {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            if key != "tags" && key != "label" {
-                                let val = String::from_utf8_lossy(&attr.value).to_string();
-                                let val_escaped = XMLBase::xml_ify(&val);
-                                current_object_xml
-                                    .push_str(&format!(r#" {}="{}""#, key, val_escaped));
-                            }
-                        }
-                        current_object_xml.push_str(">");
-                    } else if name == "mxCell" && (in_root || in_object) {
-                        // This is an object, start capturing XML
-                        // IMPORTANT: quick_xml automatically decodes XML entities in attribute values
-                        // So if symbols.drawio has value="cdsTerm(&quot;G&quot;)",
-                        // attr.value will be "cdsTerm(\"G\")" (decoded)
-                        // We need to re-escape it for the XML string we're building
-                        if !in_object {
-                            in_object = true;
-                            current_object_xml = format!("<mxCell");
-                        } else {
-                            // Inside UserObject, add mxCell
-                            current_object_xml.push_str("<mxCell");
-                        }
-                        for attr in e.attributes().flatten() {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            // attr.value is already decoded by quick_xml, so we need to escape it
-                            let val = String::from_utf8_lossy(&attr.value).to_string();
-                            // Escape special characters for XML output
-                            // Use xml_ify which properly handles &, <, >, ", '
-                            let val_escaped = XMLBase::xml_ify(&val);
-                            current_object_xml.push_str(&format!(r#" {}="{}""#, key, val_escaped));
-                        }
-                        current_object_xml.push_str(">");
-                    } else if in_object
-                        && (name == "mxGeometry" || name == "mxPoint" || name == "Array")
-                    {
-                        let tag = format!("<{}", name);
-                        current_object_xml.push_str(&tag);
-                        for attr in e.attributes().flatten() {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            // attr.value is already decoded by quick_xml
-                            let val = String::from_utf8_lossy(&attr.value).to_string();
-                            // Escape for XML output
-                            let val_escaped = XMLBase::xml_ify(&val);
-                            current_object_xml.push_str(&format!(r#" {}="{}""#, key, val_escaped));
-                        }
-                        if name == "mxPoint" || name == "Array" {
-                            current_object_xml.push_str(" />");
-                        } else {
-                            current_object_xml.push_str(">");
-                        }
                     }
+                    b"root" if in_diagram => in_root = true,
+                    // `<object>` is the same wrapper in newer draw.io versions
+                    b"UserObject" | b"object" | b"mxCell" if in_root => {
+                        let mut cell = CellBuilder::new();
+                        cell.push_event(&event);
+                        current_cell = Some(cell);
+                    }
+                    // Elements that are not cells are kept verbatim
+                    _ if in_root => {
+                        let end = e.to_end().into_owned();
+                        reader
+                            .read_to_end(end.name())
+                            .map_err(DrawcktError::XmlParsing)?;
+                        let xml = &content[event_start..reader.buffer_position()];
+                        current_objects.extend(Self::raw_xml(xml));
+                    }
+                    _ => {}
+                },
+                Event::Empty(e) if in_root && e.name().as_ref() == b"mxCell" => {
+                    current_layer_cells.extend(Self::layer_cell(e));
                 }
-                Ok(Event::End(e)) => {
-                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-
-                    if name == "diagram" {
+                Event::Empty(e)
+                    if in_root
+                        && !matches!(e.name().as_ref(), b"UserObject" | b"object" | b"mxCell") =>
+                {
+                    let xml = &content[event_start..reader.buffer_position()];
+                    current_objects.extend(Self::raw_xml(xml));
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"diagram" => {
                         if let Some(page_name) = current_page_name.take() {
-                            let objects = std::mem::take(&mut current_objects);
-                            let origin_bounding_box = BoundingBox::union(
-                                objects.iter().filter_map(DiagramObject::bounding_box),
-                            )
-                            .unwrap_or_else(|| BoundingBox::new(0.0, 0.0, 0.0, 0.0));
                             pages.insert(
-                                page_name.clone(),
-                                SymbolPageData {
-                                    objects,
-                                    origin_bounding_box,
-                                },
+                                page_name,
+                                SymbolPageData::new(
+                                    std::mem::take(&mut current_objects),
+                                    current_style_hash.take(),
+                                    current_symbol_hash.take(),
+                                    current_page_id.take(),
+                                    std::mem::take(&mut current_layer_cells),
+                                ),
                             );
-                            current_layer_set.clear();
                         }
                         in_diagram = false;
                         in_root = false;
-                    } else if name == "root" {
-                        in_root = false;
-                    } else if name == "UserObject" && in_object {
-                        // End of UserObject, parse the complete object (including inner mxCell)
-                        current_object_xml.push_str("</UserObject>");
-                        // Parse XML and create Object or Edge instance
-                        current_objects.push(parse_xml_to_object(&current_object_xml)?);
-                        in_object = false;
-                        current_object_xml.clear();
-                    } else if name == "mxCell" && in_object {
-                        current_object_xml.push_str("</mxCell>");
-                        // Parse XML and create Object, Edge, or XmlBase (for groups) instance
-                        current_objects.push(parse_xml_to_object(&current_object_xml)?);
-                        in_object = false;
-                        current_object_xml.clear();
-                    } else if in_object && name == "mxGeometry" {
-                        current_object_xml.push_str("</mxGeometry>");
-                    }
-                }
-                Ok(Event::Empty(e)) => {
-                    // Handle self-closing tags
-                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    if in_object && (name == "mxGeometry" || name == "mxPoint" || name == "Array") {
-                        // Handle self-closing tags like <mxGeometry ... /> within objects
-                        let tag = format!("<{}", name);
-                        current_object_xml.push_str(&tag);
-                        for attr in e.attributes().flatten() {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            // attr.value is already decoded by quick_xml
-                            let val = String::from_utf8_lossy(&attr.value).to_string();
-                            // Escape for XML output
-                            let val_escaped = XMLBase::xml_ify(&val);
-                            current_object_xml.push_str(&format!(r#" {}="{}""#, key, val_escaped));
-                        }
-                        current_object_xml.push_str(" />");
                     }
-                }
-                Ok(Event::Eof) => break,
-                Err(e) => return Err(DrawcktError::XmlParsing(e)),
+                    b"root" => in_root = false,
+                    _ => {}
+                },
                 _ => {}
             }
             buf.clear();
@@ -1172,10 +3282,35 @@ impl<'a> Renderer<'a> {
         Ok(pages)
     }
 
-    fn parse_layer_name(s: &str) -> DrawcktResult<String> {
-        match s {
-            "instance" | "annotate" | "pin" | "device" => Ok(s.to_string()),
-            _ => Err(DrawcktError::UnknownLayer(s.to_string())),
+    // Layer cell of an empty `<mxCell>` under the root cell "0", the other
+    // empty cells (the root cell included) are left to the page
+    fn layer_cell(e: &BytesStart) -> Option<XMLBase> {
+        let mut cell = XMLBase::with_class("mxCell".to_string());
+        for attr in e.attributes().flatten() {
+            let value = attr_value(&attr);
+            match attr.key.as_ref() {
+                b"id" => cell.id = value,
+                b"parent" => cell.xml_parent = Some(value),
+                b"value" => cell.value = Some(value),
+                b"visible" => cell.visible = Some(value),
+                key => {
+                    let key = String::from_utf8_lossy(key).into_owned();
+                    cell.preserved_attributes.insert(key, value);
+                }
+            }
+        }
+        (cell.xml_parent.as_deref() == Some("0")).then_some(cell)
+    }
+
+    // Raw XML object of an element `parse_drawio_file` cannot map, elements
+    // without an id are dropped
+    fn raw_xml(xml: &str) -> Option<DiagramObject> {
+        match RawXml::new(xml) {
+            Ok(raw) => Some(DiagramObject::Raw(raw)),
+            Err(e) => {
+                warn!("unsupported element dropped: {e}");
+                None
+            }
         }
     }
 
@@ -1184,23 +3319,147 @@ impl<'a> Renderer<'a> {
         old_style: &LayerStyles,
         new_style: &LayerStyles,
     ) -> DrawcktResult<String> {
+        new_style
+            .validate()
+            .map_err(DrawcktError::InvalidLayerStyles)?;
         // Each symbol file should have only one page
         let (page_name, page_data) = Self::parse_drawio_file(content)?
             .pop()
             .ok_or(DrawcktError::NoPage)?;
-        let mut page = Page::new(Some(page_name.clone()), false);
+        let mut file = new_style.draw_file();
+        file.add_page(Self::restyle_page(
+            page_name, page_data, old_style, new_style,
+        )?);
+        updated_xml(content, file)
+    }
+
+    /// [`Self::update_style`] of a rendered schematic file with any number of
+    /// pages. The objects on the layers of `old_style` are restyled, all the
+    /// others (e.g. drawn by hand) are kept untouched. The layer cells that
+    /// the style change leaves as is are kept, the others are regenerated
+    /// from `new_style`.
+    pub fn update_schematic_style(
+        content: &str,
+        old_style: &LayerStyles,
+        new_style: &LayerStyles,
+    ) -> DrawcktResult<String> {
+        new_style
+            .validate()
+            .map_err(DrawcktError::InvalidLayerStyles)?;
+        let pages = Self::parse_drawio_file(content)?;
+        if pages.is_empty() {
+            return Err(DrawcktError::NoPage);
+        }
+        let mut file = new_style.draw_file();
+        for (page_name, page_data) in pages {
+            file.add_page(Self::restyle_page(
+                page_name, page_data, old_style, new_style,
+            )?);
+        }
+        updated_xml(content, file)
+    }
+
+    /// Add the [`CrossRefs`] connectors of the nets drawn on several pages of
+    /// a rendered schematic file, replacing the ones of a previous call
+    pub fn add_cross_refs(
+        content: &str,
+        layer_styles: &LayerStyles,
+        style: &CrossRefStyle,
+    ) -> DrawcktResult<String> {
+        layer_styles
+            .validate()
+            .map_err(DrawcktError::InvalidLayerStyles)?;
+        let pages = Self::parse_drawio_file(content)?;
+        if pages.is_empty() {
+            return Err(DrawcktError::NoPage);
+        }
+        let mut new_pages: Vec<Page> = pages
+            .into_iter()
+            .map(|(page_name, page_data)| {
+                let page_id = page_data.page_id.unwrap_or_else(|| page_name.clone());
+                let mut page = Page::new(Some(page_id), false);
+                page.set_name(page_name);
+                Self::restyle_layer_cells(
+                    &mut page,
+                    page_data.layer_cells,
+                    layer_styles,
+                    layer_styles,
+                );
+                layer_styles.init_layers(&mut page);
+                for obj in page_data.objects {
+                    if !is_connector(&obj) {
+                        page.add_object(obj);
+                    }
+                }
+                page
+            })
+            .collect();
+        CrossRefs::collect(&new_pages).add_connectors(&mut new_pages, layer_styles, style)?;
+        let mut file = layer_styles.draw_file();
+        for page in new_pages {
+            file.add_page(page);
+        }
+        Ok(file.xml()?.to_string())
+    }
+
+    // Page of `page_data` with the new layer cells and restyled objects
+    fn restyle_page(
+        page_name: String,
+        mut page_data: SymbolPageData,
+        old_style: &LayerStyles,
+        new_style: &LayerStyles,
+    ) -> DrawcktResult<Page> {
+        let page_id = page_data
+            .page_id
+            .take()
+            .unwrap_or_else(|| page_name.clone());
+        let mut page = Page::new(Some(page_id), false);
         page.set_name(page_name);
-        new_style.init_layers(&mut page)?;
+        // A generated symbol stays up to date with the new style
+        if let Some(symbol_hash) = page_data.symbol_hash.clone() {
+            page.set_attribute(
+                "styleHash".to_string(),
+                format!("{:08x}", new_style.symbol_hash()),
+            )?;
+            page.set_attribute("symbolHash".to_string(), symbol_hash)?;
+        }
+        let layer_cells = std::mem::take(&mut page_data.layer_cells);
+        Self::restyle_layer_cells(&mut page, layer_cells, old_style, new_style);
+        new_style.init_layers(&mut page);
         for obj_res in page_data.update_style(old_style, new_style) {
             // Get the new group bounding box
             if let Some(obj) = obj_res? {
                 page.add_object(obj);
             }
         }
-        let mut file = DrawFile::new();
-        file.add_page(page);
-        Ok(file.xml().to_string())
+        Ok(page)
+    }
+
+    // The layer cells of a page in their order: a cell of `old_style` that
+    // `new_style` leaves as is keeps its visibility and attributes, the
+    // changed ones are regenerated and the ones of disabled layers dropped.
+    // The cells not generated by the styles are kept.
+    fn restyle_layer_cells(
+        page: &mut Page,
+        cells: Vec<XMLBase>,
+        old_style: &LayerStyles,
+        new_style: &LayerStyles,
+    ) {
+        let old_cells = old_style.layer_cells();
+        let new_cells = new_style.layer_cells();
+        for cell in cells {
+            let old = old_cells.iter().find(|(_, id, _)| *id == cell.id);
+            match new_cells.iter().find(|(_, id, _)| *id == cell.id) {
+                Some(new) if old != Some(new) => {
+                    let (visible, id, name) = new.clone();
+                    page.add_layer_cell(visible, id, name);
+                }
+                None if old.is_some() => {}
+                _ => page.add_object(DiagramObject::XmlBase(cell)),
+            }
+        }
     }
+
     pub fn update_symbol_content(
         schematic_content: &str,
         symbol_content: &str,
@@ -1208,6 +3467,7 @@ impl<'a> Renderer<'a> {
         schematic: &Schematic,
         style: &LayerStyles,
     ) -> DrawcktResult<String> {
+        style.validate().map_err(DrawcktError::InvalidLayerStyles)?;
         // Each symbol file should have only one page
         let (page_name, page_data) = Self::parse_drawio_file(schematic_content)?
             .pop()
@@ -1218,115 +3478,164 @@ impl<'a> Renderer<'a> {
 
         let mut schematic_page = Page::new(Some(page_name.clone()), false);
         schematic_page.set_name(page_name);
-        style.init_layers(&mut schematic_page)?;
+        style.init_layers(&mut schematic_page);
         let mut inst_need_update = HashSet::new();
         for instance in &schematic.instances {
             if symbol_mapping.contains(&instance.symbol_id) {
-                inst_need_update.insert(&instance.name);
+                inst_need_update.insert(instance.name.as_str());
                 let group_transform = GroupTransform::new(
                     symbol_page_data.origin_bounding_box,
-                    instance.x * SCALE,
-                    -instance.y * SCALE,
+                    instance.x * schematic.scale(),
+                    -instance.y * schematic.scale(),
                     instance.orient,
                     &instance.name,
                     instance.symbol_id.cell.as_ref(),
-                );
+                )
+                .with_params(instance.params.clone());
                 for obj in &symbol_page_data.objects {
                     // Get the new group bounding box
                     schematic_page.add_object(group_transform.new_obj(obj)?);
                 }
             }
         }
+        // The group cells of the updated instances are dropped with their objects
+        let group_ids: HashSet<String> = inst_need_update
+            .iter()
+            .map(|name| sanitize_id(name))
+            .collect();
         for obj in page_data.objects {
-            if let Some(tag) = obj.tag()
-                && inst_need_update.contains(tag)
+            if obj.tags().any(|tag| inst_need_update.contains(tag))
+                || matches!(&obj, DiagramObject::XmlBase(base)
+                    if base.group_geometry.is_some() && group_ids.contains(&base.id))
             {
             } else {
                 schematic_page.add_object(obj);
             }
         }
-        let mut file = DrawFile::new();
+        let mut file = style.draw_file();
         file.add_page(schematic_page);
-        Ok(file.xml().to_string())
+        Ok(file.xml()?.to_string())
     }
 }
 
 impl LayerStyles {
-    fn init_layers(&self, page: &mut Page) -> DrawcktResult<()> {
-        page.add_layer_cell(
+    /// An empty file loading the [`Self::custom_fonts`]
+    pub fn draw_file(&self) -> DrawFile {
+        let mut file = DrawFile::new();
+        for (name, url) in &self.custom_fonts {
+            file.add_custom_font(name, url);
+        }
+        file
+    }
+
+    // Whether `id` is one of the layer cells of a disabled layer
+    fn is_disabled_layer_id(&self, id: &str) -> bool {
+        self.stacked_layers().iter().any(|layer| {
+            !self.layer_enabled(layer)
+                && (id == layer.id_user()
+                    || id == layer.id_label()
+                    || id == layer.id_shape(false)
+                    || id == layer.id_shape(true))
+        })
+    }
+
+    // Add the layer cells missing from the page and the line and fill style
+    // classes
+    fn init_layers(&self, page: &mut Page) {
+        for (visible, id, name) in self.layer_cells() {
+            if page.objects().iter().all(|obj| obj.id() != id) {
+                page.add_layer_cell(visible, id, name);
+            }
+        }
+        for layer in self.stacked_layers() {
+            if !self.layer_enabled(&layer) {
+                continue;
+            }
+            for is_intersection in [false, true] {
+                if is_intersection && layer != Layer::Wire {
+                    continue;
+                }
+                let classes = (1..=5)
+                    .filter_map(FillStyleKind::from_number)
+                    .map(|fill_style| self.fill_class(&layer, is_intersection, fill_style))
+                    .chain([self.line_class(&layer, is_intersection)]);
+                for class in classes {
+                    page.define_style(class.name, class.style);
+                }
+            }
+        }
+    }
+
+    // Style class of the lines of a layer, named after their layer cell
+    fn line_class(&self, layer: &Layer, is_intersection: bool) -> StyleClass {
+        let layer_style = self.shape_style(layer, is_intersection);
+        let mut edge = Edge::new_wire(Some(String::new()));
+        edge.set_stroke_width(Some(layer_style.stroke_width));
+        edge.set_stroke_color(Some(layer_style.stroke_color.clone().into_owned()));
+        edge.set_opacity(layer_style.opacity);
+        edge.set_line_join(layer_style.line_join);
+        edge.set_start_cap(layer_style.line_cap);
+        edge.set_end_cap(layer_style.line_cap);
+        StyleClass::new(layer.id_shape(is_intersection), edge.style().to_string())
+    }
+
+    // Style class of the closed shapes of a layer painted with `fill_style`,
+    // e.g. `layer-device-shape-fill2`
+    fn fill_class(
+        &self,
+        layer: &Layer,
+        is_intersection: bool,
+        fill_style: FillStyleKind,
+    ) -> StyleClass {
+        let layer_style = self.shape_style(layer, is_intersection);
+        let mut obj = Object::new(Some(String::new()));
+        Renderer::apply_fill_style(&mut obj, fill_style, layer_style);
+        StyleClass::new(
+            format!(
+                "{}-fill{}",
+                layer.id_shape(is_intersection),
+                fill_style.number()
+            ),
+            obj.style().to_string(),
+        )
+    }
+
+    // Visibility, id and name of the layer cells of a page, bottom to top,
+    // `layer_order` is assumed to be valid
+    fn layer_cells(&self) -> Vec<(bool, String, String)> {
+        let mut cells = vec![(
             true,
             "layer-background-your-drawing".to_string(),
             "background-your-drawing".to_string(),
-        );
-        let mut instance = false;
-        let mut annotate = false;
-        let mut pin = false;
-        let mut device = false;
-        let mut wire = false;
-        let mut text = false;
-        for layer in self.layer_order.iter().rev() {
-            match layer {
-                Layer::Instance => {
-                    if instance {
-                        return Err(DrawcktError::RepeatLayer(*layer));
-                    }
-                    instance = true;
-                }
-                Layer::Annotate => {
-                    if annotate {
-                        return Err(DrawcktError::RepeatLayer(*layer));
-                    }
-                    annotate = true;
-                }
-                Layer::Pin => {
-                    if pin {
-                        return Err(DrawcktError::RepeatLayer(*layer));
-                    }
-                    pin = true;
-                }
-                Layer::Device => {
-                    if device {
-                        return Err(DrawcktError::RepeatLayer(*layer));
-                    }
-                    device = true;
-                }
-                Layer::Wire => {
-                    if wire {
-                        return Err(DrawcktError::RepeatLayer(*layer));
-                    }
-                    wire = true;
-                }
-                Layer::Text => {
-                    if text {
-                        return Err(DrawcktError::RepeatLayer(*layer));
-                    }
-                    text = true;
-                }
+        )];
+        for layer in &self.stacked_layers() {
+            if !self.layer_enabled(layer) {
+                continue;
             }
-            page.add_layer_cell(true, layer.id_user(), format!("{layer}-your-drawing"));
+            cells.push((true, layer.id_user(), format!("{layer}-your-drawing")));
             if *layer == Layer::Wire {
-                page.add_layer_cell(
+                cells.push((
                     self.wire_show_intersection,
                     layer.id_shape(true),
                     format!("{layer}-intersection"),
-                );
+                ));
             }
-            page.add_layer_cell(
+            cells.push((
                 self.layer_style(layer).shape_sch_visible,
                 layer.id_shape(false),
                 format!("{layer}-shape"),
-            );
-            page.add_layer_cell(
+            ));
+            cells.push((
                 self.layer_style(layer).label_sch_visible,
                 layer.id_label(),
                 format!("{layer}-label"),
-            );
+            ));
         }
-        page.add_layer_cell(
+        cells.push((
             true,
             "layer-top-your-drawing".to_string(),
             "top-your-drawing".to_string(),
-        );
-        Ok(())
+        ));
+        cells
     }
 }
@@ -0,0 +1,106 @@
+//! Mapping of the cells of a rendered schematic back to the schematic items,
+//! see [`Renderer::render_schematic_file_with_report`].
+//!
+//! [`Renderer::render_schematic_file_with_report`]: crate::renderer::Renderer::render_schematic_file_with_report
+
+use crate::error::DrawcktResult;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Attribute of the rendered cells holding the [kind](CellSource::kind) of
+/// their source, e.g. `drawckt="wire"`, so that
+/// [`Renderer::render_overlay`] finds them again on an edited page, and
+/// `crossRef` for the connectors of [`CrossRefs`]. Left out with
+/// [`Renderer::with_flatten_instances`].
+///
+/// [`CrossRefs`]: crate::crossref::CrossRefs
+///
+/// [`Renderer::render_overlay`]: crate::renderer::Renderer::render_overlay
+/// [`Renderer::with_flatten_instances`]: crate::renderer::Renderer::with_flatten_instances
+pub const SOURCE_ATTRIBUTE: &str = "drawckt";
+
+/// Schematic item a rendered cell comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CellSource {
+    /// Root cell "0" or a layer cell
+    Root,
+    /// Object of the symbol of `instances[index]`, its id is the symbol
+    /// object id prefixed by `{name}-` with the name passed through
+    /// [`drawrs::xml_base::sanitize_id`]
+    #[serde(rename_all = "camelCase")]
+    Instance {
+        name: String,
+        index: usize,
+        lib: String,
+        cell: String,
+    },
+    /// Box with the connection points of the terminals of `instances[index]`
+    Terminals { name: String, index: usize },
+    /// Click target over `instances[index]`, see
+    /// [`Renderer::with_instance_hit_boxes`]
+    ///
+    /// [`Renderer::with_instance_hit_boxes`]: crate::renderer::Renderer::with_instance_hit_boxes
+    InstanceHit { name: String, index: usize },
+    /// Parameter text of `instances[index]`
+    InstanceParams { name: String, index: usize },
+    /// Merged line of the `wires` indices of the net
+    Wire { net: String, indices: Vec<usize> },
+    /// Mark of an open wire end of the net
+    OpenEnd { net: String },
+    /// Label of `pins[index]`
+    Pin { name: String, index: usize },
+    /// `labels[index]`
+    Label { index: usize },
+    /// `shapes[index]`
+    Shape { index: usize },
+    /// Part of the layer legend, see [`Renderer::with_layer_legend`]
+    ///
+    /// [`Renderer::with_layer_legend`]: crate::renderer::Renderer::with_layer_legend
+    Legend,
+}
+
+impl CellSource {
+    /// Name of the variant, the `kind` of the JSON report
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Root => "root",
+            Self::Instance { .. } => "instance",
+            Self::Terminals { .. } => "terminals",
+            Self::InstanceHit { .. } => "instanceHit",
+            Self::InstanceParams { .. } => "instanceParams",
+            Self::Wire { .. } => "wire",
+            Self::OpenEnd { .. } => "openEnd",
+            Self::Pin { .. } => "pin",
+            Self::Label { .. } => "label",
+            Self::Shape { .. } => "shape",
+            Self::Legend => "legend",
+        }
+    }
+}
+
+/// Source of each cell of a rendered schematic page, keyed by cell id in
+/// the order of the page objects
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RenderReport {
+    pub cells: IndexMap<String, CellSource>,
+}
+
+impl RenderReport {
+    pub fn get(&self, id: &str) -> Option<&CellSource> {
+        self.cells.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn to_json(&self) -> DrawcktResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
@@ -1,13 +1,21 @@
 use core::fmt;
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
 
+use crate::error::{DrawcktError, DrawcktResult};
+use crate::netlist::SNAP_TOLERANCE;
 use drawrs::{Orient, diagram::text_format::Justify};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
+use log::warn;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
-#[serde(rename_all = "lowercase")]
+pub use drawrs::{LineCap, LineJoin};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Layer {
     Instance,
     Annotate,
@@ -15,22 +23,85 @@ pub enum Layer {
     Device,
     Wire,
     Text,
+    /// User-defined layer styled by [`LayerStyles::custom_layers`]
+    Custom(String),
 }
 
 impl fmt::Display for Layer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(match self {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Layer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Layer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(Layer::from_name(s))
+    }
+}
+
+// Any name, the built-in ones as examples
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Layer {
+    fn schema_name() -> Cow<'static, str> {
+        "Layer".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "minLength": 1,
+            "examples": ["instance", "annotate", "pin", "device", "wire", "text"]
+        })
+    }
+}
+
+/// Kind of the layer cells created for each layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerCell {
+    User,
+    Label,
+    Shape,
+    Intersection,
+}
+
+impl Layer {
+    pub fn name(&self) -> &str {
+        match self {
             Layer::Instance => "instance",
             Layer::Annotate => "annotate",
             Layer::Pin => "pin",
             Layer::Device => "device",
             Layer::Wire => "wire",
             Layer::Text => "text",
-        })
+            Layer::Custom(name) => name,
+        }
+    }
+    /// Built-in layer of that name, otherwise a custom layer
+    pub fn from_name(name: impl Into<String>) -> Self {
+        let name = name.into();
+        match name.as_str() {
+            "instance" => Layer::Instance,
+            "annotate" => Layer::Annotate,
+            "pin" => Layer::Pin,
+            "device" => Layer::Device,
+            "wire" => Layer::Wire,
+            "text" => Layer::Text,
+            _ => Layer::Custom(name),
+        }
     }
-}
-
-impl Layer {
     pub fn id_user(&self) -> String {
         format!("layer-{self}-your-drawing")
     }
@@ -44,39 +115,183 @@ impl Layer {
             format!("layer-{self}-shape")
         }
     }
+    /// Inverse of `id_user`, `id_label` and `id_shape`
+    pub fn parse_id(id: &str) -> Option<(Self, LayerCell)> {
+        let rest = id.strip_prefix("layer-")?;
+        let (name, cell) = if let Some(name) = rest.strip_suffix("-your-drawing") {
+            (name, LayerCell::User)
+        } else if let Some(name) = rest.strip_suffix("-label") {
+            (name, LayerCell::Label)
+        } else if let Some(name) = rest.strip_suffix("-shape") {
+            (name, LayerCell::Shape)
+        } else if let Some(name) = rest.strip_suffix("-intersection") {
+            (name, LayerCell::Intersection)
+        } else {
+            return None;
+        };
+        Some((Self::from_name(name), cell))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "LayerStyleSerde")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LayerStyle {
     pub stroke_color: Cow<'static, str>,
     pub stroke_width: f64,
+    /// Fill color of the filled shapes, the stroke color when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_color: Option<Cow<'static, str>>,
     pub text_color: Cow<'static, str>,
     pub font_zoom: f64,
-    pub font_family: Cow<'static, str>,
+    pub font_family: Font,
+    /// Whether the label layer cell is visible in the schematic
     pub label_sch_visible: bool,
+    /// Whether the shape layer cell is visible in the schematic
     pub shape_sch_visible: bool,
+    /// Opacity of the shapes and labels in percent, opaque when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<i32>,
+    /// Corner style of the strokes, draw.io's default when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_join: Option<LineJoin>,
+    /// End style of the lines, both `startCap` and `endCap`, draw.io's
+    /// default when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_cap: Option<LineCap>,
+    /// Padding between the label text and its box, in pixels
+    pub label_spacing: f64,
+    /// Bottom padding of the bottom-justified labels as a fraction of the
+    /// font height, negative values move the text towards the anchor
+    pub label_baseline_adjust: f64,
+    /// Soft-wrap the label lines wider than this at word boundaries, in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrap_width: Option<f64>,
+    /// Fill color of a rounded box drawn behind the labels, no box when
+    /// neither it nor `label_border` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_background: Option<Cow<'static, str>>,
+    /// Border color of the box of the labels, see `label_background`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_border: Option<Cow<'static, str>>,
+}
+
+// Deserialized form of `LayerStyle`, the legacy `sch_visible` is the default
+// of both `label_sch_visible` and `shape_sch_visible`
+#[derive(Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct LayerStyleSerde {
+    stroke_color: Option<Cow<'static, str>>,
+    stroke_width: Option<f64>,
+    fill_color: Option<Cow<'static, str>>,
+    text_color: Option<Cow<'static, str>>,
+    font_zoom: Option<f64>,
+    font_family: Option<Font>,
+    /// Legacy visibility of both layer cells, the default of
+    /// `label_sch_visible` and `shape_sch_visible`
+    sch_visible: Option<bool>,
+    label_sch_visible: Option<bool>,
+    shape_sch_visible: Option<bool>,
+    opacity: Option<i32>,
+    line_join: Option<LineJoin>,
+    line_cap: Option<LineCap>,
+    label_spacing: Option<f64>,
+    label_baseline_adjust: Option<f64>,
+    wrap_width: Option<f64>,
+    label_background: Option<Cow<'static, str>>,
+    label_border: Option<Cow<'static, str>>,
+}
+
+impl From<LayerStyleSerde> for LayerStyle {
+    fn from(style: LayerStyleSerde) -> Self {
+        let default = Self::default();
+        Self {
+            stroke_color: style.stroke_color.unwrap_or(default.stroke_color),
+            stroke_width: style.stroke_width.unwrap_or(default.stroke_width),
+            fill_color: style.fill_color,
+            text_color: style.text_color.unwrap_or(default.text_color),
+            font_zoom: style.font_zoom.unwrap_or(default.font_zoom),
+            font_family: style.font_family.unwrap_or(default.font_family),
+            label_sch_visible: style
+                .label_sch_visible
+                .or(style.sch_visible)
+                .unwrap_or(default.label_sch_visible),
+            shape_sch_visible: style
+                .shape_sch_visible
+                .or(style.sch_visible)
+                .unwrap_or(default.shape_sch_visible),
+            opacity: style.opacity,
+            line_join: style.line_join,
+            line_cap: style.line_cap,
+            label_spacing: style.label_spacing.unwrap_or(default.label_spacing),
+            label_baseline_adjust: style
+                .label_baseline_adjust
+                .unwrap_or(default.label_baseline_adjust),
+            wrap_width: style.wrap_width,
+            label_background: style.label_background,
+            label_border: style.label_border,
+        }
+    }
 }
 
 impl LayerStyle {
+    #[allow(clippy::too_many_arguments)]
     pub const fn new(
         stroke_color: &'static str,
         stroke_width: f64,
+        fill_color: Option<&'static str>,
         text_color: &'static str,
         font_zoom: f64,
-        font_family: &'static str,
+        font_family: Font,
         label_sch_visible: bool,
         shape_sch_visible: bool,
+        opacity: Option<i32>,
+        line_join: Option<LineJoin>,
+        line_cap: Option<LineCap>,
+        label_spacing: f64,
+        label_baseline_adjust: f64,
+        wrap_width: Option<f64>,
+        label_background: Option<&'static str>,
+        label_border: Option<&'static str>,
     ) -> Self {
         Self {
             stroke_color: Cow::Borrowed(stroke_color),
             stroke_width,
+            fill_color: match fill_color {
+                Some(color) => Some(Cow::Borrowed(color)),
+                None => None,
+            },
             text_color: Cow::Borrowed(text_color),
             font_zoom,
-            font_family: Cow::Borrowed(font_family),
+            font_family,
             label_sch_visible,
             shape_sch_visible,
+            opacity,
+            line_join,
+            line_cap,
+            label_spacing,
+            label_baseline_adjust,
+            wrap_width,
+            label_background: match label_background {
+                Some(color) => Some(Cow::Borrowed(color)),
+                None => None,
+            },
+            label_border: match label_border {
+                Some(color) => Some(Cow::Borrowed(color)),
+                None => None,
+            },
         }
     }
+
+    /// Fill color of the filled shapes
+    pub fn fill_color(&self) -> &str {
+        self.fill_color.as_deref().unwrap_or(&self.stroke_color)
+    }
+
+    /// Whether the labels are drawn in a box, see `label_background`
+    pub fn has_label_box(&self) -> bool {
+        self.label_background.is_some() || self.label_border.is_some()
+    }
 }
 
 impl Default for LayerStyle {
@@ -84,30 +299,120 @@ impl Default for LayerStyle {
         Self {
             stroke_color: "#000000".into(),
             stroke_width: 1.0,
+            fill_color: None,
             text_color: "#000000".into(),
             font_zoom: 1.0,
-            font_family: "Times New Roman".into(),
+            font_family: Font::Other("Times New Roman".to_string()),
             label_sch_visible: true,
             shape_sch_visible: true,
+            opacity: None,
+            line_join: None,
+            line_cap: None,
+            label_spacing: 0.0,
+            label_baseline_adjust: -0.125,
+            wrap_width: None,
+            label_background: None,
+            label_border: None,
         }
     }
 }
 
+/// Visual style of the layers, usually read from a style.json file with
+/// [`LayerStyles::load`]. Missing fields take their [`Default`] value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LayerStyles {
+    /// Format version of the style file, files without it are version 0
+    #[serde(default)]
+    pub version: u32,
     pub layer_order: [Layer; 6],
     pub device: LayerStyle,
     pub instance: LayerStyle,
     pub wire: LayerStyle,
     pub wire_show_intersection: bool,
     pub wire_intersection_scale: f64,
+    /// Style of the wire intersection dots, the wire style when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wire_intersection: Option<LayerStyle>,
+    /// Always fill the wire intersection dots (fillStyle 2)
+    #[serde(default)]
+    pub wire_intersection_filled: bool,
     pub annotate: LayerStyle,
     pub pin: LayerStyle,
     pub text: LayerStyle,
+    /// Overrides of the [`Font::css_family`] mapping
+    #[serde(default)]
+    pub font_families: IndexMap<Font, String>,
+    /// URL of the web fonts by family name, e.g. a corporate font named in a
+    /// fallback list, see [`drawrs::DrawFile::add_custom_font`]
+    #[serde(default)]
+    pub custom_fonts: IndexMap<String, String>,
+    /// Wire color by net name or glob pattern (`*` and `?`), e.g. `"VDD*"`
+    #[serde(default)]
+    pub net_colors: IndexMap<String, String>,
+    /// Layers to render, `None` renders all of them
+    #[serde(default)]
+    pub layers_enabled: Option<IndexSet<Layer>>,
+    /// Styles of the custom layers, stacked above the built-in layers
+    /// with the first one on top
+    #[serde(default)]
+    pub custom_layers: IndexMap<String, LayerStyle>,
+    /// Show instance parameters below each instance on the annotate layer
+    #[serde(default)]
+    pub show_instance_params: bool,
+    /// Fill color of the open wire end marks, see
+    /// [`Renderer::with_mark_open_ends`](crate::renderer::Renderer::with_mark_open_ends)
+    #[serde(default = "default_open_end_color")]
+    pub wire_open_end_color: Cow<'static, str>,
+    /// Distance from a top-level pin to the near edge of its name, in user units
+    #[serde(default = "default_pin_label_offset")]
+    pub pin_label_offset: f64,
+    /// Text height of the top-level pin names, in user units
+    #[serde(default = "default_pin_label_height")]
+    pub pin_label_height: f64,
+    /// Corner of the content the layer legend is placed at, see
+    /// [`Renderer::with_layer_legend`](crate::renderer::Renderer::with_layer_legend)
+    #[serde(default)]
+    pub layer_legend_corner: LegendCorner,
+    /// Smallest font size of the labels, in pixels, smaller ones are raised
+    /// to it with a warning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_font_size: Option<f64>,
+    /// Largest font size of the labels, in pixels, larger ones are lowered
+    /// to it with a warning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_font_size: Option<f64>,
+}
+
+/// Corner of the schematic content a legend is placed at, outside of the
+/// content: above the top corners and below the bottom ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum LegendCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
 }
 
 impl LayerStyles {
-    pub(crate) fn layer_style<'a>(&'a self, layer: &Layer) -> &'a LayerStyle {
+    /// `font_size` within [`Self::min_font_size`] and [`Self::max_font_size`],
+    /// `text` and `layer` name the label in the warning
+    pub(crate) fn clamp_font_size(&self, font_size: f64, text: &str, layer: &Layer) -> f64 {
+        let clamped = match (self.min_font_size, self.max_font_size) {
+            (Some(min), _) if font_size < min => min,
+            (_, Some(max)) if font_size > max => max,
+            _ => return font_size,
+        };
+        warn!("font size {font_size} of label {text:?} on layer {layer} is clamped to {clamped}");
+        clamped
+    }
+
+    /// Style of a layer, unknown custom layers get the annotate style
+    pub fn layer_style<'a>(&'a self, layer: &Layer) -> &'a LayerStyle {
         match layer {
             Layer::Instance => &self.instance,
             Layer::Annotate => &self.annotate,
@@ -115,13 +420,260 @@ impl LayerStyles {
             Layer::Device => &self.device,
             Layer::Wire => &self.wire,
             Layer::Text => &self.text,
+            // Unknown custom layers are rejected when rendering
+            Layer::Custom(name) => self.custom_layers.get(name).unwrap_or(&self.annotate),
+        }
+    }
+
+    /// Style of a layer to edit, `None` for an unknown custom layer
+    pub fn layer_style_mut(&mut self, layer: &Layer) -> Option<&mut LayerStyle> {
+        match layer {
+            Layer::Instance => Some(&mut self.instance),
+            Layer::Annotate => Some(&mut self.annotate),
+            Layer::Pin => Some(&mut self.pin),
+            Layer::Device => Some(&mut self.device),
+            Layer::Wire => Some(&mut self.wire),
+            Layer::Text => Some(&mut self.text),
+            Layer::Custom(name) => self.custom_layers.get_mut(name),
+        }
+    }
+
+    /// All layers with their styles from top to bottom: the custom layers,
+    /// then the built-in ones in `layer_order`
+    pub fn iter(&self) -> impl Iterator<Item = (Layer, &LayerStyle)> {
+        self.stacked_layers().into_iter().rev().map(|layer| {
+            let style = self.layer_style(&layer);
+            (layer, style)
+        })
+    }
+
+    /// Layer of a name as written in the JSON, `None` for a custom layer
+    /// without a style
+    pub fn layer_by_name(&self, name: &str) -> Option<Layer> {
+        let layer = Layer::from_name(name);
+        self.has_layer(&layer).then_some(layer)
+    }
+
+    /// Set the order of the built-in layers, from top to bottom. The order
+    /// must pass [`Self::validate`].
+    pub fn set_layer_order(&mut self, order: &[Layer]) -> DrawcktResult<()> {
+        let layer_order: [Layer; 6] =
+            order
+                .to_vec()
+                .try_into()
+                .map_err(|order: Vec<Layer>| DrawcktError::InvalidOption {
+                    option: "layer_order".to_string(),
+                    value: order.iter().map(Layer::name).collect::<Vec<_>>().join(","),
+                })?;
+        let issues = layer_order_issues(&layer_order);
+        if !issues.is_empty() {
+            return Err(DrawcktError::InvalidLayerStyles(issues));
         }
+        self.layer_order = layer_order;
+        Ok(())
     }
+
+    /// Check that `layer_order` has each built-in layer exactly once and
+    /// that no font family has a `;`, otherwise all the repeated and missing
+    /// layers and the invalid families are returned
+    pub fn validate(&self) -> Result<(), Vec<LayerStyleIssue>> {
+        let mut issues = layer_order_issues(&self.layer_order);
+        let styles = [
+            &self.device,
+            &self.instance,
+            &self.wire,
+            &self.annotate,
+            &self.pin,
+            &self.text,
+        ];
+        for style in styles
+            .into_iter()
+            .chain(&self.wire_intersection)
+            .chain(self.custom_layers.values())
+        {
+            let family = self.css_family(&style.font_family);
+            let issue = LayerStyleIssue::FontFamily(family.to_string());
+            // draw.io reads no escape in the `fontFamily` style entry
+            if family.contains(';') && !issues.contains(&issue) {
+                issues.push(issue);
+            }
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Style of the wire intersection dots
+    pub(crate) fn intersection_style(&self) -> &LayerStyle {
+        self.wire_intersection.as_ref().unwrap_or(&self.wire)
+    }
+
+    // Style of the shapes of a layer, or of the wire intersection layer
+    pub(crate) fn shape_style<'a>(
+        &'a self,
+        layer: &Layer,
+        is_intersection: bool,
+    ) -> &'a LayerStyle {
+        if is_intersection && *layer == Layer::Wire {
+            self.intersection_style()
+        } else {
+            self.layer_style(layer)
+        }
+    }
+
+    /// Hash of the style settings the symbol pages are rendered with, see
+    /// [`Shape::content_hash`]. The schematic-only settings, e.g. the wire
+    /// style, the legend or the custom fonts, are left out so changing them
+    /// does not make the rendered symbols stale.
+    pub fn symbol_hash(&self) -> u32 {
+        #[derive(Serialize)]
+        struct SymbolStyles<'a> {
+            layer_order: &'a [Layer; 6],
+            device: &'a LayerStyle,
+            instance: &'a LayerStyle,
+            annotate: &'a LayerStyle,
+            pin: &'a LayerStyle,
+            text: &'a LayerStyle,
+            font_families: &'a IndexMap<Font, String>,
+            layers_enabled: &'a Option<IndexSet<Layer>>,
+            custom_layers: &'a IndexMap<String, LayerStyle>,
+            min_font_size: Option<f64>,
+            max_font_size: Option<f64>,
+        }
+        serialized_hash(&SymbolStyles {
+            layer_order: &self.layer_order,
+            device: &self.device,
+            instance: &self.instance,
+            annotate: &self.annotate,
+            pin: &self.pin,
+            text: &self.text,
+            font_families: &self.font_families,
+            layers_enabled: &self.layers_enabled,
+            custom_layers: &self.custom_layers,
+            min_font_size: self.min_font_size,
+            max_font_size: self.max_font_size,
+        })
+    }
+
+    /// Whether the layer is built-in or has a custom style
+    pub fn has_layer(&self, layer: &Layer) -> bool {
+        match layer {
+            Layer::Custom(name) => self.custom_layers.contains_key(name),
+            _ => true,
+        }
+    }
+
+    /// All layers from bottom to top
+    pub fn stacked_layers(&self) -> Vec<Layer> {
+        let mut layers: Vec<Layer> = self.layer_order.iter().rev().cloned().collect();
+        for name in self.custom_layers.keys().rev() {
+            let layer = Layer::from_name(name.as_str());
+            if !layers.contains(&layer) {
+                layers.push(layer);
+            }
+        }
+        layers
+    }
+
+    pub fn layer_enabled(&self, layer: &Layer) -> bool {
+        self.layers_enabled
+            .as_ref()
+            .is_none_or(|layers| layers.contains(layer))
+    }
+
+    /// CSS font family of `font`, taking `font_families` overrides into account
+    pub fn css_family<'a>(&'a self, font: &'a Font) -> &'a str {
+        self.font_families
+            .get(font)
+            .map(String::as_str)
+            .unwrap_or_else(|| font.css_family())
+    }
+
+    /// Color override of a net, an exact match wins over the first matching glob
+    pub fn net_color(&self, net: &str) -> Option<&str> {
+        self.net_colors
+            .get(net)
+            .or_else(|| {
+                self.net_colors
+                    .iter()
+                    .find(|(pattern, _)| glob_match(pattern, net))
+                    .map(|(_, color)| color)
+            })
+            .map(String::as_str)
+    }
+}
+
+/// Problem found by [`LayerStyles::validate`]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LayerStyleIssue {
+    #[error("layer {0} is repeated in layer_order")]
+    RepeatLayer(Layer),
+    #[error("layer {0} is missing from layer_order, its objects would not be drawn")]
+    MissingLayer(Layer),
+    #[error("font family {0:?} has a ';', which would end its draw.io style entry")]
+    FontFamily(String),
+}
+
+// Each repeated layer once, then the missing built-in layers
+fn layer_order_issues(layer_order: &[Layer]) -> Vec<LayerStyleIssue> {
+    let mut seen = HashSet::new();
+    let mut issues = Vec::new();
+    for layer in layer_order {
+        if !seen.insert(layer) && !issues.contains(&LayerStyleIssue::RepeatLayer(layer.clone())) {
+            issues.push(LayerStyleIssue::RepeatLayer(layer.clone()));
+        }
+    }
+    for layer in [
+        Layer::Instance,
+        Layer::Annotate,
+        Layer::Pin,
+        Layer::Device,
+        Layer::Wire,
+        Layer::Text,
+    ] {
+        if !seen.contains(&layer) {
+            issues.push(LayerStyleIssue::MissingLayer(layer));
+        }
+    }
+    issues
+}
+
+/// Glob matching supporting `*` (any sequence) and `?` (any character)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text index it matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 impl Default for LayerStyles {
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             layer_order: [
                 Layer::Text,
                 Layer::Pin,
@@ -135,25 +687,383 @@ impl Default for LayerStyles {
             wire: LayerStyle::default(),
             wire_show_intersection: true,
             wire_intersection_scale: 1.0,
+            wire_intersection: None,
+            wire_intersection_filled: false,
             annotate: LayerStyle::default(),
             pin: LayerStyle::default(),
             text: LayerStyle::default(),
+            font_families: IndexMap::new(),
+            custom_fonts: IndexMap::new(),
+            net_colors: IndexMap::new(),
+            layers_enabled: None,
+            custom_layers: IndexMap::new(),
+            show_instance_params: false,
+            wire_open_end_color: default_open_end_color(),
+            pin_label_offset: default_pin_label_offset(),
+            pin_label_height: default_pin_label_height(),
+            layer_legend_corner: LegendCorner::default(),
+            min_font_size: None,
+            max_font_size: None,
+        }
+    }
+}
+
+impl LayerStyles {
+    /// Current format version of the style files
+    pub const VERSION: u32 = 1;
+
+    /// Read a style file in the format of its extension, JSON by default,
+    /// see [`Self::from_json`]
+    #[cfg(feature = "std-fs")]
+    pub fn load(path: impl AsRef<Path>) -> DrawcktResult<Self> {
+        let path = path.as_ref();
+        let format = InputFormat::from_path(path).unwrap_or_default();
+        Self::from_reader(format, std::fs::File::open(path)?)
+    }
+
+    /// Parse a style file: older versions are migrated and newer ones fail,
+    /// missing fields take their default and unknown fields are ignored with
+    /// a warning
+    pub fn from_json(content: &str) -> DrawcktResult<Self> {
+        let mut deserializer = serde_json::Deserializer::from_str(content);
+        let styles = Self::deserialize_migrated(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(styles)
+    }
+
+    /// Parse a style file in any [`InputFormat`], see [`Self::from_json`]
+    pub fn from_reader(format: InputFormat, mut reader: impl Read) -> DrawcktResult<Self> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        match format {
+            InputFormat::Json => Self::from_json(&content),
+            InputFormat::Toml => Ok(Self::deserialize_migrated(toml::Deserializer::new(
+                &content,
+            ))?),
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => Ok(Self::deserialize_migrated(
+                serde_yaml::Deserializer::from_str(&content),
+            )?),
+            #[cfg(not(feature = "yaml"))]
+            InputFormat::Yaml => Err(DrawcktError::FormatNotEnabled(format)),
+        }
+    }
+
+    fn deserialize_migrated<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let mut styles: Self = serde_ignored::deserialize(deserializer, |path| {
+            warn!("unknown style field {path} is ignored");
+        })?;
+        // Migrate from the version of the file, one version at a time
+        match styles.version {
+            // Version 0 had a single `sch_visible` per layer, which
+            // `LayerStyle` still reads as the default of the shape and label
+            // visibility
+            0 => styles.version = 1,
+            Self::VERSION => {}
+            version => {
+                return Err(serde::de::Error::custom(format!(
+                    "style file version {version} is newer than the supported version {}",
+                    Self::VERSION
+                )));
+            }
         }
+        Ok(styles)
+    }
+
+    /// JSON Schema of the style files
+    #[cfg(feature = "schemars")]
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(Self)
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Schematic {
     pub design: DesignId<'static>,
     pub instances: Vec<Instance>,
     pub wires: Vec<Wire>,
     pub pins: Vec<Pin>,
     pub symbols: Vec<Symbol>,
+    #[serde(deserialize_with = "deserialize_shapes")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<Shape>"))]
     pub labels: Vec<Shape>,
+    #[serde(deserialize_with = "deserialize_shapes")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<Shape>"))]
     pub shapes: Vec<Shape>,
+    /// Unit of the coordinates and text heights, user units when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<Units>,
+    /// Size of a draw.io pixel in `units`, overriding the default scale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units_per_drawio_pixel: Option<f64>,
+}
+
+/// Serialization format of the schematic and style input files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    #[default]
+    Json,
+    /// Needs the `yaml` feature
+    Yaml,
+    Toml,
+}
+
+impl InputFormat {
+    /// Format of the file extension, `None` for unknown extensions
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| ext.parse().ok())
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+        })
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = DrawcktError;
+
+    fn from_str(s: &str) -> DrawcktResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            _ => Err(DrawcktError::InvalidOption {
+                option: "--format".to_string(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Unit of the schematic coordinates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Units {
+    /// Schematic user units, one inch
+    #[default]
+    UserUnits,
+    Micron,
+    Mil,
+    Millimeter,
+}
+
+impl Units {
+    /// Number of units in a user unit
+    pub fn per_user_unit(&self) -> f64 {
+        match self {
+            Self::UserUnits => 1.0,
+            Self::Micron => 25400.0,
+            Self::Mil => 1000.0,
+            Self::Millimeter => 25.4,
+        }
+    }
+}
+
+/// Semantic problem found by [`Schematic::validate`]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SchematicIssue {
+    #[error("design {0} is empty")]
+    EmptyDesign(&'static str),
+    #[error("instance #{index} {name}: symbol {symbol} not found")]
+    UnknownSymbol {
+        index: usize,
+        name: String,
+        symbol: String,
+    },
+    #[error("instance #{index} {name}: duplicate of instance #{first}")]
+    DuplicateInstance {
+        index: usize,
+        name: String,
+        first: usize,
+    },
+    #[error("wire #{index} on net {net}: {points} point(s), at least 2 are needed")]
+    ShortWire {
+        index: usize,
+        net: String,
+        points: usize,
+    },
+    #[error("{item}: {points} point(s), at least {min} are needed")]
+    TooFewPoints {
+        item: String,
+        points: usize,
+        min: usize,
+    },
+    #[error("{item}: non-finite coordinate")]
+    NonFinite { item: String },
+    #[error("{item}: empty name")]
+    EmptyName { item: String },
+    #[error("units_per_drawio_pixel {0} is not a positive number")]
+    InvalidScale(f64),
+}
+
+impl Schematic {
+    /// Draw.io pixels in a user unit
+    pub const USER_UNIT_SCALE: f64 = 200.0;
+
+    /// Draw.io pixels in a unit of the coordinates. A `units_per_drawio_pixel`
+    /// that is not a positive number is ignored, see [`Self::validate`].
+    pub fn scale(&self) -> f64 {
+        match self.units_per_drawio_pixel {
+            Some(units_per_pixel) if units_per_pixel.is_finite() && units_per_pixel > 0.0 => {
+                1.0 / units_per_pixel
+            }
+            _ => Self::USER_UNIT_SCALE / self.units.unwrap_or_default().per_user_unit(),
+        }
+    }
+
+    /// Distance in the units of the coordinates below which two points are
+    /// connected, [`SNAP_TOLERANCE`] user units
+    pub(crate) fn snap_tolerance(&self) -> f64 {
+        SNAP_TOLERANCE * self.units.unwrap_or_default().per_user_unit()
+    }
+
+    /// Draw.io pixels in a user unit, the scale of the constants and of the
+    /// style lengths that do not depend on the schematic units
+    pub fn user_unit_scale(&self) -> f64 {
+        self.scale() * self.units.unwrap_or_default().per_user_unit()
+    }
+
+    /// Read a schematic file in the format of its extension, JSON by default
+    #[cfg(feature = "std-fs")]
+    pub fn load(path: impl AsRef<Path>) -> DrawcktResult<Self> {
+        let path = path.as_ref();
+        let format = InputFormat::from_path(path).unwrap_or_default();
+        Self::from_reader(format, std::fs::File::open(path)?)
+    }
+
+    /// Parse a schematic in any [`InputFormat`]
+    pub fn from_reader(format: InputFormat, mut reader: impl Read) -> DrawcktResult<Self> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        match format {
+            InputFormat::Json => Ok(serde_json::from_str(&content)?),
+            InputFormat::Toml => Ok(toml::from_str(&content)?),
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => Ok(serde_yaml::from_str(&content)?),
+            #[cfg(not(feature = "yaml"))]
+            InputFormat::Yaml => Err(DrawcktError::FormatNotEnabled(format)),
+        }
+    }
+
+    /// JSON Schema of the schematic files
+    #[cfg(feature = "schemars")]
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(Self)
+    }
+
+    /// Indices of the wires with at least 2 points, grouped by net in order
+    /// of the first wire of each net
+    pub fn wires_by_net(&self) -> IndexMap<&str, Vec<usize>> {
+        let mut wires_by_net: IndexMap<&str, Vec<usize>> = IndexMap::new();
+        for (i, wire) in self.wires.iter().enumerate() {
+            if wire.points.len() >= 2 {
+                wires_by_net.entry(wire.net.as_str()).or_default().push(i);
+            }
+        }
+        wires_by_net
+    }
+
+    /// Check the references, point counts, coordinates and scale,
+    /// returning all the issues at once
+    pub fn validate(&self) -> Result<(), Vec<SchematicIssue>> {
+        let mut issues = Vec::new();
+        if self.design.lib.is_empty() {
+            issues.push(SchematicIssue::EmptyDesign("lib"));
+        }
+        if self.design.cell.is_empty() {
+            issues.push(SchematicIssue::EmptyDesign("cell"));
+        }
+        if let Some(units_per_pixel) = self.units_per_drawio_pixel
+            && !(units_per_pixel.is_finite() && units_per_pixel > 0.0)
+        {
+            issues.push(SchematicIssue::InvalidScale(units_per_pixel));
+        }
+
+        let symbols: HashSet<&DesignId<'static>> = self.symbols.iter().map(|s| &s.id).collect();
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        for (index, instance) in self.instances.iter().enumerate() {
+            if !symbols.contains(&instance.symbol_id) {
+                issues.push(SchematicIssue::UnknownSymbol {
+                    index,
+                    name: instance.name.clone(),
+                    symbol: instance.symbol_id.to_string(),
+                });
+            }
+            let first = *names.entry(&instance.name).or_insert(index);
+            if first != index {
+                issues.push(SchematicIssue::DuplicateInstance {
+                    index,
+                    name: instance.name.clone(),
+                    first,
+                });
+            }
+            if !(instance.x.is_finite() && instance.y.is_finite()) {
+                issues.push(SchematicIssue::NonFinite {
+                    item: format!("instance #{index} {}", instance.name),
+                });
+            }
+        }
+
+        for (index, wire) in self.wires.iter().enumerate() {
+            if wire.points.len() < 2 {
+                issues.push(SchematicIssue::ShortWire {
+                    index,
+                    net: wire.net.clone(),
+                    points: wire.points.len(),
+                });
+            }
+            if !all_finite(&wire.points) {
+                issues.push(SchematicIssue::NonFinite {
+                    item: format!("wire #{index} on net {}", wire.net),
+                });
+            }
+        }
+        for (index, pin) in self.pins.iter().enumerate() {
+            if !(pin.x.is_finite() && pin.y.is_finite()) {
+                issues.push(SchematicIssue::NonFinite {
+                    item: format!("pin #{index} {}", pin.name),
+                });
+            }
+        }
+        for (index, label) in self.labels.iter().enumerate() {
+            label.validate(format!("label #{index}"), &mut issues);
+        }
+        for (index, shape) in self.shapes.iter().enumerate() {
+            shape.validate(format!("shape #{index}"), &mut issues);
+        }
+        for symbol in &self.symbols {
+            for (index, shape) in symbol.shapes.iter().enumerate() {
+                shape.validate(format!("symbol {} shape #{index}", symbol.id), &mut issues);
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+
+fn all_finite(points: &[[OrderedFloat<f64>; 2]]) -> bool {
+    points.iter().flatten().all(|v| v.is_finite())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DesignId<'a> {
     pub lib: Cow<'a, str>,
     pub cell: Cow<'a, str>,
@@ -169,6 +1079,13 @@ impl DesignId<'static> {
 }
 
 impl<'a> DesignId<'a> {
+    pub fn new(lib: impl Into<Cow<'a, str>>, cell: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            lib: lib.into(),
+            cell: cell.into(),
+        }
+    }
+
     pub fn owned(&self) -> DesignId<'static> {
         DesignId {
             lib: Cow::Owned(self.lib.clone().into_owned()),
@@ -183,7 +1100,34 @@ impl fmt::Display for DesignId<'_> {
     }
 }
 
+/// Inverse of the `{lib}/{cell}` display, e.g. of a symbol page name
+impl FromStr for DesignId<'static> {
+    type Err = DrawcktError;
+
+    fn from_str(s: &str) -> DrawcktResult<Self> {
+        match s.split_once('/') {
+            Some((lib, cell)) if !lib.is_empty() && !cell.is_empty() && !cell.contains('/') => {
+                Ok(Self::new(lib.to_string(), cell.to_string()))
+            }
+            _ => Err(DrawcktError::InvalidPageName(s.to_string())),
+        }
+    }
+}
+
+impl<'a> From<(&'a str, &'a str)> for DesignId<'a> {
+    fn from((lib, cell): (&'a str, &'a str)) -> Self {
+        Self::new(lib, cell)
+    }
+}
+
+impl From<(String, String)> for DesignId<'static> {
+    fn from((lib, cell): (String, String)) -> Self {
+        Self::new(lib, cell)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Instance {
     pub name: String,
     #[serde(flatten)]
@@ -191,15 +1135,21 @@ pub struct Instance {
     pub x: f64,
     pub y: f64,
     pub orient: Orient,
+    /// Device parameters, e.g. `w`, `l`, `m`
+    #[serde(default)]
+    pub params: IndexMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Wire {
     pub net: String,
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<[f64; 2]>"))]
     pub points: Vec<[OrderedFloat<f64>; 2]>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Pin {
     pub name: String,
     pub direction: String,
@@ -208,18 +1158,50 @@ pub struct Pin {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Symbol {
     #[serde(flatten)]
     pub id: DesignId<'static>,
+    #[serde(deserialize_with = "deserialize_shapes")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<Shape>"))]
     pub shapes: IndexSet<Shape>,
     pub pins: Vec<TemplatePin>,
 }
 
+/// How the ids of the objects inside a symbol are generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdScheme {
+    /// `{lib}/{cell}-{layer}-{idx}`, inserting a shape shifts the following ids
+    Index,
+    /// `{lib}/{cell}-{layer}-{hash}`, stable as long as the shape is unchanged
+    #[default]
+    Content,
+}
+
 impl Symbol {
+    /// Hash of the shapes and pins, see [`Shape::content_hash`]
+    pub fn content_hash(&self) -> u32 {
+        serialized_hash(self)
+    }
+
     // Generate ID for symbol layer/object/edge: {lib}/{cell}-{layer}-{idx}
     pub fn gen_obj_id(&self, layer: &Layer, idx: usize) -> String {
         format!("{}-{}-{}", self.id, layer, idx)
     }
+
+    // Generate ID from the shape content: {lib}/{cell}-{layer}-{hash}[-{n}],
+    // `used` holds the ids already generated for the symbol
+    pub fn gen_obj_content_id(&self, shape: &Shape, used: &mut HashSet<String>) -> String {
+        let base = format!("{}-{}-{:08x}", self.id, shape.layer(), shape.content_hash());
+        let mut id = base.clone();
+        let mut counter = 1;
+        while !used.insert(id.clone()) {
+            id = format!("{base}-{counter}");
+            counter += 1;
+        }
+        id
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -233,9 +1215,28 @@ pub enum Font {
     Stick,
     Swedish,
     MilSpec,
+    /// Any other font, used as CSS font family as is
     Other(String),
 }
 
+impl Font {
+    /// Default CSS font family used to display the font
+    pub fn css_family(&self) -> &str {
+        match self {
+            Font::EuroStyle => "Eurostile, Arial, sans-serif",
+            Font::Fixed => "Courier New, monospace",
+            Font::Gothic => "Century Gothic, Arial, sans-serif",
+            Font::Math => "Cambria Math, Times New Roman, serif",
+            Font::Roman => "Times New Roman, serif",
+            Font::Script => "Brush Script MT, cursive",
+            Font::Stick => "Courier New, monospace",
+            Font::Swedish => "Arial, sans-serif",
+            Font::MilSpec => "Arial, sans-serif",
+            Font::Other(s) => s,
+        }
+    }
+}
+
 impl Serialize for Font {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -278,33 +1279,183 @@ impl<'de> Deserialize<'de> for Font {
     }
 }
 
+// Any CSS font family, the Virtuoso font names as examples
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Font {
+    fn schema_name() -> Cow<'static, str> {
+        "Font".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "examples": [
+                "euroStyle", "fixed", "gothic", "math", "roman",
+                "script", "stick", "swedish", "milSpec"
+            ]
+        })
+    }
+}
+
+/// How a closed shape is painted. Read from the legacy `fillStyle` number
+/// (1 to 5, 0 is read as 1) or from its name, e.g. `"filledPattern"`, and
+/// written back as the number.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum FillStyleKind {
+    /// Not filled, only outlined (1)
+    #[default]
+    Outline,
+    /// Filled with color (2)
+    Filled,
+    /// Filled with an X pattern (3)
+    FilledX,
+    /// Filled with a pattern (4)
+    FilledPattern,
+    /// Filled with a pattern and outlined (5)
+    FilledPatternOutline,
+}
+
+impl FillStyleKind {
+    pub fn from_number(n: u64) -> Option<Self> {
+        match n {
+            0 | 1 => Some(Self::Outline),
+            2 => Some(Self::Filled),
+            3 => Some(Self::FilledX),
+            4 => Some(Self::FilledPattern),
+            5 => Some(Self::FilledPatternOutline),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        match self {
+            Self::Outline => 1,
+            Self::Filled => 2,
+            Self::FilledX => 3,
+            Self::FilledPattern => 4,
+            Self::FilledPatternOutline => 5,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "outline" => Some(Self::Outline),
+            "filled" => Some(Self::Filled),
+            "filledX" => Some(Self::FilledX),
+            "filledPattern" => Some(Self::FilledPattern),
+            "filledPatternOutline" => Some(Self::FilledPatternOutline),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for FillStyleKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8(self.number())
+    }
+}
+
+impl<'de> Deserialize<'de> for FillStyleKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor;
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = FillStyleKind;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a fill style number from 1 to 5 or a fill style name")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                FillStyleKind::from_number(v)
+                    .ok_or_else(|| E::custom(format!("unknown fill style {v}")))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v)
+                    .ok()
+                    .and_then(FillStyleKind::from_number)
+                    .ok_or_else(|| E::custom(format!("unknown fill style {v}")))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                FillStyleKind::from_name(v)
+                    .ok_or_else(|| E::custom(format!("unknown fill style {v:?}")))
+            }
+        }
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for FillStyleKind {
+    fn schema_name() -> Cow<'static, str> {
+        "FillStyleKind".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "Legacy fillStyle number, 0 is read as 1, or its name",
+            "oneOf": [
+                { "type": "integer", "minimum": 0, "maximum": 5 },
+                {
+                    "type": "string",
+                    "enum": [
+                        "outline", "filled", "filledX", "filledPattern", "filledPatternOutline"
+                    ]
+                }
+            ]
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
 #[serde(tag = "type")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Shape {
     #[serde(rename = "polygon")]
     Polygon {
         #[serde(deserialize_with = "deserialize_layer")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Layer"))]
         layer: Layer,
-        #[serde(rename = "fillStyle", default = "default_fill_style")]
-        fill_style: u8,
+        #[serde(rename = "fillStyle", default)]
+        fill_style: FillStyleKind,
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<[f64; 2]>"))]
         points: Vec<[OrderedFloat<f64>; 2]>,
+        /// Rings cut out of the polygon
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<Vec<[f64; 2]>>"))]
+        holes: Vec<Vec<[OrderedFloat<f64>; 2]>>,
+        /// Round the corners
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        rounded: bool,
     },
     #[serde(rename = "rect")]
     Rect {
         #[serde(deserialize_with = "deserialize_layer")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Layer"))]
         layer: Layer,
-        #[serde(rename = "fillStyle", default = "default_fill_style")]
-        fill_style: u8,
+        #[serde(rename = "fillStyle", default)]
+        fill_style: FillStyleKind,
         #[serde(rename = "bBox")]
+        #[cfg_attr(feature = "schemars", schemars(with = "[[f64; 2]; 2]"))]
         b_box: [[OrderedFloat<f64>; 2]; 2],
     },
     #[serde(rename = "label")]
     Label {
         #[serde(deserialize_with = "deserialize_layer")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Layer"))]
         layer: Layer,
         text: String,
+        #[cfg_attr(feature = "schemars", schemars(with = "[f64; 2]"))]
         xy: [OrderedFloat<f64>; 2],
         orient: String,
+        #[cfg_attr(feature = "schemars", schemars(with = "f64"))]
         height: OrderedFloat<f64>,
         justify: Justify,
         font: Font,
@@ -312,36 +1463,84 @@ pub enum Shape {
     #[serde(rename = "line")]
     Line {
         #[serde(deserialize_with = "deserialize_layer")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Layer"))]
         layer: Layer,
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<[f64; 2]>"))]
         points: Vec<[OrderedFloat<f64>; 2]>,
     },
     #[serde(rename = "ellipse")]
     Ellipse {
         #[serde(deserialize_with = "deserialize_layer")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Layer"))]
         layer: Layer,
-        #[serde(rename = "fillStyle", default = "default_fill_style")]
-        fill_style: u8,
+        #[serde(rename = "fillStyle", default)]
+        fill_style: FillStyleKind,
         #[serde(rename = "bBox")]
+        #[cfg_attr(feature = "schemars", schemars(with = "[[f64; 2]; 2]"))]
         b_box: [[OrderedFloat<f64>; 2]; 2],
     },
     #[serde(rename = "arc")]
     Arc {
         #[serde(deserialize_with = "deserialize_layer")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Layer"))]
         layer: Layer,
-        #[serde(rename = "fillStyle", default = "default_fill_style")]
-        fill_style: u8,
+        #[serde(rename = "fillStyle", default)]
+        fill_style: FillStyleKind,
         #[serde(rename = "bBox")]
+        #[cfg_attr(feature = "schemars", schemars(with = "[[f64; 2]; 2]"))]
         b_box: [[OrderedFloat<f64>; 2]; 2],
         /// startAngle yes float Starting angle of the arc (in radians)
         #[serde(rename = "startAngle")]
+        #[cfg_attr(feature = "schemars", schemars(with = "f64"))]
         start_angle: OrderedFloat<f64>,
         /// stopAngle yes float Stopping angle of the arc (counterclockwise in radians)
         #[serde(rename = "stopAngle")]
+        #[cfg_attr(feature = "schemars", schemars(with = "f64"))]
         stop_angle: OrderedFloat<f64>,
     },
 }
 
 impl Shape {
+    /// Hash of the serialized shape, stable across runs and platforms (FNV-1a)
+    pub fn content_hash(&self) -> u32 {
+        serialized_hash(self)
+    }
+
+    fn validate(&self, item: String, issues: &mut Vec<SchematicIssue>) {
+        let (finite, points, min) = match self {
+            Self::Polygon { points, holes, .. } => (
+                all_finite(points) && holes.iter().all(|hole| all_finite(hole)),
+                points.len(),
+                3,
+            ),
+            Self::Line { points, .. } => (all_finite(points), points.len(), 2),
+            Self::Rect { b_box, .. } | Self::Ellipse { b_box, .. } => (all_finite(b_box), 2, 2),
+            Self::Arc {
+                b_box,
+                start_angle,
+                stop_angle,
+                ..
+            } => (
+                all_finite(b_box) && start_angle.is_finite() && stop_angle.is_finite(),
+                2,
+                2,
+            ),
+            Self::Label { xy, height, .. } => {
+                (xy.iter().all(|v| v.is_finite()) && height.is_finite(), 1, 1)
+            }
+        };
+        if points < min {
+            issues.push(SchematicIssue::TooFewPoints {
+                item: item.clone(),
+                points,
+                min,
+            });
+        }
+        if !finite {
+            issues.push(SchematicIssue::NonFinite { item });
+        }
+    }
+
     // Helper function to extract layer from Shape
     pub fn layer(&self) -> &Layer {
         match self {
@@ -354,8 +1553,64 @@ impl Shape {
         }
     }
 }
-fn default_fill_style() -> u8 {
-    1 // Default: Not filled, only outlined
+
+fn default_open_end_color() -> Cow<'static, str> {
+    Cow::Borrowed("#FF0000")
+}
+
+fn default_pin_label_offset() -> f64 {
+    0.175
+}
+
+fn default_pin_label_height() -> f64 {
+    0.1
+}
+
+// Shape list whose errors name the index of the failing shape
+fn deserialize_shapes<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    C: FromIterator<Shape>,
+{
+    struct Visitor<C>(std::marker::PhantomData<C>);
+    impl<'de, C: FromIterator<Shape>> serde::de::Visitor<'de> for Visitor<C> {
+        type Value = C;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a list of shapes")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<C, A::Error>
+        where
+            A: serde::de::SeqAccess<'de>,
+        {
+            let mut shapes = Vec::new();
+            loop {
+                match seq.next_element::<Shape>() {
+                    Ok(Some(shape)) => shapes.push(shape),
+                    Ok(None) => return Ok(shapes.into_iter().collect()),
+                    Err(e) => {
+                        return Err(serde::de::Error::custom(format_args!(
+                            "shape #{}: {e}",
+                            shapes.len()
+                        )));
+                    }
+                }
+            }
+        }
+    }
+    deserializer.deserialize_seq(Visitor(std::marker::PhantomData))
+}
+
+// FNV-1a hash of the JSON serialization, folded to 32 bits
+fn serialized_hash(value: &impl Serialize) -> u32 {
+    let serialized = serde_json::to_string(value).unwrap_or_default();
+    let hash = serialized
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    (hash ^ (hash >> 32)) as u32
 }
 
 fn deserialize_layer<'de, D>(deserializer: D) -> Result<Layer, D::Error>
@@ -363,18 +1618,14 @@ where
     D: serde::Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    match s.as_str() {
-        "instance" => Ok(Layer::Instance),
-        "annotate" => Ok(Layer::Annotate),
-        "pin" => Ok(Layer::Pin),
-        "device" => Ok(Layer::Device),
-        "wire" => Ok(Layer::Wire),
-        "text" => Ok(Layer::Text),
-        _ => Err(serde::de::Error::custom(format!("Unknown layer: {}", s))),
+    if s.is_empty() {
+        return Err(serde::de::Error::custom("Empty layer name"));
     }
+    Ok(Layer::from_name(s))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TemplatePin {
     pub name: String,
     pub direction: String,
@@ -0,0 +1,145 @@
+//! Counts of the items of a schematic, without rendering it, see
+//! [`Schematic::stats`].
+
+use crate::error::DrawcktResult;
+use crate::schematic::{DesignId, Schematic, Shape};
+use indexmap::IndexMap;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Breakdown of the items of a schematic, the maps are in order of first
+/// appearance
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchematicStats {
+    /// Instances of each symbol, keyed by `{lib}/{cell}`
+    pub instances: IndexMap<String, usize>,
+    /// Segments of the drawn wires of each net, a wire of `n` points has
+    /// `n - 1` segments. The nets are grouped as they are rendered, wires
+    /// with less than 2 points are not drawn.
+    pub wire_segments: IndexMap<String, usize>,
+    /// Pins of each direction
+    pub pins: IndexMap<String, usize>,
+    /// Shapes drawn on each layer: the schematic labels and shapes, and the
+    /// symbol shapes once per instance
+    pub shapes: IndexMap<String, usize>,
+    /// `[[min_x, min_y], [max_x, max_y]]` of the instance origins, wire
+    /// points, pins, labels and shapes in schematic units, `None` for an
+    /// empty schematic
+    pub extents: Option<[[f64; 2]; 2]>,
+}
+
+impl SchematicStats {
+    pub fn instance_count(&self) -> usize {
+        self.instances.values().sum()
+    }
+
+    pub fn wire_segment_count(&self) -> usize {
+        self.wire_segments.values().sum()
+    }
+
+    pub fn pin_count(&self) -> usize {
+        self.pins.values().sum()
+    }
+
+    pub fn shape_count(&self) -> usize {
+        self.shapes.values().sum()
+    }
+
+    pub fn to_json(&self) -> DrawcktResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // Grow the extents to `[x, y]`
+    fn extend(&mut self, [x, y]: [f64; 2]) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        let [min, max] = self.extents.get_or_insert([[x, y], [x, y]]);
+        *min = [min[0].min(x), min[1].min(y)];
+        *max = [max[0].max(x), max[1].max(y)];
+    }
+}
+
+/// One-line summary, e.g. for the log
+impl fmt::Display for SchematicStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} instances of {} symbols, {} wire segments on {} nets, {} pins, {} shapes",
+            self.instance_count(),
+            self.instances.len(),
+            self.wire_segment_count(),
+            self.wire_segments.len(),
+            self.pin_count(),
+            self.shape_count()
+        )?;
+        if let Some([[min_x, min_y], [max_x, max_y]]) = self.extents {
+            write!(f, ", extents ({min_x}, {min_y}) to ({max_x}, {max_y})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Schematic {
+    /// Counts of the instances, wire segments, pins and shapes, and the
+    /// extents of the schematic
+    pub fn stats(&self) -> SchematicStats {
+        let mut stats = SchematicStats::default();
+        // Instances of each symbol, to count their shapes
+        let mut symbols: HashMap<&DesignId<'static>, usize> =
+            self.symbols.iter().map(|symbol| (&symbol.id, 0)).collect();
+        for instance in &self.instances {
+            *stats
+                .instances
+                .entry(instance.symbol_id.to_string())
+                .or_default() += 1;
+            if let Some(count) = symbols.get_mut(&instance.symbol_id) {
+                *count += 1;
+            }
+            stats.extend([instance.x, instance.y]);
+        }
+        for (net, indices) in self.wires_by_net() {
+            let segments = indices.iter().map(|&i| self.wires[i].points.len() - 1);
+            stats.wire_segments.insert(net.to_string(), segments.sum());
+            for &i in &indices {
+                for [x, y] in &self.wires[i].points {
+                    stats.extend([**x, **y]);
+                }
+            }
+        }
+        for pin in &self.pins {
+            *stats.pins.entry(pin.direction.clone()).or_default() += 1;
+            stats.extend([pin.x, pin.y]);
+        }
+        for shape in self.labels.iter().chain(&self.shapes) {
+            *stats.shapes.entry(shape.layer().to_string()).or_default() += 1;
+            for [x, y] in shape_points(shape) {
+                stats.extend([**x, **y]);
+            }
+        }
+        for symbol in &self.symbols {
+            let count = symbols[&symbol.id];
+            if count == 0 {
+                continue;
+            }
+            for shape in &symbol.shapes {
+                *stats.shapes.entry(shape.layer().to_string()).or_default() += count;
+            }
+        }
+        stats
+    }
+}
+
+// Points defining the shape, the holes of a polygon are inside its outline
+fn shape_points(shape: &Shape) -> &[[OrderedFloat<f64>; 2]] {
+    match shape {
+        Shape::Line { points, .. } | Shape::Polygon { points, .. } => points,
+        Shape::Rect { b_box, .. } | Shape::Ellipse { b_box, .. } | Shape::Arc { b_box, .. } => {
+            b_box
+        }
+        Shape::Label { xy, .. } => std::slice::from_ref(xy),
+    }
+}
@@ -1,5 +1,15 @@
+use crate::DrawcktError;
+use crate::builder::{SchematicBuilder, SymbolBuilder};
+use crate::diff::diff_schematics;
+use crate::geometry::{Point, endpoint_degrees, merge_polylines, split_at_junctions};
+use crate::netlist::Unconnected;
+use crate::options::RenderOptions;
 use crate::renderer::Renderer;
-use crate::schematic::Font;
+use crate::schematic::{
+    DesignId, FillStyleKind, Font, IdScheme, Layer, LayerCell, LayerStyle, LayerStyleIssue,
+    LayerStyles, LegendCorner, Schematic, SchematicIssue, Shape, Units, Wire,
+};
+use drawrs::DiagramObject;
 use ordered_float::OrderedFloat;
 
 /// Helper function to convert Vec<Vec<[f64; 2]>> to Vec<Vec<[OrderedFloat<f64>; 2]>>
@@ -14,6 +24,18 @@ fn convert_lines(lines: &[Vec<[f64; 2]>]) -> Vec<Vec<[OrderedFloat<f64>; 2]>> {
         .collect()
 }
 
+/// [`merge_polylines`] of lines of ordered float pairs
+fn merge_lines(lines: &[Vec<[OrderedFloat<f64>; 2]>]) -> Vec<Vec<[OrderedFloat<f64>; 2]>> {
+    let lines: Vec<Vec<Point>> = lines
+        .iter()
+        .map(|line| line.iter().copied().map(Point::from).collect())
+        .collect();
+    merge_polylines(&lines)
+        .into_iter()
+        .map(|line| line.into_iter().map(Point::into).collect())
+        .collect()
+}
+
 /// Macro to assert that merged_lines contains a path in either forward or reverse direction
 macro_rules! assert_contains_path {
     ($merged_lines:expr, $($point:expr),+ $(,)?) => {
@@ -35,7 +57,7 @@ macro_rules! assert_contains_path {
 fn test_merge_lines_1() {
     let lines = vec![vec![[0.0, 0.0], [1.0, 1.0]], vec![[1.0, 1.0], [2.0, 2.0]]];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_eq!(
         merged_lines,
         vec![vec![
@@ -54,7 +76,7 @@ fn test_merge_lines_2() {
         vec![[2.75, -1.25], [2.90625, -1.25]],
     ];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_eq!(
         merged_lines,
         vec![vec![
@@ -76,7 +98,7 @@ fn test_merge_lines_3() {
         vec![[2.75, -1.25], [2.75, -1.125]],
     ];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_contains_path!(
         merged_lines,
         [2.75, -1.0],
@@ -98,7 +120,7 @@ fn test_merge_lines_3_reordered() {
         vec![[2.75, -1.25], [2.75, -1.125]],
     ];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_contains_path!(
         merged_lines,
         [2.75, -1.0],
@@ -121,7 +143,7 @@ fn test_merge_lines_4() {
         vec![[-1.25, -1.625], [-1.125, -1.625]],
     ];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_contains_path!(merged_lines, [1.90625, -1.40625], [2.125, -1.40625]);
     assert_contains_path!(merged_lines, [4.90625, -1.40625], [5.125, -1.40625]);
     assert_contains_path!(
@@ -144,7 +166,7 @@ fn test_merge_lines_5() {
         vec![[4.0, -1.25], [4.0, -1.125]],
     ];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_contains_path!(merged_lines, [4.0, -1.375], [4.0, -1.25]);
     assert_contains_path!(merged_lines, [4.0, -1.25], [4.0, -1.125]);
     assert_contains_path!(
@@ -173,7 +195,7 @@ fn test_merge_lines_6() {
         vec![[0.1875, -0.0625], [0.25, -0.09375], [0.1875, -0.125]],
     ];
     let converted_lines = convert_lines(&lines);
-    let merged_lines = Renderer::merge_lines(converted_lines.iter().collect());
+    let merged_lines = merge_lines(&converted_lines);
     assert_contains_path!(
         merged_lines,
         [0.25, -0.1875],
@@ -193,6 +215,195 @@ fn test_merge_lines_6() {
     assert_contains_path!(merged_lines, [0.0, 0.0], [0.09375, 0.0]);
 }
 
+#[test]
+fn test_simplify_polyline() {
+    let epsilon = crate::renderer::DEFAULT_SIMPLIFY_EPSILON;
+    let straight: Vec<[f64; 2]> = (0..10).map(|i| [i as f64 * 0.125, 1.0]).collect();
+    let straight = &convert_lines(&[straight])[0];
+    assert_eq!(
+        Renderer::simplify_polyline(straight, epsilon),
+        convert_lines(&[vec![[0.0, 1.0], [1.125, 1.0]]])[0]
+    );
+
+    let l_shape = convert_lines(&[vec![
+        [0.0, 0.0],
+        [0.5, 0.0],
+        [1.0, 0.0],
+        [1.0, 0.5],
+        [1.0, 1.0],
+    ]]);
+    assert_eq!(
+        Renderer::simplify_polyline(&l_shape[0], epsilon),
+        convert_lines(&[vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]])[0]
+    );
+
+    // Doubling back is a corner, an offset larger than epsilon too
+    let back = convert_lines(&[vec![[0.0, 0.0], [1.0, 0.0], [0.5, 0.0]]]);
+    assert_eq!(Renderer::simplify_polyline(&back[0], epsilon), back[0]);
+    let bent = convert_lines(&[vec![[0.0, 0.0], [0.5, 0.001], [1.0, 0.0]]]);
+    assert_eq!(Renderer::simplify_polyline(&bent[0], epsilon), bent[0]);
+    assert_eq!(Renderer::simplify_polyline(&bent[0], 0.01).len(), 2);
+}
+
+#[test]
+fn test_chopped_wire_has_no_waypoints() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.wires.push(Wire {
+        net: "net1".to_string(),
+        points: convert_lines(&[(0..10).map(|i| [0.0, i as f64 * 0.25]).collect()]).remove(0),
+    });
+    let layer_styles = LayerStyles::default();
+    let waypoints = |renderer: Renderer| {
+        let symbols = renderer.render_symbols_file().unwrap();
+        let page = renderer.render_schematic_page(&symbols).unwrap();
+        page.objects()
+            .iter()
+            .find_map(|obj| match obj {
+                DiagramObject::Edge(edge) if obj.id().starts_with("wire-net1-") => {
+                    Some(edge.geometry_ref().intermediate_points().len())
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+    assert_eq!(waypoints(Renderer::new(&schematic, &layer_styles)), 0);
+    assert_eq!(
+        waypoints(Renderer::new(&schematic, &layer_styles).with_simplify_epsilon(None)),
+        8
+    );
+}
+
+#[test]
+fn test_orthogonalize() {
+    // A square mesh with a middle bar, every point skewed by up to 0.00005
+    let mut lines = convert_lines(&[
+        vec![[0.0, 0.0], [1.0, 0.00005]],
+        vec![[1.0, 0.00005], [1.00004, 1.0]],
+        vec![[1.00004, 1.0], [0.00002, 1.00003]],
+        vec![[0.00002, 1.00003], [0.0, 0.0]],
+        vec![[0.5, 0.00003], [0.50005, 1.0]],
+        vec![[2.0, 0.0], [3.0, 1.0]],
+    ]);
+    let diagonal = lines[5].clone();
+    Renderer::orthogonalize(&mut lines.iter_mut().collect::<Vec<_>>(), 0.001);
+    for line in &lines[..5] {
+        let [a, b] = [line[0], line[1]];
+        assert!(a[0] == b[0] || a[1] == b[1], "{line:?} is not orthogonal");
+    }
+    assert_eq!(lines[5], diagonal);
+    // Shared corners moved together, so the ring still merges into one line
+    assert_eq!(lines[0][1], lines[1][0]);
+    assert_eq!(lines[3][1], lines[0][0]);
+    let merged = merge_lines(&lines[..4]);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].len(), 5);
+}
+
+#[test]
+fn test_merge_lines_drops_duplicates() {
+    let lines = convert_lines(&[
+        vec![[0.0, 0.0], [1.0, 0.0]],
+        vec![[1.0, 0.0], [0.0, 0.0]],
+        vec![[0.0, 0.0], [1.0, 0.0]],
+        vec![[2.0, 2.0], [2.0, 2.0]],
+        vec![[1.0, 0.0], [1.0, 1.0]],
+    ]);
+    let merged = merge_lines(&lines);
+    assert_eq!(merged.len(), 1);
+    assert_contains_path!(merged, [0.0, 0.0], [1.0, 0.0], [1.0, 1.0]);
+}
+
+#[test]
+fn test_merge_lines_prepends_polyline() {
+    // The second line ends where the first one starts
+    let lines = convert_lines(&[
+        vec![[2.0, 0.0], [3.0, 0.0]],
+        vec![[0.0, 1.0], [0.0, 0.0], [2.0, 0.0]],
+    ]);
+    assert_eq!(
+        merge_lines(&lines),
+        convert_lines(&[vec![[0.0, 1.0], [0.0, 0.0], [2.0, 0.0], [3.0, 0.0]]])
+    );
+}
+
+fn polylines(lines: &[Vec<[f64; 2]>]) -> Vec<Vec<Point>> {
+    lines
+        .iter()
+        .map(|line| line.iter().copied().map(Point::from).collect())
+        .collect()
+}
+
+#[test]
+fn test_endpoint_degrees() {
+    // A T junction, a closed ring and a single point
+    let lines = polylines(&[
+        vec![[0.0, 0.0], [1.0, 0.0]],
+        vec![[1.0, 0.0], [2.0, 0.0]],
+        vec![[1.0, 0.0], [1.0, 1.0]],
+        vec![[5.0, 5.0], [6.0, 5.0], [6.0, 6.0], [5.0, 5.0]],
+        vec![[9.0, 9.0]],
+    ]);
+    let degrees = endpoint_degrees(&lines);
+    assert_eq!(degrees.len(), 5);
+    assert_eq!(degrees[&Point::new(1.0, 0.0)], 3);
+    assert_eq!(degrees[&Point::new(0.0, 0.0)], 1);
+    assert_eq!(degrees[&Point::new(5.0, 5.0)], 2);
+    assert!(!degrees.contains_key(&Point::new(6.0, 5.0)));
+}
+
+#[test]
+fn test_split_at_junctions() {
+    // A line ending on the middle of another one, and a free polyline
+    let lines = polylines(&[
+        vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+        vec![[1.0, 1.0], [1.0, 0.0]],
+        vec![[5.0, 0.0], [5.0, 1.0], [6.0, 1.0]],
+    ]);
+    let split = split_at_junctions(&lines);
+    assert_eq!(
+        split,
+        polylines(&[
+            vec![[0.0, 0.0], [1.0, 0.0]],
+            vec![[1.0, 0.0], [2.0, 0.0]],
+            vec![[1.0, 1.0], [1.0, 0.0]],
+            vec![[5.0, 0.0], [5.0, 1.0], [6.0, 1.0]],
+        ])
+    );
+    assert_eq!(endpoint_degrees(&split)[&Point::new(1.0, 0.0)], 3);
+    // Merging does not cross the junction again
+    assert_eq!(merge_polylines(&split), split);
+}
+
+#[test]
+fn test_duplicate_shapes_render_once() {
+    let mut schematic = single_instance_schematic("R0");
+    let rect: Shape = serde_json::from_value(serde_json::json!(
+        {"type": "rect", "layer": "annotate", "fillStyle": 1, "bBox": [[0.0, 0.0], [1.0, 1.0]]}
+    ))
+    .unwrap();
+    let label: Shape = serde_json::from_value(serde_json::json!(
+        {"type": "label", "layer": "annotate", "text": "VDD", "xy": [0.0, 0.0], "orient": "R0",
+         "height": 0.1, "justify": "lowerLeft", "font": "stick"}
+    ))
+    .unwrap();
+    schematic.shapes = vec![rect.clone(), rect.clone(), rect];
+    schematic.labels = vec![label.clone(), label];
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let (page, report) = renderer
+        .render_schematic_page_with_report(&symbols)
+        .unwrap();
+    let ids: Vec<&str> = page
+        .objects()
+        .iter()
+        .map(|obj| obj.id())
+        .filter(|id| id.starts_with("shape-") || id.starts_with("label-"))
+        .collect();
+    assert_eq!(ids, ["label-0", "shape-0"]);
+    assert_eq!(report.len(), page.objects().len());
+}
+
 #[test]
 fn test_font_serde_other_roundtrip() {
     let custom: Font = serde_json::from_str("\"myCustomFont\"").unwrap();
@@ -203,3 +414,1824 @@ fn test_font_serde_other_roundtrip() {
     assert_eq!(known, Font::Stick);
     assert_eq!(serde_json::to_string(&known).unwrap(), "\"stick\"");
 }
+
+#[test]
+fn test_layer_styles_font_family() {
+    let json = include_str!("../../drawckt-web/static/style/virtuoso.json");
+    let mut layer_styles: LayerStyles = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        layer_styles.device.font_family,
+        Font::Other("Verdana".to_string())
+    );
+    assert_eq!(
+        layer_styles.css_family(&layer_styles.device.font_family),
+        "Verdana"
+    );
+
+    layer_styles.device.font_family = Font::Stick;
+    assert_eq!(
+        layer_styles.css_family(&Font::Stick),
+        "Courier New, monospace"
+    );
+    layer_styles
+        .font_families
+        .insert(Font::Stick, "Consolas".to_string());
+    assert_eq!(layer_styles.css_family(&Font::Stick), "Consolas");
+
+    let roundtrip: LayerStyles =
+        serde_json::from_str(&serde_json::to_string(&layer_styles).unwrap()).unwrap();
+    assert_eq!(roundtrip.device.font_family, Font::Stick);
+    assert_eq!(roundtrip.css_family(&Font::Stick), "Consolas");
+}
+
+#[test]
+fn test_custom_fonts() {
+    let mut schematic = single_instance_schematic("R0");
+    let label: Shape = serde_json::from_value(serde_json::json!(
+        {"type": "label", "layer": "annotate", "text": "VDD", "xy": [0.0, 0.0], "orient": "R0",
+         "height": 0.1, "justify": "lowerLeft", "font": "stick"}
+    ))
+    .unwrap();
+    schematic.labels = vec![label];
+    let mut layer_styles = LayerStyles::default();
+    layer_styles.annotate.font_family =
+        Font::Other("Corp Mono, DejaVu Sans Mono, monospace".to_string());
+    layer_styles.custom_fonts.insert(
+        "Corp Mono".to_string(),
+        "https://fonts.example.com/corp.css".to_string(),
+    );
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    assert!(content.contains(
+        "fontFamily=Corp Mono, DejaVu Sans Mono, monospace;fontSource=https%3A%2F%2Ffonts.example.com%2Fcorp.css;"
+    ));
+    assert_eq!(content.matches("fontSource=").count(), 1);
+}
+
+/// Schematic with a single resistor symbol and one instance of it
+fn single_instance_schematic(orient: &str) -> Schematic {
+    serde_json::from_value(serde_json::json!({
+        "design": {"lib": "test", "cell": "top"},
+        "instances": [
+            {"name": "X12", "lib": "basic", "cell": "res", "x": 1.0, "y": 2.0, "orient": orient}
+        ],
+        "wires": [],
+        "pins": [],
+        "labels": [],
+        "shapes": [],
+        "symbols": [{
+            "lib": "basic",
+            "cell": "res",
+            "shapes": [
+                {"type": "rect", "layer": "device", "fillStyle": 1, "bBox": [[-0.1, -0.2], [0.1, 0.2]]}
+            ],
+            "pins": []
+        }]
+    }))
+    .unwrap()
+}
+
+// Names made into the same id by the sanitizing still give distinct ids
+#[test]
+fn test_sanitized_name_collisions() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.instances[0].name = "I1/I2".to_string();
+    let mut instance = schematic.instances[0].clone();
+    instance.name = "I1_I2".to_string();
+    schematic.instances.push(instance);
+    for net in ["a-b", "a_b"] {
+        schematic.wires.push(Wire {
+            net: net.to_string(),
+            points: vec![
+                [OrderedFloat(0.0), OrderedFloat(0.0)],
+                [OrderedFloat(1.0), OrderedFloat(0.0)],
+            ],
+        });
+    }
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let mut ids = std::collections::HashSet::new();
+    for obj in page.objects() {
+        assert!(
+            ids.insert(obj.id().to_string()),
+            "duplicate id {}",
+            obj.id()
+        );
+    }
+    assert!(ids.iter().any(|id| id.starts_with("wire-a_b-")));
+    assert!(ids.iter().any(|id| id.starts_with("wire-a_b_")));
+}
+
+#[test]
+fn test_instance_name_with_markup() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.instances[0].name = "I1/I2<3>".to_string();
+    schematic.instances[0].params = [("r".to_string(), "1k".to_string())].into();
+    let layer_styles = LayerStyles {
+        show_instance_params: true,
+        ..Default::default()
+    };
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+
+    let mut reader = quick_xml::Reader::from_str(&content);
+    while !matches!(reader.read_event().unwrap(), quick_xml::events::Event::Eof) {}
+    let (_, page_data) = Renderer::parse_drawio_file(&content)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let instance_objects: Vec<_> = page_data
+        .objects()
+        .iter()
+        .filter(|obj| obj.tag().is_some())
+        .collect();
+    assert!(
+        instance_objects
+            .iter()
+            .any(|obj| obj.id() == "I1_I2_3__c92c71b4-params")
+    );
+    for obj in instance_objects {
+        assert_eq!(obj.tags().collect::<Vec<_>>(), ["I1/I2<3>"]);
+        assert!(obj.id().starts_with("I1_I2_3__c92c71b4-"), "{}", obj.id());
+    }
+}
+
+#[test]
+fn test_instance_link_template() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    assert_eq!(renderer.instance_link(&schematic.instances[0]), None);
+
+    let renderer = renderer.with_instance_link_template(Some(
+        "https://pdk.example.com/{lib}/{cell}?inst={name}&lib={lib}".to_string(),
+    ));
+    let link = "https://pdk.example.com/basic/res?inst=X12&lib=basic";
+    assert_eq!(
+        renderer.instance_link(&schematic.instances[0]).as_deref(),
+        Some(link)
+    );
+
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    assert!(content.contains(&format!(r#"link="{}""#, link.replace('&', "&amp;"))));
+    let (_, page_data) = Renderer::parse_drawio_file(&content)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let linked: Vec<_> = page_data
+        .objects()
+        .iter()
+        .filter(|obj| obj.link().is_some())
+        .collect();
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].xml_parent(), Some("layer-device-shape"));
+    assert_eq!(linked[0].link().map(String::as_str), Some(link));
+}
+
+#[test]
+fn test_instance_link_flag() {
+    let mut options = RenderOptions::default();
+    assert!(RenderOptions::flag_takes_value("--instance-link"));
+    options
+        .apply_flag("--instance-link", Some("https://x/{cell}"))
+        .unwrap();
+    assert_eq!(
+        options.instance_link_template.as_deref(),
+        Some("https://x/{cell}")
+    );
+    assert!(options.apply_flag("--instance-link", None).is_err());
+}
+
+#[test]
+fn test_design_id_from_str() {
+    let id: DesignId = "analogLib/nmos4".parse().unwrap();
+    assert_eq!(id, DesignId::new("analogLib", "nmos4"));
+    assert_eq!(id, DesignId::from(("analogLib", "nmos4")));
+    assert_eq!(id.to_string().parse::<DesignId>().unwrap(), id);
+    for name in ["nmos4", "/nmos4", "analogLib/", "a/b/c"] {
+        assert!(
+            matches!(name.parse::<DesignId>(), Err(DrawcktError::InvalidPageName(n)) if n == name),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_error_context_bad_orient() {
+    let schematic = single_instance_schematic("R180");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let err = renderer.render_schematic_file(&symbols).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "instance X12 (basic/res): shape #0: Drawrs error: UnsupportedOrient: R180"
+    );
+    assert!(matches!(err.root_cause(), DrawcktError::Drawrs(_)));
+}
+
+#[test]
+fn test_schematic_page_has_no_dangling_parents() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.wires.push(Wire {
+        net: "a".to_string(),
+        points: vec![
+            [OrderedFloat(0.0), OrderedFloat(0.0)],
+            [OrderedFloat(1.0), OrderedFloat(0.0)],
+        ],
+    });
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    assert!(!page.has_background());
+    assert!(page.check_parents().is_empty());
+}
+
+#[test]
+fn test_wires_under_wire_layer_view() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.wires.push(Wire {
+        net: "a".to_string(),
+        points: vec![
+            [OrderedFloat(0.0), OrderedFloat(0.0)],
+            [OrderedFloat(1.0), OrderedFloat(0.0)],
+        ],
+    });
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+
+    let wire_layer = page
+        .layers()
+        .find(|layer| layer.id() == Layer::Wire.id_shape(false))
+        .unwrap();
+    assert!(wire_layer.visible());
+    assert!(!wire_layer.is_empty());
+    assert!(
+        wire_layer
+            .objects()
+            .all(|obj| matches!(obj, DiagramObject::Edge(_)))
+    );
+    for layer in page.layers().filter(|layer| layer.id() != wire_layer.id()) {
+        assert!(layer.objects().all(|obj| !obj.id().starts_with("wire-")));
+    }
+    let wires: Vec<_> = page
+        .objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with("wire-"))
+        .collect();
+    assert!(!wires.is_empty());
+    assert!(
+        wires
+            .iter()
+            .all(|obj| obj.xml_parent() == Some(wire_layer.id()))
+    );
+}
+
+#[test]
+fn test_layer_iteration_and_lookup() {
+    let mut layer_styles = LayerStyles::default();
+    layer_styles
+        .custom_layers
+        .insert("esd".to_string(), layer_styles.device.clone());
+    let order: Vec<Layer> = layer_styles.iter().map(|(layer, _)| layer).collect();
+    let mut expected = vec![Layer::Custom("esd".to_string())];
+    expected.extend(layer_styles.layer_order.iter().cloned());
+    assert_eq!(order, expected);
+
+    assert_eq!(layer_styles.layer_by_name("wire"), Some(Layer::Wire));
+    assert_eq!(
+        layer_styles.layer_by_name("esd"),
+        Some(Layer::Custom("esd".to_string()))
+    );
+    assert_eq!(layer_styles.layer_by_name("missing"), None);
+
+    layer_styles
+        .layer_style_mut(&Layer::Wire)
+        .unwrap()
+        .stroke_width = 3.0;
+    assert_eq!(layer_styles.layer_style(&Layer::Wire).stroke_width, 3.0);
+    assert!(
+        layer_styles
+            .layer_style_mut(&Layer::Custom("missing".to_string()))
+            .is_none()
+    );
+}
+
+#[test]
+fn test_set_layer_order() {
+    let mut layer_styles = LayerStyles::default();
+    let mut order = layer_styles.layer_order.clone();
+    order.reverse();
+    layer_styles.set_layer_order(&order).unwrap();
+    assert_eq!(layer_styles.layer_order, order);
+    let top = layer_styles.iter().next().map(|(layer, _)| layer);
+    assert_eq!(top.as_ref(), order.first());
+
+    let mut repeated = order.clone();
+    repeated[1] = repeated[0].clone();
+    assert!(matches!(
+        layer_styles.set_layer_order(&repeated),
+        Err(DrawcktError::InvalidLayerStyles(issues)) if issues == [
+            LayerStyleIssue::RepeatLayer(repeated[0].clone()),
+            LayerStyleIssue::MissingLayer(order[1].clone()),
+        ]
+    ));
+    assert!(matches!(
+        layer_styles.set_layer_order(&order[1..]),
+        Err(DrawcktError::InvalidOption { .. })
+    ));
+    assert_eq!(layer_styles.layer_order, order);
+}
+
+#[test]
+fn test_validate_layer_styles() {
+    assert_eq!(LayerStyles::default().validate(), Ok(()));
+
+    // Every problem is reported at once
+    let layer_styles = LayerStyles {
+        layer_order: [
+            Layer::Wire,
+            Layer::Wire,
+            Layer::Pin,
+            Layer::Pin,
+            Layer::Pin,
+            Layer::Custom("esd".to_string()),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        layer_styles.validate(),
+        Err(vec![
+            LayerStyleIssue::RepeatLayer(Layer::Wire),
+            LayerStyleIssue::RepeatLayer(Layer::Pin),
+            LayerStyleIssue::MissingLayer(Layer::Instance),
+            LayerStyleIssue::MissingLayer(Layer::Annotate),
+            LayerStyleIssue::MissingLayer(Layer::Device),
+            LayerStyleIssue::MissingLayer(Layer::Text),
+        ])
+    );
+
+    // A family with a `;` is reported once, overrides included
+    let mut layer_styles = LayerStyles::default();
+    layer_styles.wire.font_family = Font::Other("Verdana;sans-serif".to_string());
+    layer_styles.text.font_family = Font::Other("Verdana;sans-serif".to_string());
+    layer_styles
+        .font_families
+        .insert(Font::Fixed, "Courier;monospace".to_string());
+    layer_styles.pin.font_family = Font::Fixed;
+    assert_eq!(
+        layer_styles.validate(),
+        Err(vec![
+            LayerStyleIssue::FontFamily("Verdana;sans-serif".to_string()),
+            LayerStyleIssue::FontFamily("Courier;monospace".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_missing_layer_fails_render() {
+    // Replacing the device layer would drop the symbol rect silently
+    let mut layer_styles = LayerStyles::default();
+    let device = layer_styles
+        .layer_order
+        .iter()
+        .position(|layer| *layer == Layer::Device)
+        .unwrap();
+    layer_styles.layer_order[device] = Layer::Custom("esd".to_string());
+    layer_styles
+        .custom_layers
+        .insert("esd".to_string(), layer_styles.device.clone());
+    let issues = vec![LayerStyleIssue::MissingLayer(Layer::Device)];
+    assert_eq!(layer_styles.validate(), Err(issues.clone()));
+
+    let schematic = single_instance_schematic("R0");
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    assert!(matches!(
+        renderer.render_symbols_file(),
+        Err(DrawcktError::InvalidLayerStyles(found)) if found == issues
+    ));
+    let mut page = drawrs::Page::new(None, true);
+    assert!(matches!(
+        renderer.place_symbol(&mut page, "basic", "res", 0.0, 0.0, drawrs::Orient::R0, 1.0),
+        Err(DrawcktError::InvalidLayerStyles(_))
+    ));
+
+    let defaults = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &defaults);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    assert!(matches!(
+        Renderer::update_style(&content, &defaults, &layer_styles),
+        Err(DrawcktError::InvalidLayerStyles(found)) if found == issues
+    ));
+}
+
+#[test]
+fn test_net_color_matching() {
+    let mut layer_styles = LayerStyles::default();
+    layer_styles
+        .net_colors
+        .insert("VDD*".to_string(), "#FF0000".to_string());
+    layer_styles
+        .net_colors
+        .insert("VDDA".to_string(), "#00FF00".to_string());
+    layer_styles
+        .net_colors
+        .insert("CLK?".to_string(), "#0000FF".to_string());
+
+    assert_eq!(layer_styles.net_color("VDDA"), Some("#00FF00"));
+    assert_eq!(layer_styles.net_color("VDD"), Some("#FF0000"));
+    assert_eq!(layer_styles.net_color("VDD_IO"), Some("#FF0000"));
+    assert_eq!(layer_styles.net_color("CLK1"), Some("#0000FF"));
+    assert_eq!(layer_styles.net_color("CLK10"), None);
+    assert_eq!(layer_styles.net_color("net1"), None);
+}
+
+#[test]
+fn test_net_color_wires() {
+    let mut schematic = single_instance_schematic("R0");
+    for net in ["VDD", "net1"] {
+        schematic.wires.push(Wire {
+            net: net.to_string(),
+            points: vec![
+                [OrderedFloat(0.0), OrderedFloat(0.0)],
+                [OrderedFloat(1.0), OrderedFloat(0.0)],
+            ],
+        });
+    }
+    let mut layer_styles = LayerStyles::default();
+    layer_styles
+        .net_colors
+        .insert("VDD*".to_string(), "#FF0000".to_string());
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let stroke = |id: &str| {
+        page.objects()
+            .iter()
+            .find(|obj| obj.id().starts_with(id))
+            .and_then(|obj| match page.resolved_object(obj).as_ref() {
+                DiagramObject::Edge(edge) => edge.stroke_color().cloned(),
+                _ => None,
+            })
+    };
+    assert_eq!(stroke("wire-VDD-").as_deref(), Some("#FF0000"));
+    assert_eq!(stroke("wire-net1-").as_deref(), Some("#000000"));
+}
+
+#[test]
+fn test_custom_layer() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.shapes.push(
+        serde_json::from_value(serde_json::json!(
+            {"type": "rect", "layer": "esd", "fillStyle": 1, "bBox": [[0.0, 0.0], [1.0, 1.0]]}
+        ))
+        .unwrap(),
+    );
+    assert_eq!(
+        schematic.shapes[0].layer(),
+        &Layer::Custom("esd".to_string())
+    );
+
+    // Without a style the custom layer is rejected
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let err = renderer.render_schematic_file(&symbols).unwrap_err();
+    assert!(matches!(err.root_cause(), DrawcktError::UnknownLayer(name) if name == "esd"));
+
+    let mut layer_styles = LayerStyles::default();
+    layer_styles
+        .custom_layers
+        .insert("esd".to_string(), LayerStyle::default());
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let rect = page.objects().iter().find(|obj| obj.id() == "shape-0");
+    assert_eq!(
+        rect.and_then(|obj| obj.xml_parent()),
+        Some("layer-esd-shape")
+    );
+    assert!(
+        page.objects()
+            .iter()
+            .any(|obj| obj.id() == "layer-esd-shape")
+    );
+    assert_eq!(
+        Layer::parse_id("layer-esd-shape"),
+        Some((Layer::Custom("esd".to_string()), LayerCell::Shape))
+    );
+}
+
+#[test]
+fn test_instance_params_below_instance() {
+    let layer_styles = LayerStyles {
+        show_instance_params: true,
+        ..Default::default()
+    };
+    // The symbol rect is 40x80 centered on the instance at (200, -400)
+    for (orient, bottom) in [("R0", -360.0), ("R90", -380.0)] {
+        let mut schematic = single_instance_schematic(orient);
+        schematic.instances[0].params = [("w", "1u"), ("l", "100n"), ("m", "2")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let renderer = Renderer::new(&schematic, &layer_styles);
+        let symbols = renderer.render_symbols_file().unwrap();
+        let page = renderer.render_schematic_page(&symbols).unwrap();
+        let params = page
+            .objects()
+            .iter()
+            .find(|obj| obj.id() == "X12-params")
+            .unwrap();
+        assert_eq!(params.xml_parent(), Some("layer-annotate-label"));
+        assert_eq!(params.tag().map(String::as_str), Some("X12"));
+        assert_eq!(params.text().map(String::as_str), Some("w=1u\nl=100n\nm=2"));
+        let bbox = params.bounding_box().unwrap();
+        assert_eq!(bbox.min_y(), bottom, "{orient}");
+        assert_eq!(bbox.min_x() + bbox.width() / 2.0, 200.0, "{orient}");
+        assert_eq!(bbox.height(), 3.0 * 15.0);
+    }
+
+    // Gated by show_instance_params
+    let mut schematic = single_instance_schematic("R0");
+    schematic.instances[0]
+        .params
+        .insert("w".to_string(), "1u".to_string());
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    assert!(!page.objects().iter().any(|obj| obj.id() == "X12-params"));
+}
+
+fn instance_object_ids(schematic: &Schematic, renderer: &Renderer) -> Vec<String> {
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let prefix = format!("{}-", schematic.instances[0].name);
+    page.objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with(&prefix))
+        .map(|obj| obj.id().to_string())
+        .collect()
+}
+
+#[test]
+fn test_content_ids_stable_on_insert() {
+    let line: Shape = serde_json::from_value(serde_json::json!(
+        {"type": "line", "layer": "device", "points": [[0.0, -0.2], [0.0, -0.4]]}
+    ))
+    .unwrap();
+    let rect: Shape = serde_json::from_value(serde_json::json!(
+        {"type": "rect", "layer": "device", "fillStyle": 1, "bBox": [[-0.3, -0.1], [-0.2, 0.1]]}
+    ))
+    .unwrap();
+    let layer_styles = LayerStyles::default();
+
+    let mut schematic = single_instance_schematic("R0");
+    schematic.symbols[0].shapes.insert(line);
+    let before = instance_object_ids(&schematic, &Renderer::new(&schematic, &layer_styles));
+    assert_eq!(before.len(), 2);
+
+    let mut inserted = schematic.clone();
+    inserted.symbols[0].shapes.shift_insert(0, rect);
+    let after = instance_object_ids(&inserted, &Renderer::new(&inserted, &layer_styles));
+    assert_eq!(after.len(), 3);
+    for id in &before {
+        assert!(after.contains(id), "{id} not in {after:?}");
+    }
+
+    // The index scheme shifts the ids of the following shapes
+    let index_ids = |schematic: &Schematic| {
+        let renderer = Renderer::new(schematic, &layer_styles).with_id_scheme(IdScheme::Index);
+        instance_object_ids(schematic, &renderer)
+    };
+    let before = index_ids(&schematic);
+    let after = index_ids(&inserted);
+    // The original rect is now the second shape
+    assert_ne!(before[0], after[1]);
+}
+
+#[test]
+fn test_validate_ok() {
+    assert_eq!(single_instance_schematic("R0").validate(), Ok(()));
+}
+
+#[test]
+fn test_validate_empty_design() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.design.cell = "".into();
+    assert_eq!(
+        schematic.validate(),
+        Err(vec![SchematicIssue::EmptyDesign("cell")])
+    );
+}
+
+#[test]
+fn test_validate_unknown_symbol() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.instances[0].symbol_id.cell = "cap".into();
+    let issues = schematic.validate().unwrap_err();
+    assert_eq!(
+        issues,
+        vec![SchematicIssue::UnknownSymbol {
+            index: 0,
+            name: "X12".to_string(),
+            symbol: "basic/cap".to_string(),
+        }]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "instance #0 X12: symbol basic/cap not found"
+    );
+}
+
+#[test]
+fn test_validate_duplicate_instance() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.instances.push(schematic.instances[0].clone());
+    schematic.instances.push(schematic.instances[0].clone());
+    assert_eq!(
+        schematic.validate(),
+        Err(vec![
+            SchematicIssue::DuplicateInstance {
+                index: 1,
+                name: "X12".to_string(),
+                first: 0,
+            },
+            SchematicIssue::DuplicateInstance {
+                index: 2,
+                name: "X12".to_string(),
+                first: 0,
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_validate_short_wire() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.wires.push(Wire {
+        net: "n1".to_string(),
+        points: vec![[OrderedFloat(0.0), OrderedFloat(0.0)]],
+    });
+    assert_eq!(
+        schematic.validate(),
+        Err(vec![SchematicIssue::ShortWire {
+            index: 0,
+            net: "n1".to_string(),
+            points: 1,
+        }])
+    );
+}
+
+#[test]
+fn test_validate_non_finite() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.instances[0].x = f64::NAN;
+    schematic.labels.push(Shape::Label {
+        layer: Layer::Text,
+        text: "a".to_string(),
+        xy: [OrderedFloat(f64::INFINITY), OrderedFloat(0.0)],
+        orient: "R0".to_string(),
+        height: OrderedFloat(0.1),
+        justify: Default::default(),
+        font: Font::Stick,
+    });
+    schematic.shapes.push(Shape::Polygon {
+        layer: Layer::Device,
+        fill_style: FillStyleKind::Outline,
+        points: vec![[OrderedFloat(0.0), OrderedFloat(0.0)]; 2],
+        holes: Vec::new(),
+        rounded: false,
+    });
+    let issues = schematic.validate().unwrap_err();
+    assert_eq!(
+        issues,
+        vec![
+            SchematicIssue::NonFinite {
+                item: "instance #0 X12".to_string()
+            },
+            SchematicIssue::NonFinite {
+                item: "label #0".to_string()
+            },
+            SchematicIssue::TooFewPoints {
+                item: "shape #0".to_string(),
+                points: 2,
+                min: 3,
+            },
+        ]
+    );
+    let err = DrawcktError::InvalidSchematic(issues);
+    assert!(
+        err.to_string()
+            .starts_with("Invalid schematic: instance #0 X12: non-finite coordinate; label #0")
+    );
+}
+
+#[test]
+fn test_validate_scale() {
+    let mut schematic = single_instance_schematic("R0");
+    let default_scale = schematic.scale();
+    for units_per_pixel in [0.0, -1.0, f64::NAN] {
+        schematic.units_per_drawio_pixel = Some(units_per_pixel);
+        assert_eq!(schematic.scale(), default_scale);
+        let issues = schematic.validate().unwrap_err();
+        assert!(matches!(issues[..], [SchematicIssue::InvalidScale(_)]));
+    }
+    schematic.units_per_drawio_pixel = Some(0.5);
+    assert_eq!(schematic.scale(), 2.0);
+    assert!(schematic.validate().is_ok());
+}
+
+// Fill colors of the highlight boxes on the diff layer
+fn diff_marks(page: &drawrs::Page) -> Vec<String> {
+    page.objects()
+        .iter()
+        .filter(|obj| obj.xml_parent() == Some("layer-diff") && obj.id().starts_with("diff-"))
+        .filter(|obj| !obj.id().starts_with("diff-ghost-"))
+        .filter_map(|obj| match obj {
+            DiagramObject::Object(o) => o.fill_color().cloned(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_diff_instance_moved() {
+    let old = single_instance_schematic("R0");
+    let mut new = old.clone();
+    new.instances[0].x = 3.0;
+
+    let diff = diff_schematics(&old, &new);
+    assert_eq!(diff.moved_instances.len(), 1);
+    assert_eq!(diff.moved_instances[0].old.x, 1.0);
+    assert_eq!(diff.moved_instances[0].new.x, 3.0);
+    assert!(diff.added_instances.is_empty() && diff.removed_instances.is_empty());
+    assert!(diff_schematics(&new, &new).is_empty());
+
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&new, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_diff(&diff, &symbols).unwrap();
+    assert_eq!(diff_marks(&page), ["#FF9900"]);
+    // The box surrounds the instance at its new position
+    let mark = page
+        .objects()
+        .iter()
+        .find(|obj| obj.id() == "diff-0")
+        .unwrap();
+    let bbox = mark.bounding_box().unwrap();
+    assert!(bbox.min_x() < 600.0 && bbox.max_x() > 600.0);
+}
+
+#[test]
+fn test_diff_instance_removed_with_symbol() {
+    let new = single_instance_schematic("R0");
+    let mut old = new.clone();
+    let mut cap = old.symbols[0].clone();
+    cap.id = DesignId::new("basic", "cap");
+    old.symbols.push(cap);
+    let mut instance = old.instances[0].clone();
+    instance.name = "C1".to_string();
+    instance.symbol_id = DesignId::new("basic", "cap");
+    old.instances.push(instance);
+
+    let diff = diff_schematics(&old, &new);
+    assert_eq!(diff.removed_instances.len(), 1);
+    assert_eq!(diff.removed_symbols.len(), 1);
+    assert_eq!(diff.removed_symbols[0].id, DesignId::new("basic", "cap"));
+
+    // The ghost is drawn from the old symbol, missing from the new symbols
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&new, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_diff(&diff, &symbols).unwrap();
+    assert_eq!(diff_marks(&page), ["#FF0000"]);
+    assert!(
+        page.objects()
+            .iter()
+            .any(|obj| obj.id().starts_with("diff-ghost-C1-"))
+    );
+}
+
+#[test]
+fn test_diff_net_renamed() {
+    let wire = |net: &str| Wire {
+        net: net.to_string(),
+        points: vec![
+            [OrderedFloat(0.0), OrderedFloat(0.0)],
+            [OrderedFloat(1.0), OrderedFloat(0.0)],
+        ],
+    };
+    let mut old = single_instance_schematic("R0");
+    old.wires.push(wire("net1"));
+    let mut new = single_instance_schematic("R0");
+    let mut reversed = wire("net2");
+    reversed.points.reverse();
+    new.wires.push(reversed);
+
+    let diff = diff_schematics(&old, &new);
+    assert_eq!(diff.removed_wires.len(), 1);
+    assert_eq!(diff.removed_wires[0].net, "net1");
+    assert_eq!(diff.added_wires.len(), 1);
+    assert_eq!(diff.added_wires[0].net, "net2");
+    assert!(diff.moved_instances.is_empty());
+
+    // Only the net is compared, the drawing direction does not matter
+    old.wires[0].net = "net2".to_string();
+    assert!(diff_schematics(&old, &new).is_empty());
+    old.wires[0].net = "net1".to_string();
+
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&new, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_diff(&diff, &symbols).unwrap();
+    assert_eq!(diff_marks(&page), ["#FF0000", "#00CC00"]);
+}
+
+/// CMOS inverter, the PMOS is the NMOS symbol shape mirrored about the x axis
+fn inverter_schematic() -> Schematic {
+    let mos = |cell: &str| {
+        serde_json::json!({
+            "lib": "analog",
+            "cell": cell,
+            "shapes": [],
+            "pins": [
+                {"name": "D", "direction": "inputOutput", "x": 0.0, "y": 0.25},
+                {"name": "G", "direction": "input", "x": -0.25, "y": 0.0},
+                {"name": "S", "direction": "inputOutput", "x": 0.0, "y": -0.25}
+            ]
+        })
+    };
+    serde_json::from_value(serde_json::json!({
+        "design": {"lib": "test", "cell": "inv"},
+        "instances": [
+            {"name": "MP0", "lib": "analog", "cell": "pmos", "x": 0.0, "y": 1.0, "orient": "MX"},
+            {"name": "MN0", "lib": "analog", "cell": "nmos", "x": 0.0, "y": 0.0, "orient": "R0"}
+        ],
+        "wires": [
+            {"net": "out", "points": [[0.0, 0.25], [0.0, 0.75]]},
+            {"net": "in", "points": [[-0.25, 1.0], [-0.5, 1.0], [-0.5, 0.0], [-0.25, 0.0]]},
+            {"net": "vdd", "points": [[0.0, 1.25], [0.0, 1.5]]},
+            {"net": "gnd", "points": [[0.0, -0.25], [0.0, -0.5]]}
+        ],
+        "pins": [
+            {"name": "in", "direction": "input", "x": -1.0, "y": 0.5},
+            {"name": "out", "direction": "output", "x": 1.0, "y": 0.5},
+            {"name": "vdd", "direction": "inputOutput", "x": 0.0, "y": 2.0},
+            {"name": "gnd", "direction": "inputOutput", "x": 0.0, "y": -1.0}
+        ],
+        "labels": [],
+        "shapes": [],
+        "symbols": [mos("pmos"), mos("nmos")]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_netlist_inverter() {
+    let netlist = inverter_schematic().to_netlist().unwrap();
+    assert_eq!(
+        netlist,
+        "* test/inv\n\
+         .SUBCKT inv in out vdd gnd\n\
+         XMP0 out in vdd analog_pmos\n\
+         XMN0 out in gnd analog_nmos\n\
+         .ENDS\n"
+    );
+}
+
+#[test]
+fn test_netlist_unconnected() {
+    let mut schematic = inverter_schematic();
+    schematic.wires.retain(|wire| wire.net != "vdd");
+    let err = schematic.to_netlist().unwrap_err();
+    assert!(matches!(err.root_cause(), DrawcktError::UnconnectedTerminal(pin) if pin == "S"));
+    assert_eq!(
+        err.to_string(),
+        "instance MP0 (analog/pmos): Terminal S is not connected"
+    );
+
+    let netlist = schematic.to_netlist_with(Unconnected::Name).unwrap();
+    assert!(netlist.contains("XMP0 out in UNCONNECTED_1 analog_pmos\n"));
+}
+
+// The snap tolerance is in user units, a micron off still connects
+#[test]
+fn test_netlist_micron_units() {
+    let mut schematic = inverter_schematic();
+    let per_unit = Units::Micron.per_user_unit();
+    schematic.units = Some(Units::Micron);
+    for instance in &mut schematic.instances {
+        (instance.x, instance.y) = (instance.x * per_unit, instance.y * per_unit);
+    }
+    for pin in &mut schematic.pins {
+        (pin.x, pin.y) = (pin.x * per_unit, pin.y * per_unit);
+    }
+    for pin in schematic.symbols.iter_mut().flat_map(|s| &mut s.pins) {
+        (pin.x, pin.y) = (pin.x * per_unit, pin.y * per_unit);
+    }
+    for wire in &mut schematic.wires {
+        for point in &mut wire.points {
+            *point = point.map(|v| OrderedFloat(v.0 * per_unit + 1e-3));
+        }
+    }
+    assert_eq!(
+        schematic.to_netlist().unwrap(),
+        inverter_schematic().to_netlist().unwrap()
+    );
+}
+
+#[test]
+fn test_avoid_label_overlap() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.pins = serde_json::from_value(serde_json::json!([
+        {"name": "inp", "direction": "input", "x": 0.0, "y": 0.0},
+        {"name": "inn", "direction": "input", "x": 0.0, "y": 0.05}
+    ]))
+    .unwrap();
+    let layer_styles = LayerStyles::default();
+    let pin_boxes = |avoid_label_overlap: bool| {
+        let renderer =
+            Renderer::new(&schematic, &layer_styles).with_avoid_label_overlap(avoid_label_overlap);
+        let symbols = renderer.render_symbols_file().unwrap();
+        let page = renderer.render_schematic_page(&symbols).unwrap();
+        ["pin-0", "pin-1"].map(|id| {
+            page.objects()
+                .iter()
+                .find(|obj| obj.id() == id)
+                .and_then(|obj| obj.bounding_box())
+                .unwrap()
+        })
+    };
+    let overlap = |[a, b]: [drawrs::BoundingBox; 2]| a.min_y() < b.max_y() && b.min_y() < a.max_y();
+
+    let before = pin_boxes(false);
+    assert!(overlap(before));
+
+    let after = pin_boxes(true);
+    assert!(!overlap(after));
+    // Only the later label moves, vertically by a multiple of its height
+    assert_eq!(
+        (after[0].min_x(), after[0].min_y()),
+        (before[0].min_x(), before[0].min_y())
+    );
+    assert_eq!(after[1].min_x(), before[1].min_x());
+    let shift = (after[1].min_y() - before[1].min_y()) / before[1].height();
+    assert_eq!(shift, shift.round());
+}
+
+#[test]
+fn test_label_entities_round_trip() {
+    let text = "R&D &amp; &quot;q&quot; <x>";
+    let mut schematic = single_instance_schematic("R0");
+    schematic.symbols[0].shapes.insert(
+        serde_json::from_value(serde_json::json!({
+            "type": "label", "layer": "device", "text": text, "xy": [0.0, 0.0],
+            "orient": "R0", "height": 0.05, "justify": "lowerLeft", "font": "stick"
+        }))
+        .unwrap(),
+    );
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    // Symbol file and schematic file are two parse/serialize cycles
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    let pages = Renderer::parse_drawio_file(&content).unwrap();
+    let texts: Vec<&str> = pages[0]
+        .objects
+        .iter()
+        .filter_map(|obj| obj.text())
+        .map(String::as_str)
+        .filter(|t| !t.is_empty())
+        .collect();
+    assert_eq!(texts, [text]);
+}
+
+#[test]
+fn test_instance_and_wire_attributes() {
+    let mut schematic = single_instance_schematic("R0");
+    schematic.wires.push(
+        serde_json::from_value(serde_json::json!({
+            "net": "VDD", "points": [[1.0, 2.2], [1.0, 3.0]]
+        }))
+        .unwrap(),
+    );
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    let pages = Renderer::parse_drawio_file(&content).unwrap();
+    let objects = &pages[0].objects;
+
+    let attribute = |obj: &DiagramObject, key: &str| obj.base().attributes.get(key).cloned();
+    let instance_objects: Vec<_> = objects
+        .iter()
+        .filter(|obj| obj.tag().map(String::as_str) == Some("X12"))
+        .collect();
+    assert!(!instance_objects.is_empty());
+    for obj in instance_objects {
+        assert_eq!(attribute(obj, "lib").as_deref(), Some("basic"));
+        assert_eq!(attribute(obj, "cell").as_deref(), Some("res"));
+    }
+    let wires: Vec<_> = objects
+        .iter()
+        .filter(|obj| matches!(obj, DiagramObject::Edge(_)))
+        .collect();
+    assert_eq!(wires.len(), 1);
+    assert_eq!(attribute(wires[0], "net").as_deref(), Some("VDD"));
+}
+
+#[test]
+fn test_update_style_preserves_unknown_attributes() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap().replacen(
+        "<mxCell style=",
+        r#"<mxCell link="https://example.com" visible="0" style="#,
+        1,
+    );
+    let updated = Renderer::update_style(&content, &layer_styles, &layer_styles).unwrap();
+    assert!(updated.contains(r#"link="https://example.com""#));
+    assert!(updated.contains(r#"visible="0""#));
+}
+
+#[test]
+fn test_parse_keeps_unsupported_elements() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let custom = r#"<custom id="custom-1" parent="1"><payload>x</payload></custom>"#;
+    let content = renderer.render_schematic_file(&symbols).unwrap().replacen(
+        "</root>",
+        &format!("{custom}</root>"),
+        1,
+    );
+
+    let (_, page_data) = Renderer::parse_drawio_file(&content)
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert!(page_data.objects().iter().any(|obj| matches!(
+        obj,
+        DiagramObject::Raw(raw) if raw.xml() == custom
+    )));
+    let updated = Renderer::update_style(&content, &layer_styles, &layer_styles).unwrap();
+    assert!(updated.contains(custom));
+}
+
+#[test]
+fn test_snap_grid_merges_wires() {
+    let mut schematic = single_instance_schematic("R0");
+    for points in [
+        [[0.0, 0.0], [0.333333, 0.0]],
+        [[0.3333334, 0.0], [1.0, 0.0]],
+    ] {
+        schematic.wires.push(Wire {
+            net: "net1".to_string(),
+            points: points.map(|p| p.map(OrderedFloat)).to_vec(),
+        });
+    }
+    let layer_styles = LayerStyles::default();
+    let wire_points = |renderer: &Renderer| {
+        let symbols = renderer.render_symbols_file().unwrap();
+        let page = renderer.render_schematic_page(&symbols).unwrap();
+        page.objects()
+            .iter()
+            .filter_map(|obj| match obj {
+                DiagramObject::Edge(edge) if obj.id().starts_with("wire-") => {
+                    let geometry = edge.geometry_ref();
+                    Some([geometry.source_point(), geometry.target_point()])
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    assert_eq!(wire_points(&renderer).len(), 2);
+
+    let renderer = Renderer::new(&schematic, &layer_styles).with_snap_grid(Some(10.0));
+    assert_eq!(
+        wire_points(&renderer),
+        [[Some([0.0, 0.0]), Some([200.0, 0.0])]]
+    );
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    for obj in page.objects() {
+        if let Some(bbox) = obj.bounding_box() {
+            for v in [bbox.min_x(), bbox.min_y(), bbox.width(), bbox.height()] {
+                assert_eq!(v % 10.0, 0.0, "{} is off the grid", obj.id());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cell_tags() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles {
+        show_instance_params: true,
+        ..Default::default()
+    };
+    let tags = |renderer: Renderer| {
+        let symbols = renderer.render_symbols_file().unwrap();
+        let page = renderer.render_schematic_page(&symbols).unwrap();
+        page.objects()
+            .iter()
+            .filter_map(|obj| obj.tag().cloned())
+            .collect::<std::collections::BTreeSet<_>>()
+    };
+    assert_eq!(
+        tags(Renderer::new(&schematic, &layer_styles)),
+        std::collections::BTreeSet::from(["X12".to_string()])
+    );
+    assert_eq!(
+        tags(Renderer::new(&schematic, &layer_styles).with_cell_tags(true)),
+        std::collections::BTreeSet::from(["X12 res".to_string()])
+    );
+}
+
+/// Warnings logged by any test, see [`captured_warnings`]
+static WARNINGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+struct CaptureLogger;
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            WARNINGS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+    fn flush(&self) {}
+}
+
+/// Install the capturing logger and return the warnings logged so far
+fn captured_warnings() -> Vec<String> {
+    if log::set_logger(&CaptureLogger).is_ok() {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+    WARNINGS.lock().unwrap().clone()
+}
+
+#[test]
+fn test_unknown_symbol_layer_falls_back() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let mut symbols = renderer.render_symbols_file().unwrap();
+    for content in symbols.0.values_mut() {
+        *content = content
+            .replace(
+                r#"parent="layer-device-shape""#,
+                r#"parent="layer-custom-shape""#,
+            )
+            .into();
+    }
+    captured_warnings();
+
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let parents: Vec<_> = page
+        .objects()
+        .iter()
+        .filter(|obj| obj.tag().map(String::as_str) == Some("X12"))
+        .map(|obj| obj.xml_parent())
+        .collect();
+    assert_eq!(parents, [Some(Layer::Annotate.id_shape(false).as_str())]);
+    assert!(captured_warnings().iter().any(|warning| {
+        warning.contains("instance X12 (basic/res)") && warning.contains("layer-custom-shape")
+    }));
+
+    let renderer = Renderer::new(&schematic, &layer_styles).with_fallback_layer(Layer::Text);
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    assert!(
+        page.objects()
+            .iter()
+            .any(|obj| obj.xml_parent() == Some(Layer::Text.id_shape(false).as_str()))
+    );
+}
+
+#[test]
+fn test_place_symbol() {
+    use drawrs::{Orient, Page};
+
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let mut page = Page::new(None, true);
+    renderer
+        .place_symbol(&mut page, "basic", "res", 100.0, 100.0, Orient::R0, 1.0)
+        .unwrap();
+    renderer
+        .place_symbol(&mut page, "basic", "res", 300.0, 100.0, Orient::R90, 0.5)
+        .unwrap();
+
+    let placed: Vec<_> = page.objects()[2..].iter().collect();
+    assert_eq!(placed.len(), 2);
+    assert_ne!(placed[0].id(), placed[1].id());
+    assert!(placed.iter().all(|obj| obj.xml_parent() == Some("1")));
+    // The 0.2 x 0.4 rect, around the origin
+    let bbox = placed[0].bounding_box().unwrap();
+    assert!(bbox.approx_eq(&drawrs::BoundingBox::new(80.0, 60.0, 40.0, 80.0), 1e-9));
+    let bbox = placed[1].bounding_box().unwrap();
+    assert_eq!([bbox.width(), bbox.height()], [20.0, 40.0]);
+    assert_eq!(bbox.center(), [300.0, 100.0]);
+
+    let err = renderer
+        .place_symbol(&mut page, "basic", "cap", 0.0, 0.0, Orient::R0, 1.0)
+        .unwrap_err();
+    assert!(matches!(err, DrawcktError::SymbolNotFound(name) if name == "basic/cap"));
+}
+
+#[test]
+fn test_load_v0_style() {
+    let v0 = r##"{
+        "instance": {"stroke_color": "#123456", "sch_visible": false},
+        "wire": {"stroke_width": 2.0},
+        "retired_option": 1
+    }"##;
+    let styles = LayerStyles::from_json(v0).unwrap();
+    let defaults = LayerStyles::default();
+    assert_eq!(styles.version, LayerStyles::VERSION);
+    assert_eq!(styles.instance.stroke_color, "#123456");
+    assert!(!styles.instance.shape_sch_visible);
+    assert!(!styles.instance.label_sch_visible);
+    assert_eq!(styles.wire.stroke_width, 2.0);
+    assert!(styles.wire.shape_sch_visible);
+    assert_eq!(styles.wire_open_end_color, "#FF0000");
+    assert_eq!(styles.pin.stroke_color, defaults.pin.stroke_color);
+    assert!(styles.custom_layers.is_empty());
+
+    // A file of a later version is not read as the current one
+    let current = format!(r#"{{"version": {}}}"#, LayerStyles::VERSION);
+    assert_eq!(
+        LayerStyles::from_json(&current).unwrap().version,
+        LayerStyles::VERSION
+    );
+    let next = format!(r#"{{"version": {}}}"#, LayerStyles::VERSION + 1);
+    let err = LayerStyles::from_json(&next).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("is newer than the supported version")
+    );
+}
+
+#[test]
+fn test_legacy_sch_visible() {
+    let legacy: LayerStyle = serde_json::from_str(r#"{"sch_visible": false}"#).unwrap();
+    assert!(!legacy.shape_sch_visible);
+    assert!(!legacy.label_sch_visible);
+    let split: LayerStyle =
+        serde_json::from_str(r#"{"sch_visible": false, "shape_sch_visible": true}"#).unwrap();
+    assert!(split.shape_sch_visible);
+    assert!(!split.label_sch_visible);
+    let default: LayerStyle = serde_json::from_str("{}").unwrap();
+    assert!(default.shape_sch_visible && default.label_sch_visible);
+}
+
+#[test]
+fn test_update_style_toggles_visibility() {
+    let schematic = single_instance_schematic("R0");
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    let label_cell = r#"id="layer-instance-label" parent="0" visible="0""#;
+    let shape_cell = r#"id="layer-instance-shape" parent="0" visible="0""#;
+    assert!(!content.contains(label_cell));
+
+    let mut new_styles = old_styles.clone();
+    new_styles.instance.label_sch_visible = false;
+    let hidden = Renderer::update_style(&content, &old_styles, &new_styles).unwrap();
+    assert!(hidden.contains(label_cell));
+    assert!(!hidden.contains(shape_cell));
+
+    let shown = Renderer::update_style(&hidden, &new_styles, &old_styles).unwrap();
+    assert!(!shown.contains(label_cell));
+}
+
+#[test]
+fn test_update_style_keeps_hand_edited_fill() {
+    let mut value = serde_json::to_value(single_instance_schematic("R0")).unwrap();
+    value["symbols"][0]["shapes"][0]["fillStyle"] = 2.into();
+    let schematic: Schematic = serde_json::from_value(value).unwrap();
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    let mut new_styles = old_styles.clone();
+    new_styles.device.stroke_color = "#00AA00".into();
+
+    // The fill derived from the stroke color follows it
+    let derived = Renderer::update_style(&content, &old_styles, &new_styles).unwrap();
+    assert!(derived.contains("fillColor=#00AA00;"));
+
+    // A fill recolored in draw.io is left alone
+    let edited = content.replace("fillColor=#000000;", "fillColor=#FFCC00;");
+    assert!(edited.contains("fillColor=#FFCC00;"));
+    let updated = Renderer::update_style(&edited, &old_styles, &new_styles).unwrap();
+    assert!(updated.contains("fillColor=#FFCC00;"));
+    assert!(!updated.contains("fillColor=#00AA00;"));
+}
+
+#[test]
+fn test_update_wire_label_text_color() {
+    let mut value = serde_json::to_value(single_instance_schematic("R0")).unwrap();
+    value["symbols"][0]["shapes"] = serde_json::json!([
+        {"type": "line", "layer": "wire", "points": [[0.0, 0.0], [0.25, 0.0]]},
+        {"type": "label", "layer": "wire", "text": "B", "xy": [0.0, 0.0], "orient": "R0",
+         "height": 0.0625, "justify": "lowerLeft", "font": "stick"}
+    ]);
+    let schematic: Schematic = serde_json::from_value(value).unwrap();
+    let mut old_styles = LayerStyles::default();
+    old_styles.wire.stroke_color = "#112233".into();
+    old_styles.wire.text_color = "#445566".into();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = symbols.0.values().next().unwrap();
+    assert!(content.contains("fontColor=#445566;"));
+    assert!(content.contains("strokeColor=#112233;"));
+
+    let mut new_styles = old_styles.clone();
+    new_styles.wire.text_color = "#778899".into();
+    let updated = Renderer::update_style(content, &old_styles, &new_styles).unwrap();
+    // Same as a render with the new style
+    let new_renderer = Renderer::new(&schematic, &new_styles);
+    let rendered = new_renderer.render_symbols_file().unwrap();
+    for text in [updated.as_str(), rendered.0.values().next().unwrap()] {
+        assert!(text.contains("fontColor=#778899;"));
+        assert!(!text.contains("fontColor=#445566;"));
+        assert!(text.contains("strokeColor=#112233;"));
+    }
+}
+
+#[test]
+fn test_update_label_keeps_captioned_box() {
+    use drawrs::Object;
+
+    let schematic = single_instance_schematic("R0");
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+
+    let mut pin_box = Object::new(Some("pin-box".to_string()));
+    pin_box.set_value("A".to_string());
+    pin_box.set_position([0.0, 0.0]);
+    pin_box.set_width(10.0);
+    pin_box.set_height(10.0);
+    pin_box.set_fill_color(Some("#FF0000".to_string()));
+    pin_box.set_stroke_color(Some("#000000".to_string()));
+    pin_box.set_font_size(Some(12.0));
+    pin_box.set_xml_parent(Some(Layer::Pin.id_label()));
+    let mut text = pin_box.clone();
+    text.set_id("pin-text".to_string());
+    text.set_fill_color(Some("none".to_string()));
+    text.set_stroke_color(Some("none".to_string()));
+    let content = content.replacen(
+        "</root>",
+        &format!("{}{}</root>", pin_box.xml(), text.xml()),
+        1,
+    );
+
+    let mut new_styles = old_styles.clone();
+    new_styles.pin.font_zoom = 2.0;
+    let updated = Renderer::update_style(&content, &old_styles, &new_styles).unwrap();
+    let (_, page_data) = Renderer::parse_drawio_file(&updated)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let find = |id: &str| {
+        page_data
+            .objects()
+            .iter()
+            .find_map(|obj| match obj {
+                DiagramObject::Object(o) if o.id() == id => Some(o),
+                _ => None,
+            })
+            .unwrap()
+    };
+    let pin_box = find("pin-box");
+    assert_eq!(pin_box.font_size(), Some(24.0));
+    assert_eq!([pin_box.width(), pin_box.height()], [10.0, 10.0]);
+    let text = find("pin-text");
+    assert_eq!(text.font_size(), Some(24.0));
+    assert_eq!(text.height(), 24.0);
+}
+
+#[test]
+fn test_update_label_of_edge() {
+    use drawrs::Edge;
+
+    let schematic = single_instance_schematic("R0");
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+
+    let mut edge = Edge::new(Some("net-label".to_string()));
+    edge.set_label(Some("VDD".to_string()));
+    edge.set_font_size(Some(12.0));
+    edge.set_font_color(Some(old_styles.wire.text_color.to_string()));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    edge.set_xml_parent(Some(Layer::Wire.id_label()));
+    let content = content.replacen("</root>", &format!("{}</root>", edge.xml()), 1);
+
+    let mut new_styles = old_styles.clone();
+    new_styles.wire.font_zoom = 2.0;
+    new_styles.wire.text_color = "#0000FF".into();
+    let updated = Renderer::update_style(&content, &old_styles, &new_styles).unwrap();
+    let (_, page_data) = Renderer::parse_drawio_file(&updated)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let Some(DiagramObject::Edge(edge)) = page_data
+        .objects()
+        .iter()
+        .find(|obj| obj.id() == "net-label")
+    else {
+        panic!("expected the label edge");
+    };
+    assert_eq!(edge.font_size(), Some(24.0));
+    assert_eq!(edge.font_color().map(String::as_str), Some("#0000FF"));
+}
+
+#[test]
+fn test_font_size_clamps() {
+    captured_warnings();
+    let mut schematic = single_instance_schematic("R0");
+    schematic.labels.push(Shape::Label {
+        layer: Layer::Text,
+        text: "tiny".to_string(),
+        xy: [OrderedFloat(0.0), OrderedFloat(0.0)],
+        orient: "R0".to_string(),
+        height: OrderedFloat(0.0001),
+        justify: Default::default(),
+        font: Font::Stick,
+    });
+    let layer_styles = LayerStyles {
+        min_font_size: Some(6.0),
+        ..Default::default()
+    };
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let label = page
+        .objects()
+        .iter()
+        .find_map(|obj| match obj {
+            DiagramObject::Object(o) if o.value().is_some_and(|v| v == "tiny") => Some(o),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(label.font_size(), Some(6.0));
+    assert_eq!([label.width(), label.height()], [12.0, 6.0]);
+    assert!(
+        captured_warnings()
+            .iter()
+            .any(|warning| { warning.contains(r#"label "tiny" on layer text is clamped to 6"#) })
+    );
+
+    // The zoom scaling of a style update is clamped too
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    let mut new_styles = layer_styles.clone();
+    new_styles.text.font_zoom = 1e6;
+    new_styles.max_font_size = Some(40.0);
+    let updated = Renderer::update_style(&content, &layer_styles, &new_styles).unwrap();
+    let (_, page_data) = Renderer::parse_drawio_file(&updated)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let label = page_data
+        .objects()
+        .iter()
+        .find_map(|obj| match obj {
+            DiagramObject::Object(o) if o.value().is_some_and(|v| v == "tiny") => Some(o),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(label.font_size(), Some(40.0));
+    assert_eq!([label.width(), label.height()], [80.0, 40.0]);
+}
+
+#[test]
+fn test_fill_style_kind_forms() {
+    let shapes: Vec<Shape> = serde_json::from_value(serde_json::json!([
+        {"type": "rect", "layer": "device", "fillStyle": 2, "bBox": [[0, 0], [1, 1]]},
+        {"type": "rect", "layer": "device", "fillStyle": "filledPattern", "bBox": [[0, 0], [1, 1]]},
+        {"type": "rect", "layer": "device", "fillStyle": 0, "bBox": [[0, 0], [1, 1]]},
+        {"type": "rect", "layer": "device", "bBox": [[0, 0], [1, 1]]}
+    ]))
+    .unwrap();
+    let kinds: Vec<_> = shapes
+        .iter()
+        .map(|shape| match shape {
+            Shape::Rect { fill_style, .. } => *fill_style,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            FillStyleKind::Filled,
+            FillStyleKind::FilledPattern,
+            FillStyleKind::Outline,
+            FillStyleKind::Outline
+        ]
+    );
+    // Written back in the legacy numeric form
+    let value = serde_json::to_value(&shapes[1]).unwrap();
+    assert_eq!(value["fillStyle"], 4);
+}
+
+#[test]
+fn test_unknown_fill_style_names_shape() {
+    let mut value = serde_json::to_value(single_instance_schematic("R0")).unwrap();
+    value["symbols"][0]["shapes"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!(
+            {"type": "rect", "layer": "device", "fillStyle": 7, "bBox": [[0, 0], [1, 1]]}
+        ));
+    let err = serde_json::from_value::<Schematic>(value).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("shape #1: unknown fill style 7"),
+        "{err}"
+    );
+
+    let err = serde_json::from_str::<Shape>(
+        r#"{"type": "ellipse", "layer": "wire", "fillStyle": "striped", "bBox": [[0, 0], [1, 1]]}"#,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains(r#"unknown fill style "striped""#),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_b_box_corner_order() {
+    let corners = [
+        [[-0.1, -0.2], [0.1, 0.2]],
+        [[0.1, 0.2], [-0.1, -0.2]],
+        [[-0.1, 0.2], [0.1, -0.2]],
+        [[0.1, -0.2], [-0.1, 0.2]],
+    ];
+    let geometries: Vec<Vec<drawrs::BoundingBox>> = corners
+        .iter()
+        .map(|b_box| {
+            let mut value = serde_json::to_value(single_instance_schematic("R0")).unwrap();
+            value["symbols"][0]["shapes"][0]["bBox"] = serde_json::json!(b_box);
+            value["shapes"] = serde_json::json!([
+                {"type": "rect", "layer": "annotate", "bBox": b_box},
+                {"type": "ellipse", "layer": "annotate", "bBox": b_box},
+                {"type": "ellipse", "layer": "wire", "fillStyle": 2, "bBox": b_box},
+                {"type": "arc", "layer": "annotate", "bBox": b_box,
+                 "startAngle": 0.0, "stopAngle": 1.0}
+            ]);
+            let schematic: Schematic = serde_json::from_value(value).unwrap();
+            let layer_styles = LayerStyles::default();
+            let renderer = Renderer::new(&schematic, &layer_styles);
+            let symbols = renderer.render_symbols_file().unwrap();
+            let page = renderer.render_schematic_page(&symbols).unwrap();
+            page.objects()
+                .iter()
+                .filter(|obj| obj.id().starts_with("shape-") || obj.id().starts_with("X12"))
+                .filter_map(|obj| obj.bounding_box())
+                .collect()
+        })
+        .collect();
+    assert_eq!(geometries[0].len(), 5);
+    assert!(geometries[0][0].approx_eq(&drawrs::BoundingBox::new(180.0, -440.0, 40.0, 80.0), 1e-9));
+    for geometry in &geometries[1..] {
+        assert_eq!(geometry, &geometries[0]);
+    }
+}
+
+#[test]
+fn test_label_baseline_adjust_scales_with_font() {
+    let mut value = serde_json::to_value(single_instance_schematic("R0")).unwrap();
+    value["shapes"] = serde_json::json!([
+        {"type": "label", "layer": "wire", "text": "a", "xy": [0.0, 0.0], "orient": "R0",
+         "height": 0.05, "justify": "lowerLeft", "font": "stick"},
+        {"type": "label", "layer": "wire", "text": "a", "xy": [0.0, 0.0], "orient": "R0",
+         "height": 0.1, "justify": "lowerLeft", "font": "stick"}
+    ]);
+    let schematic: Schematic = serde_json::from_value(value).unwrap();
+    let mut layer_styles = LayerStyles::default();
+    layer_styles.wire.label_spacing = 1.0;
+    layer_styles.wire.label_baseline_adjust = -0.25;
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    let labels: Vec<&drawrs::Object> = page
+        .objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with("shape-"))
+        .filter_map(|obj| match obj {
+            DiagramObject::Object(obj) => Some(obj),
+            _ => None,
+        })
+        .collect();
+    let [small, large] = labels.as_slice() else {
+        panic!("expected two labels, got {labels:?}");
+    };
+    let spacing = |obj: &drawrs::Object, key: &str| -> f64 {
+        obj.style_property(key).unwrap().parse().unwrap()
+    };
+    assert_eq!(spacing(small, "spacing"), 1.0);
+    assert_eq!(spacing(large, "spacing"), 1.0);
+    // The box bottom stays on the anchor, the text offset follows the font height
+    let (small_box, large_box) = (small.geometry_ref(), large.geometry_ref());
+    assert_eq!(small_box.y() + small_box.height(), 0.0);
+    assert_eq!(large_box.y() + large_box.height(), 0.0);
+    assert_eq!(spacing(small, "spacingBottom"), -0.25 * small_box.height());
+    assert_eq!(spacing(large, "spacingBottom"), -0.25 * large_box.height());
+    assert_eq!(
+        spacing(large, "spacingBottom"),
+        2.0 * spacing(small, "spacingBottom")
+    );
+}
+
+#[test]
+fn test_builder_matches_json() {
+    let built = SchematicBuilder::new("test", "top")
+        .add_symbol(SymbolBuilder::new("basic", "res").rect(
+            Layer::Device,
+            [-0.1, -0.2],
+            [0.1, 0.2],
+        ))
+        .add_instance("X12", "basic", "res", 1.0, 2.0, drawrs::Orient::R0)
+        .build()
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&built).unwrap(),
+        serde_json::to_value(single_instance_schematic("R0")).unwrap()
+    );
+}
+
+#[test]
+fn test_builder_reports_all_issues() {
+    let err = SchematicBuilder::new("test", "top")
+        .add_symbol(SymbolBuilder::new("basic", "res").pin("", "input", 0.0, 0.0))
+        .add_instance("", "basic", "res", 0.0, 0.0, drawrs::Orient::R0)
+        .add_instance("X1", "basic", "cap", 0.0, 0.0, drawrs::Orient::R0)
+        .add_wire("a", &[[0.0, 0.0]])
+        .build()
+        .unwrap_err();
+    let DrawcktError::InvalidSchematic(issues) = err else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!(
+        issues.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "symbol basic/res pin: empty name",
+            "instance #0: empty name",
+            "wire #0 on net a: 1 point(s), at least 2 are needed",
+            "instance #1 X1: symbol basic/cap not found",
+        ]
+    );
+}
+
+#[test]
+fn test_stale_symbol_hash() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let mut symbols = renderer.render_symbols_file().unwrap();
+    assert!(renderer.render_schematic_page(&symbols).is_ok());
+
+    let content = symbols.0.values_mut().next().unwrap();
+    let hash = format!(
+        r#"symbolHash="{:08x}""#,
+        schematic.symbols[0].content_hash()
+    );
+    assert!(content.contains(&hash));
+    *content = content.replace(&hash, r#"symbolHash="00000000""#).into();
+    let Err(err) = renderer.render_schematic_page(&symbols) else {
+        panic!("stale symbol is rendered");
+    };
+    assert!(matches!(
+        &err,
+        DrawcktError::StaleSymbol { lib, cell, .. } if lib == "basic" && cell == "res"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Symbol basic/res is stale: the symbol shapes or pins changed since it was rendered"
+    );
+
+    // Lenient rendering only warns
+    let renderer = Renderer::new(&schematic, &layer_styles).with_allow_stale_symbols(true);
+    assert!(renderer.render_schematic_page(&symbols).is_ok());
+}
+
+#[test]
+fn test_stale_symbol_style() {
+    let schematic = single_instance_schematic("R0");
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+
+    let mut new_styles = LayerStyles::default();
+    new_styles.device.stroke_color = "#FF0000".into();
+    let renderer = Renderer::new(&schematic, &new_styles);
+    let Err(err) = renderer.render_schematic_page(&symbols) else {
+        panic!("stale symbol is rendered");
+    };
+    assert_eq!(
+        err.to_string(),
+        "Symbol basic/res is stale: the layer styles changed since it was rendered"
+    );
+
+    // Updating the symbol style refreshes its provenance
+    let updated = crate::renderer::SymbolContexts(
+        symbols
+            .0
+            .iter()
+            .map(|(id, content)| {
+                let content = Renderer::update_style(content, &old_styles, &new_styles).unwrap();
+                (id.clone(), content.into())
+            })
+            .collect(),
+    );
+    assert!(renderer.render_schematic_page(&updated).is_ok());
+}
+
+// Undoing a symbol edit after a style change renders the symbol of the
+// previous style again
+#[test]
+fn test_undo_symbol_after_style_change() {
+    let schematic = single_instance_schematic("R0");
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+
+    // Schematic-only settings do not make the symbols stale
+    let mut new_styles = LayerStyles::default();
+    new_styles.wire.stroke_color = "#FF0000".into();
+    new_styles.layer_legend_corner = LegendCorner::TopLeft;
+    new_styles.custom_fonts.insert(
+        "Inter".to_string(),
+        "https://fonts.example/inter".to_string(),
+    );
+    assert_eq!(old_styles.symbol_hash(), new_styles.symbol_hash());
+    let renderer = Renderer::new(&schematic, &new_styles);
+    assert!(renderer.render_schematic_page(&symbols).is_ok());
+
+    // A symbol style change does, unless stale symbols are allowed
+    new_styles.pin.stroke_color = "#00FF00".into();
+    let renderer = Renderer::new(&schematic, &new_styles);
+    assert!(matches!(
+        renderer.render_schematic_page(&symbols),
+        Err(DrawcktError::StaleSymbol { .. })
+    ));
+    let renderer = renderer.with_allow_stale_symbols(true);
+    assert!(renderer.render_schematic_page(&symbols).is_ok());
+}
+
+#[test]
+fn test_edited_symbol_box_warns() {
+    let schematic = single_instance_schematic("R0");
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let mut symbols = renderer.render_symbols_file().unwrap();
+    let box_warnings = || {
+        captured_warnings()
+            .into_iter()
+            .filter(|warning| warning.starts_with("symbol basic/res: the box of its page"))
+            .count()
+    };
+    let before = box_warnings();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    assert_eq!(box_warnings(), before);
+
+    // A giant rectangle drawn by hand on the symbol page
+    let giant = r#"<mxCell id="giant" value="" style="rounded=0;" vertex="1" parent="layer-device-shape">
+          <mxGeometry x="-500" y="-500" width="1000" height="1000" as="geometry" />
+        </mxCell>
+      </root>"#;
+    for content in symbols.0.values_mut() {
+        *content = content.replacen("</root>", giant, 1).into();
+    }
+    let edited = renderer.render_schematic_page(&symbols).unwrap();
+    assert_eq!(box_warnings(), before + 1);
+    // The instance is still rendered, with the giant rectangle
+    assert_eq!(edited.objects().len(), page.objects().len() + 1);
+}
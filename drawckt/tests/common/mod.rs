@@ -0,0 +1,72 @@
+//! Fixtures and render helpers shared by the integration tests
+
+// Each test crate uses a part of the helpers
+#![allow(dead_code)]
+
+use drawckt::renderer::Renderer;
+use drawckt::schematic::Schematic;
+use drawrs::{DiagramObject, Object, Page};
+
+/// The schematic of `tests/case1`
+pub fn case1() -> Schematic {
+    serde_json::from_str(include_str!("../case1/schematic.json"))
+        .expect("Failed to parse schematic")
+}
+
+/// Schematic of the JSON `fields`, e.g. its `shapes`, the other fields empty
+pub fn schematic(fields: serde_json::Value) -> Schematic {
+    let mut json = serde_json::json!({
+        "design": {"lib": "test", "cell": "top"},
+        "instances": [],
+        "wires": [],
+        "pins": [],
+        "labels": [],
+        "shapes": [],
+        "symbols": []
+    });
+    for (key, value) in fields.as_object().expect("Fields of a schematic") {
+        json[key] = value.clone();
+    }
+    serde_json::from_value(json).expect("Failed to parse schematic")
+}
+
+/// Schematic page of `renderer`, with the symbols it renders
+pub fn render_page(renderer: &Renderer) -> Page {
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    renderer
+        .render_schematic_page(&symbols)
+        .expect("Failed to render schematic")
+}
+
+/// Schematic file of `renderer`, with the symbols it renders
+pub fn render_file(renderer: &Renderer) -> String {
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    renderer
+        .render_schematic_file(&symbols)
+        .expect("Failed to render schematic")
+}
+
+/// The single page of a rendered file
+pub fn parse_page(content: &str) -> Page {
+    let (_, page_data) = Renderer::parse_drawio_file(content)
+        .expect("Failed to parse")
+        .pop()
+        .expect("No page");
+    let mut page = Page::new(None, false);
+    for obj in page_data.objects() {
+        page.add_object(obj.clone());
+    }
+    page
+}
+
+/// The object `id` of `page`
+pub fn object(page: &Page, id: &str) -> Object {
+    match page.objects().iter().find(|obj| obj.id() == id) {
+        Some(DiagramObject::Object(obj)) => obj.clone(),
+        other => panic!("expected the object {id}, got {other:?}"),
+    }
+}
@@ -0,0 +1,195 @@
+#![cfg(feature = "cli")]
+
+use assert_cmd::assert::Assert;
+use assert_cmd::cargo::cargo_bin_cmd;
+use drawckt::cli::{EXIT_INVALID, EXIT_IO, EXIT_USAGE};
+use drawckt::schematic::LayerStyles;
+use std::path::{Path, PathBuf};
+
+const SCHEMATIC: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/case1/schematic.json");
+const STYLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/case1/style.json");
+
+// Empty scratch directory of a test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("drawckt-cli-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create the directory");
+    dir
+}
+
+fn drawckt(args: &[&str], dir: &Path) -> Assert {
+    cargo_bin_cmd!("drawckt")
+        .args(args)
+        .current_dir(dir)
+        .assert()
+}
+
+#[test]
+fn symbols_and_schematic() {
+    let dir = temp_dir("render");
+    drawckt(
+        &[
+            "symbols",
+            SCHEMATIC,
+            "--style",
+            STYLE,
+            "--deterministic-ids",
+        ],
+        &dir,
+    )
+    .success();
+    assert!(dir.join("symbols/basic/ipin.drawio").is_file());
+
+    drawckt(
+        &[
+            "schematic",
+            SCHEMATIC,
+            "--style",
+            STYLE,
+            "--svg",
+            "-o",
+            "out.drawio",
+        ],
+        &dir,
+    )
+    .success();
+    assert!(dir.join("out.drawio").is_file());
+    assert!(dir.join("out.svg").is_file());
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[test]
+fn schematic_without_symbols_fails() {
+    let dir = temp_dir("no-symbols");
+    drawckt(&["schematic", SCHEMATIC, "--symbols", "missing"], &dir).code(EXIT_IO as i32);
+    assert!(!dir.join("schematic.drawio").exists());
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[test]
+fn update_style() {
+    let dir = temp_dir("update-style");
+    drawckt(
+        &["symbols", SCHEMATIC, "--single-file", "symbols.drawio"],
+        &dir,
+    )
+    .success();
+    let file = dir.join("symbols.drawio");
+    let before = std::fs::read_to_string(&file).unwrap();
+
+    let mut style = LayerStyles::default();
+    style.device.stroke_color = "#123456".into();
+    std::fs::write(dir.join("new.json"), serde_json::to_string(&style).unwrap()).unwrap();
+    drawckt(
+        &["update-style", "--style", "new.json", "symbols.drawio"],
+        &dir,
+    )
+    .success();
+    let after = std::fs::read_to_string(&file).unwrap();
+    assert!(!before.contains("#123456"));
+    assert!(after.contains("#123456"));
+
+    // A style file that cannot be read
+    drawckt(
+        &[
+            "update-style",
+            "--old-style",
+            "missing.json",
+            "symbols.drawio",
+        ],
+        &dir,
+    )
+    .code(EXIT_IO as i32);
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[test]
+fn check() {
+    let dir = temp_dir("check");
+    drawckt(&["check", SCHEMATIC, "--style", STYLE], &dir).success();
+    // Nothing is written
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+    let schematic = serde_json::json!({
+        "design": {"lib": "test", "cell": "top"},
+        "instances": [
+            {"name": "X1", "lib": "basic", "cell": "missing", "x": 0.0, "y": 0.0, "orient": "R0"}
+        ],
+        "wires": [],
+        "pins": [],
+        "labels": [],
+        "shapes": [],
+        "symbols": []
+    });
+    std::fs::write(dir.join("invalid.json"), schematic.to_string()).unwrap();
+    let assert = drawckt(&["check", "invalid.json"], &dir).code(EXIT_INVALID as i32);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("symbol basic/missing not found"));
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[test]
+fn stats() {
+    let dir = temp_dir("stats");
+    let assert = drawckt(&["stats", SCHEMATIC], &dir).success();
+    let stats: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert!(
+        stats["instances"]
+            .as_object()
+            .is_some_and(|map| !map.is_empty())
+    );
+
+    drawckt(&["stats", "missing.json"], &dir).code(EXIT_IO as i32);
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[test]
+fn usage_errors() {
+    let dir = temp_dir("usage");
+    for args in [
+        &[][..],
+        &["render"],
+        &["symbols"],
+        &["stats", SCHEMATIC, "--format", "xml"],
+        &["symbols", SCHEMATIC, "--snap-grid", "fine"],
+    ] {
+        drawckt(args, &dir).code(EXIT_USAGE as i32);
+    }
+    drawckt(&["--help"], &dir).success();
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[test]
+fn legacy_render_symbols() {
+    let dir = temp_dir("legacy");
+    cargo_bin_cmd!("render_symbols")
+        .args([SCHEMATIC, STYLE, "out"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    assert!(dir.join("out/basic/ipin.drawio").is_file());
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
+
+#[cfg(feature = "raster")]
+#[test]
+fn schematic_png() {
+    let dir = temp_dir("png");
+    drawckt(&["symbols", SCHEMATIC, "--style", STYLE], &dir).success();
+    let args = [
+        "schematic",
+        SCHEMATIC,
+        "--style",
+        STYLE,
+        "--png",
+        "out.png",
+        "--dpi",
+        "48",
+    ];
+    drawckt(&args, &dir).success();
+    let png = std::fs::read(dir.join("out.png")).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    // `--dpi` only goes with `--png`
+    drawckt(&["schematic", SCHEMATIC, "--dpi", "48"], &dir).code(EXIT_USAGE as i32);
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
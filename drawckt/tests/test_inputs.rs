@@ -0,0 +1,254 @@
+use drawckt::DrawcktError;
+use drawckt::schematic::{InputFormat, Schematic, Wire};
+use ordered_float::OrderedFloat;
+
+#[cfg(feature = "std-fs")]
+fn fixture(name: &str) -> String {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/formats")
+        .join(name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Schematics do not implement `PartialEq`, compare their serialization
+#[cfg(feature = "std-fs")]
+fn schematic_json(path: &str) -> serde_json::Value {
+    let schematic = Schematic::load(path).expect("Failed to load schematic");
+    serde_json::to_value(schematic).unwrap()
+}
+
+#[cfg(feature = "std-fs")]
+fn styles_json(path: &str) -> serde_json::Value {
+    let styles = drawckt::schematic::LayerStyles::load(path).expect("Failed to load styles");
+    serde_json::to_value(styles).unwrap()
+}
+
+#[test]
+fn test_input_format_from_path() {
+    assert_eq!(InputFormat::from_path("a.json"), Some(InputFormat::Json));
+    assert_eq!(InputFormat::from_path("a.YML"), Some(InputFormat::Yaml));
+    assert_eq!(InputFormat::from_path("a.yaml"), Some(InputFormat::Yaml));
+    assert_eq!(InputFormat::from_path("a.toml"), Some(InputFormat::Toml));
+    assert_eq!(InputFormat::from_path("a.txt"), None);
+    assert_eq!(InputFormat::from_path("a"), None);
+    assert!(matches!(
+        "xml".parse::<InputFormat>(),
+        Err(DrawcktError::InvalidOption { option, value }) if option == "--format" && value == "xml"
+    ));
+}
+
+#[cfg(feature = "std-fs")]
+#[test]
+fn test_toml_matches_json() {
+    let json = schematic_json(&fixture("schematic.json"));
+    assert_eq!(schematic_json(&fixture("schematic.toml")), json);
+    let shapes = &json["symbols"][0]["shapes"];
+    assert_eq!(shapes[1]["fillStyle"], 1);
+    assert_eq!(shapes[2]["holes"][0][2], serde_json::json!([0.1, 0.15]));
+
+    assert_eq!(
+        styles_json(&fixture("style.toml")),
+        styles_json(&fixture("style.json"))
+    );
+}
+
+#[test]
+fn test_reader_overrides_extension() {
+    let content = include_bytes!("formats/schematic.toml");
+    let schematic = Schematic::from_reader(InputFormat::Toml, content.as_slice()).unwrap();
+    assert_eq!(schematic.symbols[0].shapes.len(), 5);
+    assert!(Schematic::from_reader(InputFormat::Json, content.as_slice()).is_err());
+}
+
+#[cfg(all(feature = "std-fs", feature = "yaml"))]
+#[test]
+fn test_yaml_matches_json() {
+    assert_eq!(
+        schematic_json(&fixture("schematic.yaml")),
+        schematic_json(&fixture("schematic.json"))
+    );
+    assert_eq!(
+        styles_json(&fixture("style.yaml")),
+        styles_json(&fixture("style.json"))
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_error_location() {
+    let yaml = "design: { lib: demo, cell: inv }\ninstances: []\nwires:\n  - net: out\n    points: [[0, 0], [1, oops]]\n";
+    let err = Schematic::from_reader(InputFormat::Yaml, yaml.as_bytes()).unwrap_err();
+    assert!(matches!(err, DrawcktError::Yaml(_)), "{err}");
+    assert!(err.to_string().contains("line 5 column"), "{err}");
+}
+
+#[cfg(all(feature = "std-fs", not(feature = "yaml")))]
+#[test]
+fn test_yaml_not_enabled() {
+    let err = Schematic::load(fixture("schematic.yaml")).unwrap_err();
+    assert!(matches!(
+        err,
+        DrawcktError::FormatNotEnabled(InputFormat::Yaml)
+    ));
+}
+
+fn formats_schematic() -> Schematic {
+    let content = include_str!("formats/schematic.json");
+    Schematic::from_reader(InputFormat::Json, content.as_bytes()).expect("Failed to load schematic")
+}
+
+fn wire(net: &str, points: &[[f64; 2]]) -> Wire {
+    Wire {
+        net: net.to_string(),
+        points: points.iter().map(|p| p.map(OrderedFloat)).collect(),
+    }
+}
+
+#[test]
+fn fixture_counts() {
+    let stats = formats_schematic().stats();
+    assert_eq!(
+        stats.instances.into_iter().collect::<Vec<_>>(),
+        [("analogLib/nmos4".to_string(), 1)]
+    );
+    assert_eq!(
+        stats.wire_segments.into_iter().collect::<Vec<_>>(),
+        [("out".to_string(), 2)]
+    );
+    assert_eq!(
+        stats.pins.into_iter().collect::<Vec<_>>(),
+        [("output".to_string(), 1)]
+    );
+    // The schematic label and dot, then the symbol shapes
+    assert_eq!(
+        stats.shapes.into_iter().collect::<Vec<_>>(),
+        [
+            ("wire".to_string(), 2),
+            ("device".to_string(), 3),
+            ("instance".to_string(), 1),
+            ("annotate".to_string(), 1),
+        ]
+    );
+    assert_eq!(stats.extents, Some([[0.0, -1.5], [1.1, 0.5]]));
+}
+
+#[test]
+fn wires_grouped_as_rendered() {
+    let mut schematic = formats_schematic();
+    schematic.instances.push(schematic.instances[0].clone());
+    schematic.wires.push(wire("in", &[[-2.0, 0.0]]));
+    schematic.wires.push(wire("vdd", &[[0.0, 1.0], [0.0, 2.0]]));
+    schematic.wires.push(wire("out", &[[1.0, 0.5], [2.0, 0.5]]));
+    let stats = schematic.stats();
+
+    // A single point is not drawn and does not count
+    assert_eq!(
+        stats.wire_segments.iter().collect::<Vec<_>>(),
+        [(&"out".to_string(), &3), (&"vdd".to_string(), &1)]
+    );
+    assert_eq!(
+        schematic.wires_by_net().keys().copied().collect::<Vec<_>>(),
+        ["out", "vdd"]
+    );
+    assert_eq!(stats.instance_count(), 2);
+    // The symbol shapes count once per instance
+    assert_eq!(stats.shape_count(), 2 + 2 * 5);
+    assert_eq!(stats.extents, Some([[0.0, -1.5], [2.0, 2.0]]));
+    assert_eq!(
+        stats.to_string(),
+        "2 instances of 1 symbols, 4 wire segments on 2 nets, 1 pins, 12 shapes, extents (0, -1.5) to (2, 2)"
+    );
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json().unwrap()).unwrap();
+    assert_eq!(json["wireSegments"]["vdd"], 1);
+}
+
+#[test]
+fn empty_schematic() {
+    let mut schematic = formats_schematic();
+    schematic.instances.clear();
+    schematic.wires.clear();
+    schematic.pins.clear();
+    schematic.labels.clear();
+    schematic.shapes.clear();
+    let stats = schematic.stats();
+    assert_eq!(stats, Default::default());
+    assert_eq!(
+        stats.to_string(),
+        "0 instances of 0 symbols, 0 wire segments on 0 nets, 0 pins, 0 shapes"
+    );
+}
+
+/// Messages of the schema violations of `instance`
+#[cfg(feature = "schemars")]
+fn violations(schema: &schemars::Schema, instance: &str) -> Vec<String> {
+    let validator =
+        jsonschema::validator_for(schema.as_value()).expect("Failed to compile the schema");
+    let instance: serde_json::Value = serde_json::from_str(instance).expect("Invalid JSON");
+    validator
+        .iter_errors(&instance)
+        .map(|e| format!("{}: {e}", e.instance_path))
+        .collect()
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn schematic_fixtures_match_schema() {
+    let schema = Schematic::json_schema();
+    for (name, fixture) in [
+        ("case1", include_str!("case1/schematic.json")),
+        ("case2", include_str!("case2/schematic.json")),
+        ("case3", include_str!("case3/schematic.json")),
+        (
+            "V2I",
+            include_str!("../../drawckt-web/static/demo/V2I.json"),
+        ),
+        (
+            "LDO",
+            include_str!("../../drawckt-web/static/demo/LDO.json"),
+        ),
+        (
+            "current_sense_amp",
+            include_str!("../../drawckt-web/static/demo/current_sense_amp.json"),
+        ),
+    ] {
+        assert_eq!(violations(&schema, fixture), Vec::<String>::new(), "{name}");
+    }
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn style_fixtures_match_schema() {
+    let schema = drawckt::schematic::LayerStyles::json_schema();
+    for (name, fixture) in [
+        ("case1", include_str!("case1/style.json")),
+        (
+            "acadmic",
+            include_str!("../../drawckt-web/static/style/acadmic.json"),
+        ),
+        (
+            "acadmic_no_label",
+            include_str!("../../drawckt-web/static/style/acadmic_no_label.json"),
+        ),
+        (
+            "virtuoso",
+            include_str!("../../drawckt-web/static/style/virtuoso.json"),
+        ),
+    ] {
+        assert_eq!(violations(&schema, fixture), Vec::<String>::new(), "{name}");
+    }
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn schema_rejects_bad_shapes() {
+    let schema = Schematic::json_schema();
+    let schematic = include_str!("case1/schematic.json").replacen(
+        r#""type": "rect", "layer": "instance", "fillStyle": 1"#,
+        r#""type": "rect", "layer": "instance", "fillStyle": 7"#,
+        1,
+    );
+    assert!(!violations(&schema, &schematic).is_empty());
+    // The serde model agrees
+    assert!(serde_json::from_str::<Schematic>(&schematic).is_err());
+}
@@ -0,0 +1,279 @@
+mod common;
+
+use drawckt::renderer::{INSTANCE_HIT_LAYER, Renderer};
+use drawckt::report::CellSource;
+use drawckt::schematic::{Layer, LayerStyles, Schematic};
+use drawckt::{SchematicBuilder, SymbolBuilder};
+use drawrs::{DiagramObject, Orient, Page};
+use std::collections::{HashMap, HashSet};
+
+/// A resistor symbol with a pin on each end
+fn res_symbol() -> SymbolBuilder {
+    SymbolBuilder::new("lib", "res")
+        .rect(Layer::Device, [0.0, 0.0], [2.0, 1.0])
+        .pin("PLUS", "inputOutput", 0.0, 0.5)
+        .pin("MINUS", "inputOutput", 2.0, 0.5)
+}
+
+fn approx(a: [f64; 2], b: [f64; 2]) -> bool {
+    (a[0] - b[0]).abs() < 1e-9 && (a[1] - b[1]).abs() < 1e-9
+}
+
+#[test]
+fn terminals_of_rotated_instance() {
+    let schematic = SchematicBuilder::new("lib", "top")
+        .add_symbol(res_symbol())
+        .add_instance("R0", "lib", "res", 0.0, 0.0, Orient::R90)
+        .build()
+        .unwrap();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles).with_connection_points(true);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+
+    let Some(DiagramObject::Object(terminals)) =
+        page.objects().iter().find(|obj| obj.id() == "R0-terminals")
+    else {
+        panic!("expected the terminals box of R0");
+    };
+    // Rotated by 90°, PLUS ends at the bottom middle and MINUS at the top middle
+    let points = terminals.connection_points();
+    assert_eq!(points.len(), 2);
+    assert!(approx(points[0], [0.5, 1.0]), "{points:?}");
+    assert!(approx(points[1], [0.5, 0.0]), "{points:?}");
+    assert!(
+        terminals
+            .style()
+            .to_string()
+            .contains("points=[[0.5,1,0],[0.5,0,0]];")
+    );
+
+    // Off by default
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+    assert!(page.objects().iter().all(|obj| obj.id() != "R0-terminals"));
+}
+
+#[test]
+fn flatten_instances() {
+    let schematic = common::case1();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    let grouped = renderer
+        .render_schematic_page(&symbols)
+        .expect("Failed to render schematic");
+    assert!(grouped.xml().unwrap().to_string().contains("<UserObject"));
+
+    let renderer = Renderer::new(&schematic, &layer_styles).with_flatten_instances(true);
+    let flattened = renderer
+        .render_schematic_page(&symbols)
+        .expect("Failed to render schematic");
+    let content = renderer
+        .render_schematic_file(&symbols)
+        .expect("Failed to render schematic");
+    assert!(!content.contains("<UserObject"));
+
+    assert_eq!(flattened.objects().len(), grouped.objects().len());
+    assert!(flattened.objects().iter().all(|obj| obj.tag().is_none()));
+    let ids: HashSet<&str> = flattened.objects().iter().map(|obj| obj.id()).collect();
+    assert_eq!(ids.len(), flattened.objects().len());
+}
+
+fn render_grouped(schematic: &Schematic, group_instances: bool) -> Page {
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(schematic, &layer_styles)
+        .with_deterministic_ids(true)
+        .with_group_instances(group_instances);
+    common::render_page(&renderer)
+}
+
+/// Page coordinates of the boxes and points of each object, the ones inside
+/// a group cell are offset by its position
+fn absolute_positions(page: &Page) -> HashMap<String, Vec<[f64; 2]>> {
+    let groups: HashMap<&str, [f64; 2]> = page
+        .objects()
+        .iter()
+        .filter_map(|obj| match obj {
+            DiagramObject::XmlBase(base) => base
+                .group_geometry
+                .map(|bbox| (base.id.as_str(), [bbox.min_x(), bbox.min_y()])),
+            _ => None,
+        })
+        .collect();
+    page.objects()
+        .iter()
+        .filter(|obj| !matches!(obj, DiagramObject::XmlBase(_)))
+        .map(|obj| {
+            let [dx, dy] = obj
+                .xml_parent()
+                .and_then(|parent| groups.get(parent))
+                .copied()
+                .unwrap_or_default();
+            let mut obj = obj.clone();
+            let mut points: Vec<[f64; 2]> =
+                obj.mut_points().map(|[x, y]| [*x + dx, *y + dy]).collect();
+            if let Some((bbox, _)) = obj.mut_box() {
+                points.push([bbox.min_x() + dx, bbox.min_y() + dy]);
+                points.push([bbox.width(), bbox.height()]);
+            }
+            (obj.id().to_string(), points)
+        })
+        .collect()
+}
+
+#[test]
+fn grouped_positions_match() {
+    let schematic = common::case1();
+    let loose = render_grouped(&schematic, false);
+    let grouped = render_grouped(&schematic, true);
+
+    let loose_positions = absolute_positions(&loose);
+    let grouped_positions = absolute_positions(&grouped);
+    assert_eq!(loose_positions.len(), grouped_positions.len());
+    for (id, points) in &loose_positions {
+        let grouped_points = &grouped_positions[id];
+        assert_eq!(points.len(), grouped_points.len(), "{id}");
+        for (a, b) in points.iter().zip(grouped_points) {
+            assert!(approx(*a, *b), "{id}: {a:?} != {b:?}");
+        }
+    }
+}
+
+#[test]
+fn one_group_per_instance() {
+    let schematic = common::case1();
+    let loose = render_grouped(&schematic, false);
+    let grouped = render_grouped(&schematic, true);
+    for instance in &schematic.instances {
+        let Some(DiagramObject::XmlBase(group)) = grouped
+            .objects()
+            .iter()
+            .find(|obj| obj.id() == instance.name)
+        else {
+            panic!("no group cell for {}", instance.name);
+        };
+        assert!(group.group_geometry.is_some());
+        assert_eq!(group.xml_parent.as_deref(), Some("layer-instance-shape"));
+        // The objects carry their former layer cell as a tag
+        for obj in grouped
+            .objects()
+            .iter()
+            .filter(|obj| obj.xml_parent() == Some(group.id.as_str()))
+        {
+            let layer = loose
+                .objects()
+                .iter()
+                .find(|loose_obj| loose_obj.id() == obj.id())
+                .and_then(|loose_obj| loose_obj.xml_parent())
+                .expect("object missing from the loose page");
+            assert!(layer.starts_with("layer-"));
+            assert!(obj.tags().any(|tag| tag == layer), "{}", obj.id());
+        }
+    }
+    // Nothing is grouped by default
+    assert!(
+        loose.objects().iter().all(
+            |obj| !matches!(obj, DiagramObject::XmlBase(base) if base.group_geometry.is_some())
+        )
+    );
+}
+
+#[test]
+fn update_style_of_grouped_file() {
+    let schematic = common::case1();
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles).with_group_instances(true);
+    let content = common::render_file(&renderer);
+    let mut new_styles = LayerStyles::default();
+    new_styles.device.stroke_color = "#123456".into();
+    let updated =
+        Renderer::update_style(&content, &old_styles, &new_styles).expect("Failed to update style");
+    // The grouped device shapes are found by their layer tag
+    let page = common::parse_page(&updated);
+    let devices: Vec<_> = page
+        .objects()
+        .iter()
+        .filter(|obj| obj.tags().any(|tag| tag == "layer-device-shape"))
+        .collect();
+    assert!(!devices.is_empty());
+    for obj in devices {
+        assert_ne!(obj.xml_parent(), Some("layer-device-shape"));
+        let style = match obj {
+            DiagramObject::Object(o) => o.style().to_string(),
+            DiagramObject::Edge(e) => e.style().to_string(),
+            _ => continue,
+        };
+        assert!(style.contains("#123456"), "{}: {style}", obj.id());
+    }
+    // The group cells are kept
+    for instance in &schematic.instances {
+        assert!(updated.contains(&format!(
+            r#"<mxCell id="{}" connectable="0""#,
+            instance.name
+        )));
+    }
+}
+
+#[test]
+fn hit_box_of_rotated_instance() {
+    let schematic = SchematicBuilder::new("lib", "top")
+        .add_symbol(res_symbol())
+        .add_instance("R0", "lib", "res", 1.0, 2.0, Orient::R90)
+        .build()
+        .unwrap();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles)
+        .with_instance_hit_boxes(true)
+        .with_instance_link_template(Some("https://pdk.example.com/{lib}/{cell}".to_string()));
+    let symbols = renderer.render_symbols_file().unwrap();
+    let (page, report) = renderer
+        .render_schematic_page_with_report(&symbols)
+        .unwrap();
+
+    // The hit layer is the top layer
+    let layers: Vec<_> = page.layers().map(|layer| layer.id().to_string()).collect();
+    assert_eq!(layers.last().map(String::as_str), Some(INSTANCE_HIT_LAYER));
+
+    let Some(DiagramObject::Object(hit)) = page.objects().iter().find(|obj| obj.id() == "R0-hit")
+    else {
+        panic!("expected the hit box of R0");
+    };
+    assert_eq!(
+        report.get("R0-hit"),
+        Some(&CellSource::InstanceHit {
+            name: "R0".to_string(),
+            index: 0
+        })
+    );
+    // The device rectangle is drawn unrotated with rotation=90 around the
+    // same center, its turned box is the one of the symbol
+    let device = page
+        .objects()
+        .iter()
+        .find(|obj| obj.xml_parent().is_some_and(|p| p == "layer-device-shape"))
+        .and_then(|obj| obj.bounding_box())
+        .unwrap();
+    let bbox = hit.geometry_ref().bounding_box();
+    assert_eq!(bbox.center(), device.center());
+    assert_eq!(
+        [bbox.width(), bbox.height()],
+        [device.height(), device.width()]
+    );
+    insta::assert_debug_snapshot!(
+        "r90_hit_box",
+        (
+            hit.xml_parent(),
+            hit.geometry_ref().bounding_box(),
+            hit.opacity(),
+            hit.fill_color(),
+            hit.stroke_color(),
+            hit.tag(),
+            hit.tooltip(),
+            hit.link(),
+            &hit.base().attributes,
+        )
+    );
+}
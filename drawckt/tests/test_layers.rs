@@ -0,0 +1,156 @@
+mod common;
+
+use drawckt::renderer::Renderer;
+use drawckt::report::CellSource;
+use drawckt::schematic::{Layer, LayerStyle, LayerStyles, LegendCorner, LineCap, LineJoin};
+use drawckt::{SchematicBuilder, SymbolBuilder};
+use drawrs::{DiagramObject, Orient};
+use std::collections::BTreeSet;
+
+#[test]
+fn render_without_annotate_layer() {
+    let schematic = common::case1();
+    let layer_styles = LayerStyles {
+        layers_enabled: Some(
+            [
+                Layer::Device,
+                Layer::Wire,
+                Layer::Pin,
+                Layer::Instance,
+                Layer::Text,
+            ]
+            .into_iter()
+            .collect(),
+        ),
+        ..Default::default()
+    };
+
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let page = common::render_page(&renderer);
+
+    // Layer cells and the layers objects are placed on
+    let layers: BTreeSet<&str> = page
+        .objects()
+        .iter()
+        .flat_map(|obj| [Some(obj.id()), obj.xml_parent()])
+        .flatten()
+        .filter(|id| id.starts_with("layer-"))
+        .collect();
+    insta::assert_debug_snapshot!("case1_without_annotate", layers);
+}
+
+fn case1_styles(layer_styles: &LayerStyles, layer: &str) -> BTreeSet<String> {
+    let schematic = common::case1();
+    let renderer = Renderer::new(&schematic, layer_styles);
+    let page = common::render_page(&renderer);
+    page.objects()
+        .iter()
+        .filter(|obj| {
+            obj.xml_parent()
+                .is_some_and(|parent| parent.starts_with(&format!("layer-{layer}-")))
+        })
+        .filter_map(|obj| match page.resolved_object(obj).as_ref() {
+            DiagramObject::Object(o) => Some(o.style().to_string()),
+            DiagramObject::Edge(e) => Some(e.style().to_string()),
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn translucent_annotate_layer() {
+    let defaults = LayerStyles::default();
+    let layer_styles = LayerStyles {
+        annotate: LayerStyle {
+            opacity: Some(50),
+            ..defaults.annotate.clone()
+        },
+        wire: LayerStyle {
+            line_join: Some(LineJoin::Round),
+            line_cap: Some(LineCap::Round),
+            ..defaults.wire.clone()
+        },
+        ..defaults
+    };
+    insta::assert_debug_snapshot!("annotate_styles", case1_styles(&layer_styles, "annotate"));
+    insta::assert_debug_snapshot!("wire_styles", case1_styles(&layer_styles, "wire"));
+}
+
+#[test]
+fn legacy_style_has_no_opacity() {
+    let layer_styles: LayerStyles =
+        serde_json::from_str(include_str!("case1/style.json")).expect("Failed to parse style");
+    assert_eq!(layer_styles.annotate.opacity, None);
+    assert_eq!(layer_styles.wire.line_join, None);
+    assert_eq!(layer_styles.wire.line_cap, None);
+    let styles = case1_styles(&layer_styles, "annotate");
+    assert!(styles.iter().all(|style| !style.contains("opacity=")));
+}
+
+#[test]
+fn update_style_sets_opacity() {
+    let schematic = common::case1();
+    let old_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let content = common::render_file(&renderer);
+    assert!(!content.contains("opacity=50;"));
+
+    let mut new_styles = old_styles.clone();
+    new_styles.annotate.opacity = Some(50);
+    new_styles.wire.line_join = Some(LineJoin::Bevel);
+    new_styles.wire.line_cap = Some(LineCap::Square);
+    let updated =
+        Renderer::update_style(&content, &old_styles, &new_styles).expect("Failed to update style");
+    assert!(updated.contains("opacity=50;"));
+    assert!(updated.contains("jointStyle=bevel;"));
+    assert!(updated.contains("startCap=square;endCap=square;"));
+}
+
+fn render_legend(layer_styles: &LayerStyles, layer_legend: bool) -> Vec<DiagramObject> {
+    let symbol = SymbolBuilder::new("lib", "res")
+        .rect(Layer::Device, [0.0, 0.0], [2.0, 1.0])
+        .pin("PLUS", "inputOutput", 0.0, 0.5);
+    let schematic = SchematicBuilder::new("lib", "top")
+        .add_symbol(symbol)
+        .add_instance("R0", "lib", "res", 0.0, 0.0, Orient::R0)
+        .add_wire("a", &[[0.0, 0.5], [-1.0, 0.5]])
+        .build()
+        .unwrap();
+    let renderer = Renderer::new(&schematic, layer_styles).with_layer_legend(layer_legend);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    let (page, report) = renderer
+        .render_schematic_page_with_report(&symbols)
+        .unwrap();
+    page.objects()
+        .iter()
+        .filter(|obj| report.get(obj.id()) == Some(&CellSource::Legend))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn layer_legend() {
+    let mut layer_styles = LayerStyles::default();
+    assert!(render_legend(&layer_styles, false).is_empty());
+
+    layer_styles.device.stroke_color = "#00FFFF".into();
+    layer_styles.layers_enabled = Some(
+        [Layer::Device, Layer::Wire, Layer::Annotate]
+            .into_iter()
+            .collect(),
+    );
+    let legend = render_legend(&layer_styles, true);
+    // Border, title and a swatch with a label per enabled layer
+    assert_eq!(legend.len(), 2 + 2 * 3);
+    let xml: Vec<String> = legend.iter().map(|obj| obj.xml().to_string()).collect();
+    insta::assert_snapshot!("layer_legend", xml.join("\n"));
+
+    // Above the content on the top left
+    layer_styles.layer_legend_corner = LegendCorner::TopLeft;
+    let top_left = render_legend(&layer_styles, true);
+    let [bottom, top] = [&legend[0], &top_left[0]].map(|obj| obj.bounding_box().unwrap());
+    assert!(top.max_y() < bottom.min_y());
+    assert!(top.min_x() < bottom.min_x());
+}
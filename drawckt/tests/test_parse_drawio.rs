@@ -32,3 +32,45 @@ fn parse_drawio_file_schematic() {
     // 使用 insta 快照测试每个 SymbolPageData 的 debug format
     insta::assert_debug_snapshot!("schematic", pages);
 }
+
+#[test]
+fn parse_drawio_file_waypoints() {
+    let content = include_str!("test_parse_drawio/waypoints.drawio");
+
+    let pages = Renderer::parse_drawio_file(content).expect("Failed to parse symbols file");
+    let objects = pages["basic/route"].objects();
+    let edge = objects
+        .iter()
+        .find_map(|obj| match obj {
+            drawrs::DiagramObject::Edge(edge) => Some(edge),
+            _ => None,
+        })
+        .expect("Missing edge");
+    let geometry = edge.geometry_ref();
+    assert_eq!(geometry.source_point(), Some([0.0, 0.0]));
+    assert_eq!(geometry.target_point(), Some([40.0, 40.0]));
+    assert_eq!(geometry.intermediate_points(), [[20.0, 0.0], [20.0, 40.0]]);
+}
+
+#[test]
+fn parse_drawio_file_object_element() {
+    let content = include_str!("test_parse_drawio/object_element.drawio");
+
+    let pages = Renderer::parse_drawio_file(content).expect("Failed to parse symbols file");
+    let objects = pages["basic/res"].objects();
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0].id(), "basic/res-device-0");
+    assert_eq!(objects[0].tag().map(String::as_str), Some("device"));
+    assert_eq!(objects[0].text().map(String::as_str), Some("R&D"));
+    assert_eq!(
+        objects[0]
+            .base()
+            .attributes
+            .get("device")
+            .map(String::as_str),
+        Some("res")
+    );
+    assert_eq!(objects[1].tag().map(String::as_str), Some("pin"));
+    // Re-serialized with the UserObject wrapper
+    assert!(objects[0].xml().to_string().starts_with("<UserObject"));
+}
@@ -0,0 +1,768 @@
+mod common;
+
+use drawckt::SchematicBuilder;
+use drawckt::renderer::Renderer;
+use drawckt::schematic::{
+    DesignId, FillStyleKind, IdScheme, LayerStyle, LayerStyles, Schematic, Shape,
+};
+use drawckt::{DrawcktError, RenderOptions};
+use drawrs::utils::stencil::decompress;
+use drawrs::{BoundingBox, DiagramObject, Page};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// One rect per fill style, 1 to 5
+fn fills_schematic() -> Schematic {
+    let shapes: Vec<_> = (1..=5)
+        .map(|fill_style| {
+            let x = fill_style as f64;
+            serde_json::json!({
+                "type": "rect", "layer": "device", "fillStyle": fill_style,
+                "bBox": [[x, 0.0], [x + 0.5, 0.5]]
+            })
+        })
+        .collect();
+    common::schematic(serde_json::json!({
+        "symbols": [{"lib": "basic", "cell": "fills", "shapes": shapes, "pins": []}]
+    }))
+}
+
+// Symbol file of the fills schematic
+fn fills_symbol(layer_styles: &LayerStyles) -> String {
+    let schematic = fills_schematic();
+    let renderer = Renderer::new(&schematic, layer_styles);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    symbols
+        .0
+        .into_values()
+        .next()
+        .expect("No symbol")
+        .into_owned()
+}
+
+fn rect_styles(content: &str) -> Vec<String> {
+    common::parse_page(content)
+        .objects()
+        .iter()
+        .filter_map(|obj| match obj {
+            DiagramObject::Object(o) => Some(o.style().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn light_fill() -> LayerStyles {
+    LayerStyles {
+        device: LayerStyle {
+            stroke_color: "#333333".into(),
+            fill_color: Some("#CCCCCC".into()),
+            ..LayerStyle::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fill_styles_with_fill_color() {
+    insta::assert_debug_snapshot!("fill_color", rect_styles(&fills_symbol(&light_fill())));
+}
+
+#[test]
+fn fill_color_defaults_to_stroke_color() {
+    let layer_styles = LayerStyles {
+        device: LayerStyle {
+            stroke_color: "#333333".into(),
+            ..LayerStyle::default()
+        },
+        ..Default::default()
+    };
+    let styles = rect_styles(&fills_symbol(&layer_styles));
+    assert_eq!(styles.len(), 5);
+    assert!(styles[0].contains("fillColor=none;strokeColor=#333333;"));
+    for style in &styles[1..] {
+        assert!(style.contains("fillColor=#333333;"));
+    }
+}
+
+#[test]
+fn update_style_propagates_fill_color() {
+    let old_styles = LayerStyles::default();
+    let updated = Renderer::update_style(&fills_symbol(&old_styles), &old_styles, &light_fill())
+        .expect("Failed to update style");
+    assert_eq!(
+        rect_styles(&updated),
+        rect_styles(&fills_symbol(&light_fill()))
+    );
+}
+
+/// A single label `text` on the text layer
+fn text_label(text: &str, justify: &str) -> Schematic {
+    common::schematic(serde_json::json!({
+        "shapes": [
+            {"type": "label", "layer": "text", "text": text, "xy": [1.0, 1.0], "orient": "R0",
+             "height": 0.0625, "justify": justify, "font": "stick"}
+        ]
+    }))
+}
+
+fn render_text_file(
+    schematic: &Schematic,
+    labels_autosize: bool,
+    layer_styles: &LayerStyles,
+) -> String {
+    let renderer = Renderer::new(schematic, layer_styles)
+        .with_deterministic_ids(true)
+        .with_labels_autosize(labels_autosize);
+    common::render_file(&renderer)
+}
+
+// The rendered label of a `text_label` file
+fn label(content: &str) -> drawrs::Object {
+    common::object(&common::parse_page(content), "shape-0")
+}
+
+fn boxed_styles() -> LayerStyles {
+    let mut layer_styles = LayerStyles::default();
+    layer_styles.text.label_background = Some("#FFFFCC".into());
+    layer_styles.text.label_border = Some("#999999".into());
+    layer_styles
+}
+
+#[test]
+fn text_note_with_background() {
+    let schematic = text_label("NOTE", "centerLeft");
+    let bare = label(&render_text_file(
+        &schematic,
+        false,
+        &LayerStyles::default(),
+    ));
+    let boxed = label(&render_text_file(&schematic, false, &boxed_styles()));
+    insta::assert_snapshot!("text_note_with_background", boxed.xml().to_string());
+    assert_eq!(boxed.fill_color().map(String::as_str), Some("#FFFFCC"));
+    assert_eq!(boxed.stroke_color().map(String::as_str), Some("#999999"));
+    assert_eq!(boxed.rounded(), Some(true));
+    // The box is padded around the text box
+    let (bare_box, boxed_box) = (
+        bare.geometry_ref().bounding_box(),
+        boxed.geometry_ref().bounding_box(),
+    );
+    assert!(boxed_box.min_x() < bare_box.min_x() && boxed_box.max_x() > bare_box.max_x());
+    assert!(boxed_box.min_y() < bare_box.min_y() && boxed_box.max_y() > bare_box.max_y());
+}
+
+#[test]
+fn update_style_adds_and_removes_background() {
+    let schematic = text_label("NOTE", "centerLeft");
+    let bare_styles = LayerStyles::default();
+    let boxed_styles = boxed_styles();
+    for (old_styles, new_styles) in [(&bare_styles, &boxed_styles), (&boxed_styles, &bare_styles)] {
+        let content = render_text_file(&schematic, false, old_styles);
+        let updated = Renderer::update_style(&content, old_styles, new_styles)
+            .expect("Failed to update style");
+        // The same note as rendered with the new styles
+        assert_eq!(
+            label(&updated).xml().to_string(),
+            label(&render_text_file(&schematic, false, new_styles))
+                .xml()
+                .to_string()
+        );
+    }
+}
+
+#[test]
+fn autosize_label() {
+    let schematic = text_label("VOUT", "centerLeft");
+    let layer_styles = LayerStyles::default();
+    let fixed = label(&render_text_file(&schematic, false, &layer_styles));
+    let autosize = label(&render_text_file(&schematic, true, &layer_styles));
+    insta::assert_snapshot!("fixed_label", fixed.xml().to_string());
+    insta::assert_snapshot!("autosize_label", autosize.xml().to_string());
+    assert!(!fixed.autosize());
+    assert!(autosize.autosize());
+    // The estimated box is kept as the initial one
+    assert_eq!(
+        autosize.geometry_ref().bounding_box(),
+        fixed.geometry_ref().bounding_box()
+    );
+}
+
+#[test]
+fn update_style_keeps_autosize_box() {
+    let schematic = text_label("VOUT", "centerLeft");
+    let old_styles = LayerStyles::default();
+    let mut new_styles = old_styles.clone();
+    new_styles.text.font_zoom *= 2.0;
+    for labels_autosize in [false, true] {
+        let content = render_text_file(&schematic, labels_autosize, &old_styles);
+        let updated = Renderer::update_style(&content, &old_styles, &new_styles)
+            .expect("Failed to update style");
+        let (before, after) = (label(&content), label(&updated));
+        assert_eq!(after.font_size(), before.font_size().map(|size| 2.0 * size));
+        // Only the label without autosize is resized from its text length
+        assert_eq!(
+            after.width() == before.width(),
+            labels_autosize,
+            "{labels_autosize}"
+        );
+        assert_eq!(after.autosize(), labels_autosize);
+    }
+}
+
+fn render_label(text: &str, justify: &str, layer_styles: &LayerStyles) -> drawrs::Object {
+    let schematic = text_label(text, justify);
+    let renderer = Renderer::new(&schematic, layer_styles);
+    common::object(&common::render_page(&renderer), "shape-0")
+}
+
+#[test]
+fn three_line_note() {
+    let note = render_label(
+        "NOTES:\n1. All resistors are 1% <thin film>\n2. Keep C1 close to U1",
+        "upperLeft",
+        &LayerStyles::default(),
+    );
+    insta::assert_snapshot!("three_line_note", note.xml().to_string());
+    // One line height per line, as wide as the longest line
+    assert_eq!(note.geometry_ref().height(), 3.0 * 15.0);
+    assert_eq!(note.geometry_ref().width(), 35.0 * 15.0 / 2.0);
+}
+
+#[test]
+fn note_block_justify() {
+    let text = "first\nsecond";
+    let top = render_label(text, "upperCenter", &LayerStyles::default());
+    let bottom = render_label(text, "lowerCenter", &LayerStyles::default());
+    let middle = render_label(text, "centerCenter", &LayerStyles::default());
+    // The whole block sits above, around or below the anchor
+    assert_eq!(top.geometry_ref().y(), -200.0);
+    assert_eq!(bottom.geometry_ref().y(), -200.0 - 30.0);
+    assert_eq!(middle.geometry_ref().y(), -200.0 - 15.0);
+}
+
+#[test]
+fn wrap_width_breaks_words() {
+    let mut layer_styles = LayerStyles::default();
+    // 12 characters of 7.5 pixels
+    layer_styles.text.wrap_width = Some(90.0);
+    let note = render_label("keep the decoupling caps close", "upperLeft", &layer_styles);
+    assert_eq!(
+        note.value().map(String::as_str),
+        Some("keep the<br>decoupling<br>caps close")
+    );
+    assert_eq!(note.geometry_ref().height(), 3.0 * 15.0);
+
+    // Single lines are left alone
+    let label = render_label("short", "upperLeft", &layer_styles);
+    assert_eq!(label.value().map(String::as_str), Some("short"));
+    assert_eq!(label.white_space(), None);
+}
+
+fn render_polygon(polygon: serde_json::Value) -> Vec<DiagramObject> {
+    let schematic = common::schematic(serde_json::json!({
+        "shapes": [polygon]
+    }));
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let page = common::render_page(&renderer);
+    page.objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with("shape-"))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn square_with_square_hole() {
+    // The hole is given with the same winding as the outline
+    let objects = render_polygon(serde_json::json!({
+        "type": "polygon", "layer": "device", "fillStyle": 2,
+        "points": [[0, 0], [1, 0], [1, 1], [0, 1]],
+        "holes": [[[0.25, 0.25], [0.75, 0.25], [0.75, 0.75], [0.25, 0.75]]]
+    }));
+    let [DiagramObject::Object(obj)] = objects.as_slice() else {
+        panic!("expected one object, got {objects:?}");
+    };
+    let shape = obj.style_property("shape").expect("No shape");
+    let stencil = shape
+        .strip_prefix("stencil(")
+        .and_then(|s| s.strip_suffix(')'))
+        .and_then(decompress)
+        .expect("Not a stencil");
+    insta::assert_snapshot!("square_with_square_hole", stencil);
+    let geometry = obj.geometry_ref();
+    assert_eq!(
+        [
+            geometry.x(),
+            geometry.y(),
+            geometry.width(),
+            geometry.height()
+        ],
+        [0.0, -200.0, 200.0, 200.0]
+    );
+    assert!(obj.poly_coords().is_empty());
+}
+
+#[test]
+fn rounded_polygon_without_holes() {
+    let objects = render_polygon(serde_json::json!({
+        "type": "polygon", "layer": "device", "rounded": true,
+        "points": [[0, 0], [1, 0], [0, 1]],
+        "holes": [[[0.1, 0.1], [0.2, 0.1]]]
+    }));
+    let [DiagramObject::Object(obj)] = objects.as_slice() else {
+        panic!("expected one object, got {objects:?}");
+    };
+    // The degenerate hole is skipped
+    assert_eq!(obj.style_property("shape"), Some("mxgraph.basic.polygon"));
+    assert_eq!(obj.poly_coords().len(), 3);
+    assert_eq!(obj.rounded(), Some(true));
+}
+
+#[test]
+fn degenerate_polygon_is_skipped() {
+    let objects = render_polygon(serde_json::json!({
+        "type": "polygon", "layer": "device",
+        "points": [[0, 0], [1, 0]]
+    }));
+    assert!(objects.is_empty());
+}
+
+// Page boxes of the pin names, by pin index
+fn pin_label_boxes(layer_styles: &LayerStyles) -> Vec<BoundingBox> {
+    let schematic = SchematicBuilder::new("lib", "pins")
+        // Wire leaving downward
+        .add_pin("down", "input", 0.0, 0.0)
+        .add_wire("down", &[[0.0, 0.0], [0.0, -1.0]])
+        .add_pin("up", "input", 2.0, 0.0)
+        .add_wire("up", &[[2.0, 1.0], [2.0, 0.0]])
+        .add_pin("left", "output", 4.0, 0.0)
+        .add_wire("left", &[[4.0, 0.0], [3.0, 0.0]])
+        .add_pin("right", "input", 6.0, 0.0)
+        .add_wire("right", &[[6.0, 0.0], [7.0, 0.0]])
+        .add_pin("floating", "input", 8.0, 0.0)
+        .build()
+        .unwrap();
+    let page = common::render_page(&Renderer::new(&schematic, layer_styles));
+    (0..schematic.pins.len())
+        .map(|i| {
+            page.objects()
+                .iter()
+                .find(|obj| obj.id() == format!("pin-{i}"))
+                .and_then(|obj| obj.bounding_box())
+                .unwrap()
+        })
+        .collect()
+}
+
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn pin_label_opposite_to_wire() {
+    let layer_styles = LayerStyles::default();
+    let scale = 200.0;
+    let offset = layer_styles.pin_label_offset * scale;
+    let boxes = pin_label_boxes(&layer_styles);
+    let [down, up, left, right, floating] = boxes.as_slice() else {
+        panic!("expected five pin labels");
+    };
+
+    // Above the pin, centered on it
+    assert!(approx(down.max_y(), -offset));
+    assert!(approx(down.center()[0], 0.0));
+    // Below the pin
+    assert!(approx(up.min_y(), offset));
+    assert!(approx(up.center()[0], 2.0 * scale));
+    // Right of the pin
+    assert!(approx(left.min_x(), 4.0 * scale + offset));
+    assert!(approx(left.center()[1], 0.0));
+    // Left of the pin, also without a wire
+    assert!(approx(right.max_x(), 6.0 * scale - offset));
+    assert!(approx(right.center()[1], 0.0));
+    assert!(approx(floating.max_x(), 8.0 * scale - offset));
+}
+
+#[test]
+fn pin_label_sizes_from_style() {
+    let layer_styles = LayerStyles {
+        pin_label_offset: 0.0625,
+        pin_label_height: 0.05,
+        ..Default::default()
+    };
+    let default_boxes = pin_label_boxes(&LayerStyles::default());
+    let boxes = pin_label_boxes(&layer_styles);
+    assert!(approx(boxes[4].max_x(), 8.0 * 200.0 - 0.0625 * 200.0));
+    assert!(approx(boxes[4].height(), default_boxes[4].height() / 2.0));
+}
+
+#[test]
+fn pin_symbols_at_pins() {
+    let schematic = SchematicBuilder::new("lib", "pins")
+        .add_pin("IN", "input", 0.0, 0.0)
+        .add_wire("IN", &[[0.0, 0.0], [1.0, 0.0]])
+        // Wire leaving upward, the glyph is turned by R90
+        .add_pin("EN", "input", 2.0, 0.0)
+        .add_wire("EN", &[[2.0, 0.0], [2.0, 1.0]])
+        // No symbol for outputs, only the name is drawn
+        .add_pin("OUT", "output", 4.0, 0.0)
+        .add_wire("OUT", &[[4.0, 0.0], [3.0, 0.0]])
+        .build()
+        .unwrap();
+    let iopin = DesignId {
+        lib: "basic".into(),
+        cell: "iopin".into(),
+    };
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles)
+        .with_pin_symbols([("input".to_string(), iopin.clone())].into_iter().collect());
+    let mut symbols = renderer.render_symbols_file().unwrap();
+    symbols
+        .0
+        .insert(iopin, include_str!("test_parse_drawio/iopin.drawio").into());
+    let page = renderer.render_schematic_page(&symbols).unwrap();
+
+    let pin_objects: Vec<String> = page
+        .objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with("pin-"))
+        .map(|obj| obj.xml().to_string())
+        .collect();
+    let glyphs = |prefix: &str| {
+        page.objects()
+            .iter()
+            .filter(|obj| obj.id().starts_with(prefix))
+            .count()
+    };
+    // The three shapes of the symbol for each input, plus the names
+    assert_eq!(glyphs("pin-0-"), 3);
+    assert_eq!(glyphs("pin-1-"), 3);
+    assert_eq!(glyphs("pin-2-"), 0);
+    assert_eq!(pin_objects.len(), 9);
+    insta::assert_snapshot!("pin_symbols_at_pins", pin_objects.join("\n"));
+}
+
+/// A resistor between two nets, with a stub hanging off net `a` and a
+/// dangling wire on net `b`
+fn open_ends_schematic() -> Schematic {
+    common::schematic(serde_json::json!({
+        "instances": [
+            {"name": "R0", "lib": "basic", "cell": "res", "x": 0.0, "y": 0.0, "orient": "R0"}
+        ],
+        "wires": [
+            {"net": "a", "points": [[0.0, 0.25], [0.0, 0.5], [0.5, 0.5]]},
+            {"net": "a", "points": [[0.25, 0.5], [0.25, 0.75]]},
+            {"net": "b", "points": [[0.0, -0.25], [0.0, -0.75]]},
+            {"net": "b", "points": [[0.5, -0.75], [1.0, -0.75]]}
+        ],
+        "pins": [
+            {"name": "a", "direction": "inputOutput", "x": 0.5, "y": 0.5}
+        ],
+        "symbols": [{
+            "lib": "basic",
+            "cell": "res",
+            "shapes": [
+                {"type": "rect", "layer": "device", "fillStyle": 1, "bBox": [[-0.1, -0.2], [0.1, 0.2]]}
+            ],
+            "pins": [
+                {"name": "PLUS", "direction": "inputOutput", "x": 0.0, "y": 0.25},
+                {"name": "MINUS", "direction": "inputOutput", "x": 0.0, "y": -0.25}
+            ]
+        }]
+    }))
+}
+
+fn open_end_marks(mark_open_ends: bool) -> Vec<String> {
+    let schematic = open_ends_schematic();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles).with_mark_open_ends(mark_open_ends);
+    let page = common::render_page(&renderer);
+    page.objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with("openend-"))
+        .map(|obj| match obj {
+            DiagramObject::Object(o) => format!(
+                "{} parent={:?} bbox={:?} fill={:?}",
+                o.id(),
+                obj.xml_parent(),
+                obj.bounding_box(),
+                o.fill_color()
+            ),
+            _ => panic!("Open end mark {} is not a vertex", obj.id()),
+        })
+        .collect()
+}
+
+#[test]
+fn mark_open_ends() {
+    insta::assert_debug_snapshot!("open_ends", open_end_marks(true));
+}
+
+#[test]
+fn open_ends_disabled_by_default() {
+    assert!(open_end_marks(false).is_empty());
+}
+
+/// A tap whose pin sits on a straight run of net `a` drawn as two collinear
+/// wires, and a top-level pin in the middle of a single wire of net `b`
+fn tap_schematic() -> Schematic {
+    common::schematic(serde_json::json!({
+        "instances": [
+            {"name": "T0", "lib": "basic", "cell": "tap", "x": 0.0, "y": 0.0, "orient": "R0"}
+        ],
+        "wires": [
+            {"net": "a", "points": [[-1.0, 0.25], [0.0, 0.25]]},
+            {"net": "a", "points": [[0.0, 0.25], [1.0, 0.25]]},
+            {"net": "b", "points": [[-1.0, -1.0], [0.0, -1.0], [1.0, -1.0]]}
+        ],
+        "pins": [
+            {"name": "b", "direction": "inputOutput", "x": 0.0, "y": -1.0}
+        ],
+        "symbols": [{
+            "lib": "basic",
+            "cell": "tap",
+            "shapes": [
+                {"type": "rect", "layer": "device", "fillStyle": 1, "bBox": [[-0.1, -0.2], [0.1, 0.2]]}
+            ],
+            "pins": [
+                {"name": "A", "direction": "inputOutput", "x": 0.0, "y": 0.25}
+            ]
+        }]
+    }))
+}
+
+/// Number of the wire edges of each net
+fn wire_edges(split_wires_at_pins: bool) -> [usize; 2] {
+    let schematic = tap_schematic();
+    let layer_styles = LayerStyles::default();
+    let renderer =
+        Renderer::new(&schematic, &layer_styles).with_split_wires_at_pins(split_wires_at_pins);
+    let page = common::render_page(&renderer);
+    ["wire-a-", "wire-b-"].map(|prefix| {
+        page.objects()
+            .iter()
+            .filter(|obj| matches!(obj, DiagramObject::Edge(_)) && obj.id().starts_with(prefix))
+            .count()
+    })
+}
+
+#[test]
+fn wires_end_at_pins() {
+    assert_eq!(wire_edges(false), [1, 1]);
+    assert_eq!(wire_edges(true), [2, 2]);
+}
+
+fn intersection_styles(page: &Page) -> BTreeSet<String> {
+    page.objects()
+        .iter()
+        .filter(|obj| obj.xml_parent() == Some("layer-wire-intersection"))
+        .filter_map(|obj| match page.resolved_object(obj).as_ref() {
+            DiagramObject::Object(o) => Some(o.style().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn render_case1(layer_styles: &LayerStyles) -> Page {
+    let schematic = common::case1();
+    let renderer = Renderer::new(&schematic, layer_styles);
+    common::render_page(&renderer)
+}
+
+fn dark_dots() -> LayerStyles {
+    let defaults = LayerStyles::default();
+    LayerStyles {
+        wire: LayerStyle {
+            stroke_color: "#3366FF".into(),
+            stroke_width: 0.5,
+            ..defaults.wire.clone()
+        },
+        wire_intersection: Some(LayerStyle {
+            stroke_color: "#001133".into(),
+            ..defaults.wire.clone()
+        }),
+        wire_intersection_filled: true,
+        ..defaults
+    }
+}
+
+#[test]
+fn intersection_style_falls_back_to_wire() {
+    let layer_styles = LayerStyles {
+        wire: LayerStyle {
+            stroke_color: "#3366FF".into(),
+            ..LayerStyle::default()
+        },
+        ..Default::default()
+    };
+    let styles = intersection_styles(&render_case1(&layer_styles));
+    assert!(!styles.is_empty());
+    assert!(styles.iter().all(|style| style.contains("#3366FF")));
+}
+
+#[test]
+fn filled_intersection_style() {
+    insta::assert_debug_snapshot!(
+        "filled_intersection_styles",
+        intersection_styles(&render_case1(&dark_dots()))
+    );
+}
+
+#[test]
+fn outlined_dots_are_forced_filled() {
+    let mut schematic = common::case1();
+    for shape in &mut schematic.shapes {
+        if let Shape::Ellipse { fill_style, .. } = shape {
+            *fill_style = FillStyleKind::Outline;
+        }
+    }
+    let layer_styles = dark_dots();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let page = common::render_page(&renderer);
+    assert_eq!(
+        intersection_styles(&page),
+        intersection_styles(&render_case1(&layer_styles))
+    );
+}
+
+#[test]
+fn update_style_fills_intersections() {
+    let old_styles = LayerStyles::default();
+    let schematic = common::case1();
+    let renderer = Renderer::new(&schematic, &old_styles);
+    let content = common::render_file(&renderer);
+    let updated = Renderer::update_style(&content, &old_styles, &dark_dots())
+        .expect("Failed to update style");
+    assert_eq!(
+        intersection_styles(&common::parse_page(&updated)),
+        intersection_styles(&render_case1(&dark_dots()))
+    );
+}
+
+#[test]
+fn toggle_intersection_visibility() {
+    let layer_styles = LayerStyles::default();
+    let schematic = common::case1();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let content = common::render_file(&renderer);
+
+    let hidden = Renderer::set_wire_intersection_visible(&content, false)
+        .expect("Failed to hide the intersections");
+    // Start tag of the layer cell
+    let layer = |content: &str| {
+        let start = content
+            .find(r#"<mxCell id="layer-wire-intersection""#)
+            .expect("No intersection layer");
+        content[start..start + content[start..].find('>').unwrap()].to_string()
+    };
+    assert!(layer(&content).contains(r#"visible="1""#));
+    assert!(layer(&hidden).contains(r#"visible="0""#));
+    // Only the visible attribute of the layer cell differs
+    let changed: Vec<(&str, &str)> = content
+        .lines()
+        .zip(hidden.lines())
+        .filter(|(a, b)| a != b)
+        .collect();
+    assert_eq!(content.lines().count(), hidden.lines().count());
+    assert_eq!(changed.len(), 1);
+    assert_eq!(
+        changed[0].0.replace(r#"visible="1""#, r#"visible="0""#),
+        changed[0].1
+    );
+
+    let shown = Renderer::set_wire_intersection_visible(&hidden, true)
+        .expect("Failed to show the intersections");
+    assert_eq!(shown, content);
+}
+
+#[test]
+fn toggle_intersection_without_layer() {
+    let content = include_str!("test_parse_drawio/iopin.drawio");
+    assert!(matches!(
+        Renderer::set_wire_intersection_visible(content, false),
+        Err(DrawcktError::LayerCellNotFound(id)) if id == "layer-wire-intersection"
+    ));
+}
+
+// Multiply the coordinates and text heights of a schematic JSON by `factor`
+fn rescale(value: &mut Value, factor: f64) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match key.as_str() {
+                    "x" | "y" | "xy" | "points" | "holes" | "bBox" | "height" => {
+                        scale_numbers(value, factor)
+                    }
+                    _ => rescale(value, factor),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rescale(item, factor)),
+        _ => {}
+    }
+}
+
+fn scale_numbers(value: &mut Value, factor: f64) {
+    match value {
+        Value::Number(n) => *value = Value::from(n.as_f64().unwrap() * factor),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| scale_numbers(item, factor)),
+        _ => {}
+    }
+}
+
+// Sorted XML of the page objects. The nets are emitted in hash order, so the
+// wire counters are dropped from the ids
+fn sorted_objects(value: Value) -> Vec<String> {
+    let schematic: Schematic = serde_json::from_value(value).expect("Failed to parse schematic");
+    let layer_styles = LayerStyles::default();
+    // Content ids hash the coordinates, index ids only depend on the order
+    let renderer = Renderer::new(&schematic, &layer_styles).with_id_scheme(IdScheme::Index);
+    let page = common::render_page(&renderer);
+    // Rounding errors of the unit conversion are far below the precision
+    let options = RenderOptions {
+        float_precision: Some(6),
+        ..Default::default()
+    };
+    let mut objects: Vec<String> = options.scoped(|| {
+        page.objects()
+            .iter()
+            .map(|obj| {
+                let xml = obj.xml().to_string();
+                match obj.id().strip_prefix("wire-") {
+                    Some(id) => xml.replace(obj.id(), id.rsplit_once('-').unwrap().0),
+                    None => xml,
+                }
+            })
+            .collect()
+    });
+    objects.sort();
+    objects
+}
+
+fn case1_json() -> Value {
+    serde_json::from_str(include_str!("case1/schematic.json")).unwrap()
+}
+
+#[test]
+fn mils_render_like_user_units() {
+    let mut mils = case1_json();
+    rescale(&mut mils, 1000.0);
+    mils["units"] = "mil".into();
+    assert_eq!(sorted_objects(mils), sorted_objects(case1_json()));
+}
+
+#[test]
+fn units_per_drawio_pixel_overrides_scale() {
+    // 25400 microns per user unit and 200 pixels per user unit
+    let mut microns = case1_json();
+    rescale(&mut microns, 25400.0);
+    microns["units"] = "micron".into();
+    microns["units_per_drawio_pixel"] = 127.0.into();
+    let schematic: Schematic = serde_json::from_value(microns.clone()).unwrap();
+    assert_eq!(schematic.scale(), 1.0 / 127.0);
+    assert_eq!(sorted_objects(microns), sorted_objects(case1_json()));
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use drawckt::renderer::Renderer;
+use drawckt::schematic::{DesignId, IdScheme, Layer, LayerStyles, Schematic, Wire};
+use drawckt::{DrawcktError, RenderOptions};
+use drawrs::DrawrsError;
+use ordered_float::OrderedFloat;
+
+#[test]
+fn partial_json_keeps_defaults() {
+    let options = RenderOptions::from_json(r#"{"snap_grid": 10.0, "unknown": true}"#).unwrap();
+    assert_eq!(
+        options,
+        RenderOptions {
+            snap_grid: Some(10.0),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn partial_toml_keeps_defaults() {
+    let options = RenderOptions::from_toml(
+        r#"
+id_scheme = "index"
+fallback_layer = "text"
+
+[pin_symbols]
+input = { lib = "basic", cell = "ipin" }
+"#,
+    )
+    .unwrap();
+    assert_eq!(options.id_scheme, IdScheme::Index);
+    assert_eq!(options.fallback_layer, Layer::Text);
+    assert!(!options.flatten_instances);
+    assert_eq!(options.snap_grid, None);
+    assert_eq!(options.float_precision, None);
+    assert_eq!(
+        options.pin_symbols.get("input"),
+        Some(&DesignId::new("basic", "ipin"))
+    );
+}
+
+#[test]
+fn flags_override_file() {
+    let mut options = RenderOptions::from_toml("snap_grid = 10.0\nid_scheme = \"index\"").unwrap();
+    options.apply_flag("--snap-grid", Some("5")).unwrap();
+    options.apply_flag("--flatten-instances", None).unwrap();
+    options.apply_flag("--mark-open-ends", None).unwrap();
+    options.apply_flag("--avoid-label-overlap", None).unwrap();
+    options.apply_flag("--layer-legend", None).unwrap();
+    options
+        .apply_flag("--label-max-displacement", Some("0.25"))
+        .unwrap();
+    options
+        .apply_flag("--pin-symbols", Some("input=basic/ipin,output=basic/opin"))
+        .unwrap();
+    assert_eq!(options.snap_grid, Some(5.0));
+    assert_eq!(options.id_scheme, IdScheme::Index);
+    assert!(options.flatten_instances);
+    assert!(options.mark_open_ends);
+    assert!(options.avoid_label_overlap);
+    assert!(options.layer_legend);
+    assert_eq!(options.label_max_displacement, 0.25);
+    assert_eq!(
+        options.pin_symbols.get("output"),
+        Some(&DesignId::new("basic", "opin"))
+    );
+    assert_eq!(options.pin_symbols.len(), 2);
+
+    assert!(options.apply_flag("--snap-grid", Some("x")).is_err());
+    assert!(
+        options
+            .apply_flag("--label-max-displacement", None)
+            .is_err()
+    );
+    assert!(
+        options
+            .apply_flag("--pin-symbols", Some("input=ipin"))
+            .is_err()
+    );
+    assert!(options.apply_flag("--id-scheme", None).is_err());
+    assert!(options.apply_flag("--bogus", None).is_err());
+}
+
+#[test]
+fn scoped_settings_are_restored() {
+    use drawrs::DrawFile;
+    use drawrs::xml_base::{DEFAULT_FLOAT_PRECISION, float_precision};
+
+    let fixed = RenderOptions {
+        float_precision: Some(1),
+        timestamp: Some("2024-01-01T00:00:00".to_string()),
+        ..Default::default()
+    };
+    let settings = || (float_precision(), DrawFile::new().modified());
+    assert_eq!(
+        fixed.scoped(settings),
+        (1, "2024-01-01T00:00:00".to_string())
+    );
+    // Without the timestamp in the options the time is live again
+    let (precision, modified) = RenderOptions::default().scoped(settings);
+    assert_eq!(precision, DEFAULT_FLOAT_PRECISION);
+    assert_ne!(modified, "2024-01-01T00:00:00");
+    assert_eq!(drawrs::file::fixed_timestamp(), None);
+}
+
+fn render_deterministic(schematic: &Schematic) -> (String, String) {
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(schematic, &layer_styles).with_deterministic_ids(true);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let schematic_file = renderer.render_schematic_file(&symbols).unwrap();
+    (symbols.to_single_file().unwrap(), schematic_file)
+}
+
+#[test]
+fn same_input_same_output() {
+    let mut schematic = common::case1();
+    // A wire of an anonymous net gets a generated id
+    schematic.wires.push(Wire {
+        net: String::new(),
+        points: vec![
+            [OrderedFloat(-10.0), OrderedFloat(-10.0)],
+            [OrderedFloat(-10.0), OrderedFloat(-20.0)],
+        ],
+    });
+    let options = RenderOptions {
+        timestamp: Some("2024-01-01T00:00:00".to_string()),
+        ..Default::default()
+    };
+    let (symbols, schematic_file) = options.scoped(|| render_deterministic(&schematic));
+    assert_eq!(
+        options.scoped(|| render_deterministic(&schematic)),
+        (symbols, schematic_file.clone())
+    );
+    assert!(schematic_file.contains(r#"modified="2024-01-01T00:00:00""#));
+    assert!(schematic_file.contains(r#"id="wire-id-"#));
+}
+
+/// Case 1 with a vertical tab in a wire label, as some exporters write
+/// multi-line labels
+fn vertical_tab_schematic() -> Schematic {
+    let json = include_str!("case1/schematic.json").replacen(
+        r#""text": "vref""#,
+        r#""text": "v\u000bref""#,
+        1,
+    );
+    serde_json::from_str(&json).expect("Failed to parse schematic")
+}
+
+fn render_text(strict_text: bool) -> Result<String, DrawcktError> {
+    let schematic = vertical_tab_schematic();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles).with_strict_text(strict_text);
+    let symbols = renderer.render_symbols_file()?;
+    renderer.render_schematic_file(&symbols)
+}
+
+#[test]
+fn control_characters_are_sanitized() {
+    let content = render_text(false).expect("Failed to render schematic");
+    assert!(!content.contains('\u{b}'));
+    assert!(content.contains("v&#xa;ref"), "the label is lost");
+    drawrs::file::validate_xml(&content).expect("Invalid XML");
+}
+
+#[test]
+fn strict_text_reports_the_label() {
+    let err = render_text(true).unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            DrawcktError::Drawrs(DrawrsError::InvalidText { value, .. }) if value.contains('\u{b}')
+        ),
+        "{err}"
+    );
+}
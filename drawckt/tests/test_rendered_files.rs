@@ -0,0 +1,351 @@
+mod common;
+
+use drawckt::crossref::is_connector;
+use drawckt::renderer::{OverlayKinds, Renderer};
+use drawckt::schematic::{LayerStyles, Schematic};
+use drawckt::{
+    CellSource, CrossRefPlacement, CrossRefStyle, CrossRefs, RenderReport, SOURCE_ATTRIBUTE,
+};
+use drawrs::{DiagramObject, DrawFile, Page};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Case 1 split in two schematics, the instances and every other wire on
+/// the first one and the other wires on the second one
+fn split_schematics() -> [Schematic; 2] {
+    let schematic = common::case1();
+    let mut first = schematic.clone();
+    let mut second = schematic;
+    second.instances.clear();
+    second.pins.clear();
+    let wires = std::mem::take(&mut first.wires);
+    for (i, wire) in wires.into_iter().enumerate() {
+        if i % 2 == 0 {
+            first.wires.push(wire);
+        } else {
+            second.wires.push(wire);
+        }
+    }
+    [first, second]
+}
+
+fn drawn_nets(schematic: &Schematic) -> BTreeSet<&str> {
+    schematic
+        .wires
+        .iter()
+        .filter(|wire| wire.points.len() >= 2)
+        .map(|wire| wire.net.as_str())
+        .collect()
+}
+
+fn render_pages(schematics: &[Schematic]) -> Vec<Page> {
+    let layer_styles = LayerStyles::default();
+    schematics
+        .iter()
+        .enumerate()
+        .map(|(i, schematic)| {
+            let renderer = Renderer::new(schematic, &layer_styles).with_deterministic_ids(true);
+            let mut page = common::render_page(&renderer);
+            page.set_name(format!("sheet{}", i + 1));
+            page
+        })
+        .collect()
+}
+
+/// Labels of the connectors of a page, by id
+fn connectors(objects: &[DiagramObject]) -> Vec<(String, String)> {
+    objects
+        .iter()
+        .filter(|obj| is_connector(obj))
+        .map(|obj| match obj {
+            DiagramObject::Object(o) => {
+                assert_eq!(obj.xml_parent(), Some("layer-wire-label"));
+                (o.id().to_string(), o.value().cloned().unwrap_or_default())
+            }
+            _ => panic!("{} is not a vertex", obj.id()),
+        })
+        .collect()
+}
+
+#[test]
+fn shared_nets_are_mirrored() {
+    let schematics = split_schematics();
+    let shared: BTreeSet<&str> = drawn_nets(&schematics[0])
+        .intersection(&drawn_nets(&schematics[1]))
+        .copied()
+        .collect();
+    assert!(!shared.is_empty());
+
+    let mut pages = render_pages(&schematics);
+    let cross_refs = CrossRefs::collect(&pages);
+    for net in &shared {
+        assert_eq!(cross_refs.nets[*net], [1, 2]);
+        assert_eq!(cross_refs.label(net, 1), Some(format!("{net} →2")));
+    }
+    let count = cross_refs
+        .add_connectors(
+            &mut pages,
+            &LayerStyles::default(),
+            &CrossRefStyle::default(),
+        )
+        .unwrap();
+    assert_eq!(count, 2 * shared.len());
+    for (i, other) in [(1, 2), (2, 1)] {
+        // The connectors of a page are numbered from 1
+        let found: BTreeSet<_> = connectors(pages[i - 1].objects())
+            .into_iter()
+            .enumerate()
+            .map(|(n, (id, label))| {
+                let net = label.strip_suffix(&format!(" →{other}")).unwrap();
+                let sanitized = drawrs::xml_base::sanitize_id(net);
+                assert_eq!(id, format!("xref-{sanitized}-{i}-{}", n + 1));
+                net.to_string()
+            })
+            .collect();
+        let expected: BTreeSet<_> = shared.iter().map(|net| net.to_string()).collect();
+        assert_eq!(found, expected, "page {i}");
+    }
+}
+
+#[test]
+fn cross_refs_of_file() {
+    let mut file = DrawFile::new();
+    for page in render_pages(&split_schematics()) {
+        file.add_page(page);
+    }
+    let content = file.xml().unwrap().to_string();
+    let layer_styles = LayerStyles::default();
+    for placement in [CrossRefPlacement::Margin, CrossRefPlacement::WireEnd] {
+        let style = CrossRefStyle {
+            placement,
+            ..CrossRefStyle::default()
+        };
+        let updated = Renderer::add_cross_refs(&content, &layer_styles, &style)
+            .expect("Failed to add the connectors");
+        // Adding them again replaces them
+        let again = Renderer::add_cross_refs(&updated, &layer_styles, &style)
+            .expect("Failed to add the connectors");
+        let count = |content: &str| content.matches(r#"drawckt="crossRef""#).count();
+        assert_eq!(count(&again), count(&updated));
+
+        let pages = Renderer::parse_drawio_file(&updated).expect("Failed to parse");
+        assert_eq!(
+            pages.keys().collect::<Vec<_>>(),
+            ["sheet1", "sheet2"].iter().collect::<Vec<_>>()
+        );
+        // Same nets on both pages, each pointing to the other one
+        let nets = |page: usize, suffix: &str| -> BTreeSet<(String, String)> {
+            connectors(pages[page - 1].objects())
+                .into_iter()
+                .map(|(id, label)| {
+                    let (id, _) = id.rsplit_once(&format!("-{page}-")).unwrap();
+                    let net = label.strip_suffix(suffix).unwrap().to_string();
+                    (id.to_string(), net)
+                })
+                .collect()
+        };
+        let first = nets(1, " →2");
+        assert!(!first.is_empty());
+        assert_eq!(first, nets(2, " →1"));
+    }
+}
+
+#[test]
+fn user_objects_are_kept() {
+    let mut pages = render_pages(&split_schematics());
+    // A note of the user with an id of the connectors
+    let mut note = drawrs::Object::new(Some("xref-note".to_string()));
+    note.set_value("see sheet 2".to_string());
+    pages[0].add_object(note.into());
+    let mut file = DrawFile::new();
+    for page in pages {
+        file.add_page(page);
+    }
+    let updated = Renderer::add_cross_refs(
+        &file.xml().unwrap().to_string(),
+        &LayerStyles::default(),
+        &CrossRefStyle::default(),
+    )
+    .expect("Failed to add the connectors");
+    let updated =
+        Renderer::add_cross_refs(&updated, &LayerStyles::default(), &CrossRefStyle::default())
+            .expect("Failed to add the connectors");
+    assert_eq!(updated.matches(r#"id="xref-note""#).count(), 1);
+}
+
+fn wire_ids(page: &Page) -> Vec<&str> {
+    page.objects()
+        .iter()
+        .map(|obj| obj.id())
+        .filter(|id| id.starts_with("wire-"))
+        .collect()
+}
+
+#[test]
+fn overlay_wires_replaces_them() {
+    let schematic = common::case1();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let full = renderer.render_schematic_page(&symbols).unwrap();
+
+    let mut page = Page::new(None, false);
+    renderer
+        .render_overlay(&symbols, &mut page, OverlayKinds::INSTANCES)
+        .unwrap();
+    assert!(wire_ids(&page).is_empty());
+    let instance_objects = page.objects().len();
+
+    renderer
+        .render_overlay(&symbols, &mut page, OverlayKinds::WIRES)
+        .unwrap();
+    renderer
+        .render_overlay(&symbols, &mut page, OverlayKinds::WIRES)
+        .unwrap();
+    let ids = wire_ids(&page);
+    assert!(!ids.is_empty());
+    assert_eq!(ids.len(), wire_ids(&full).len());
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+
+    // The instances and the layer cells are left as is
+    let open_ends = page
+        .objects()
+        .iter()
+        .filter(|obj| obj.id().starts_with("openend-"))
+        .count();
+    assert_eq!(
+        page.objects().len(),
+        instance_objects + ids.len() + open_ends
+    );
+
+    // Overlaying everything gives the objects of a full render
+    renderer
+        .render_overlay(&symbols, &mut page, OverlayKinds::ALL)
+        .unwrap();
+    let ids: HashSet<&str> = page.objects().iter().map(|obj| obj.id()).collect();
+    assert_eq!(ids.len(), page.objects().len());
+    assert_eq!(page.objects().len(), full.objects().len());
+    assert_eq!(
+        OverlayKinds::WIRES
+            | OverlayKinds::PINS
+            | OverlayKinds::INSTANCES
+            | OverlayKinds::LABELS
+            | OverlayKinds::SHAPES,
+        OverlayKinds::ALL
+    );
+}
+
+// Number of the objects rendered for a source kind
+fn source_count(page: &Page, kind: &str) -> usize {
+    page.objects()
+        .iter()
+        .filter(|obj| obj.attribute(SOURCE_ATTRIBUTE).is_some_and(|k| k == kind))
+        .count()
+}
+
+#[test]
+fn overlay_instances_by_source() {
+    let mut schematic = common::case1();
+    // The ids of its objects start as the wire ids
+    schematic.instances[0].name = "wire".into();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer.render_symbols_file().unwrap();
+    let full = renderer.render_schematic_page(&symbols).unwrap();
+    let wires = source_count(&full, "wire");
+    assert!(wires > 0);
+
+    // The marks are read back from the file
+    let content = renderer.render_schematic_file(&symbols).unwrap();
+    let mut page = common::parse_page(&content);
+    assert_eq!(source_count(&page, "wire"), wires);
+
+    // The objects of the removed instance go, the wires stay
+    let instance_objects = source_count(&full, "instance");
+    let mut schematic = schematic.clone();
+    let removed = schematic.instances.remove(0);
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    renderer
+        .render_overlay(&symbols, &mut page, OverlayKinds::INSTANCES)
+        .unwrap();
+    assert_eq!(source_count(&page, "wire"), wires);
+    assert!(source_count(&page, "instance") < instance_objects);
+    assert!(
+        page.objects()
+            .iter()
+            .all(|obj| !obj.tags().any(|tag| tag == removed.name))
+    );
+}
+
+// Number of cells of each id in the XML
+fn cell_ids(xml: &str) -> HashMap<String, usize> {
+    let mut reader = Reader::from_str(xml);
+    let mut ids = HashMap::new();
+    loop {
+        match reader.read_event().expect("Malformed XML") {
+            Event::Start(e) | Event::Empty(e)
+                if matches!(e.name().as_ref(), b"mxCell" | b"UserObject") =>
+            {
+                if let Some(id) = e.try_get_attribute("id").unwrap() {
+                    let id = id.unescape_value().unwrap().into_owned();
+                    *ids.entry(id).or_default() += 1;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    ids
+}
+
+#[test]
+fn every_cell_in_report() {
+    let schematic = common::case1();
+    let layer_styles = LayerStyles {
+        show_instance_params: true,
+        ..Default::default()
+    };
+    let renderer = Renderer::new(&schematic, &layer_styles).with_mark_open_ends(true);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    let (xml, report) = renderer
+        .render_schematic_file_with_report(&symbols)
+        .expect("Failed to render schematic");
+
+    let ids = cell_ids(&xml);
+    assert_eq!(ids.len(), report.len());
+    for (id, count) in &ids {
+        assert_eq!(*count, 1, "{id} is emitted {count} times");
+        assert!(report.get(id).is_some(), "{id} is missing from the report");
+    }
+
+    // Each wire is reported with exactly one merged line
+    let mut wires = vec![0; schematic.wires.len()];
+    for (id, source) in &report.cells {
+        match source {
+            CellSource::Wire { net, indices } => {
+                assert!(!indices.is_empty(), "{id} has no source wire");
+                for i in indices {
+                    assert_eq!(&schematic.wires[*i].net, net);
+                    wires[*i] += 1;
+                }
+            }
+            CellSource::Instance { name, index, .. } => {
+                assert_eq!(&schematic.instances[*index].name, name);
+                assert!(id.starts_with(&format!("{name}-")));
+            }
+            _ => {}
+        }
+    }
+    assert!(wires.iter().all(|count| *count == 1));
+
+    let json = report.to_json().unwrap();
+    let parsed: RenderReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+    assert_eq!(
+        serde_json::to_value(report.get("0").unwrap()).unwrap(),
+        serde_json::json!({"kind": "root"})
+    );
+}
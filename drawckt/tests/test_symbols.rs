@@ -0,0 +1,241 @@
+mod common;
+
+use drawckt::renderer::{Renderer, SymbolContexts};
+use drawckt::schematic::{DesignId, InputFormat, LayerStyles, Schematic};
+use drawckt::{DrawcktError, RenderOptions};
+
+/// Parsed pages of every symbol, which ignores the volatile file header
+fn parsed_symbols(symbols: &SymbolContexts) -> Vec<(String, String)> {
+    symbols
+        .0
+        .iter()
+        .map(|(symbol_id, content)| {
+            let pages = Renderer::parse_drawio_file(content).expect("Failed to parse symbol");
+            (symbol_id.to_string(), format!("{pages:?}"))
+        })
+        .collect()
+}
+
+#[test]
+fn single_file_round_trip() {
+    let schematic = common::case1();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+
+    let single_file = symbols.to_single_file().expect("Failed to combine symbols");
+    let pages = Renderer::parse_drawio_file(&single_file).expect("Failed to parse single file");
+    let page_names: Vec<String> = pages.keys().cloned().collect();
+    let symbol_names: Vec<String> = symbols.0.keys().map(|id| id.to_string()).collect();
+    assert_eq!(page_names, symbol_names);
+
+    let split = SymbolContexts::from_single_file(&single_file).expect("Failed to split symbols");
+    assert_eq!(parsed_symbols(&split), parsed_symbols(&symbols));
+}
+
+#[cfg(feature = "std-fs")]
+#[test]
+fn directory_round_trip() {
+    let schematic = common::case1();
+    let layer_styles = LayerStyles::default();
+    let renderer = Renderer::new(&schematic, &layer_styles);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+
+    let dir = std::env::temp_dir().join(format!("drawckt-symbols-{}", std::process::id()));
+    symbols.write_to_dir(&dir).expect("Failed to write symbols");
+    let loaded = SymbolContexts::load_from_dir(&dir).expect("Failed to load symbols");
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+
+    // Directory order is not defined, compare as sorted lists
+    let mut expected = parsed_symbols(&symbols);
+    let mut loaded_from_dir = parsed_symbols(&loaded);
+    expected.sort();
+    loaded_from_dir.sort();
+    assert_eq!(loaded_from_dir, expected);
+
+    // Both forms describe the same symbols
+    let split = SymbolContexts::from_single_file(&loaded.to_single_file().unwrap()).unwrap();
+    let mut from_single_file = parsed_symbols(&split);
+    from_single_file.sort();
+    assert_eq!(from_single_file, expected);
+}
+
+#[test]
+fn single_file_invalid_page_name() {
+    let content = r#"<mxfile pages="1">
+  <diagram name="no-slash" id="page"><mxGraphModel><root><mxCell id="0" /></root></mxGraphModel></diagram>
+</mxfile>"#;
+    let Err(err) = SymbolContexts::from_single_file(content) else {
+        panic!("Expected an invalid page name");
+    };
+    assert!(matches!(err, DrawcktError::InvalidPageName(name) if name == "no-slash"));
+}
+
+#[test]
+fn single_file_character_references() {
+    let content = r#"<mxfile pages="1">
+  <diagram name="lib/a&#38;b" id="p&#x26;1"><mxGraphModel><root><mxCell id="0" /></root></mxGraphModel></diagram>
+</mxfile>"#;
+    let symbols = SymbolContexts::from_single_file(content).expect("Failed to split symbols");
+    let id = DesignId::new("lib", "a&b");
+    assert_eq!(symbols.0.keys().collect::<Vec<_>>(), [&id]);
+    assert!(symbols.0[&id].contains(r#"<diagram name="lib/a&amp;b" id="p&amp;1">"#));
+
+    // Combined and split again, the names and ids are the same
+    let single_file = symbols.to_single_file().expect("Failed to combine symbols");
+    assert!(single_file.contains(r#"<diagram name="lib/a&amp;b" id="p&amp;1">"#));
+    let split = SymbolContexts::from_single_file(&single_file).expect("Failed to split symbols");
+    assert_eq!(split.0.keys().collect::<Vec<_>>(), [&id]);
+    assert_eq!(parsed_symbols(&split), parsed_symbols(&symbols));
+}
+
+// The render path without the filesystem, as in a browser: JSON strings in,
+// drawio strings out. Runs with `--no-default-features` too
+const SCHEMATIC: &str = include_str!("case1/schematic.json");
+const STYLE: &str = include_str!("case1/style.json");
+
+fn render_uploaded(symbols: &SymbolContexts) -> String {
+    let schematic = Schematic::from_reader(InputFormat::Json, SCHEMATIC.as_bytes()).unwrap();
+    let layer_styles = LayerStyles::from_json(STYLE).unwrap();
+    let renderer = Renderer::new(&schematic, &layer_styles).with_deterministic_ids(true);
+    let options = RenderOptions {
+        timestamp: Some("2024-01-01T00:00:00".to_string()),
+        ..Default::default()
+    };
+    options.scoped(|| {
+        renderer
+            .render_schematic_file(symbols)
+            .expect("Failed to render schematic")
+    })
+}
+
+/// Symbol files as a user would upload them, owned strings by `{lib}/{cell}`
+fn uploaded_symbols() -> Vec<(String, String)> {
+    let schematic = Schematic::from_reader(InputFormat::Json, SCHEMATIC.as_bytes()).unwrap();
+    let layer_styles = LayerStyles::from_json(STYLE).unwrap();
+    let renderer = Renderer::new(&schematic, &layer_styles).with_deterministic_ids(true);
+    let symbols = renderer
+        .render_symbols_file()
+        .expect("Failed to render symbols");
+    symbols
+        .0
+        .iter()
+        .map(|(id, content)| (id.to_string(), content.to_string()))
+        .collect()
+}
+
+#[test]
+fn symbols_from_memory() {
+    let uploaded = uploaded_symbols();
+    let collected: SymbolContexts = uploaded
+        .iter()
+        .map(|(name, content)| (name.parse::<DesignId>().unwrap(), content.as_str()))
+        .collect();
+    let mut inserted = SymbolContexts::new();
+    for (name, content) in uploaded.clone() {
+        inserted.insert(name.parse().unwrap(), content);
+    }
+    assert_eq!(collected.0.len(), inserted.0.len());
+
+    let content = render_uploaded(&collected);
+    assert_eq!(render_uploaded(&inserted), content);
+    drawrs::file::validate_xml(&content).expect("Invalid XML");
+    let pages = Renderer::parse_drawio_file(&content).expect("Failed to parse schematic");
+    assert_eq!(pages.len(), 1);
+    assert!(!pages[0].objects().is_empty());
+}
+
+#[test]
+fn missing_symbol_is_an_error() {
+    let mut symbols: SymbolContexts = uploaded_symbols()
+        .into_iter()
+        .map(|(name, content)| (name.parse::<DesignId>().unwrap(), content))
+        .collect();
+    symbols.0.pop();
+    let schematic = Schematic::from_reader(InputFormat::Json, SCHEMATIC.as_bytes()).unwrap();
+    let layer_styles = LayerStyles::default();
+    assert!(
+        Renderer::new(&schematic, &layer_styles)
+            .render_schematic_file(&symbols)
+            .is_err()
+    );
+}
+
+#[cfg(feature = "std-fs")]
+fn render_schematic(
+    schematic: &Schematic,
+    layer_styles: &LayerStyles,
+    symbols: &SymbolContexts,
+) -> String {
+    Renderer::new(schematic, layer_styles)
+        .with_deterministic_ids(true)
+        .render_schematic_file(symbols)
+        .expect("Failed to render schematic")
+}
+
+// The reload of a watch cycle: the symbols of a directory are loaded once,
+// then only the changed files are read again
+#[cfg(feature = "std-fs")]
+#[test]
+fn reload_changed_symbols() {
+    let schematic = common::case1();
+    let dir = std::env::temp_dir().join(format!("drawckt-reload-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let old_styles = LayerStyles::default();
+    Renderer::new(&schematic, &old_styles)
+        .render_symbols_file()
+        .expect("Failed to render symbols")
+        .write_to_dir(&dir)
+        .expect("Failed to write symbols");
+    let mut symbols = SymbolContexts::load_from_dir(&dir).expect("Failed to load symbols");
+    let count = symbols.0.len();
+    let id = DesignId::new("basic", "ipin");
+    let old_content = symbols.0[&id].to_string();
+
+    // The symbols are rendered again with another style, a watched file
+    // that is not a symbol changes too
+    let mut new_styles = LayerStyles::default();
+    new_styles.pin.stroke_color = "#123456".into();
+    Renderer::new(&schematic, &new_styles)
+        .render_symbols_file()
+        .expect("Failed to render symbols")
+        .write_to_dir(&dir)
+        .expect("Failed to write symbols");
+    let ipin = dir.join("basic/ipin.drawio");
+    let notes = dir.join("basic/notes.txt");
+    std::fs::write(&notes, "not a symbol").unwrap();
+    assert_eq!(symbols.reload_changed([&ipin, &notes]).unwrap(), 1);
+    assert_ne!(symbols.0[&id], old_content);
+    assert_eq!(symbols.0[&id], std::fs::read_to_string(&ipin).unwrap());
+    assert_eq!(symbols.0.len(), count);
+    // Reloading all the rewritten files is the same as loading the whole
+    // directory again
+    let rewritten: Vec<_> = ["basic", "tsmcN28"]
+        .into_iter()
+        .flat_map(|lib| dir.join(lib).read_dir().unwrap())
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(symbols.reload_changed(&rewritten).unwrap(), count);
+    let loaded = SymbolContexts::load_from_dir(&dir).expect("Failed to load symbols");
+    // Both renders in the same second
+    let options = RenderOptions {
+        timestamp: Some("2024-01-01T00:00:00".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        options.scoped(|| render_schematic(&schematic, &new_styles, &symbols)),
+        options.scoped(|| render_schematic(&schematic, &new_styles, &loaded))
+    );
+
+    // A deleted file removes its symbol, once
+    std::fs::remove_file(&ipin).unwrap();
+    assert_eq!(symbols.reload_changed([&ipin]).unwrap(), 1);
+    assert_eq!(symbols.reload_changed([&ipin]).unwrap(), 0);
+    assert!(!symbols.0.contains_key(&id));
+    assert_eq!(symbols.0.len(), count - 1);
+    std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+}
@@ -0,0 +1,414 @@
+mod common;
+
+use drawckt::DrawcktError;
+use drawckt::renderer::Renderer;
+use drawckt::schematic::{LayerStyle, LayerStyles};
+use drawrs::{DiagramObject, Edge, Object};
+
+const ANNOTATION: &str = r##"<mxCell id="my-note" value="check this" style="rounded=0;whiteSpace=wrap;strokeColor=#000000;fillColor=#FFF2CC;" vertex="1" parent="layer-annotate-your-drawing">
+          <mxGeometry x="12" y="34" width="120" height="60" as="geometry" />
+        </mxCell>
+        "##;
+
+fn rendered_schematic(layer_styles: &LayerStyles) -> String {
+    let schematic = common::case1();
+    let renderer = Renderer::new(&schematic, layer_styles);
+    common::render_file(&renderer)
+}
+
+/// Two copies of the schematic page, the first one with a hand-drawn note
+fn edited_file(content: &str) -> String {
+    let start = content.find("<diagram ").unwrap();
+    let end = content.rfind("</diagram>").unwrap() + "</diagram>".len();
+    let diagram = &content[start..end];
+    let edited = diagram.replacen("</root>", &format!("{ANNOTATION}</root>"), 1);
+    let copy =
+        diagram
+            .replacen(r#"name=""#, r#"name="copy-"#, 1)
+            .replacen(r#"id=""#, r#"id="copy-"#, 1);
+    format!(
+        "{}{edited}\n  {copy}{}",
+        &content[..start].replace(r#"pages="1""#, r#"pages="2""#),
+        &content[end..]
+    )
+}
+
+fn wire_colors(objects: &[DiagramObject]) -> Vec<String> {
+    objects
+        .iter()
+        .filter(|obj| obj.xml_parent() == Some("layer-wire-shape"))
+        .filter_map(|obj| match obj {
+            DiagramObject::Edge(edge) => edge.stroke_color().cloned(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn wire_color_update_keeps_annotation() {
+    let old_styles = LayerStyles::default();
+    let content = edited_file(&rendered_schematic(&old_styles));
+    let new_styles = LayerStyles {
+        wire: LayerStyle {
+            stroke_color: "#3366FF".into(),
+            ..old_styles.wire.clone()
+        },
+        ..LayerStyles::default()
+    };
+    let updated = Renderer::update_schematic_style(&content, &old_styles, &new_styles)
+        .expect("Failed to update the style");
+
+    let before = Renderer::parse_drawio_file(&content).unwrap();
+    let after = Renderer::parse_drawio_file(&updated).unwrap();
+    assert_eq!(
+        after.keys().collect::<Vec<_>>(),
+        before.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(after.len(), 2);
+    for (name, page) in &after {
+        let colors = wire_colors(page.objects());
+        assert!(!colors.is_empty(), "{name}");
+        assert!(colors.iter().all(|color| color == "#3366FF"), "{name}");
+        assert_eq!(page.objects().len(), before[name].objects().len(), "{name}");
+    }
+    // The layer cells are regenerated on each page
+    assert_eq!(
+        updated.matches(r#"<mxCell id="layer-wire-shape""#).count(),
+        2
+    );
+
+    // The note keeps its style and geometry although it has the old wire color
+    let note = |pages: &indexmap::IndexMap<String, drawckt::renderer::SymbolPageData>| {
+        let page = pages.first().unwrap().1;
+        let note = page
+            .objects()
+            .iter()
+            .find(|obj| obj.id() == "my-note")
+            .expect("The note is lost");
+        format!("{note:?}")
+    };
+    assert_eq!(note(&after), note(&before));
+}
+
+#[test]
+fn empty_file_has_no_page() {
+    let content = r#"<mxfile host="Electron" pages="0"></mxfile>"#;
+    assert!(matches!(
+        Renderer::update_schematic_style(content, &LayerStyles::default(), &LayerStyles::default()),
+        Err(DrawcktError::NoPage)
+    ));
+}
+
+#[test]
+fn same_style_is_a_fixpoint() {
+    let styles = LayerStyles::default();
+    let rendered = rendered_schematic(&styles);
+    let update = |content: &str| {
+        Renderer::update_schematic_style(content, &styles, &styles)
+            .expect("Failed to update the style")
+    };
+    // A rendered file is left byte for byte as is
+    assert_eq!(update(&rendered), rendered);
+    // Edited files and files saved by draw.io are normalized once
+    let mut contents = vec![("edited".to_string(), edited_file(&rendered))];
+    for name in [
+        "schematic",
+        "iopin",
+        "rupolym",
+        "waypoints",
+        "object_element",
+    ] {
+        let path = format!("tests/test_parse_drawio/{name}.drawio");
+        let content = std::fs::read_to_string(&path).expect("Failed to read the file");
+        contents.push((path, content));
+    }
+    for (name, content) in contents {
+        let once = update(&content);
+        assert_eq!(update(&once), once, "{name}");
+    }
+    // Same for the single page files of `update_style`
+    let symbol = std::fs::read_to_string("tests/test_parse_drawio/rupolym.drawio")
+        .expect("Failed to read the file");
+    let update = |content: &str| {
+        Renderer::update_style(content, &styles, &styles).expect("Failed to update the style")
+    };
+    let once = update(&symbol);
+    assert_eq!(update(&once), once);
+}
+
+#[test]
+fn update_keeps_page_ids_and_hidden_layers() {
+    let old_styles = LayerStyles::default();
+    let content = edited_file(&rendered_schematic(&old_styles)).replace(
+        r#"<mxCell id="layer-text-label" parent="0" visible="1""#,
+        r#"<mxCell id="layer-text-label" parent="0" visible="0""#,
+    );
+    let new_styles = LayerStyles {
+        wire: LayerStyle {
+            stroke_color: "#3366FF".into(),
+            ..old_styles.wire.clone()
+        },
+        ..LayerStyles::default()
+    };
+    let updated = Renderer::update_schematic_style(&content, &old_styles, &new_styles)
+        .expect("Failed to update the style");
+    let page_ids = |content: &str| -> Vec<String> {
+        content
+            .match_indices("<diagram ")
+            .map(|(start, _)| {
+                let tag = &content[start..start + content[start..].find('>').unwrap()];
+                let id = &tag[tag.find(r#" id=""#).unwrap() + 5..];
+                id[..id.find('"').unwrap()].to_string()
+            })
+            .collect()
+    };
+    assert_eq!(page_ids(&updated), page_ids(&content));
+    assert_eq!(
+        updated
+            .matches(r#"<mxCell id="layer-text-label" parent="0" visible="0""#)
+            .count(),
+        2
+    );
+    assert_eq!(
+        updated.matches(r#"<mxCell id="layer-wire-shape""#).count(),
+        2
+    );
+}
+
+/// Rewrite a style the way `update_style` does, by parsing it into an
+/// object or edge and formatting it back
+fn round_trip(style: &str, is_edge: bool) -> String {
+    if is_edge {
+        let mut edge = Edge::new(None);
+        edge.parse_and_set_style(style);
+        edge.style().to_string()
+    } else {
+        let mut object = Object::new(None);
+        object.parse_and_set_style(style);
+        object.style().to_string()
+    }
+}
+
+/// Styles of the cells of a drawio file, with the `edge="1"` flag
+fn cell_styles(content: &str) -> Vec<(String, bool)> {
+    content
+        .split("<mxCell ")
+        .skip(1)
+        .filter_map(|cell| {
+            let tag = &cell[..cell.find('>')?];
+            let start = tag.find("style=\"")? + "style=\"".len();
+            let end = start + tag[start..].find('"')?;
+            let style = tag[start..end]
+                .replace("&quot;", "\"")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&amp;", "&");
+            Some((style, tag.contains("edge=\"1\"")))
+        })
+        .collect()
+}
+
+/// Key-value pairs of a style, a bare token has an empty value
+fn style_map(style: &str) -> Vec<(&str, &str)> {
+    let mut map: Vec<(&str, &str)> = style
+        .split(';')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.split_once('=').unwrap_or((part, "")))
+        .collect();
+    map.sort();
+    map
+}
+
+/// Numbers are written with at most the 4 decimals of the XML precision,
+/// `polyCoords` and `points` compare their coordinates
+fn same_value(a: &str, b: &str) -> bool {
+    let numbers = |s: &str| -> Option<Vec<f64>> {
+        s.split(['[', ']', ','])
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse().ok())
+            .collect()
+    };
+    match (numbers(a), numbers(b)) {
+        (Some(a), Some(b)) if !a.is_empty() => {
+            a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| (a - b).abs() <= 5e-5)
+        }
+        _ => a == b,
+    }
+}
+
+/// Semantic equality of two styles, the canonical form writes the justify
+/// of objects and `rounded` of edges even when they hold the defaults
+fn assert_same_style(original: &str, formatted: &str) {
+    let defaults = [
+        ("align", "center"),
+        ("verticalAlign", "middle"),
+        ("rounded", "0"),
+    ];
+    let complete = |style: &str| {
+        let mut map = style_map(style)
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+        for (key, value) in defaults {
+            if !map.iter().any(|(k, _)| k == key) {
+                map.push((key.to_string(), value.to_string()));
+            }
+        }
+        map.sort();
+        map
+    };
+    let (original_map, formatted_map) = (complete(original), complete(formatted));
+    let keys = |map: &[(String, String)]| map.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        keys(&original_map),
+        keys(&formatted_map),
+        "{original} -> {formatted}"
+    );
+    for ((key, a), (_, b)) in original_map.iter().zip(&formatted_map) {
+        assert!(
+            same_value(a, b),
+            "{key}: {a} -> {b} in {original} -> {formatted}"
+        );
+    }
+}
+
+#[test]
+fn fixture_styles_round_trip() {
+    let mut count = 0;
+    for (name, content) in [
+        ("iopin", include_str!("test_parse_drawio/iopin.drawio")),
+        ("rupolym", include_str!("test_parse_drawio/rupolym.drawio")),
+        (
+            "schematic",
+            include_str!("test_parse_drawio/schematic.drawio"),
+        ),
+        (
+            "waypoints",
+            include_str!("test_parse_drawio/waypoints.drawio"),
+        ),
+        (
+            "object_element",
+            include_str!("test_parse_drawio/object_element.drawio"),
+        ),
+    ] {
+        for (style, is_edge) in cell_styles(content) {
+            let formatted = round_trip(&style, is_edge);
+            assert_same_style(&style, &formatted);
+            // The canonical form is stable
+            assert_eq!(
+                round_trip(&formatted, is_edge),
+                formatted,
+                "{name}: {style}"
+            );
+            count += 1;
+        }
+    }
+    assert!(count > 100, "only {count} styles found");
+}
+
+#[test]
+fn canonical_values() {
+    // Integers without a fraction, booleans as 0/1
+    let formatted = round_trip("strokeWidth=2.0;rounded=1;fontSize=12.50;", false);
+    assert!(formatted.contains("strokeWidth=2;"), "{formatted}");
+    assert!(formatted.contains("rounded=1;"), "{formatted}");
+    assert!(formatted.contains("fontSize=12.5;"), "{formatted}");
+    assert_eq!(
+        round_trip("strokeColor=#000000;endArrow=none;rounded=0;", true),
+        "strokeColor=#000000;endArrow=none;rounded=0;"
+    );
+    // Unsupported keys keep their original relative order
+    assert_eq!(
+        round_trip("spacingBottom=-2;html=1;spacing=0;shape=ellipse;", false),
+        "align=center;verticalAlign=middle;spacingBottom=-2;html=1;spacing=0;shape=ellipse;"
+    );
+}
+
+#[test]
+fn unparsed_values_are_kept() {
+    for (style, is_edge) in [
+        // Bare style names
+        ("ellipse;whiteSpace=wrap;html=1;", false),
+        ("text;html=1;", false),
+        (
+            "endArrow=none;edgeStyle=orthogonalEdgeStyle;orthogonalLoop;",
+            true,
+        ),
+        // Supported keys whose value does not parse
+        ("opacity=50.5;", false),
+        ("fillStyle=dots;", false),
+        ("jointStyle=bevel;", false),
+        ("endArrow=none;startCap=butt;", true),
+        ("flipH=true;", false),
+        ("polyCoords=[oops];", false),
+        ("endArrow=none;strokeWidth=thin;endSize=6.5;", true),
+        // Keys lost by the formatter before
+        ("legacyAnchorPoints=0;", false),
+        ("endArrow=classic;endFill=0;", true),
+    ] {
+        let formatted = round_trip(style, is_edge);
+        assert_same_style(style, &formatted);
+        assert_eq!(round_trip(&formatted, is_edge), formatted, "{style}");
+    }
+}
+
+#[test]
+fn setters_replace_kept_values() {
+    let mut object = Object::new(None);
+    object.parse_and_set_style("opacity=50.5;fontSize=big;");
+    object.set_opacity(Some(40));
+    object.set_font_size(Some(10.0));
+    assert_eq!(
+        object.style().to_string(),
+        "opacity=40;fontSize=10;align=center;verticalAlign=middle;"
+    );
+    object.parse_and_set_style("opacity=30.5;");
+    object.parse_and_set_style("opacity=20;");
+    assert_eq!(object.opacity(), Some(20));
+    assert_eq!(object.style_property("opacity"), None);
+
+    let mut edge = Edge::new(None);
+    edge.parse_and_set_style("endFill=0;");
+    assert_eq!(edge.style_property("endFill"), Some("0"));
+    edge.set_end_fill_target(true);
+    assert_eq!(edge.style().to_string(), "endFill=1;");
+
+    // Spacing setters use the XML float format
+    object.set_spacing_bottom(0.1 + 0.2);
+    assert_eq!(object.style_property("spacingBottom"), Some("0.3"));
+}
+
+#[test]
+fn font_family_lists_round_trip() {
+    for (style, is_edge) in [
+        (
+            "fontFamily=Verdana, DejaVu Sans, sans-serif;fontSize=12;",
+            false,
+        ),
+        ("endArrow=none;fontFamily='Times New Roman', serif;", true),
+        (
+            "fontFamily=CorpSans, Arial;fontSource=https%3A%2F%2Ffonts.example.com%2Fcorp.css;",
+            false,
+        ),
+    ] {
+        let formatted = round_trip(style, is_edge);
+        assert_same_style(style, &formatted);
+        assert_eq!(round_trip(&formatted, is_edge), formatted, "{style}");
+    }
+    let mut object = Object::new(None);
+    object.parse_and_set_style("fontFamily=Verdana, DejaVu Sans, sans-serif;");
+    assert_eq!(
+        object.font_family().map(String::as_str),
+        Some("Verdana, DejaVu Sans, sans-serif")
+    );
+    // A semicolon would end the style entry early, the family is kept
+    assert!(
+        object
+            .set_font_family(Some("Verdana;sans-serif".to_string()))
+            .is_err()
+    );
+    assert_eq!(
+        object.font_family().map(String::as_str),
+        Some("Verdana, DejaVu Sans, sans-serif")
+    );
+}
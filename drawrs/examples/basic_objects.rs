@@ -36,7 +36,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Write the file
     let output_file = "Basic Objects.drawio";
-    let xml_content = file.xml().to_string();
+    let xml_content = file.xml()?.to_string();
     fs::write(output_file, xml_content)?;
     println!("DrawFile written to: {}", output_file);
     Ok(())
@@ -122,7 +122,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge1 = Edge::new(None);
     edge1.set_source(Some(d_input_id.clone()));
     edge1.set_target(Some(junction1_id.clone()));
-    edge1.set_line_end_target(Some("classic".to_string()));
     edge1.set_stroke_width(Some(5.0));
     edge1.set_xml_parent(Some("1".to_string()));
     page.add_object(edge1.into());
@@ -130,7 +129,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge1b = Edge::new(None);
     edge1b.set_source(Some(junction1_id.clone()));
     edge1b.set_target(Some(nand1_id.clone()));
-    edge1b.set_line_end_target(Some("classic".to_string()));
     edge1b.set_stroke_width(Some(5.0));
     edge1b.set_xml_parent(Some("1".to_string()));
     page.add_object(edge1b.into());
@@ -139,7 +137,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge2 = Edge::new(None);
     edge2.set_source(Some(clk_input_id.clone()));
     edge2.set_target(Some(nand1_id.clone()));
-    edge2.set_line_end_target(Some("classic".to_string()));
     edge2.set_stroke_width(Some(5.0));
     edge2.set_xml_parent(Some("1".to_string()));
     page.add_object(edge2.into());
@@ -158,7 +155,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge3b = Edge::new(None);
     edge3b.set_source(Some(junction2_id.clone()));
     edge3b.set_target(Some(nand2_id.clone()));
-    edge3b.set_line_end_target(Some("classic".to_string()));
     edge3b.set_stroke_width(Some(5.0));
     edge3b.set_xml_parent(Some("1".to_string()));
     page.add_object(edge3b.into());
@@ -167,7 +163,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge4 = Edge::new(None);
     edge4.set_source(Some(clk_input_id.clone()));
     edge4.set_target(Some(nand2_id.clone()));
-    edge4.set_line_end_target(Some("classic".to_string()));
     edge4.set_stroke_width(Some(5.0));
     edge4.set_xml_parent(Some("1".to_string()));
     page.add_object(edge4.into());
@@ -187,7 +182,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge6 = Edge::new(None);
     edge6.set_source(Some(q_output_id.clone()));
     edge6.set_target(Some(junction1_id.clone()));
-    edge6.set_line_end_target(Some("classic".to_string()));
     edge6.set_stroke_width(Some(5.0));
     edge6.set_xml_parent(Some("1".to_string()));
     page.add_object(edge6.into());
@@ -207,7 +201,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Write the file
     let output_file = "Latch Circuit.drawio";
-    let xml_content = file.xml().to_string();
+    let xml_content = file.xml()?.to_string();
     fs::write(output_file, xml_content)?;
     println!("Latch circuit written to: {}", output_file);
     Ok(())
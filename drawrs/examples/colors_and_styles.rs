@@ -52,7 +52,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Write the file
     let output_file = "Colors and Styles.drawio";
-    let xml_content = file.xml().to_string();
+    let xml_content = file.xml()?.to_string();
     fs::write(output_file, xml_content)?;
     println!("Colors and styles example written to: {}", output_file);
     Ok(())
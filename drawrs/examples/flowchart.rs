@@ -48,21 +48,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut edge1 = Edge::new(None);
     edge1.set_source(Some(start_id.clone()));
     edge1.set_target(Some(process_id.clone()));
-    edge1.set_line_end_target(Some("classic".to_string()));
     edge1.set_xml_parent(Some("1".to_string()));
     page.add_object(edge1.into());
 
     let mut edge2 = Edge::new(None);
     edge2.set_source(Some(process_id));
     edge2.set_target(Some(decision_id.clone()));
-    edge2.set_line_end_target(Some("classic".to_string()));
     edge2.set_xml_parent(Some("1".to_string()));
     page.add_object(edge2.into());
 
     let mut edge3 = Edge::new(None);
     edge3.set_source(Some(decision_id));
     edge3.set_target(Some(end_id));
-    edge3.set_line_end_target(Some("classic".to_string()));
     edge3.set_xml_parent(Some("1".to_string()));
     page.add_object(edge3.into());
 
@@ -70,7 +67,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Write the file
     let output_file = "Simple Flowchart.drawio";
-    let xml_content = file.xml().to_string();
+    let xml_content = file.xml()?.to_string();
     fs::write(output_file, xml_content)?;
     println!("Flowchart written to: {}", output_file);
     Ok(())
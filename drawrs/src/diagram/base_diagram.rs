@@ -1,8 +1,14 @@
 use crate::xml_base::XMLBase;
 use indexmap::IndexMap;
 use std::borrow::Cow;
+use std::fmt;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "DiagramBaseModel", from = "DiagramBaseModel")
+)]
 pub struct DiagramBase {
     base: XMLBase,
     page: Option<String>, // Reference to page by ID
@@ -85,20 +91,27 @@ impl DiagramBase {
         self.page.clone().unwrap_or_else(|| "1".to_string())
     }
 
-    /// Parse style string into key-value pairs
+    /// Parse style string into key-value pairs, a bare token such as
+    /// `ellipse;` is returned with an empty value
     pub fn parse_style_string(style_str: &str) -> Vec<(&str, &str)> {
-        let mut key_value_list = Vec::new();
-        for part in style_str.split(';') {
-            if part.is_empty() {
-                continue;
-            } else if part.contains('=') {
-                let parts: Vec<&str> = part.splitn(2, '=').collect();
-                if parts.len() == 2 {
-                    key_value_list.push((parts[0], parts[1]));
-                }
+        style_str
+            .split(';')
+            .filter(|part| !part.is_empty())
+            .map(|part| part.split_once('=').unwrap_or((part, "")))
+            .collect()
+    }
+
+    /// Write the unsupported properties in their original order, the ones
+    /// with an empty value as bare tokens
+    pub fn write_unsupported_style(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        for (key, value) in &self.unsupported_style_properties {
+            if value.is_empty() {
+                write!(f, "{key};")?;
+            } else {
+                write!(f, "{key}={value};")?;
             }
         }
-        key_value_list
+        Ok(())
     }
 
     /// Apply a single style property (for unsupported keys only)
@@ -125,12 +138,7 @@ impl DiagramBase {
         }
 
         // Add unsupported properties
-        for (key, value) in &self.unsupported_style_properties {
-            style_str.push_str(key);
-            style_str.push('=');
-            style_str.push_str(value);
-            style_str.push(';');
-        }
+        let _ = self.write_unsupported_style(&mut style_str);
 
         style_str
     }
@@ -160,3 +168,83 @@ pub fn width_input_check(width: Option<i32>) -> Option<i32> {
         Some(w) => Some(w),
     }
 }
+
+/// Serialized form of [`DiagramBase`], exposing only the cell attributes
+/// and the style keys that have no dedicated field
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DiagramBaseModel {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    page: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tags: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tooltip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    attributes: IndexMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    visible: Option<String>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    preserved_attributes: IndexMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    preserved_elements: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    style: IndexMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    style_class: Option<crate::xml_base::StyleClass>,
+}
+
+#[cfg(feature = "serde")]
+impl From<DiagramBase> for DiagramBaseModel {
+    fn from(base: DiagramBase) -> Self {
+        Self {
+            id: base.base.id,
+            parent: base.base.xml_parent,
+            page: base.page,
+            tags: base.base.tag,
+            tooltip: base.base.tooltip,
+            link: base.base.link,
+            attributes: base.base.attributes,
+            visible: base.base.visible,
+            preserved_attributes: base.base.preserved_attributes,
+            preserved_elements: base.base.preserved_elements,
+            value: base.base.value,
+            style: base
+                .unsupported_style_properties
+                .into_iter()
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect(),
+            style_class: base.base.style_class,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<DiagramBaseModel> for DiagramBase {
+    fn from(model: DiagramBaseModel) -> Self {
+        let mut base = Self::with_page(Some(model.id), model.page);
+        base.base.xml_parent = model.parent;
+        base.base.tag = model.tags;
+        base.base.tooltip = model.tooltip;
+        base.base.link = model.link;
+        base.base.attributes = model.attributes;
+        base.base.visible = model.visible;
+        base.base.preserved_attributes = model.preserved_attributes;
+        base.base.preserved_elements = model.preserved_elements;
+        base.base.value = model.value;
+        base.base.style_class = model.style_class;
+        base.unsupported_style_properties = model
+            .style
+            .into_iter()
+            .map(|(k, v)| (Cow::Owned(k), Cow::Owned(v)))
+            .collect();
+        base
+    }
+}
@@ -1,33 +1,123 @@
 use crate::XMLBase;
 use crate::diagram::base_diagram::DiagramBase;
 use crate::diagram::geometry::Geometry;
+use crate::diagram::objects::{LineCap, LineJoin};
+use crate::diagram::text_format::{Justify, JustifyX, JustifyY, TextFormat};
+use crate::error::DrawrsResult;
+use crate::xml_base::{StyleAttr, StyleClass, XmlAttr, XmlContext, XmlFloat, font_source};
+use indexmap::IndexMap;
 use std::borrow::Cow;
 use std::fmt;
 
+/// With the `serde` feature, the style keys are named after their mxGraph
+/// counterparts (`strokeColor`, `endArrow`, `startFill`, ...) and the cell
+/// attributes are flattened in, as for [`Object`](crate::Object).
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct Edge {
+    #[cfg_attr(feature = "serde", serde(flatten))]
     base: DiagramBase,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     source: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     target: Option<String>,
     waypoints: String,
     connection: String,
     pattern: String,
+    #[cfg_attr(feature = "serde", serde(skip, default = "edge_default"))]
     edge: i32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     stroke_color: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     stroke_width: Option<f64>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     fill_color: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "endArrow", default, skip_serializing_if = "Option::is_none")
+    )]
     line_end_target: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "startArrow",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
     line_end_source: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "endFill", default))]
     end_fill_target: bool,
+    #[cfg_attr(feature = "serde", serde(rename = "startFill", default))]
     end_fill_source: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     end_size: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     start_size: Option<i32>,
-    rounded: i32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    rounded: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     opacity: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "jointStyle",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    line_join: Option<LineJoin>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "startCap", default, skip_serializing_if = "Option::is_none")
+    )]
+    start_cap: Option<LineCap>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "endCap", default, skip_serializing_if = "Option::is_none")
+    )]
+    end_cap: Option<LineCap>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    text_format: TextFormat,
     geometry: Geometry,
 }
 
+#[cfg(feature = "serde")]
+fn edge_default() -> i32 {
+    1
+}
+
 impl Edge {
+    /// An edge with no style key set, draw.io draws it with its defaults,
+    /// e.g. a `classic` end arrow
     pub fn new(id: Option<String>) -> Self {
         let base = DiagramBase::new(id);
 
@@ -42,17 +132,32 @@ impl Edge {
             stroke_color: None,
             stroke_width: None,
             fill_color: None,
-            line_end_target: Some("none".to_string()),
+            line_end_target: None,
             line_end_source: None,
             end_fill_target: false,
             end_fill_source: false,
             end_size: None,
             start_size: None,
-            rounded: 0,
+            rounded: None,
             opacity: None,
+            line_join: None,
+            start_cap: None,
+            end_cap: None,
+            text_format: TextFormat::new(),
             geometry: Geometry::new(),
         }
     }
+
+    /// A plain line, without an arrow and with sharp corners, e.g. for the
+    /// wires of a schematic
+    pub fn new_wire(id: Option<String>) -> Self {
+        Self {
+            line_end_target: Some("none".to_string()),
+            rounded: Some(0),
+            ..Self::new(id)
+        }
+    }
+
     pub fn base(&self) -> &XMLBase {
         self.base.base()
     }
@@ -151,6 +256,8 @@ impl Edge {
         self.line_end_target.as_ref()
     }
 
+    /// `None` omits `endArrow` from the style, draw.io then draws a
+    /// `classic` arrow, `Some("none")` draws no arrow
     pub fn set_line_end_target(&mut self, end: Option<String>) {
         self.line_end_target = end;
     }
@@ -169,6 +276,7 @@ impl Edge {
 
     pub fn set_end_fill_target(&mut self, fill: bool) {
         self.end_fill_target = fill;
+        self.base.remove_style_property("endFill");
     }
 
     pub fn end_fill_source(&self) -> bool {
@@ -177,6 +285,7 @@ impl Edge {
 
     pub fn set_end_fill_source(&mut self, fill: bool) {
         self.end_fill_source = fill;
+        self.base.remove_style_property("startFill");
     }
 
     pub fn end_size(&self) -> Option<i32> {
@@ -203,56 +312,245 @@ impl Edge {
         self.opacity = opacity;
     }
 
-    /// Internal helper to apply a single style property
+    pub fn line_join(&self) -> Option<LineJoin> {
+        self.line_join
+    }
+
+    pub fn set_line_join(&mut self, line_join: Option<LineJoin>) {
+        self.line_join = line_join;
+    }
+
+    pub fn start_cap(&self) -> Option<LineCap> {
+        self.start_cap
+    }
+
+    pub fn set_start_cap(&mut self, start_cap: Option<LineCap>) {
+        self.start_cap = start_cap;
+        self.base.remove_style_property("startCap");
+    }
+
+    pub fn end_cap(&self) -> Option<LineCap> {
+        self.end_cap
+    }
+
+    pub fn set_end_cap(&mut self, end_cap: Option<LineCap>) {
+        self.end_cap = end_cap;
+        self.base.remove_style_property("endCap");
+    }
+
+    pub fn font_color(&self) -> Option<&String> {
+        self.text_format.font_color()
+    }
+
+    pub fn set_font_color(&mut self, color: Option<String>) {
+        self.text_format.set_font_color(color);
+    }
+
+    pub fn font_size(&self) -> Option<f64> {
+        self.text_format.font_size()
+    }
+
+    pub fn set_font_size(&mut self, size: Option<f64>) {
+        self.text_format.set_font_size(size);
+    }
+
+    pub fn font_family(&self) -> Option<&String> {
+        self.text_format.font_family()
+    }
+
+    /// Set the font family, see [`TextFormat::set_font_family`]
+    pub fn set_font_family(&mut self, family: Option<String>) -> DrawrsResult<()> {
+        self.text_format.set_font_family(family)?;
+        // The source belongs to the former family
+        self.base.remove_style_property("fontSource");
+        Ok(())
+    }
+
+    pub fn label_background_color(&self) -> Option<&String> {
+        self.text_format.label_background_color()
+    }
+
+    /// Box behind the label text, e.g. to hide the edge under it
+    pub fn set_label_background_color(&mut self, color: Option<String>) {
+        self.text_format.set_label_background_color(color);
+    }
+
+    /// Alignment of the label around its anchor on the edge, only written
+    /// when it differs from the centered default
+    pub fn justify(&self) -> &Justify {
+        self.text_format.justify()
+    }
+
+    pub fn set_justify(&mut self, justify: Justify) {
+        self.text_format.set_justify(justify);
+    }
+
+    pub fn justify_mut(&mut self) -> &mut Justify {
+        self.text_format.justify_mut()
+    }
+
+    /// Apply a single style property, unsupported keys and supported ones
+    /// whose value fails to parse are kept verbatim
     pub fn apply_style_property(&mut self, key: &str, value: &str) {
+        if self.apply_supported_style_property(key, value) {
+            // A value kept verbatim before is replaced by the parsed one
+            self.base.remove_style_property(key);
+        } else {
+            self.base
+                .apply_style_property(Cow::Owned(key.to_string()), Cow::Owned(value.to_string()));
+        }
+    }
+
+    fn apply_supported_style_property(&mut self, key: &str, value: &str) -> bool {
         match key {
             "strokeColor" => self.stroke_color = Some(value.to_string()),
-            "strokeWidth" => {
-                if let Ok(sw) = value.parse::<f64>() {
-                    self.stroke_width = Some(sw);
-                }
-            }
+            "strokeWidth" => match value.parse::<f64>() {
+                Ok(sw) => self.stroke_width = Some(sw),
+                Err(_) => return false,
+            },
             "fillColor" => self.fill_color = Some(value.to_string()),
             "endArrow" => self.line_end_target = Some(value.to_string()),
             "startArrow" => self.line_end_source = Some(value.to_string()),
-            "endFill" => {
-                if let Ok(ef) = value.parse::<i32>() {
-                    self.end_fill_target = ef != 0;
-                }
-            }
-            "startFill" => {
-                if let Ok(sf) = value.parse::<i32>() {
-                    self.end_fill_source = sf != 0;
+            "endFill" => match value.parse::<i32>() {
+                Ok(0) => {
+                    // An explicit 0, i.e. an open arrow, is not the default of
+                    // every arrow and is kept verbatim
+                    self.end_fill_target = false;
+                    return false;
                 }
-            }
-            "endSize" => {
-                if let Ok(es) = value.parse::<i32>() {
-                    self.end_size = Some(es);
+                Ok(_) => self.end_fill_target = true,
+                Err(_) => return false,
+            },
+            "startFill" => match value.parse::<i32>() {
+                Ok(0) => {
+                    self.end_fill_source = false;
+                    return false;
                 }
-            }
-            "startSize" => {
-                if let Ok(ss) = value.parse::<i32>() {
-                    self.start_size = Some(ss);
+                Ok(_) => self.end_fill_source = true,
+                Err(_) => return false,
+            },
+            "endSize" => match value.parse::<i32>() {
+                Ok(es) => self.end_size = Some(es),
+                Err(_) => return false,
+            },
+            "startSize" => match value.parse::<i32>() {
+                Ok(ss) => self.start_size = Some(ss),
+                Err(_) => return false,
+            },
+            "opacity" => match value.parse::<i32>() {
+                Ok(op) => self.opacity = Some(op),
+                Err(_) => return false,
+            },
+            "jointStyle" => match value.parse() {
+                Ok(line_join) => self.line_join = Some(line_join),
+                Err(_) => return false,
+            },
+            "startCap" => match value.parse() {
+                Ok(start_cap) => self.start_cap = Some(start_cap),
+                Err(_) => return false,
+            },
+            "endCap" => match value.parse() {
+                Ok(end_cap) => self.end_cap = Some(end_cap),
+                Err(_) => return false,
+            },
+            "rounded" => match value.parse::<i32>() {
+                Ok(r) => self.rounded = Some(if r != 0 { 1 } else { 0 }),
+                Err(_) => return false,
+            },
+            "fontColor" => self.text_format.set_font_color(Some(value.to_string())),
+            "fontSize" => match value.parse::<f64>() {
+                Ok(fs) => self.text_format.set_font_size(Some(fs)),
+                Err(_) => return false,
+            },
+            "fontFamily" => {
+                if self
+                    .text_format
+                    .set_font_family(Some(value.to_string()))
+                    .is_err()
+                {
+                    return false;
                 }
             }
-            "opacity" => {
-                if let Ok(op) = value.parse::<i32>() {
-                    self.opacity = Some(op);
+            "labelBackgroundColor" => self
+                .text_format
+                .set_label_background_color(Some(value.to_string())),
+            "align" => {
+                self.text_format.justify_mut().x = match value {
+                    "left" => JustifyX::Left,
+                    "right" => JustifyX::Right,
+                    _ => JustifyX::Center,
                 }
             }
-            "rounded" => {
-                if let Ok(r) = value.parse::<i32>() {
-                    self.rounded = if r != 0 { 1 } else { 0 };
+            "verticalAlign" => {
+                self.text_format.justify_mut().y = match value {
+                    "top" => JustifyY::Top,
+                    "bottom" => JustifyY::Bottom,
+                    _ => JustifyY::Middle,
                 }
             }
-            _ => {
-                // For unsupported style properties, store in base
-                self.base.apply_style_property(
-                    Cow::Owned(key.to_string()),
-                    Cow::Owned(value.to_string()),
-                );
+            _ => return false,
+        }
+        true
+    }
+
+    /// Value of a style key without a dedicated field, e.g. `dashed`
+    pub fn style_property(&self, key: &str) -> Option<&str> {
+        self.base
+            .unsupported_style_properties()
+            .get(key)
+            .map(|v| v.as_ref())
+    }
+
+    /// Drop a style key, whether it has a dedicated field or is kept
+    /// verbatim. Without `endArrow` draw.io draws its default arrow.
+    pub fn remove_style_property(&mut self, key: &str) {
+        match key {
+            "strokeColor" => self.stroke_color = None,
+            "strokeWidth" => self.stroke_width = None,
+            "fillColor" => self.fill_color = None,
+            "endArrow" => self.line_end_target = None,
+            "startArrow" => self.line_end_source = None,
+            "endFill" => self.end_fill_target = false,
+            "startFill" => self.end_fill_source = false,
+            "endSize" => self.end_size = None,
+            "startSize" => self.start_size = None,
+            "opacity" => self.opacity = None,
+            "jointStyle" => self.line_join = None,
+            "startCap" => self.start_cap = None,
+            "endCap" => self.end_cap = None,
+            "rounded" => self.rounded = None,
+            "fontColor" => self.text_format.set_font_color(None),
+            "fontSize" => self.text_format.set_font_size(None),
+            "fontFamily" => {
+                let _ = self.text_format.set_font_family(None);
             }
+            "labelBackgroundColor" => self.text_format.set_label_background_color(None),
+            "align" => self.text_format.justify_mut().x = JustifyX::Center,
+            "verticalAlign" => self.text_format.justify_mut().y = JustifyY::Middle,
+            _ => {}
         }
+        self.base.remove_style_property(key);
+    }
+
+    /// Reset the style to the one of [`Self::new`], the style class, the
+    /// geometry and the cell attributes are kept
+    pub fn clear_style(&mut self) {
+        self.stroke_color = None;
+        self.stroke_width = None;
+        self.fill_color = None;
+        self.line_end_target = None;
+        self.line_end_source = None;
+        self.end_fill_target = false;
+        self.end_fill_source = false;
+        self.end_size = None;
+        self.start_size = None;
+        self.rounded = None;
+        self.opacity = None;
+        self.line_join = None;
+        self.start_cap = None;
+        self.end_cap = None;
+        self.text_format = TextFormat::new();
+        self.base.unsupported_style_properties_mut().clear();
     }
 
     pub fn set_page(&mut self, page: Option<String>) {
@@ -267,10 +565,63 @@ impl Edge {
         self.base_mut().tag = tag;
     }
 
+    /// Share the style of a class, written before the own style of the
+    /// cell, see [`StyleClass`]
+    pub fn set_style_class(&mut self, class: Option<StyleClass>) {
+        self.base_mut().style_class = class;
+    }
+
+    pub fn style_class(&self) -> Option<&StyleClass> {
+        self.base().style_class.as_ref()
+    }
+
     pub fn tag(&self) -> Option<&String> {
         self.base().tag.as_ref()
     }
 
+    /// Entries of the space separated tags
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.base().tags()
+    }
+
+    /// Add space separated tags, skipping the ones already present
+    pub fn add_tag(&mut self, tag: &str) {
+        self.base_mut().add_tag(tag);
+    }
+
+    /// Hover text, emitted on the `UserObject` wrapper
+    pub fn set_tooltip(&mut self, tooltip: Option<String>) {
+        self.base_mut().tooltip = tooltip;
+    }
+
+    pub fn tooltip(&self) -> Option<&String> {
+        self.base().tooltip.as_ref()
+    }
+
+    /// Hyperlink followed on click, emitted on the `UserObject` wrapper
+    pub fn set_link(&mut self, link: Option<String>) {
+        self.base_mut().link = link;
+    }
+
+    pub fn link(&self) -> Option<&String> {
+        self.base().link.as_ref()
+    }
+
+    /// Custom data attribute, emitted on the `UserObject` wrapper unless the
+    /// key is one of [`XMLBase::RESERVED_ATTRIBUTES`]. A key that is not an
+    /// XML name is rejected, see [`XMLBase::is_xml_name`].
+    pub fn set_attribute(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> DrawrsResult<()> {
+        self.base_mut().set_attribute(key.into(), value.into())
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&String> {
+        self.base().attributes.get(key)
+    }
+
     // Parse style string and set all relevant properties
     pub fn parse_and_set_style(&mut self, style_str: &str) {
         // Parse style string into key-value pairs
@@ -291,21 +642,27 @@ impl Edge {
     }
 
     pub fn xml(&self) -> EdgeXml<'_> {
-        EdgeXml(self)
+        self.xml_in(XmlContext::default())
+    }
+
+    pub(crate) fn xml_in<'a>(&'a self, context: XmlContext<'a>) -> EdgeXml<'a> {
+        EdgeXml(self, context)
     }
 }
 
-pub struct EdgeXml<'a>(&'a Edge);
+pub struct EdgeXml<'a>(&'a Edge, XmlContext<'a>);
 
 impl<'a> fmt::Display for EdgeXml<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let style = EdgeStyleFormatter(self.0);
-        let parent_id = self.0.base.xml_parent_id();
-        let value = self
-            .0
-            .label()
-            .map(|l| crate::xml_base::XMLBase::xml_ify(l))
-            .unwrap_or_else(|| "".to_string());
+        let style = StyleAttr(
+            self.0.base().style_class.as_ref(),
+            self.1,
+            EdgeStyleFormatter(self.0, self.1.font_sources),
+        );
+        let parent_id = XmlAttr(self.0.base.xml_parent().map_or("1", String::as_str));
+        let cell_attrs = self.0.base.base().mx_cell_attributes();
+        let cell_children = self.0.base.base().mx_cell_children();
+        let value = XmlAttr(self.0.label().map_or("", String::as_str));
 
         // Only include source and target if they are set and not "1"
         let source_id = self.0.source.as_ref().map(|s| s.as_str());
@@ -317,42 +674,46 @@ impl<'a> fmt::Display for EdgeXml<'a> {
             && source_id.unwrap() != "1"
             && target_id.unwrap() != "1";
 
-        if let Some(tag) = self.0.tag() {
-            // When tag is present, wrap in UserObject and mxCell should not have id attribute
+        if let Some(attrs) = self.0.base.base().user_object_attributes() {
+            // When tag or tooltip is present, wrap in UserObject and mxCell should not have id attribute
             // Value (label) goes to UserObject label, not mxCell value
             if has_source_target {
                 write!(
                     f,
-                    r#"<UserObject label="{}" tags="{}" id="{}">
-        <mxCell style="{}" edge="{}" parent="{}" source="{}" target="{}">
-          {}
+                    r#"<UserObject label="{}"{} id="{}">
+        <mxCell style="{}" edge="{}" parent="{}" source="{}" target="{}"{}>
+          {}{}
         </mxCell>
         </UserObject>"#,
                     value,
-                    crate::xml_base::XMLBase::xml_ify(tag),
-                    self.0.base.id(),
+                    attrs,
+                    XmlAttr(self.0.base.id()),
                     style,
                     self.0.edge,
                     parent_id,
-                    source_id.unwrap(),
-                    target_id.unwrap(),
-                    self.0.geometry.xml()
+                    XmlAttr(source_id.unwrap()),
+                    XmlAttr(target_id.unwrap()),
+                    cell_attrs,
+                    self.0.geometry.xml(),
+                    cell_children
                 )
             } else {
                 write!(
                     f,
-                    r#"<UserObject label="{}" tags="{}" id="{}">
-        <mxCell style="{}" edge="{}" parent="{}">
-          {}
+                    r#"<UserObject label="{}"{} id="{}">
+        <mxCell style="{}" edge="{}" parent="{}"{}>
+          {}{}
         </mxCell>
         </UserObject>"#,
                     value,
-                    crate::xml_base::XMLBase::xml_ify(tag),
-                    self.0.base.id(),
+                    attrs,
+                    XmlAttr(self.0.base.id()),
                     style,
                     self.0.edge,
                     parent_id,
-                    self.0.geometry.xml()
+                    cell_attrs,
+                    self.0.geometry.xml(),
+                    cell_children
                 )
             }
         } else {
@@ -360,37 +721,42 @@ impl<'a> fmt::Display for EdgeXml<'a> {
             if has_source_target {
                 write!(
                     f,
-                    r#"<mxCell id="{}" value="{}" style="{}" edge="{}" parent="{}" source="{}" target="{}">
-          {}
+                    r#"<mxCell id="{}" value="{}" style="{}" edge="{}" parent="{}" source="{}" target="{}"{}>
+          {}{}
         </mxCell>"#,
-                    self.0.base.id(),
+                    XmlAttr(self.0.base.id()),
                     value,
                     style,
                     self.0.edge,
                     parent_id,
-                    source_id.unwrap(),
-                    target_id.unwrap(),
-                    self.0.geometry.xml()
+                    XmlAttr(source_id.unwrap()),
+                    XmlAttr(target_id.unwrap()),
+                    cell_attrs,
+                    self.0.geometry.xml(),
+                    cell_children
                 )
             } else {
                 write!(
                     f,
-                    r#"<mxCell id="{}" value="{}" style="{}" edge="{}" parent="{}">
-          {}
+                    r#"<mxCell id="{}" value="{}" style="{}" edge="{}" parent="{}"{}>
+          {}{}
         </mxCell>"#,
-                    self.0.base.id(),
+                    XmlAttr(self.0.base.id()),
                     value,
                     style,
                     self.0.edge,
                     parent_id,
-                    self.0.geometry.xml()
+                    cell_attrs,
+                    self.0.geometry.xml(),
+                    cell_children
                 )
             }
         }
     }
 }
 
-struct EdgeStyleFormatter<'a>(&'a Edge);
+// With the custom fonts of the file being written, if any
+struct EdgeStyleFormatter<'a>(&'a Edge, Option<&'a IndexMap<String, String>>);
 
 impl<'a> fmt::Display for EdgeStyleFormatter<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -399,7 +765,7 @@ impl<'a> fmt::Display for EdgeStyleFormatter<'a> {
             write!(f, "strokeColor={};", sc)?;
         }
         if let Some(sw) = self.0.stroke_width {
-            write!(f, "strokeWidth={};", sw)?;
+            write!(f, "strokeWidth={};", XmlFloat(sw))?;
         }
         if let Some(ref fc) = self.0.fill_color {
             write!(f, "fillColor={};", fc)?;
@@ -425,21 +791,52 @@ impl<'a> fmt::Display for EdgeStyleFormatter<'a> {
         if let Some(op) = self.0.opacity {
             write!(f, "opacity={};", op)?;
         }
-        // Always include rounded
-        write!(f, "rounded={};", self.0.rounded)?;
-
-        // Add unsupported properties
-        for (key, value) in self.0.base.unsupported_style_properties() {
-            write!(f, "{}={};", key, value)?;
+        if let Some(lj) = self.0.line_join {
+            write!(f, "jointStyle={};", lj.to_str())?;
+        }
+        if let Some(sc) = self.0.start_cap {
+            write!(f, "startCap={};", sc.to_str())?;
+        }
+        if let Some(ec) = self.0.end_cap {
+            write!(f, "endCap={};", ec.to_str())?;
+        }
+        if let Some(fc) = self.0.text_format.font_color() {
+            write!(f, "fontColor={};", fc)?;
+        }
+        if let Some(fs) = self.0.text_format.font_size() {
+            write!(f, "fontSize={};", XmlFloat(fs))?;
+        }
+        if let Some(ff) = self.0.text_format.font_family() {
+            write!(f, "fontFamily={};", ff)?;
+            if self
+                .0
+                .base
+                .unsupported_style_properties()
+                .get("fontSource")
+                .is_none()
+                && let Some(source) = font_source(self.1, ff)
+            {
+                write!(f, "fontSource={};", source)?;
+            }
+        }
+        if let Some(bg) = self.0.text_format.label_background_color() {
+            write!(f, "labelBackgroundColor={};", bg)?;
+        }
+        if *self.0.text_format.justify() != Justify::default() {
+            f.write_str(&self.0.text_format.justify().format())?;
+        }
+        if let Some(rounded) = self.0.rounded {
+            write!(f, "rounded={};", rounded)?;
         }
 
-        Ok(())
+        // Add unsupported properties
+        self.0.base.write_unsupported_style(f)
     }
 }
 
 impl Edge {
     pub fn style(&self) -> impl fmt::Display + '_ {
-        EdgeStyleFormatter(self)
+        EdgeStyleFormatter(self, None)
     }
 }
 
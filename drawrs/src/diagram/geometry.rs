@@ -1,17 +1,50 @@
 use crate::BoundingBox;
 use crate::transform::FlipRotation;
+use crate::xml_base::{XmlAttr, XmlFloat};
 use std::fmt;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct Geometry {
+    #[cfg_attr(feature = "serde", serde(flatten))]
     bounding_box: BoundingBox,
+    #[cfg_attr(feature = "serde", serde(skip, default = "as_attribute_default"))]
     as_attribute: String,
     // For edge geometry
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     relative: Option<bool>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     source_point: Option<[f64; 2]>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     target_point: Option<[f64; 2]>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
     intermediate_points: Vec<[f64; 2]>,
+    #[cfg_attr(feature = "serde", serde(default, flatten))]
     flip_rotation: FlipRotation,
+    /// Unrecognized child elements kept from parsing (e.g. `<mxRectangle>`), as raw XML
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    preserved_elements: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+fn as_attribute_default() -> String {
+    "geometry".to_string()
 }
 
 impl Geometry {
@@ -24,6 +57,7 @@ impl Geometry {
             target_point: None,
             intermediate_points: Vec::new(),
             flip_rotation: FlipRotation::default(),
+            preserved_elements: Vec::new(),
         }
     }
 
@@ -36,6 +70,7 @@ impl Geometry {
             target_point: None,
             intermediate_points: Vec::new(),
             flip_rotation: FlipRotation::default(),
+            preserved_elements: Vec::new(),
         }
     }
 
@@ -51,6 +86,15 @@ impl Geometry {
         self.intermediate_points.push(point);
     }
 
+    pub fn preserved_elements(&self) -> &[String] {
+        &self.preserved_elements
+    }
+
+    /// Keep a raw child element, re-emitted inside `<mxGeometry>`
+    pub fn add_preserved_element(&mut self, element: String) {
+        self.preserved_elements.push(element);
+    }
+
     pub fn flip_rotation(&self) -> &FlipRotation {
         &self.flip_rotation
     }
@@ -68,19 +112,19 @@ impl Geometry {
     }
 
     pub fn x(&self) -> f64 {
-        self.bounding_box.min_x
+        self.bounding_box.min_x()
     }
 
     pub fn y(&self) -> f64 {
-        self.bounding_box.min_y
+        self.bounding_box.min_y()
     }
 
     pub fn width(&self) -> f64 {
-        self.bounding_box.width
+        self.bounding_box.width()
     }
 
     pub fn height(&self) -> f64 {
-        self.bounding_box.height
+        self.bounding_box.height()
     }
 
     pub fn bounding_box(&self) -> BoundingBox {
@@ -135,39 +179,19 @@ impl Geometry {
     }
 
     pub fn set_x(&mut self, x: f64) {
-        self.bounding_box = BoundingBox::new(
-            x,
-            self.bounding_box.min_y,
-            self.bounding_box.width,
-            self.bounding_box.height,
-        );
+        self.bounding_box.set_min_x(x);
     }
 
     pub fn set_y(&mut self, y: f64) {
-        self.bounding_box = BoundingBox::new(
-            self.bounding_box.min_x,
-            y,
-            self.bounding_box.width,
-            self.bounding_box.height,
-        );
+        self.bounding_box.set_min_y(y);
     }
 
     pub fn set_width(&mut self, width: f64) {
-        self.bounding_box = BoundingBox::new(
-            self.bounding_box.min_x,
-            self.bounding_box.min_y,
-            width,
-            self.bounding_box.height,
-        );
+        self.bounding_box.set_width(width);
     }
 
     pub fn set_height(&mut self, height: f64) {
-        self.bounding_box = BoundingBox::new(
-            self.bounding_box.min_x,
-            self.bounding_box.min_y,
-            self.bounding_box.width,
-            height,
-        );
+        self.bounding_box.set_height(height);
     }
 
     pub fn xml(&self) -> GeometryXml<'_> {
@@ -190,14 +214,14 @@ impl<'a> fmt::Display for GeometryXml<'a> {
                 r#"<mxGeometry width="{}" height="{}" relative="{}" as="{}">
             <mxPoint x="{}" y="{}" as="sourcePoint" />
             <mxPoint x="{}" y="{}" as="targetPoint" />"#,
-                self.0.bounding_box.width,
-                self.0.bounding_box.height,
+                XmlFloat(self.0.bounding_box.width()),
+                XmlFloat(self.0.bounding_box.height()),
                 if relative { "1" } else { "0" },
-                self.0.as_attribute,
-                source[0],
-                source[1],
-                target[0],
-                target[1]
+                XmlAttr(&self.0.as_attribute),
+                XmlFloat(source[0]),
+                XmlFloat(source[1]),
+                XmlFloat(target[0]),
+                XmlFloat(target[1])
             )?;
 
             // Add intermediate points if any
@@ -207,24 +231,36 @@ impl<'a> fmt::Display for GeometryXml<'a> {
                     write!(
                         f,
                         "\n              <mxPoint x=\"{}\" y=\"{}\" />",
-                        point[0], point[1]
+                        XmlFloat(point[0]),
+                        XmlFloat(point[1])
                     )?;
                 }
                 write!(f, "\n            </Array>")?;
             }
+            for element in &self.0.preserved_elements {
+                write!(f, "\n            {element}")?;
+            }
 
             write!(f, "\n          </mxGeometry>")
         } else {
             // Regular geometry
             write!(
                 f,
-                r#"<mxGeometry x="{}" y="{}" width="{}" height="{}" as="{}" />"#,
-                self.0.bounding_box.min_x,
-                self.0.bounding_box.min_y,
-                self.0.bounding_box.width,
-                self.0.bounding_box.height,
+                r#"<mxGeometry x="{}" y="{}" width="{}" height="{}" as="{}""#,
+                XmlFloat(self.0.bounding_box.min_x()),
+                XmlFloat(self.0.bounding_box.min_y()),
+                XmlFloat(self.0.bounding_box.width()),
+                XmlFloat(self.0.bounding_box.height()),
                 self.0.as_attribute
-            )
+            )?;
+            if self.0.preserved_elements.is_empty() {
+                return write!(f, " />");
+            }
+            write!(f, ">")?;
+            for element in &self.0.preserved_elements {
+                write!(f, "\n            {element}")?;
+            }
+            write!(f, "\n          </mxGeometry>")
         }
     }
 }
@@ -1,11 +1,17 @@
 use crate::XMLBase;
 use crate::diagram::base_diagram::DiagramBase;
 use crate::diagram::geometry::Geometry;
-use crate::diagram::text_format::{Justify, TextFormat};
+use crate::diagram::text_format::{Justify, JustifyX, JustifyY, TextFormat};
+use crate::error::DrawrsResult;
+use crate::transform::FlipRotation;
+use crate::xml_base::{StyleAttr, StyleClass, XmlAttr, XmlContext, XmlFloat, font_source};
+use indexmap::IndexMap;
 use std::borrow::Cow;
 use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum FillStyle {
     Hatch,
     Solid,
@@ -40,23 +46,151 @@ impl FillStyle {
     }
 }
 
+/// Shape of the stroke at corners, the `jointStyle` style key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum LineJoin {
+    Round,
+    Miter,
+    Bevel,
+}
+
+impl std::str::FromStr for LineJoin {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "round" => Ok(LineJoin::Round),
+            "miter" => Ok(LineJoin::Miter),
+            "bevel" => Ok(LineJoin::Bevel),
+            _ => Err(()),
+        }
+    }
+}
+
+impl LineJoin {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            LineJoin::Round => "round",
+            LineJoin::Miter => "miter",
+            LineJoin::Bevel => "bevel",
+        }
+    }
+}
+
+/// Shape of the stroke at the ends of a line, the `startCap` and `endCap`
+/// style keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum LineCap {
+    Flat,
+    Round,
+    Square,
+}
+
+impl std::str::FromStr for LineCap {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "flat" => Ok(LineCap::Flat),
+            "round" => Ok(LineCap::Round),
+            "square" => Ok(LineCap::Square),
+            _ => Err(()),
+        }
+    }
+}
+
+impl LineCap {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            LineCap::Flat => "flat",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }
+    }
+}
+
+/// With the `serde` feature, the style keys are named after their mxGraph
+/// counterparts (`fillColor`, `strokeWidth`, ...) and the cell attributes
+/// (`id`, `parent`, `tags`, `value`, `style` extras) are flattened in.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct Object {
+    #[cfg_attr(feature = "serde", serde(flatten))]
     base: DiagramBase,
     geometry: Geometry,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     white_space: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     rounded: Option<bool>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     fill_color: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     stroke_color: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     stroke_width: Option<f64>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     opacity: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "jointStyle",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    line_join: Option<LineJoin>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     fill_style: Option<FillStyle>,
     // glass: Option<bool>,
     // shadow: Option<bool>,
     // line_pattern: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
     text_format: TextFormat,
+    #[cfg_attr(feature = "serde", serde(skip, default = "vertex_default"))]
     vertex: i32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
     poly_coords: Vec<[f64; 2]>, // Polygon coordinates as normalized (0-1) points relative to bounding box
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    connection_points: Vec<[f64; 2]>, // Connection points as normalized (0-1) points relative to bounding box
+}
+
+#[cfg(feature = "serde")]
+fn vertex_default() -> i32 {
+    1
 }
 
 impl Object {
@@ -75,6 +209,7 @@ impl Object {
             stroke_color: None,
             stroke_width: None,
             opacity: None,
+            line_join: None,
             fill_style: None,
             // glass: None,
             // shadow: None,
@@ -82,6 +217,7 @@ impl Object {
             text_format: TextFormat::new(),
             vertex: 1,
             poly_coords: Vec::new(),
+            connection_points: Vec::new(),
         }
     }
     pub fn points_mut(&mut self) -> impl Iterator<Item = &mut [f64; 2]> {
@@ -142,6 +278,10 @@ impl Object {
         self.geometry.set_height(height);
     }
 
+    pub fn geometry_ref(&self) -> &Geometry {
+        &self.geometry
+    }
+
     pub fn geometry_mut(&mut self) -> &mut Geometry {
         &mut self.geometry
     }
@@ -168,6 +308,7 @@ impl Object {
 
     pub fn set_stroke_width(&mut self, width: Option<f64>) {
         self.stroke_width = width;
+        self.base.remove_style_property("strokeWidth");
     }
 
     pub fn poly_coords(&self) -> &Vec<[f64; 2]> {
@@ -182,6 +323,19 @@ impl Object {
         self.poly_coords = coords;
     }
 
+    /// Points draw.io snaps new edges to, normalized (0-1) to the box of the
+    /// unrotated shape, written as the `points` style. A `points` style with
+    /// other perimeter flags or offsets, e.g. `[[0,0.5,1,2,0]]`, is not parsed
+    /// and kept verbatim, see [`Self::style_property`].
+    pub fn connection_points(&self) -> &[[f64; 2]] {
+        &self.connection_points
+    }
+
+    pub fn set_connection_points(&mut self, points: Vec<[f64; 2]>) {
+        self.connection_points = points;
+        self.base.remove_style_property("points");
+    }
+
     pub fn font_color(&self) -> Option<&String> {
         self.text_format.font_color()
     }
@@ -196,14 +350,32 @@ impl Object {
 
     pub fn set_font_size(&mut self, size: Option<f64>) {
         self.text_format.set_font_size(size);
+        self.base.remove_style_property("fontSize");
     }
 
     pub fn font_family(&self) -> Option<&String> {
         self.text_format.font_family()
     }
 
-    pub fn set_font_family(&mut self, family: Option<String>) {
-        self.text_format.set_font_family(family);
+    /// Set the font family, see [`TextFormat::set_font_family`]
+    pub fn set_font_family(&mut self, family: Option<String>) -> DrawrsResult<()> {
+        self.text_format.set_font_family(family)?;
+        // The source belongs to the former family
+        self.base.remove_style_property("fontSource");
+        Ok(())
+    }
+
+    pub fn label_background_color(&self) -> Option<&String> {
+        self.text_format.label_background_color()
+    }
+
+    /// Box behind the label text, e.g. to hide the lines it crosses
+    pub fn set_label_background_color(&mut self, color: Option<String>) {
+        self.text_format.set_label_background_color(color);
+    }
+
+    pub fn justify(&self) -> &Justify {
+        self.text_format.justify()
     }
 
     pub fn set_justify(&mut self, justify: Justify) {
@@ -213,12 +385,45 @@ impl Object {
         self.text_format.justify_mut()
     }
 
+    pub fn white_space(&self) -> Option<&String> {
+        self.white_space.as_ref()
+    }
+
+    /// `wrap` breaks the text at the object width
+    pub fn set_white_space(&mut self, white_space: Option<String>) {
+        self.white_space = white_space;
+    }
+
+    /// Interpret the value as HTML, e.g. `<br>` line breaks
+    pub fn set_html(&mut self, html: bool) {
+        self.base.apply_style_property(
+            Cow::Borrowed("html"),
+            Cow::Borrowed(if html { "1" } else { "0" }),
+        );
+    }
+
+    /// Let draw.io fit the box to the text whenever the value or the font
+    /// changes, the box written with the object is only the initial one
+    pub fn set_autosize(&mut self, autosize: bool) {
+        if autosize {
+            self.base
+                .apply_style_property(Cow::Borrowed("autosize"), Cow::Borrowed("1"));
+        } else {
+            self.base.remove_style_property("autosize");
+        }
+    }
+
+    pub fn autosize(&self) -> bool {
+        self.style_property("autosize") == Some("1")
+    }
+
     pub fn rounded(&self) -> Option<bool> {
         self.rounded
     }
 
     pub fn set_rounded(&mut self, rounded: Option<bool>) {
         self.rounded = rounded;
+        self.base.remove_style_property("rounded");
     }
 
     pub fn opacity(&self) -> Option<i32> {
@@ -227,6 +432,16 @@ impl Object {
 
     pub fn set_opacity(&mut self, opacity: Option<i32>) {
         self.opacity = opacity;
+        self.base.remove_style_property("opacity");
+    }
+
+    pub fn line_join(&self) -> Option<LineJoin> {
+        self.line_join
+    }
+
+    pub fn set_line_join(&mut self, line_join: Option<LineJoin>) {
+        self.line_join = line_join;
+        self.base.remove_style_property("jointStyle");
     }
 
     pub fn fill_style(&self) -> Option<&FillStyle> {
@@ -235,86 +450,106 @@ impl Object {
 
     pub fn set_fill_style(&mut self, fill_style: Option<FillStyle>) {
         self.fill_style = fill_style;
+        self.base.remove_style_property("fillStyle");
     }
 
-    /// Internal helper to apply a single style property
+    /// Internal helper to apply a single style property, unsupported keys
+    /// and supported ones whose value fails to parse are kept verbatim
     pub fn apply_style_property(&mut self, key: &str, value: &str) {
+        if self.apply_supported_style_property(key, value) {
+            // A value kept verbatim before is replaced by the parsed one
+            self.base.remove_style_property(key);
+        } else {
+            self.base
+                .apply_style_property(Cow::Owned(key.to_string()), Cow::Owned(value.to_string()));
+        }
+    }
+
+    fn apply_supported_style_property(&mut self, key: &str, value: &str) -> bool {
         match key {
             "whiteSpace" => self.white_space = Some(value.to_string()),
             "fillColor" => self.fill_color = Some(value.to_string()),
             "strokeColor" => self.stroke_color = Some(value.to_string()),
-            "strokeWidth" => {
-                if let Ok(sw) = value.parse::<f64>() {
-                    self.stroke_width = Some(sw);
-                }
-            }
-            "opacity" => {
-                if let Ok(op) = value.parse::<i32>() {
-                    self.opacity = Some(op);
-                }
-            }
-            "rounded" => {
-                if let Ok(r) = value.parse::<i32>() {
-                    self.rounded = Some(r != 0);
-                }
-            }
-            "fillStyle" => {
-                if let Some(fill_style) = FillStyle::from_str(value) {
-                    self.fill_style = Some(fill_style);
-                }
-            }
+            "strokeWidth" => match value.parse::<f64>() {
+                Ok(sw) => self.stroke_width = Some(sw),
+                Err(_) => return false,
+            },
+            "opacity" => match value.parse::<i32>() {
+                Ok(op) => self.opacity = Some(op),
+                Err(_) => return false,
+            },
+            "jointStyle" => match value.parse() {
+                Ok(line_join) => self.line_join = Some(line_join),
+                Err(_) => return false,
+            },
+            "rounded" => match value.parse::<i32>() {
+                Ok(r) => self.rounded = Some(r != 0),
+                Err(_) => return false,
+            },
+            "fillStyle" => match FillStyle::from_str(value) {
+                Some(fill_style) => self.fill_style = Some(fill_style),
+                None => return false,
+            },
             "fontColor" => self.text_format.set_font_color(Some(value.to_string())),
-            "fontSize" => {
-                if let Ok(fs) = value.parse::<f64>() {
-                    self.text_format.set_font_size(Some(fs));
+            "fontSize" => match value.parse::<f64>() {
+                Ok(fs) => self.text_format.set_font_size(Some(fs)),
+                Err(_) => return false,
+            },
+            "fontFamily" => {
+                if self
+                    .text_format
+                    .set_font_family(Some(value.to_string()))
+                    .is_err()
+                {
+                    return false;
                 }
             }
-            "fontFamily" => self.text_format.set_font_family(Some(value.to_string())),
+            "labelBackgroundColor" => self
+                .text_format
+                .set_label_background_color(Some(value.to_string())),
             "align" | "verticalAlign" => {
                 // Handle justify - need to parse both align and verticalAlign together
                 // This is handled in parse_and_set_style
             }
-            "polyCoords" => {
-                if let Ok(coords) = Self::parse_poly_coords(value) {
-                    self.poly_coords = coords;
-                }
-            }
-            "flipH" => {
-                if let Ok(flip_h) = value.parse::<usize>() {
-                    self.geometry_mut()
-                        .flip_rotation_mut()
-                        .set_flip_h(Some(flip_h));
-                }
-            }
-            "flipV" => {
-                if let Ok(flip_v) = value.parse::<usize>() {
-                    self.geometry_mut()
-                        .flip_rotation_mut()
-                        .set_flip_v(Some(flip_v));
-                }
-            }
-            "rotation" => {
-                if let Ok(rotation) = value.parse::<f64>() {
-                    self.geometry_mut()
-                        .flip_rotation_mut()
-                        .set_rotation(Some(rotation));
-                }
-            }
-            "legacyAnchorPoints" => {
-                if let Ok(legacy_anchor_points) = value.parse::<usize>() {
-                    self.geometry_mut()
-                        .flip_rotation_mut()
-                        .set_legacy_anchor_points(Some(legacy_anchor_points));
-                }
-            }
-            _ => {
-                // For unsupported style properties, store in base
-                self.base.apply_style_property(
-                    Cow::Owned(key.to_string()),
-                    Cow::Owned(value.to_string()),
-                );
-            }
+            "polyCoords" => match Self::parse_poly_coords(value) {
+                Ok(coords) => self.poly_coords = coords,
+                Err(()) => return false,
+            },
+            "points" => match Self::parse_connection_points(value) {
+                Ok(points) => self.connection_points = points,
+                Err(()) => return false,
+            },
+            "flipH" => match value.parse::<usize>() {
+                Ok(flip_h) => self
+                    .geometry_mut()
+                    .flip_rotation_mut()
+                    .set_flip_h(Some(flip_h)),
+                Err(_) => return false,
+            },
+            "flipV" => match value.parse::<usize>() {
+                Ok(flip_v) => self
+                    .geometry_mut()
+                    .flip_rotation_mut()
+                    .set_flip_v(Some(flip_v)),
+                Err(_) => return false,
+            },
+            "rotation" => match value.parse::<f64>() {
+                Ok(rotation) => self
+                    .geometry_mut()
+                    .flip_rotation_mut()
+                    .set_rotation(Some(rotation)),
+                Err(_) => return false,
+            },
+            "legacyAnchorPoints" => match value.parse::<usize>() {
+                Ok(legacy_anchor_points) => self
+                    .geometry_mut()
+                    .flip_rotation_mut()
+                    .set_legacy_anchor_points(Some(legacy_anchor_points)),
+                Err(_) => return false,
+            },
+            _ => return false,
         }
+        true
     }
 
     // Parse polyCoords string format: [[x1,y1],[x2,y2],...]
@@ -372,6 +607,29 @@ impl Object {
         }
     }
 
+    // Parse points string format: [[x1,y1,0],...] as written by `xml`, the
+    // points with a perimeter flag or offsets, `[x,y,perimeter,dx,dy]`, or
+    // without the flag are errors so that the value is kept verbatim
+    fn parse_connection_points(value: &str) -> Result<Vec<[f64; 2]>, ()> {
+        let inner = value
+            .trim()
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .ok_or(())?;
+        inner
+            .split(']')
+            .map(|point| point.trim_start_matches([',', '[', ' ']))
+            .filter(|point| !point.trim().is_empty())
+            .map(|point| {
+                let values: Vec<_> = point.split(',').map(|v| v.trim().parse::<f64>()).collect();
+                match values[..] {
+                    [Ok(x), Ok(y), Ok(0.0)] => Ok([x, y]),
+                    _ => Err(()),
+                }
+            })
+            .collect()
+    }
+
     // Parse style string and set all relevant properties
     pub fn parse_and_set_style(&mut self, style_str: &str) {
         // Parse justify from the entire style string first, but only if it contains align or verticalAlign
@@ -406,27 +664,190 @@ impl Object {
         self.base_mut().tag = tag;
     }
 
+    /// Share the style of a class, written before the own style of the
+    /// cell, see [`StyleClass`]
+    pub fn set_style_class(&mut self, class: Option<StyleClass>) {
+        self.base_mut().style_class = class;
+    }
+
+    pub fn style_class(&self) -> Option<&StyleClass> {
+        self.base().style_class.as_ref()
+    }
+
     pub fn style(&self) -> impl fmt::Display + '_ {
-        ObjectStyleFormatter(self)
+        ObjectStyleFormatter(self, None)
     }
 
     pub fn tag(&self) -> Option<&String> {
         self.base().tag.as_ref()
     }
 
+    /// Entries of the space separated tags
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.base().tags()
+    }
+
+    /// Add space separated tags, skipping the ones already present
+    pub fn add_tag(&mut self, tag: &str) {
+        self.base_mut().add_tag(tag);
+    }
+
+    /// Hover text, emitted on the `UserObject` wrapper
+    pub fn set_tooltip(&mut self, tooltip: Option<String>) {
+        self.base_mut().tooltip = tooltip;
+    }
+
+    pub fn tooltip(&self) -> Option<&String> {
+        self.base().tooltip.as_ref()
+    }
+
+    /// Hyperlink followed on click, emitted on the `UserObject` wrapper
+    pub fn set_link(&mut self, link: Option<String>) {
+        self.base_mut().link = link;
+    }
+
+    pub fn link(&self) -> Option<&String> {
+        self.base().link.as_ref()
+    }
+
+    /// Custom data attribute, emitted on the `UserObject` wrapper unless the
+    /// key is one of [`XMLBase::RESERVED_ATTRIBUTES`]. A key that is not an
+    /// XML name is rejected, see [`XMLBase::is_xml_name`].
+    pub fn set_attribute(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> DrawrsResult<()> {
+        self.base_mut().set_attribute(key.into(), value.into())
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&String> {
+        self.base().attributes.get(key)
+    }
+
+    /// Value of a style key without a dedicated field, e.g. `shape`
+    pub fn style_property(&self, key: &str) -> Option<&str> {
+        self.base
+            .unsupported_style_properties()
+            .get(key)
+            .map(|v| v.as_ref())
+    }
+
+    /// Drop a style key, whether it has a dedicated field or is kept
+    /// verbatim. `align` and `verticalAlign` are always written and go back
+    /// to `center` and `middle`.
+    pub fn remove_style_property(&mut self, key: &str) {
+        match key {
+            "whiteSpace" => self.white_space = None,
+            "fillColor" => self.fill_color = None,
+            "strokeColor" => self.stroke_color = None,
+            "strokeWidth" => self.stroke_width = None,
+            "opacity" => self.opacity = None,
+            "jointStyle" => self.line_join = None,
+            "rounded" => self.rounded = None,
+            "fillStyle" => self.fill_style = None,
+            "fontColor" => self.text_format.set_font_color(None),
+            "fontSize" => self.text_format.set_font_size(None),
+            "fontFamily" => {
+                let _ = self.text_format.set_font_family(None);
+            }
+            "labelBackgroundColor" => self.text_format.set_label_background_color(None),
+            "align" => self.text_format.justify_mut().x = JustifyX::Center,
+            "verticalAlign" => self.text_format.justify_mut().y = JustifyY::Middle,
+            "polyCoords" => self.poly_coords.clear(),
+            "points" => self.connection_points.clear(),
+            "flipH" => self.geometry.flip_rotation_mut().set_flip_h(None),
+            "flipV" => self.geometry.flip_rotation_mut().set_flip_v(None),
+            "rotation" => self.geometry.flip_rotation_mut().set_rotation(None),
+            "legacyAnchorPoints" => self
+                .geometry
+                .flip_rotation_mut()
+                .set_legacy_anchor_points(None),
+            _ => {}
+        }
+        self.base.remove_style_property(key);
+    }
+
+    /// Reset the style to the one of [`Self::new`], the style class, the
+    /// geometry box and the cell attributes are kept
+    pub fn clear_style(&mut self) {
+        self.white_space = None;
+        self.rounded = None;
+        self.fill_color = None;
+        self.stroke_color = None;
+        self.stroke_width = None;
+        self.opacity = None;
+        self.line_join = None;
+        self.fill_style = None;
+        self.text_format = TextFormat::new();
+        self.poly_coords.clear();
+        self.connection_points.clear();
+        self.geometry.set_flip_rotation(FlipRotation::default());
+        self.base.unsupported_style_properties_mut().clear();
+    }
+
     pub fn set_shape(&mut self, shape: String) {
         self.base
             .apply_style_property(Cow::Borrowed("shape"), Cow::Owned(shape));
     }
 
+    /// Draw the object as a custom stencil
+    pub fn set_stencil(&mut self, stencil: &crate::utils::Stencil) {
+        self.set_shape(stencil.style_value());
+    }
+
     pub fn set_start_angle(&mut self, angle: f64) {
-        self.base
-            .apply_style_property(Cow::Borrowed("startAngle"), Cow::Owned(angle.to_string()));
+        self.base.apply_style_property(
+            Cow::Borrowed("startAngle"),
+            Cow::Owned(XmlFloat(angle).to_string()),
+        );
     }
 
     pub fn set_end_angle(&mut self, angle: f64) {
-        self.base
-            .apply_style_property(Cow::Borrowed("endAngle"), Cow::Owned(angle.to_string()));
+        self.base.apply_style_property(
+            Cow::Borrowed("endAngle"),
+            Cow::Owned(XmlFloat(angle).to_string()),
+        );
+    }
+
+    /// Padding between the text and the bounds on all sides
+    pub fn set_spacing(&mut self, spacing: f64) {
+        self.base.apply_style_property(
+            Cow::Borrowed("spacing"),
+            Cow::Owned(XmlFloat(spacing).to_string()),
+        );
+    }
+
+    /// Extra top padding, added to [`Self::set_spacing`]
+    pub fn set_spacing_top(&mut self, spacing: f64) {
+        self.base.apply_style_property(
+            Cow::Borrowed("spacingTop"),
+            Cow::Owned(XmlFloat(spacing).to_string()),
+        );
+    }
+
+    /// Extra bottom padding, added to [`Self::set_spacing`]
+    pub fn set_spacing_bottom(&mut self, spacing: f64) {
+        self.base.apply_style_property(
+            Cow::Borrowed("spacingBottom"),
+            Cow::Owned(XmlFloat(spacing).to_string()),
+        );
+    }
+
+    /// Extra left padding, added to [`Self::set_spacing`]
+    pub fn set_spacing_left(&mut self, spacing: f64) {
+        self.base.apply_style_property(
+            Cow::Borrowed("spacingLeft"),
+            Cow::Owned(XmlFloat(spacing).to_string()),
+        );
+    }
+
+    /// Extra right padding, added to [`Self::set_spacing`]
+    pub fn set_spacing_right(&mut self, spacing: f64) {
+        self.base.apply_style_property(
+            Cow::Borrowed("spacingRight"),
+            Cow::Owned(XmlFloat(spacing).to_string()),
+        );
     }
 
     pub fn set_aspect(&mut self, aspect: String) {
@@ -459,56 +880,70 @@ impl Object {
     }
 
     pub fn xml(&self) -> ObjectXml<'_> {
-        ObjectXml(self)
+        self.xml_in(XmlContext::default())
+    }
+
+    pub(crate) fn xml_in<'a>(&'a self, context: XmlContext<'a>) -> ObjectXml<'a> {
+        ObjectXml(self, context)
     }
 }
 
-pub struct ObjectXml<'a>(&'a Object);
+pub struct ObjectXml<'a>(&'a Object, XmlContext<'a>);
 
 impl<'a> fmt::Display for ObjectXml<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let style = ObjectStyleFormatter(self.0);
-        let parent_id = self.0.base.xml_parent_id();
-        let value =
-            crate::xml_base::XMLBase::xml_ify(self.0.value().map(|s| s.as_str()).unwrap_or(""));
+        let style = StyleAttr(
+            self.0.base().style_class.as_ref(),
+            self.1,
+            ObjectStyleFormatter(self.0, self.1.font_sources),
+        );
+        let parent_id = XmlAttr(self.0.base.xml_parent().map_or("1", String::as_str));
+        let cell_attrs = self.0.base.base().mx_cell_attributes();
+        let cell_children = self.0.base.base().mx_cell_children();
+        let value = XmlAttr(self.0.value().map_or("", String::as_str));
 
-        if let Some(tag) = self.0.tag() {
-            // When tag is present, wrap in UserObject and mxCell should not have id attribute
+        if let Some(attrs) = self.0.base.base().user_object_attributes() {
+            // When tag or tooltip is present, wrap in UserObject and mxCell should not have id attribute
             // Value goes to UserObject label, not mxCell value
             write!(
                 f,
-                r#"<UserObject label="{}" tags="{}" id="{}">
-        <mxCell style="{}" vertex="{}" parent="{}">
-          {}
+                r#"<UserObject label="{}"{} id="{}">
+        <mxCell style="{}" vertex="{}" parent="{}"{}>
+          {}{}
         </mxCell>
         </UserObject>"#,
                 value,
-                crate::xml_base::XMLBase::xml_ify(tag),
-                self.0.base.id(),
+                attrs,
+                XmlAttr(self.0.base.id()),
                 style,
                 self.0.vertex,
                 parent_id,
-                self.0.geometry.xml()
+                cell_attrs,
+                self.0.geometry.xml(),
+                cell_children
             )
         } else {
             // Normal case: mxCell with id
             write!(
                 f,
-                r#"<mxCell id="{}" value="{}" style="{}" vertex="{}" parent="{}">
-          {}
+                r#"<mxCell id="{}" value="{}" style="{}" vertex="{}" parent="{}"{}>
+          {}{}
         </mxCell>"#,
-                self.0.base.id(),
+                XmlAttr(self.0.base.id()),
                 value,
                 style,
                 self.0.vertex,
                 parent_id,
-                self.0.geometry.xml()
+                cell_attrs,
+                self.0.geometry.xml(),
+                cell_children
             )
         }
     }
 }
 
-struct ObjectStyleFormatter<'a>(&'a Object);
+// The custom fonts of the file give the `fontSource` of the font family
+struct ObjectStyleFormatter<'a>(&'a Object, Option<&'a IndexMap<String, String>>);
 
 impl<'a> fmt::Display for ObjectStyleFormatter<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -523,11 +958,14 @@ impl<'a> fmt::Display for ObjectStyleFormatter<'a> {
             write!(f, "strokeColor={};", sc)?;
         }
         if let Some(sw) = self.0.stroke_width {
-            write!(f, "strokeWidth={};", sw)?;
+            write!(f, "strokeWidth={};", XmlFloat(sw))?;
         }
         if let Some(op) = self.0.opacity {
             write!(f, "opacity={};", op)?;
         }
+        if let Some(lj) = self.0.line_join {
+            write!(f, "jointStyle={};", lj.to_str())?;
+        }
         if let Some(rd) = self.0.rounded {
             write!(f, "rounded={};", if rd { "1" } else { "0" })?;
         }
@@ -538,10 +976,23 @@ impl<'a> fmt::Display for ObjectStyleFormatter<'a> {
             write!(f, "fontColor={};", fc)?;
         }
         if let Some(fs) = self.0.text_format.font_size() {
-            write!(f, "fontSize={};", fs)?;
+            write!(f, "fontSize={};", XmlFloat(fs))?;
         }
         if let Some(ff) = self.0.text_format.font_family() {
             write!(f, "fontFamily={};", ff)?;
+            if self
+                .0
+                .base
+                .unsupported_style_properties()
+                .get("fontSource")
+                .is_none()
+                && let Some(source) = font_source(self.1, ff)
+            {
+                write!(f, "fontSource={};", source)?;
+            }
+        }
+        if let Some(bg) = self.0.text_format.label_background_color() {
+            write!(f, "labelBackgroundColor={};", bg)?;
         }
 
         // Add justify properties (align and verticalAlign)
@@ -564,7 +1015,17 @@ impl<'a> fmt::Display for ObjectStyleFormatter<'a> {
                 if i > 0 {
                     write!(f, ",")?;
                 }
-                write!(f, "[{},{}]", p[0], p[1])?;
+                write!(f, "[{},{}]", XmlFloat(p[0]), XmlFloat(p[1]))?;
+            }
+            write!(f, "];")?;
+        }
+        if !self.0.connection_points.is_empty() {
+            write!(f, "points=[")?;
+            for (i, p) in self.0.connection_points.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "[{},{},0]", XmlFloat(p[0]), XmlFloat(p[1]))?;
             }
             write!(f, "];")?;
         }
@@ -575,14 +1036,13 @@ impl<'a> fmt::Display for ObjectStyleFormatter<'a> {
             write!(f, "flipV={};", flip_v)?;
         }
         if let Some(rotation) = self.0.geometry.flip_rotation().rotation() {
-            write!(f, "rotation={};", rotation)?;
+            write!(f, "rotation={};", XmlFloat(rotation))?;
         }
-        // Add unsupported properties
-        for (key, value) in self.0.base.unsupported_style_properties() {
-            write!(f, "{}={};", key, value)?;
+        if let Some(legacy) = self.0.geometry.flip_rotation().legacy_anchor_points() {
+            write!(f, "legacyAnchorPoints={};", legacy)?;
         }
-
-        Ok(())
+        // Add unsupported properties
+        self.0.base.write_unsupported_style(f)
     }
 }
 
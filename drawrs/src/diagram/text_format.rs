@@ -1,3 +1,4 @@
+use crate::error::{DrawrsError, DrawrsResult};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -63,6 +64,31 @@ impl<'de> Deserialize<'de> for Justify {
     }
 }
 
+// Same names as the custom serialization
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Justify {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Justify".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "enum": [
+                "upperLeft",
+                "upperCenter",
+                "upperRight",
+                "centerLeft",
+                "centerCenter",
+                "centerRight",
+                "lowerLeft",
+                "lowerCenter",
+                "lowerRight"
+            ]
+        })
+    }
+}
+
 impl Justify {
     pub fn new() -> Self {
         Self {
@@ -143,10 +169,17 @@ impl Default for Justify {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
 pub struct TextFormat {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     font_size: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     font_color: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     font_family: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    label_background_color: Option<String>,
     justify: Justify,
     // bold: bool,
     // italic: bool,
@@ -159,6 +192,7 @@ impl TextFormat {
             font_size: None,
             font_color: None,
             font_family: None,
+            label_background_color: None,
             justify: Justify::new(),
             // bold: false,
             // italic: false,
@@ -186,8 +220,26 @@ impl TextFormat {
         self.font_family.as_ref()
     }
 
-    pub fn set_font_family(&mut self, family: Option<String>) {
+    /// Set the CSS font family, e.g. a fallback list `"Verdana, DejaVu Sans,
+    /// sans-serif"`. A family with a `;` is rejected: it would end the style
+    /// entry, and draw.io reads no escape in the `fontFamily` value.
+    pub fn set_font_family(&mut self, family: Option<String>) -> DrawrsResult<()> {
+        if let Some(family) = family.as_ref().filter(|family| family.contains(';')) {
+            return Err(DrawrsError::InvalidValue(
+                "fontFamily".to_string(),
+                family.clone(),
+            ));
+        }
         self.font_family = family;
+        Ok(())
+    }
+
+    pub fn label_background_color(&self) -> Option<&String> {
+        self.label_background_color.as_ref()
+    }
+
+    pub fn set_label_background_color(&mut self, color: Option<String>) {
+        self.label_background_color = color;
     }
 
     pub fn justify(&self) -> &Justify {
@@ -56,6 +56,10 @@ impl BarChart {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn calculate_scale(&self) -> f64 {
         let max_value: f64 = self.data.values().fold(0.0f64, |acc: f64, &v| acc.max(v));
         if max_value == 0.0 {
@@ -78,15 +82,15 @@ impl BarChart {
                 .unwrap_or_else(|| "#66ccff".to_string());
 
             // Create bar
-            let mut bar = Object::new(None);
-            bar.set_position([
-                x_offset,
-                self.position[1] + self.max_bar_height - bar_height,
-            ]);
-            bar.set_width(self.bar_width);
-            bar.set_height(bar_height);
-            bar.set_fill_color(Some(color));
-            bar.set_stroke_color(Some("#000000".to_string()));
+            let bar = bar_object(
+                [
+                    x_offset,
+                    self.position[1] + self.max_bar_height - bar_height,
+                ],
+                self.bar_width,
+                bar_height,
+                color,
+            );
 
             // Create label
             let mut label_obj = Object::new(None);
@@ -122,3 +126,14 @@ impl BarChart {
         }
     }
 }
+
+/// Bar standing on the baseline, shared by the bar-based charts
+pub(crate) fn bar_object(position: [f64; 2], width: f64, height: f64, color: String) -> Object {
+    let mut bar = Object::new(None);
+    bar.set_position(position);
+    bar.set_width(width);
+    bar.set_height(height);
+    bar.set_fill_color(Some(color));
+    bar.set_stroke_color(Some("#000000".to_string()));
+    bar
+}
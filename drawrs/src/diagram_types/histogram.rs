@@ -0,0 +1,337 @@
+use crate::diagram::{Edge, Object};
+use crate::diagram_types::bar_chart::bar_object;
+use crate::error::{DrawrsError, DrawrsResult};
+use crate::page::DiagramObject;
+
+/// How samples are split into bins
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinSpec {
+    /// Fixed number of equal-width bins
+    Count(usize),
+    /// Fixed bin width
+    Width(f64),
+    /// Freedman–Diaconis rule, falling back to Sturges' rule when the IQR is zero
+    Auto,
+}
+
+pub struct Histogram {
+    samples: Vec<f64>,
+    bins: BinSpec,
+    edges: Vec<f64>,
+    counts: Vec<usize>,
+    position: [f64; 2],
+    bar_width: f64,
+    max_bar_height: f64,
+    bar_color: String,
+    show_mean: bool,
+    show_sigma: bool,
+    pub objects: Vec<DiagramObject>,
+}
+
+impl Histogram {
+    pub const DEFAULT_BAR_WIDTH: f64 = 30.0;
+    pub const DEFAULT_MAX_BAR_HEIGHT: f64 = 200.0;
+    pub const TICK_LABEL_WIDTH: f64 = 40.0;
+    pub const TICK_LABEL_HEIGHT: f64 = 20.0;
+    /// Upper bound on the number of bins, so a tiny width or an outlier
+    /// cannot allocate an unbounded bin table
+    pub const MAX_BINS: usize = 10_000;
+
+    pub fn new(samples: Vec<f64>, bins: BinSpec) -> DrawrsResult<Self> {
+        let (edges, counts) = Self::compute_bins(&samples, bins)?;
+        let mut histogram = Self {
+            samples,
+            bins,
+            edges,
+            counts,
+            position: [0.0, 0.0],
+            bar_width: Self::DEFAULT_BAR_WIDTH,
+            max_bar_height: Self::DEFAULT_MAX_BAR_HEIGHT,
+            bar_color: "#66ccff".to_string(),
+            show_mean: false,
+            show_sigma: false,
+            objects: Vec::new(),
+        };
+
+        histogram.build_chart();
+        Ok(histogram)
+    }
+
+    pub fn samples(&self) -> &[f64] {
+        &self.samples
+    }
+
+    pub fn bins(&self) -> BinSpec {
+        self.bins
+    }
+
+    /// Bin edges, one more than the number of bins
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Number of bins
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn position(&self) -> [f64; 2] {
+        self.position
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.samples.iter().sum::<f64>() / self.samples.len() as f64
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        let mean = self.mean();
+        let var = self
+            .samples
+            .iter()
+            .map(|x| (x - mean) * (x - mean))
+            .sum::<f64>()
+            / self.samples.len() as f64;
+        var.sqrt()
+    }
+
+    pub fn set_bar_color(&mut self, color: String) {
+        self.bar_color = color;
+        self.build_chart();
+    }
+
+    /// Draw a dashed vertical line at the mean
+    pub fn set_show_mean(&mut self, show: bool) {
+        self.show_mean = show;
+        self.build_chart();
+    }
+
+    /// Draw dashed vertical lines at mean ± σ
+    pub fn set_show_sigma(&mut self, show: bool) {
+        self.show_sigma = show;
+        self.build_chart();
+    }
+
+    pub fn update_data(&mut self, samples: Vec<f64>, bins: BinSpec) -> DrawrsResult<()> {
+        let (edges, counts) = Self::compute_bins(&samples, bins)?;
+        self.samples = samples;
+        self.bins = bins;
+        self.edges = edges;
+        self.counts = counts;
+        self.build_chart();
+        Ok(())
+    }
+
+    fn compute_bins(samples: &[f64], bins: BinSpec) -> DrawrsResult<(Vec<f64>, Vec<usize>)> {
+        if samples.is_empty() {
+            return Err(DrawrsError::EmptyData);
+        }
+        for (i, x) in samples.iter().enumerate() {
+            if !x.is_finite() {
+                return Err(DrawrsError::InvalidValue(
+                    format!("sample[{}]", i),
+                    x.to_string(),
+                ));
+            }
+        }
+        let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = max - min;
+        if range == 0.0 {
+            return Err(DrawrsError::DegenerateData(format!(
+                "all {} samples are equal to {}",
+                samples.len(),
+                min
+            )));
+        }
+
+        let (count, width) = match bins {
+            BinSpec::Count(count) => {
+                if count == 0 {
+                    return Err(DrawrsError::InvalidValue(
+                        "bins".to_string(),
+                        count.to_string(),
+                    ));
+                }
+                if count > Self::MAX_BINS {
+                    return Err(DrawrsError::InvalidValue(
+                        "bins".to_string(),
+                        format!("{count} exceeds the maximum of {}", Self::MAX_BINS),
+                    ));
+                }
+                (count, range / count as f64)
+            }
+            BinSpec::Width(width) => {
+                if !(width.is_finite() && width > 0.0) {
+                    return Err(DrawrsError::InvalidValue(
+                        "bin width".to_string(),
+                        width.to_string(),
+                    ));
+                }
+                (bin_count(range / width)?, width)
+            }
+            BinSpec::Auto => {
+                let mut sorted = samples.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+                let n = samples.len() as f64;
+                if iqr > 0.0 {
+                    let width = 2.0 * iqr / n.cbrt();
+                    (bin_count(range / width)?, width)
+                } else {
+                    let count = n.log2().ceil() as usize + 1;
+                    (count, range / count as f64)
+                }
+            }
+        };
+
+        let edges = (0..=count).map(|i| min + i as f64 * width).collect();
+        let mut counts = vec![0; count];
+        for x in samples {
+            // The last bin is closed on the right so `max` is counted
+            let idx = (((x - min) / width).floor() as usize).min(count - 1);
+            counts[idx] += 1;
+        }
+        Ok((edges, counts))
+    }
+
+    pub fn calculate_scale(&self) -> f64 {
+        let max_count = self.counts.iter().copied().max().unwrap_or(0);
+        if max_count == 0 {
+            return 1.0;
+        }
+        self.max_bar_height / max_count as f64
+    }
+
+    /// Horizontal page position of a sample value
+    fn value_x(&self, value: f64) -> f64 {
+        let first = self.edges[0];
+        let last = self.edges[self.edges.len() - 1];
+        self.position[0] + (value - first) / (last - first) * self.bar_width * self.len() as f64
+    }
+
+    fn build_chart(&mut self) {
+        self.objects.clear();
+        let scale = self.calculate_scale();
+        let baseline = self.position[1] + self.max_bar_height;
+
+        // Bars touch each other, histograms have no inter-bar spacing
+        for (i, count) in self.counts.iter().enumerate() {
+            let bar_height = *count as f64 * scale;
+            let bar = bar_object(
+                [
+                    self.position[0] + i as f64 * self.bar_width,
+                    baseline - bar_height,
+                ],
+                self.bar_width,
+                bar_height,
+                self.bar_color.clone(),
+            );
+            self.objects.push(bar.into());
+        }
+
+        // Tick labels centered on the bin edges
+        for (i, edge) in self.edges.iter().enumerate() {
+            let mut tick = Object::new(None);
+            tick.set_value(format_tick(*edge));
+            tick.set_position([
+                self.position[0] + i as f64 * self.bar_width - Self::TICK_LABEL_WIDTH / 2.0,
+                baseline + 5.0,
+            ]);
+            tick.set_width(Self::TICK_LABEL_WIDTH);
+            tick.set_height(Self::TICK_LABEL_HEIGHT);
+            tick.set_font_size(Some(10.0));
+            self.objects.push(tick.into());
+        }
+
+        let mean = self.mean();
+        let std_dev = self.std_dev();
+        if self.show_mean {
+            self.push_marker(mean, "μ");
+        }
+        if self.show_sigma {
+            self.push_marker(mean - std_dev, "μ-σ");
+            self.push_marker(mean + std_dev, "μ+σ");
+        }
+    }
+
+    fn push_marker(&mut self, value: f64, text: &str) {
+        let x = self.value_x(value);
+        let top = self.position[1];
+        let baseline = top + self.max_bar_height;
+
+        let mut line = Edge::new_wire(None);
+        line.set_stroke_color(Some("#FF0000".to_string()));
+        line.apply_style_property("dashed", "1");
+        line.geometry().set_relative(Some(true));
+        line.geometry().set_source_point(Some([x, baseline]));
+        line.geometry().set_target_point(Some([x, top]));
+        self.objects.push(line.into());
+
+        let mut label = Object::new(None);
+        label.set_value(text.to_string());
+        label.set_position([
+            x - Self::TICK_LABEL_WIDTH / 2.0,
+            top - Self::TICK_LABEL_HEIGHT,
+        ]);
+        label.set_width(Self::TICK_LABEL_WIDTH);
+        label.set_height(Self::TICK_LABEL_HEIGHT);
+        label.set_font_size(Some(10.0));
+        label.set_font_color(Some("#FF0000".to_string()));
+        self.objects.push(label.into());
+    }
+
+    pub fn move_to(&mut self, position: [f64; 2]) {
+        let delta_x = position[0] - self.position[0];
+        let delta_y = position[1] - self.position[1];
+        self.position = position;
+
+        for obj in &mut self.objects {
+            for point in obj.mut_points() {
+                point[0] += delta_x;
+                point[1] += delta_y;
+            }
+            if let Some((bbox, _)) = obj.mut_box() {
+                bbox.translate(delta_x, delta_y);
+            }
+        }
+    }
+}
+
+/// Number of bins covering `bins` bin widths, capped at [`Histogram::MAX_BINS`]
+fn bin_count(bins: f64) -> DrawrsResult<usize> {
+    let count = bins.ceil().max(1.0);
+    if count > Histogram::MAX_BINS as f64 {
+        return Err(DrawrsError::InvalidValue(
+            "bins".to_string(),
+            format!("{count} exceeds the maximum of {}", Histogram::MAX_BINS),
+        ));
+    }
+    Ok(count as usize)
+}
+
+/// Quantile of sorted data with linear interpolation
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+fn format_tick(value: f64) -> String {
+    let s = format!("{:.3}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
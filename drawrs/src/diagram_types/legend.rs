@@ -1,22 +1,39 @@
 use crate::diagram::Object;
+use crate::diagram::text_format::{Justify, JustifyX, JustifyY};
 use crate::error::{DrawrsError, DrawrsResult};
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
+const SWATCH_SIZE: f64 = 20.0;
+const LABEL_WIDTH: f64 = 100.0;
+const ROW_HEIGHT: f64 = 25.0;
+const BORDER_PADDING: f64 = 5.0;
+const LEFT: Justify = Justify {
+    x: JustifyX::Left,
+    y: JustifyY::Middle,
+};
+
+/// Color swatches with their labels, one row per entry of the mapping in
+/// its iteration order
 pub struct Legend {
-    mapping: HashMap<String, String>,
+    mapping: IndexMap<String, String>,
     position: [f64; 2],
+    title: Option<String>,
+    border: bool,
     objects: Vec<Object>,
 }
 
 impl Legend {
-    pub fn new(mapping: HashMap<String, String>) -> DrawrsResult<Self> {
+    pub fn new(mapping: impl IntoIterator<Item = (String, String)>) -> DrawrsResult<Self> {
+        let mapping: IndexMap<String, String> = mapping.into_iter().collect();
         if mapping.is_empty() {
             return Err(DrawrsError::EmptyMapping);
         }
 
         let mut legend = Self {
-            mapping: mapping.clone(),
+            mapping,
             position: [0.0, 0.0],
+            title: None,
+            border: false,
             objects: Vec::new(),
         };
 
@@ -26,28 +43,57 @@ impl Legend {
 
     fn build_legend(&mut self) {
         self.objects.clear();
-        let mut y_offset = self.position[1];
+        let padding = if self.border { BORDER_PADDING } else { 0.0 };
+        let x = self.position[0] + padding;
+        let mut y_offset = self.position[1] + padding;
+
+        if let Some(title) = &self.title {
+            let mut title_obj = Object::new(None);
+            title_obj.set_value(title.clone());
+            title_obj.set_position([x, y_offset]);
+            title_obj.set_width(SWATCH_SIZE + 5.0 + LABEL_WIDTH);
+            title_obj.set_height(SWATCH_SIZE);
+            title_obj.set_fill_color(Some("none".to_string()));
+            title_obj.set_stroke_color(Some("none".to_string()));
+            title_obj.set_justify(LEFT);
+            title_obj.apply_style_property("fontStyle", "1");
+            self.objects.push(title_obj);
+            y_offset += ROW_HEIGHT;
+        }
 
         for (label, color) in &self.mapping {
             // Create color box
             let mut color_box = Object::new(None);
-            color_box.set_position([self.position[0], y_offset]);
-            color_box.set_width(20.0);
-            color_box.set_height(20.0);
+            color_box.set_position([x, y_offset]);
+            color_box.set_width(SWATCH_SIZE);
+            color_box.set_height(SWATCH_SIZE);
             color_box.set_fill_color(Some(color.clone()));
             color_box.set_stroke_color(Some("#000000".to_string()));
 
             // Create label
             let mut label_obj = Object::new(None);
             label_obj.set_value(label.clone());
-            label_obj.set_position([self.position[0] + 25.0, y_offset]);
-            label_obj.set_width(100.0);
-            label_obj.set_height(20.0);
+            label_obj.set_position([x + SWATCH_SIZE + 5.0, y_offset]);
+            label_obj.set_width(LABEL_WIDTH);
+            label_obj.set_height(SWATCH_SIZE);
+            label_obj.set_justify(LEFT);
 
             self.objects.push(color_box);
             self.objects.push(label_obj);
 
-            y_offset += 25.0;
+            y_offset += ROW_HEIGHT;
+        }
+
+        if self.border {
+            // Drawn first so the rows stay on top of it
+            let [width, height] = self.size();
+            let mut border = Object::new(None);
+            border.set_position(self.position);
+            border.set_width(width);
+            border.set_height(height);
+            border.set_fill_color(Some("#FFFFFF".to_string()));
+            border.set_stroke_color(Some("#000000".to_string()));
+            self.objects.insert(0, border);
         }
     }
 
@@ -59,6 +105,37 @@ impl Legend {
         self.position
     }
 
+    /// Width and height of the legend, including the title and the border
+    pub fn size(&self) -> [f64; 2] {
+        let rows = self.mapping.len() + usize::from(self.title.is_some());
+        let padding = if self.border { BORDER_PADDING } else { 0.0 };
+        [
+            SWATCH_SIZE + 5.0 + LABEL_WIDTH + 2.0 * padding,
+            rows as f64 * ROW_HEIGHT - (ROW_HEIGHT - SWATCH_SIZE) + 2.0 * padding,
+        ]
+    }
+
+    /// Objects of the legend, the border first
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    pub fn into_objects(self) -> Vec<Object> {
+        self.objects
+    }
+
+    /// Bold title row above the entries
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+        self.build_legend();
+    }
+
+    /// White box with a black outline behind the legend
+    pub fn set_border(&mut self, border: bool) {
+        self.border = border;
+        self.build_legend();
+    }
+
     pub fn move_to(&mut self, position: [f64; 2]) {
         let delta_x = position[0] - self.position[0];
         let delta_y = position[1] - self.position[1];
@@ -70,7 +147,11 @@ impl Legend {
         }
     }
 
-    pub fn update_mapping(&mut self, mapping: HashMap<String, String>) -> DrawrsResult<()> {
+    pub fn update_mapping(
+        &mut self,
+        mapping: impl IntoIterator<Item = (String, String)>,
+    ) -> DrawrsResult<()> {
+        let mapping: IndexMap<String, String> = mapping.into_iter().collect();
         if mapping.is_empty() {
             return Err(DrawrsError::EmptyMapping);
         }
@@ -1,9 +1,13 @@
 pub mod bar_chart;
 pub mod binary_tree;
+pub mod histogram;
 pub mod legend;
 pub mod pie_chart;
+pub mod waveform;
 
 pub use bar_chart::BarChart;
 pub use binary_tree::{BinaryNodeObject, BinaryTreeDiagram};
+pub use histogram::{BinSpec, Histogram};
 pub use legend::Legend;
 pub use pie_chart::PieChart;
+pub use waveform::{Level, Waveform};
@@ -0,0 +1,350 @@
+use crate::diagram::text_format::{Justify, JustifyX};
+use crate::diagram::{Edge, FillStyle, Object};
+use crate::error::{DrawrsError, DrawrsResult};
+use crate::page::DiagramObject;
+use crate::transform::BoundingBox;
+use crate::xml_base::XMLBase;
+
+/// Logic level of a signal during one time step
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    High,
+    Low,
+    /// High impedance, drawn as a gray line at mid level
+    Z,
+    /// Unknown, drawn as a hatched box
+    X,
+    /// Bus value, drawn as a hexagon-edged box with the value as label
+    Data(String),
+}
+
+impl Level {
+    fn is_binary(&self) -> bool {
+        matches!(self, Level::High | Level::Low)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum SignalKind {
+    Levels(Vec<Level>),
+    /// Expanded to the length of the longest signal when building
+    Clock {
+        period_steps: usize,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct Signal {
+    name: String,
+    kind: SignalKind,
+}
+
+/// Timing diagram of digital signals.
+///
+/// All objects are children of one group cell, so their geometry is relative
+/// to the group and moving the waveform only moves the group.
+pub struct Waveform {
+    signals: Vec<Signal>,
+    position: [f64; 2],
+    step_width: f64,
+    lane_height: f64,
+    lane_spacing: f64,
+    label_width: f64,
+    container: XMLBase,
+    objects: Vec<DiagramObject>,
+}
+
+impl Waveform {
+    pub const DEFAULT_STEP_WIDTH: f64 = 40.0;
+    pub const DEFAULT_LANE_HEIGHT: f64 = 20.0;
+    pub const DEFAULT_LANE_SPACING: f64 = 10.0;
+    pub const DEFAULT_LABEL_WIDTH: f64 = 80.0;
+    pub const Z_COLOR: &'static str = "#808080";
+    pub const X_COLOR: &'static str = "#FF0000";
+
+    pub fn new() -> Self {
+        let mut container = XMLBase::new(None);
+        container.xml_class = "mxCell".to_string();
+        let mut waveform = Self {
+            signals: Vec::new(),
+            position: [0.0, 0.0],
+            step_width: Self::DEFAULT_STEP_WIDTH,
+            lane_height: Self::DEFAULT_LANE_HEIGHT,
+            lane_spacing: Self::DEFAULT_LANE_SPACING,
+            label_width: Self::DEFAULT_LABEL_WIDTH,
+            container,
+            objects: Vec::new(),
+        };
+        waveform.build_waveform();
+        waveform
+    }
+
+    pub fn add_signal(&mut self, name: impl Into<String>, levels: Vec<Level>) -> DrawrsResult<()> {
+        if levels.is_empty() {
+            return Err(DrawrsError::EmptyData);
+        }
+        self.signals.push(Signal {
+            name: name.into(),
+            kind: SignalKind::Levels(levels),
+        });
+        self.build_waveform();
+        Ok(())
+    }
+
+    /// Add a clock which is high for the first half of each period.
+    /// The clock spans the longest signal of the waveform.
+    pub fn add_clock(&mut self, name: impl Into<String>, period_steps: usize) -> DrawrsResult<()> {
+        let name = name.into();
+        if period_steps < 2 {
+            return Err(DrawrsError::InvalidValue(name, period_steps.to_string()));
+        }
+        self.signals.push(Signal {
+            name,
+            kind: SignalKind::Clock { period_steps },
+        });
+        self.build_waveform();
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signals.is_empty()
+    }
+
+    /// Number of time steps, i.e. the length of the longest signal
+    pub fn steps(&self) -> usize {
+        self.signals
+            .iter()
+            .map(|signal| match &signal.kind {
+                SignalKind::Levels(levels) => levels.len(),
+                SignalKind::Clock { period_steps } => *period_steps,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn position(&self) -> [f64; 2] {
+        self.position
+    }
+
+    pub fn step_width(&self) -> f64 {
+        self.step_width
+    }
+
+    pub fn set_step_width(&mut self, step_width: f64) {
+        self.step_width = step_width;
+        self.build_waveform();
+    }
+
+    pub fn lane_height(&self) -> f64 {
+        self.lane_height
+    }
+
+    pub fn set_lane_height(&mut self, lane_height: f64) {
+        self.lane_height = lane_height;
+        self.build_waveform();
+    }
+
+    pub fn label_width(&self) -> f64 {
+        self.label_width
+    }
+
+    pub fn set_label_width(&mut self, label_width: f64) {
+        self.label_width = label_width;
+        self.build_waveform();
+    }
+
+    /// Id of the group cell containing all the waveform objects
+    pub fn container_id(&self) -> &str {
+        &self.container.id
+    }
+
+    /// Parent of the group cell, e.g. an annotate layer
+    pub fn set_xml_parent(&mut self, parent: Option<String>) {
+        self.container.xml_parent = parent.clone();
+        if let Some(DiagramObject::XmlBase(container)) = self.objects.first_mut() {
+            container.xml_parent = parent;
+        }
+    }
+
+    /// The group cell followed by its children
+    pub fn objects(&self) -> &[DiagramObject] {
+        &self.objects
+    }
+
+    pub fn into_objects(self) -> Vec<DiagramObject> {
+        self.objects
+    }
+
+    pub fn move_to(&mut self, position: [f64; 2]) {
+        self.position = position;
+        let bbox = self.container.group_geometry.as_mut().unwrap();
+        bbox.set_min_x(position[0]);
+        bbox.set_min_y(position[1]);
+        if let Some(DiagramObject::XmlBase(container)) = self.objects.first_mut() {
+            container.group_geometry = self.container.group_geometry;
+        }
+    }
+
+    fn expand_levels(kind: &SignalKind, steps: usize) -> Vec<Level> {
+        match kind {
+            SignalKind::Levels(levels) => levels.clone(),
+            SignalKind::Clock { period_steps } => (0..steps.max(*period_steps))
+                .map(|step| {
+                    if step % period_steps < period_steps.div_ceil(2) {
+                        Level::High
+                    } else {
+                        Level::Low
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn build_waveform(&mut self) {
+        self.objects.clear();
+        let steps = self.steps();
+        let lanes = self.signals.len();
+        let height = if lanes == 0 {
+            0.0
+        } else {
+            lanes as f64 * self.lane_height + (lanes - 1) as f64 * self.lane_spacing
+        };
+        self.container.group_geometry = Some(BoundingBox::new(
+            self.position[0],
+            self.position[1],
+            self.label_width + steps as f64 * self.step_width,
+            height,
+        ));
+        let mut children: Vec<DiagramObject> = Vec::new();
+        for (i, signal) in self.signals.iter().enumerate() {
+            let top = i as f64 * (self.lane_height + self.lane_spacing);
+
+            let mut label = Object::new(None);
+            label.set_value(signal.name.clone());
+            label.set_position([0.0, top]);
+            label.set_width(self.label_width);
+            label.set_height(self.lane_height);
+            label.set_justify(Justify::new().with_x(JustifyX::Left));
+            children.push(label.into());
+
+            let levels = Self::expand_levels(&signal.kind, steps);
+            let mut start = 0;
+            while start < levels.len() {
+                // Binary levels are joined into one step polyline,
+                // other levels run while the value is unchanged
+                let end = if levels[start].is_binary() {
+                    start
+                        + levels[start..]
+                            .iter()
+                            .take_while(|level| level.is_binary())
+                            .count()
+                } else {
+                    start
+                        + levels[start..]
+                            .iter()
+                            .take_while(|level| **level == levels[start])
+                            .count()
+                };
+                let object = match &levels[start] {
+                    Level::High | Level::Low => self.step_edge(&levels[start..end], start, top),
+                    Level::Z => self.z_edge(start, end, top),
+                    Level::X => self.x_box(start, end, top),
+                    Level::Data(value) => self.data_box(value, start, end, top),
+                };
+                children.push(object);
+                start = end;
+            }
+        }
+
+        self.objects
+            .push(DiagramObject::XmlBase(self.container.clone()));
+        for mut child in children {
+            child.set_xml_parent(Some(self.container.id.clone()));
+            self.objects.push(child);
+        }
+    }
+
+    fn step_x(&self, step: usize) -> f64 {
+        self.label_width + step as f64 * self.step_width
+    }
+
+    fn level_y(&self, level: &Level, top: f64) -> f64 {
+        match level {
+            Level::High => top,
+            Level::Low => top + self.lane_height,
+            _ => top + self.lane_height / 2.0,
+        }
+    }
+
+    fn new_edge(&self, color: &str) -> Edge {
+        let mut edge = Edge::new_wire(None);
+        edge.set_stroke_color(Some(color.to_string()));
+        edge.geometry().set_relative(Some(true));
+        edge
+    }
+
+    fn step_edge(&self, levels: &[Level], first_step: usize, top: f64) -> DiagramObject {
+        let mut edge = self.new_edge("#000000");
+        let mut y = self.level_y(&levels[0], top);
+        edge.geometry()
+            .set_source_point(Some([self.step_x(first_step), y]));
+        for (offset, pair) in levels.windows(2).enumerate() {
+            if pair[0] != pair[1] {
+                let x = self.step_x(first_step + offset + 1);
+                let next_y = self.level_y(&pair[1], top);
+                edge.geometry().add_intermediate_point([x, y]);
+                edge.geometry().add_intermediate_point([x, next_y]);
+                y = next_y;
+            }
+        }
+        edge.geometry()
+            .set_target_point(Some([self.step_x(first_step + levels.len()), y]));
+        edge.into()
+    }
+
+    fn z_edge(&self, start: usize, end: usize, top: f64) -> DiagramObject {
+        let mut edge = self.new_edge(Self::Z_COLOR);
+        let y = self.level_y(&Level::Z, top);
+        edge.geometry()
+            .set_source_point(Some([self.step_x(start), y]));
+        edge.geometry()
+            .set_target_point(Some([self.step_x(end), y]));
+        edge.into()
+    }
+
+    fn lane_box(&self, start: usize, end: usize, top: f64) -> Object {
+        let mut obj = Object::new(None);
+        obj.set_position([self.step_x(start), top]);
+        obj.set_width((end - start) as f64 * self.step_width);
+        obj.set_height(self.lane_height);
+        obj.set_stroke_color(Some("#000000".to_string()));
+        obj
+    }
+
+    fn x_box(&self, start: usize, end: usize, top: f64) -> DiagramObject {
+        let mut obj = self.lane_box(start, end, top);
+        obj.set_fill_color(Some(Self::X_COLOR.to_string()));
+        obj.set_fill_style(Some(FillStyle::Hatch));
+        obj.into()
+    }
+
+    fn data_box(&self, value: &str, start: usize, end: usize, top: f64) -> DiagramObject {
+        let mut obj = self.lane_box(start, end, top);
+        obj.set_value(value.to_string());
+        obj.set_shape("hexagon".to_string());
+        obj.apply_style_property("perimeter", "hexagonPerimeter2");
+        obj.apply_style_property("fixedSize", "1");
+        obj.apply_style_property("size", &(self.step_width / 8.0).to_string());
+        obj.into()
+    }
+}
+
+impl Default for Waveform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -23,6 +23,9 @@ pub enum DrawrsError {
     #[error("Invalid value for key '{0}': {1}")]
     InvalidValue(String, String),
 
+    #[error("Invalid XML attribute name: '{0}'")]
+    InvalidAttributeName(String),
+
     #[error("Binary node error: {0}")]
     BinaryNode(String),
 
@@ -32,6 +35,9 @@ pub enum DrawrsError {
     #[error("Data cannot be empty")]
     EmptyData,
 
+    #[error("Degenerate data: {0}")]
+    DegenerateData(String),
+
     #[error("Mapping cannot be empty")]
     EmptyMapping,
 
@@ -41,8 +47,24 @@ pub enum DrawrsError {
     #[error("BinaryNodeObject cannot have more than two children")]
     TooManyChildren,
 
+    #[error("Parent cycle through cell {0}")]
+    ParentCycle(String),
+
+    #[error("No cell with id {0}")]
+    UnknownId(String),
+
+    #[error("Cell id {0} is already used")]
+    DuplicateId(String),
+
+    #[error("Cell {id} has characters not allowed in XML: {value:?}")]
+    InvalidText { id: String, value: String },
+
     #[error("UnsupportedOrient: {0:?}")]
     UnsupportedOrient(Orient),
+
+    #[cfg(feature = "raster")]
+    #[error("PNG rendering error: {0}")]
+    Raster(String),
 }
 
 /// Convenience type alias for Result
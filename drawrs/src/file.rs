@@ -1,14 +1,51 @@
-use crate::page::Page;
-use crate::xml_base::XMLBase;
-use chrono::Utc;
+use crate::error::{DrawrsError, DrawrsResult};
+use crate::page::{Page, PageXml};
+use crate::xml_base::{XMLBase, XmlAttr, is_xml_char, with_float_precision};
+use indexmap::IndexMap;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::cell::RefCell;
 use std::fmt;
 
+thread_local! {
+    static FIXED_TIMESTAMP: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Write `timestamp` as the `modified` attribute of the files on this thread
+/// instead of the current time, for reproducible output. `None` restores the
+/// current time.
+pub fn set_fixed_timestamp(timestamp: Option<String>) {
+    FIXED_TIMESTAMP.with(|fixed| *fixed.borrow_mut() = timestamp);
+}
+
+/// The timestamp of [`set_fixed_timestamp`] on this thread, if any
+pub fn fixed_timestamp() -> Option<String> {
+    FIXED_TIMESTAMP.with(|fixed| fixed.borrow().clone())
+}
+
+#[cfg(feature = "clock")]
+fn current_time() -> String {
+    chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+#[cfg(not(feature = "clock"))]
+fn current_time() -> String {
+    "1970-01-01T00:00:00".to_string()
+}
+
 pub struct DrawFile {
     pub base: XMLBase,
     pub pages: Vec<Page>,
     pub host: String,
     pub file_type: String,
     pub version: String,
+    /// Maximum number of decimals of the floats in [`DrawFile::xml`], `None`
+    /// uses the thread default. A page's own precision takes priority.
+    pub float_precision: Option<usize>,
+    /// URL of the web fonts by family name, see [`Self::add_custom_font`]
+    pub custom_fonts: IndexMap<String, String>,
+    /// `modified` attribute of this file, see [`Self::modified`]
+    pub timestamp: Option<String>,
 }
 
 impl DrawFile {
@@ -21,6 +58,9 @@ impl DrawFile {
             host: "Electron".to_string(),
             file_type: "device".to_string(),
             version: "21.6.5".to_string(),
+            float_precision: None,
+            custom_fonts: IndexMap::new(),
+            timestamp: None,
         }
     }
 
@@ -37,43 +77,167 @@ impl DrawFile {
         self.pages.push(page);
     }
 
+    /// Load the font `name` from `url` (e.g. a Google Fonts CSS link) where
+    /// it is not installed. Draw.io reads the font source of each cell, so
+    /// the cells whose `fontFamily` list names the font get a `fontSource`
+    /// with the URL when the file is written, unless they have one.
+    pub fn add_custom_font(&mut self, name: impl Into<String>, url: impl Into<String>) {
+        self.custom_fonts.insert(name.into(), url.into());
+    }
+
     pub fn remove_page(&mut self, page_id: &str) {
         self.pages.retain(|p| p.id() != page_id);
     }
 
+    /// [`Page::replace_text`] on every page, returns the total number of replacements
+    pub fn replace_text(&mut self, pattern: &str, replacement: &str) -> usize {
+        self.pages
+            .iter_mut()
+            .map(|page| page.replace_text(pattern, replacement))
+            .sum()
+    }
+
+    /// [`Page::replace_text_regex`] on every page
+    #[cfg(feature = "regex")]
+    pub fn replace_text_regex(&mut self, pattern: &regex::Regex, replacement: &str) -> usize {
+        self.pages
+            .iter_mut()
+            .map(|page| page.replace_text_regex(pattern, replacement))
+            .sum()
+    }
+
     pub fn stats(&self) -> String {
         let object_count: usize = self.pages.iter().map(|p| p.objects().len()).sum();
         format!("Pages: {} | Objects: {}", self.pages.len(), object_count)
     }
 
+    /// The timestamp of the file, or else the fixed timestamp of
+    /// [`set_fixed_timestamp`], or else the current time. Without the `clock`
+    /// feature the default is the Unix epoch.
     pub fn modified(&self) -> String {
-        Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+        if let Some(timestamp) = &self.timestamp {
+            return timestamp.clone();
+        }
+        if let Some(timestamp) = fixed_timestamp() {
+            return timestamp;
+        }
+        current_time()
     }
 
     pub fn agent(&self) -> String {
         format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
     }
 
-    pub fn xml(&self) -> DrawFileXml<'_> {
-        DrawFileXml(self)
+    /// The XML of the file, fails with the first page that cannot be
+    /// written, see [`Page::xml`]
+    pub fn xml(&self) -> DrawrsResult<DrawFileXml<'_>> {
+        Ok(DrawFileXml {
+            file: self,
+            pages: self
+                .pages
+                .iter()
+                .map(|page| Ok(page.xml()?.with_font_sources(&self.custom_fonts)))
+                .collect::<DrawrsResult<_>>()?,
+        })
+    }
+
+    /// Strict check of the texts: fail with the first one holding characters
+    /// XML 1.0 does not allow, which [`Self::xml`] would silently drop
+    pub fn check_text(&self) -> DrawrsResult<()> {
+        for page in &self.pages {
+            if !page.name().chars().all(is_xml_char) {
+                return Err(DrawrsError::InvalidText {
+                    id: page.id().to_string(),
+                    value: page.name().to_string(),
+                });
+            }
+            for obj in page.objects() {
+                if let Some(value) = obj.invalid_text() {
+                    return Err(DrawrsError::InvalidText {
+                        id: obj.id().to_string(),
+                        value,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read [`Self::xml`] back with quick_xml, as a sanity check of the output
+    pub fn validate_xml(&self) -> DrawrsResult<()> {
+        validate_xml(&self.xml()?.to_string())
+    }
+}
+
+/// Check that `xml` is a well-formed document of XML 1.0 characters, with
+/// balanced elements and valid entities
+pub fn validate_xml(xml: &str) -> DrawrsResult<()> {
+    if let Some(c) = xml.chars().find(|c| !is_xml_char(*c)) {
+        return Err(DrawrsError::XmlParse(format!(
+            "character {c:?} is not allowed in XML"
+        )));
+    }
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let start = match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                depth += 1;
+                e
+            }
+            Event::Empty(e) => e,
+            Event::End(_) => {
+                // Mismatched end tags are errors of the reader
+                depth = depth.saturating_sub(1);
+                buf.clear();
+                continue;
+            }
+            Event::Text(text) => {
+                text.unescape()?;
+                buf.clear();
+                continue;
+            }
+            Event::Eof if depth > 0 => {
+                return Err(DrawrsError::XmlParse(format!("{depth} unclosed elements")));
+            }
+            Event::Eof => return Ok(()),
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+        for attr in start.attributes() {
+            attr.map_err(quick_xml::Error::from)?.unescape_value()?;
+        }
+        buf.clear();
     }
 }
 
-pub struct DrawFileXml<'a>(&'a DrawFile);
+pub struct DrawFileXml<'a> {
+    file: &'a DrawFile,
+    pages: Vec<PageXml<'a>>,
+}
 
 impl<'a> fmt::Display for DrawFileXml<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        with_float_precision(self.file.float_precision, || self.write_file(f))
+    }
+}
+
+impl DrawFileXml<'_> {
+    fn write_file(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             r#"<mxfile host="{}" modified="{}" agent="{}" version="{}" pages="{}">"#,
-            self.0.host,
-            self.0.modified(),
-            self.0.agent(),
-            self.0.version,
-            self.0.pages.len()
+            XmlAttr(&self.file.host),
+            XmlAttr(&self.file.modified()),
+            XmlAttr(&self.file.agent()),
+            XmlAttr(&self.file.version),
+            self.file.pages.len()
         )?;
-        for page in &self.0.pages {
-            write!(f, "\n  {}", page.xml())?;
+        for page in &self.pages {
+            write!(f, "\n  {}", page)?;
         }
         write!(f, "\n</mxfile>")
     }
@@ -3,17 +3,25 @@ pub mod diagram_types;
 pub mod error;
 pub mod file;
 pub mod page;
+#[cfg(feature = "raster")]
+pub mod raster;
+pub mod raw_xml;
+pub mod svg;
 pub mod transform;
 pub mod utils;
 pub mod xml_base;
 pub mod xml_parser;
 
-pub use diagram::{DiagramBase, Edge, FillStyle, Geometry, Object};
-pub use diagram_types::{BarChart, BinaryNodeObject, BinaryTreeDiagram, Legend, PieChart};
+pub use diagram::{DiagramBase, Edge, FillStyle, Geometry, LineCap, LineJoin, Object};
+pub use diagram_types::{
+    BarChart, BinSpec, BinaryNodeObject, BinaryTreeDiagram, Histogram, Legend, Level, PieChart,
+    Waveform,
+};
 pub use error::{DrawrsError, DrawrsResult};
 pub use file::DrawFile;
-pub use page::{DiagramObject, Page};
-pub use transform::{BoundingBox, GroupTransform, Orient};
+pub use page::{DiagramObject, LayerView, Page};
+pub use raw_xml::RawXml;
+pub use transform::{BoundingBox, GroupTransform, Orient, Transform2D};
 pub use utils::{PageSize, StandardColor};
-pub use xml_base::XMLBase;
-pub use xml_parser::parse_xml_to_object;
+pub use xml_base::{StyleClass, XMLBase};
+pub use xml_parser::{CellBuilder, parse_xml_to_object};
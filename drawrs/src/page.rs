@@ -1,7 +1,13 @@
+use crate::error::{DrawrsError, DrawrsResult};
 use crate::transform::FlipRotation;
-use crate::xml_base::XMLBase;
+use crate::xml_base::{
+    StyleClass, XMLBase, XmlAttr, XmlContext, is_xml_char, merge_styles, with_float_precision,
+};
 use crate::{BoundingBox, diagram::text_format::Justify};
+use indexmap::IndexMap;
 use itertools::Either;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
 pub struct Page {
     objects: Vec<DiagramObject>,
@@ -21,6 +27,8 @@ pub struct Page {
     height: f64,
     math: i32,
     shadow: i32,
+    float_precision: Option<usize>,
+    style_classes: IndexMap<String, String>,
     diagram: Diagram,
 }
 
@@ -59,6 +67,8 @@ impl Page {
             height: 1100.0,
             math: 0,
             shadow: 0,
+            float_precision: None,
+            style_classes: IndexMap::new(),
             diagram,
         };
 
@@ -89,18 +99,159 @@ impl Page {
         self.diagram.name = name;
     }
 
+    /// Custom attribute of the `<diagram>` element
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.diagram.base.attributes.get(key).map(String::as_str)
+    }
+
+    /// Set a custom attribute of the `<diagram>` element, e.g. the provenance
+    /// of a generated page. A key that is not an XML name is rejected.
+    pub fn set_attribute(&mut self, key: String, value: String) -> DrawrsResult<()> {
+        self.diagram.base.set_attribute(key, value)
+    }
+
     pub fn set_page_num(&mut self, page_num: usize) {
         self.page_num = page_num;
     }
 
+    /// Maximum number of decimals of the floats in [`Page::xml`], `None` uses
+    /// the thread default, see [`set_float_precision`](crate::xml_base::set_float_precision)
+    pub fn set_float_precision(&mut self, decimals: Option<usize>) {
+        self.float_precision = decimals;
+    }
+
+    pub fn float_precision(&self) -> Option<usize> {
+        self.float_precision
+    }
+
+    /// Define (or redefine) the style class `name`, the objects and edges
+    /// with the [`StyleClass`] `name` are written with `style` in place of
+    /// the style of their class, followed by their own style, a key of their
+    /// own replacing the one of the class in place
+    pub fn define_style(&mut self, name: impl Into<String>, style: impl Into<String>) {
+        self.style_classes.insert(name.into(), style.into());
+    }
+
+    /// Style classes in definition order, see [`Page::define_style`]
+    pub fn style_classes(&self) -> &IndexMap<String, String> {
+        &self.style_classes
+    }
+
+    /// The object with the style of its class applied to its own style, as
+    /// it is written to the XML. Borrowed when it has no class.
+    pub fn resolved_object<'a>(&self, obj: &'a DiagramObject) -> Cow<'a, DiagramObject> {
+        let Some(class) = obj.style_class() else {
+            return Cow::Borrowed(obj);
+        };
+        let class_style = self.style_classes.get(&class.name).unwrap_or(&class.style);
+        let mut resolved = obj.clone();
+        match &mut resolved {
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => {}
+            DiagramObject::Object(o) => {
+                let style = merge_styles(class_style, &o.style().to_string());
+                o.parse_and_set_style(&style);
+            }
+            DiagramObject::Edge(e) => {
+                let style = merge_styles(class_style, &e.style().to_string());
+                e.parse_and_set_style(&style);
+            }
+        }
+        resolved.base_mut().style_class = None;
+        Cow::Owned(resolved)
+    }
+
     pub fn objects(&self) -> &[DiagramObject] {
         &self.objects
     }
 
-    pub fn add_object(&mut self, obj: DiagramObject) {
+    pub fn objects_mut(&mut self) -> &mut [DiagramObject] {
+        &mut self.objects
+    }
+
+    /// Add an object to the page. Objects and edges without a parent are
+    /// written under the background cell "1"; on a page without it they are
+    /// put on the first layer cell instead, see [`Self::has_background`].
+    pub fn add_object(&mut self, mut obj: DiagramObject) {
+        if obj.xml_parent().is_none()
+            && !matches!(obj, DiagramObject::XmlBase(_) | DiagramObject::Raw(_))
+            && !self.has_background()
+        {
+            match self.first_layer_id() {
+                Some(layer) => obj.set_xml_parent(Some(layer.to_string())),
+                None => log::warn!(
+                    "object {} has no parent and the page has neither a background nor a layer cell",
+                    obj.id()
+                ),
+            }
+        }
         self.objects.push(obj);
     }
 
+    /// Whether the page has the background cell "1", the default parent of
+    /// the objects and edges
+    pub fn has_background(&self) -> bool {
+        self.objects
+            .iter()
+            .any(|obj| obj.id() == "1" && obj.xml_parent() == Some("0"))
+    }
+
+    // Id of the first cell under the root cell "0"
+    fn first_layer_id(&self) -> Option<&str> {
+        self.objects
+            .iter()
+            .find(|obj| matches!(obj, DiagramObject::XmlBase(_)) && obj.xml_parent() == Some("0"))
+            .map(DiagramObject::id)
+    }
+
+    /// Ids of the objects whose parent is not on the page, draw.io drops
+    /// them silently. Objects and edges without a parent count as children
+    /// of the background cell "1".
+    pub fn check_parents(&self) -> Vec<String> {
+        let ids: HashSet<&str> = self.objects.iter().map(DiagramObject::id).collect();
+        self.objects
+            .iter()
+            .filter(|obj| Self::parent_of(obj).is_some_and(|parent| !ids.contains(parent)))
+            .map(|obj| obj.id().to_string())
+            .collect()
+    }
+
+    /// The layer cells (the cells under the root cell "0", including the
+    /// background cell "1") in page order, each with the objects parented to
+    /// it. Objects and edges without a parent belong to "1". The parent
+    /// links are indexed once, the whole iteration is O(n).
+    pub fn layers(&self) -> impl Iterator<Item = LayerView<'_>> {
+        let mut children: HashMap<&str, Vec<&DiagramObject>> = HashMap::new();
+        for obj in &self.objects {
+            if let Some(parent) = Self::parent_of(obj) {
+                children.entry(parent).or_default().push(obj);
+            }
+        }
+        self.objects.iter().filter_map(move |obj| match obj {
+            DiagramObject::XmlBase(cell) if cell.xml_parent.as_deref() == Some("0") => {
+                Some(LayerView {
+                    cell,
+                    objects: children.remove(cell.id.as_str()).unwrap_or_default(),
+                })
+            }
+            _ => None,
+        })
+    }
+
+    /// Objects parented to the cell `id`, e.g. the members of a group cell
+    pub fn children_of<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a DiagramObject> {
+        self.objects
+            .iter()
+            .filter(move |obj| Self::parent_of(obj) == Some(id))
+    }
+
+    // Parent as written to the XML, objects and edges default to "1"
+    fn parent_of(obj: &DiagramObject) -> Option<&str> {
+        match obj {
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => obj.xml_parent(),
+            _ => Some(obj.xml_parent().unwrap_or("1")),
+        }
+    }
+
     pub fn add_layer_cell(&mut self, visible: bool, id: String, name: String) {
         let mut layer_cell = XMLBase::new(Some(id));
         layer_cell.xml_class = "mxCell".to_string();
@@ -110,62 +261,478 @@ impl Page {
         self.add_object(DiagramObject::XmlBase(layer_cell));
     }
 
+    /// Make the object ids unique by appending `-{n}` to ids already used by
+    /// an earlier object, returns the number of renamed objects
+    pub fn dedup_ids(&mut self) -> usize {
+        let mut used: HashSet<String> = HashSet::new();
+        let mut renamed = 0;
+        for obj in &mut self.objects {
+            if used.insert(obj.id().to_string()) {
+                continue;
+            }
+            let id = (1..)
+                .map(|n| format!("{}-{n}", obj.id()))
+                .find(|id| !used.contains(id))
+                .unwrap();
+            used.insert(id.clone());
+            obj.set_id(id);
+            renamed += 1;
+        }
+        renamed
+    }
+
+    /// Copy the object `id` with its descendants (the children of a group or
+    /// container), each copy taking the id of its original with `id_suffix`
+    /// appended. Parents, edge sources and edge targets inside the copied
+    /// subtree point to the copies, the ones outside are kept. Returns the
+    /// id of the copy of `id`.
+    pub fn duplicate_object(&mut self, id: &str, id_suffix: &str) -> DrawrsResult<String> {
+        let root = self
+            .objects
+            .iter()
+            .position(|obj| obj.id() == id)
+            .ok_or_else(|| DrawrsError::UnknownId(id.to_string()))?;
+        // Subtree in page order, a parent before its children
+        let mut subtree: HashSet<&str> = HashSet::from([id]);
+        let mut members = vec![root];
+        let mut grown = true;
+        while grown {
+            grown = false;
+            for (i, obj) in self.objects.iter().enumerate() {
+                if !subtree.contains(obj.id())
+                    && obj
+                        .xml_parent()
+                        .is_some_and(|parent| subtree.contains(parent))
+                {
+                    subtree.insert(obj.id());
+                    members.push(i);
+                    grown = true;
+                }
+            }
+        }
+        let ids: HashSet<&str> = self.objects.iter().map(DiagramObject::id).collect();
+        if let Some(used) = members
+            .iter()
+            .map(|&i| format!("{}{id_suffix}", self.objects[i].id()))
+            .find(|new_id| ids.contains(new_id.as_str()))
+        {
+            return Err(DrawrsError::DuplicateId(used));
+        }
+
+        let rename = |old: &str| subtree.contains(old).then(|| format!("{old}{id_suffix}"));
+        let copies: Vec<DiagramObject> = members
+            .iter()
+            .map(|&i| {
+                let mut copy = self.objects[i].clone();
+                copy.set_id(format!("{}{id_suffix}", copy.id()));
+                if let Some(parent) = copy.xml_parent().and_then(rename) {
+                    copy.set_xml_parent(Some(parent));
+                }
+                if let DiagramObject::Edge(edge) = &mut copy {
+                    if let Some(source) = edge.source().and_then(|s| rename(s)) {
+                        edge.set_source(Some(source));
+                    }
+                    if let Some(target) = edge.target().and_then(|t| rename(t)) {
+                        edge.set_target(Some(target));
+                    }
+                }
+                copy
+            })
+            .collect();
+        let new_id = copies[0].id().to_string();
+        self.objects.extend(copies);
+        Ok(new_id)
+    }
+
+    /// Move the objects `ids` by `[dx, dy]`, their boxes and points. The
+    /// children of a group or container are relative to it and move with it.
+    pub fn translate_objects(&mut self, ids: &[&str], dx: f64, dy: f64) {
+        self.transform_objects(ids, |[x, y]| [x + dx, y + dy], 1.0);
+    }
+
+    /// Scale the objects `ids` by `factor` about the page point `about`,
+    /// their boxes and points. The children of a group or container keep
+    /// their geometry relative to it.
+    pub fn scale_objects(&mut self, ids: &[&str], factor: f64, about: [f64; 2]) {
+        let [ax, ay] = about;
+        self.transform_objects(
+            ids,
+            |[x, y]| [ax + (x - ax) * factor, ay + (y - ay) * factor],
+            factor,
+        );
+    }
+
+    // Map the points and box corners of the objects `ids` with `f`, the box
+    // sizes are multiplied by `factor`
+    fn transform_objects(&mut self, ids: &[&str], f: impl Fn([f64; 2]) -> [f64; 2], factor: f64) {
+        let map_box = |bbox: &mut BoundingBox| {
+            let [x, y] = f([bbox.min_x(), bbox.min_y()]);
+            *bbox = BoundingBox::new(x, y, bbox.width() * factor, bbox.height() * factor);
+        };
+        for obj in self
+            .objects
+            .iter_mut()
+            .filter(|obj| ids.contains(&obj.id()))
+        {
+            for point in obj.mut_points() {
+                *point = f(*point);
+            }
+            if let Some((bbox, _)) = obj.mut_box() {
+                map_box(bbox);
+            }
+            if let DiagramObject::XmlBase(base) = obj
+                && let Some(bbox) = base.group_geometry.as_mut()
+            {
+                map_box(bbox);
+            }
+        }
+    }
+
+    /// Objects in emission order: the root cells ("0", "1" and the layer
+    /// cells) first, then every other cell after its parent, in insertion
+    /// order otherwise. Parents missing from the page are ignored, a parent
+    /// cycle is an error.
+    pub fn ordered_objects(&self) -> DrawrsResult<Vec<&DiagramObject>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Visit {
+            New,
+            InChain,
+            Done,
+        }
+
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for (i, obj) in self.objects.iter().enumerate() {
+            index.entry(obj.id()).or_insert(i);
+        }
+        // Objects and edges without a parent are emitted under "1"
+        let parent = |i: usize| {
+            let obj = &self.objects[i];
+            index.get(Self::parent_of(obj)?).copied()
+        };
+        let root_cells = (0..self.objects.len()).filter(|&i| {
+            matches!(self.objects[i], DiagramObject::XmlBase(_))
+                && parent(i).is_none_or(|p| parent(p).is_none())
+        });
+
+        let mut visit = vec![Visit::New; self.objects.len()];
+        let mut order = Vec::with_capacity(self.objects.len());
+        let mut chain = Vec::new();
+        for start in root_cells.chain(0..self.objects.len()) {
+            // Walk up to the first emitted ancestor, then emit top-down
+            chain.clear();
+            let mut current = Some(start);
+            while let Some(i) = current {
+                match visit[i] {
+                    Visit::Done => break,
+                    Visit::InChain => {
+                        return Err(DrawrsError::ParentCycle(self.objects[i].id().to_string()));
+                    }
+                    Visit::New => {
+                        visit[i] = Visit::InChain;
+                        chain.push(i);
+                        current = parent(i);
+                    }
+                }
+            }
+            for &i in chain.iter().rev() {
+                visit[i] = Visit::Done;
+                order.push(&self.objects[i]);
+            }
+        }
+        Ok(order)
+    }
+
     pub fn remove_object(&mut self, obj_id: &str) {
         self.objects.retain(|o| o.id() != obj_id);
     }
 
-    pub fn xml(&self) -> PageXml<'_> {
-        PageXml(self)
+    /// Keep only the objects for which `f` returns true
+    pub fn retain_objects(&mut self, f: impl FnMut(&DiagramObject) -> bool) {
+        self.objects.retain(f);
+    }
+
+    /// The XML of the page, the cells in [`Self::ordered_objects`] order.
+    /// A parent cycle is an error, draw.io cannot load such a page.
+    pub fn xml(&self) -> DrawrsResult<PageXml<'_>> {
+        Ok(PageXml {
+            page: self,
+            objects: self.ordered_objects()?,
+            font_sources: None,
+        })
     }
+
+    // Labels of the objects and edges, layer and group cells are left out
+    fn labels_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.objects
+            .iter_mut()
+            .filter(|obj| !matches!(obj, DiagramObject::XmlBase(_)))
+            .filter_map(DiagramObject::text_mut)
+    }
+
+    /// Replace every occurrence of `pattern` in the object and edge labels
+    /// (the `UserObject` label of tagged cells), returns the number of
+    /// replacements. Ids, styles and layer names are left as is.
+    pub fn replace_text(&mut self, pattern: &str, replacement: &str) -> usize {
+        if pattern.is_empty() {
+            return 0;
+        }
+        self.labels_mut()
+            .map(|text| {
+                let count = text.matches(pattern).count();
+                if count > 0 {
+                    *text = text.replace(pattern, replacement);
+                }
+                count
+            })
+            .sum()
+    }
+
+    /// [`Self::replace_text`] with a regular expression, `replacement` may
+    /// refer to the capture groups as in [`regex::Regex::replace_all`]
+    #[cfg(feature = "regex")]
+    pub fn replace_text_regex(&mut self, pattern: &regex::Regex, replacement: &str) -> usize {
+        self.labels_mut()
+            .map(|text| {
+                let count = pattern.find_iter(text).count();
+                if count > 0 {
+                    *text = pattern.replace_all(text, replacement).into_owned();
+                }
+                count
+            })
+            .sum()
+    }
+}
+
+/// A layer cell of a page with its objects, see [`Page::layers`]
+pub struct LayerView<'a> {
+    cell: &'a XMLBase,
+    objects: Vec<&'a DiagramObject>,
 }
 
-pub struct PageXml<'a>(&'a Page);
+impl<'a> LayerView<'a> {
+    pub fn id(&self) -> &'a str {
+        &self.cell.id
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.cell.value.as_deref().unwrap_or("")
+    }
+
+    pub fn cell(&self) -> &'a XMLBase {
+        self.cell
+    }
+
+    /// Layers are visible unless `visible="0"`
+    pub fn visible(&self) -> bool {
+        self.cell.visible.as_deref() != Some("0")
+    }
+
+    /// Whether the layer cell has `locked=1` in its kept `style` attribute
+    pub fn locked(&self) -> bool {
+        self.cell
+            .preserved_attributes
+            .get("style")
+            .is_some_and(|style| style.split(';').any(|kv| kv.trim() == "locked=1"))
+    }
+
+    /// Objects parented directly to the layer in page order, the members of
+    /// a group are reached with [`Page::children_of`]
+    pub fn objects(&self) -> impl Iterator<Item = &'a DiagramObject> + '_ {
+        self.objects.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+pub struct PageXml<'a> {
+    page: &'a Page,
+    // draw.io drops cells that come before their parent
+    objects: Vec<&'a DiagramObject>,
+    // The custom fonts of the file the page is written in
+    font_sources: Option<&'a IndexMap<String, String>>,
+}
+
+impl<'a> PageXml<'a> {
+    pub(crate) fn with_font_sources(mut self, fonts: &'a IndexMap<String, String>) -> Self {
+        self.font_sources = Some(fonts);
+        self
+    }
+}
 
 impl<'a> std::fmt::Display for PageXml<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        with_float_precision(self.page.float_precision, || self.write_page(f))
+    }
+}
+
+impl PageXml<'_> {
+    fn write_page(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            r#"<diagram name="{}" id="{}">
+            r#"<diagram name="{}" id="{}"{}>
     <mxGraphModel dx="{}" dy="{}" grid="{}" gridSize="{}" guides="{}" toolTips="{}" connect="{}" arrows="{}" fold="{}" page="{}" pageScale="{}" pageWidth="{}" pageHeight="{}" math="{}" shadow="{}">
       <root>"#,
-            self.0.name,
-            self.0.diagram.base.id,
-            self.0.dx,
-            self.0.dy,
-            self.0.grid,
-            self.0.grid_size,
-            self.0.guides,
-            self.0.tooltips,
-            self.0.connect,
-            self.0.arrows,
-            self.0.fold,
-            self.0.page_num,
-            self.0.scale,
-            self.0.width,
-            self.0.height,
-            self.0.math,
-            self.0.shadow
+            XmlAttr(&self.page.name),
+            XmlAttr(&self.page.diagram.base.id),
+            DiagramAttributes(&self.page.diagram.base.attributes),
+            self.page.dx,
+            self.page.dy,
+            self.page.grid,
+            self.page.grid_size,
+            self.page.guides,
+            self.page.tooltips,
+            self.page.connect,
+            self.page.arrows,
+            self.page.fold,
+            self.page.page_num,
+            self.page.scale,
+            self.page.width,
+            self.page.height,
+            self.page.math,
+            self.page.shadow
         )?;
-        for obj in &self.0.objects {
-            write!(f, "\n        {}", obj.xml())?;
+        let context = XmlContext {
+            style_classes: Some(&self.page.style_classes),
+            font_sources: self.font_sources,
+        };
+        for obj in &self.objects {
+            write!(f, "\n        {}", obj.xml_in(context))?;
         }
         write!(f, "\n      </root>\n    </mxGraphModel>\n  </diagram>")
     }
 }
 
+struct DiagramAttributes<'a>(&'a indexmap::IndexMap<String, String>);
+
+impl std::fmt::Display for DiagramAttributes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in self.0.iter().filter(|(key, _)| XMLBase::is_xml_name(key)) {
+            write!(f, r#" {}="{}""#, key, XmlAttr(value))?;
+        }
+        Ok(())
+    }
+}
+
 impl Default for Page {
     fn default() -> Self {
         Self::new(None, true)
     }
 }
 
+/// Serialized form of [`Page`], the diagram id and name are stored once
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageModel<'a> {
+    id: std::borrow::Cow<'a, str>,
+    name: std::borrow::Cow<'a, str>,
+    page_num: usize,
+    dx: f64,
+    dy: f64,
+    grid: i32,
+    grid_size: i32,
+    guides: i32,
+    tooltips: i32,
+    connect: i32,
+    arrows: i32,
+    fold: i32,
+    scale: f64,
+    width: f64,
+    height: f64,
+    math: i32,
+    shadow: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    float_precision: Option<usize>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    style_classes: Cow<'a, IndexMap<String, String>>,
+    objects: std::borrow::Cow<'a, [DiagramObject]>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Page {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PageModel {
+            id: self.id().into(),
+            name: self.name.as_str().into(),
+            page_num: self.page_num,
+            dx: self.dx,
+            dy: self.dy,
+            grid: self.grid,
+            grid_size: self.grid_size,
+            guides: self.guides,
+            tooltips: self.tooltips,
+            connect: self.connect,
+            arrows: self.arrows,
+            fold: self.fold,
+            scale: self.scale,
+            width: self.width,
+            height: self.height,
+            math: self.math,
+            shadow: self.shadow,
+            float_precision: self.float_precision,
+            style_classes: Cow::Borrowed(&self.style_classes),
+            objects: self.objects.as_slice().into(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Page {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let model = PageModel::deserialize(deserializer)?;
+        let mut diagram_base = XMLBase::new(Some(model.id.into_owned()));
+        diagram_base.xml_class = "diagram".to_string();
+        let name = model.name.into_owned();
+        Ok(Self {
+            objects: model.objects.into_owned(),
+            name: name.clone(),
+            page_num: model.page_num,
+            dx: model.dx,
+            dy: model.dy,
+            grid: model.grid,
+            grid_size: model.grid_size,
+            guides: model.guides,
+            tooltips: model.tooltips,
+            connect: model.connect,
+            arrows: model.arrows,
+            fold: model.fold,
+            scale: model.scale,
+            width: model.width,
+            height: model.height,
+            math: model.math,
+            shadow: model.shadow,
+            float_precision: model.float_precision,
+            style_classes: model.style_classes.into_owned(),
+            diagram: Diagram {
+                base: diagram_base,
+                name,
+            },
+        })
+    }
+}
+
 use crate::diagram::{Edge, Object};
+use crate::raw_xml::RawXml;
 
+/// With the `serde` feature, objects are tagged by kind:
+/// `{"cell": {..}}`, `{"object": {..}}`, `{"edge": {..}}` or `{"raw": ".."}`
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum DiagramObject {
+    #[cfg_attr(feature = "serde", serde(rename = "cell"))]
     XmlBase(XMLBase),
     Object(Object),
     Edge(Edge),
+    /// Verbatim XML of an unsupported cell, see [`RawXml`]
+    Raw(RawXml),
 }
 
 impl DiagramObject {
@@ -174,6 +741,7 @@ impl DiagramObject {
             DiagramObject::XmlBase(x) => x,
             DiagramObject::Object(o) => o.base(),
             DiagramObject::Edge(e) => e.base(),
+            DiagramObject::Raw(r) => r.base(),
         }
     }
     pub fn base_mut(&mut self) -> &mut XMLBase {
@@ -181,12 +749,28 @@ impl DiagramObject {
             DiagramObject::XmlBase(x) => x,
             DiagramObject::Object(o) => o.base_mut(),
             DiagramObject::Edge(e) => e.base_mut(),
+            DiagramObject::Raw(r) => r.base_mut(),
         }
     }
     pub fn text(&self) -> Option<&String> {
         self.base().value.as_ref()
     }
 
+    /// First text of the object with characters XML 1.0 does not allow,
+    /// including its style
+    pub fn invalid_text(&self) -> Option<String> {
+        if let Some(text) = self.base().invalid_text() {
+            return Some(text.to_string());
+        }
+        let style = match self {
+            DiagramObject::Object(o) => o.style().to_string(),
+            DiagramObject::Edge(e) => e.style().to_string(),
+            DiagramObject::Raw(r) => r.xml().to_string(),
+            DiagramObject::XmlBase(_) => return None,
+        };
+        (!style.chars().all(is_xml_char)).then_some(style)
+    }
+
     pub fn set_text(&mut self, text: String) {
         self.base_mut().value = Some(text);
     }
@@ -204,8 +788,8 @@ impl DiagramObject {
     }
     pub fn mut_points(&mut self) -> impl Iterator<Item = &mut [f64; 2]> {
         match self {
-            DiagramObject::XmlBase(_) => {
-                // XmlBase has no points
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => {
+                // XmlBase has no points, the raw XML is left alone
                 Either::Left(Either::Left(std::iter::empty()))
             }
             DiagramObject::Object(o) => Either::Left(Either::Right(o.points_mut())),
@@ -218,8 +802,8 @@ impl DiagramObject {
 
     pub fn mut_box(&mut self) -> Option<(&mut BoundingBox, &mut FlipRotation)> {
         match self {
-            DiagramObject::XmlBase(_) => {
-                // XmlBase has no boxes
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => {
+                // XmlBase has no boxes, the raw XML is left alone
                 None
             }
             DiagramObject::Object(o) => {
@@ -241,9 +825,38 @@ impl DiagramObject {
     pub fn set_tag(&mut self, tag: Option<String>) {
         self.base_mut().tag = tag;
     }
+    pub fn style_class(&self) -> Option<&StyleClass> {
+        self.base().style_class.as_ref()
+    }
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.base().tags()
+    }
+    pub fn add_tag(&mut self, tag: &str) {
+        self.base_mut().add_tag(tag);
+    }
+    pub fn set_attribute(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> DrawrsResult<()> {
+        self.base_mut().set_attribute(key.into(), value.into())
+    }
+    pub fn attribute(&self, key: &str) -> Option<&String> {
+        self.base().attributes.get(key)
+    }
+    pub fn link(&self) -> Option<&String> {
+        self.base().link.as_ref()
+    }
+    pub fn set_link(&mut self, link: Option<String>) {
+        self.base_mut().link = link;
+    }
 
     pub fn xml(&self) -> DiagramObjectXml<'_> {
-        DiagramObjectXml(self)
+        self.xml_in(XmlContext::default())
+    }
+
+    pub(crate) fn xml_in<'a>(&'a self, context: XmlContext<'a>) -> DiagramObjectXml<'a> {
+        DiagramObjectXml(self, context)
     }
 
     /// Get bounding box for objects (for Objects only, returns None for XmlBase and Edge)
@@ -289,14 +902,15 @@ impl DiagramObject {
     }
 }
 
-pub struct DiagramObjectXml<'a>(&'a DiagramObject);
+pub struct DiagramObjectXml<'a>(&'a DiagramObject, XmlContext<'a>);
 
 impl<'a> std::fmt::Display for DiagramObjectXml<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.0 {
             DiagramObject::XmlBase(x) => write!(f, "{}", x.xml()),
-            DiagramObject::Object(o) => write!(f, "{}", o.xml()),
-            DiagramObject::Edge(e) => write!(f, "{}", e.xml()),
+            DiagramObject::Object(o) => write!(f, "{}", o.xml_in(self.1)),
+            DiagramObject::Edge(e) => write!(f, "{}", e.xml_in(self.1)),
+            DiagramObject::Raw(r) => f.write_str(r.xml()),
         }
     }
 }
@@ -318,3 +932,9 @@ impl From<Edge> for DiagramObject {
         DiagramObject::Edge(e)
     }
 }
+
+impl From<RawXml> for DiagramObject {
+    fn from(r: RawXml) -> Self {
+        DiagramObject::Raw(r)
+    }
+}
@@ -0,0 +1,301 @@
+//! PNG rendering of a [`Page`]: its [SVG preview](crate::svg) rasterized
+//! with resvg.
+//!
+//! The `fontFamily` of the texts are looked up in a font database, see
+//! [`Fonts`]: a family that is not installed falls back to the generic
+//! family of its name, e.g. `Courier New` to `monospace`, then to
+//! `sans-serif`, the generic families are mapped to installed fonts.
+
+use crate::error::{DrawrsError, DrawrsResult};
+use crate::page::Page;
+use log::warn;
+use once_cell::sync::Lazy;
+use resvg::tiny_skia::{Color, Pixmap, Transform};
+use resvg::usvg::fontdb::{self, Database};
+use resvg::usvg::{self, FontFamily, FontResolver, FontStretch, FontStyle};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Resolution of the page coordinates, one pixel per unit as in draw.io
+pub const BASE_DPI: f64 = 96.0;
+// Larger images are rendered at a lower resolution
+const MAX_PIXELS: f64 = 64.0 * 1024.0 * 1024.0;
+// Installed fonts tried for each generic family, in order
+const GENERIC_FONTS: [(fontdb::Family<'static>, &[&str]); 5] = [
+    (
+        fontdb::Family::SansSerif,
+        &[
+            "Helvetica",
+            "Arial",
+            "Liberation Sans",
+            "DejaVu Sans",
+            "Noto Sans",
+        ],
+    ),
+    (
+        fontdb::Family::Serif,
+        &[
+            "Times New Roman",
+            "Liberation Serif",
+            "DejaVu Serif",
+            "Noto Serif",
+        ],
+    ),
+    (
+        fontdb::Family::Monospace,
+        &[
+            "Courier New",
+            "Liberation Mono",
+            "DejaVu Sans Mono",
+            "Noto Sans Mono",
+        ],
+    ),
+    (
+        fontdb::Family::Cursive,
+        &["Brush Script MT", "Comic Sans MS"],
+    ),
+    (fontdb::Family::Fantasy, &["Impact", "Papyrus"]),
+];
+
+static SYSTEM_FONTS: Lazy<Fonts> = Lazy::new(Fonts::system);
+
+/// Fonts of the texts of the PNG images: a font database, and the families
+/// of the page drawn with another installed family. The default has no
+/// font, the texts are not drawn until fonts are loaded.
+#[derive(Clone, Default)]
+pub struct Fonts {
+    db: Arc<Database>,
+    aliases: HashMap<String, String>,
+}
+
+impl Fonts {
+    /// The fonts installed on the system
+    pub fn system() -> Self {
+        let mut db = Database::new();
+        db.load_system_fonts();
+        let mut fonts = Self {
+            db: Arc::new(db),
+            aliases: HashMap::new(),
+        };
+        fonts.map_generic_families();
+        fonts
+    }
+
+    /// Add the fonts of a font file, e.g. a `.ttf` or `.otf` file, returns
+    /// their families
+    pub fn load_font_file(&mut self, path: impl AsRef<Path>) -> DrawrsResult<Vec<String>> {
+        let known: HashSet<fontdb::ID> = self.db.faces().map(|face| face.id).collect();
+        Arc::make_mut(&mut self.db).load_font_file(path)?;
+        self.map_generic_families();
+        Ok(self.families_except(&known))
+    }
+
+    /// Add the fonts of the content of a font file, returns their families
+    pub fn load_font_data(&mut self, data: Vec<u8>) -> Vec<String> {
+        let known: HashSet<fontdb::ID> = self.db.faces().map(|face| face.id).collect();
+        Arc::make_mut(&mut self.db).load_font_data(data);
+        self.map_generic_families();
+        self.families_except(&known)
+    }
+
+    // Families of the faces other than `known`
+    fn families_except(&self, known: &HashSet<fontdb::ID>) -> Vec<String> {
+        let mut families = Vec::new();
+        for face in self.db.faces().filter(|face| !known.contains(&face.id)) {
+            if let Some((family, _)) = face.families.first()
+                && !families.contains(family)
+            {
+                families.push(family.clone());
+            }
+        }
+        families
+    }
+
+    /// Draw the texts of `family` with the installed font family `font`
+    pub fn set_alias(&mut self, family: impl Into<String>, font: impl Into<String>) {
+        self.aliases.insert(family.into(), font.into());
+    }
+
+    /// Whether a font of `family` is installed, the aliases left out
+    pub fn has_family(&self, family: &str) -> bool {
+        self.db
+            .faces()
+            .any(|face| face.families.iter().any(|(name, _)| name == family))
+    }
+
+    // Each generic family is the first of its `GENERIC_FONTS` installed,
+    // otherwise any installed family
+    fn map_generic_families(&mut self) {
+        let any = self
+            .db
+            .faces()
+            .find_map(|face| face.families.first())
+            .map(|(name, _)| name.clone());
+        let mut families = Vec::new();
+        for (generic, candidates) in GENERIC_FONTS {
+            let installed = candidates
+                .iter()
+                .find(|font| self.has_family(font))
+                .map(|font| font.to_string())
+                .or_else(|| any.clone());
+            if let Some(installed) = installed {
+                families.push((generic, installed));
+            }
+        }
+        let db = Arc::make_mut(&mut self.db);
+        for (generic, installed) in families {
+            match generic {
+                fontdb::Family::SansSerif => db.set_sans_serif_family(installed),
+                fontdb::Family::Serif => db.set_serif_family(installed),
+                fontdb::Family::Monospace => db.set_monospace_family(installed),
+                fontdb::Family::Cursive => db.set_cursive_family(installed),
+                _ => db.set_fantasy_family(installed),
+            }
+        }
+    }
+
+    // The families of a `font-family` list to look up: each named family
+    // after its alias, then the generic families of the names, `sans-serif`
+    // last
+    fn query_families<'a>(&'a self, families: &'a [FontFamily]) -> Vec<fontdb::Family<'a>> {
+        let mut query = Vec::new();
+        for family in families {
+            match family {
+                FontFamily::Serif => query.push(fontdb::Family::Serif),
+                FontFamily::SansSerif => query.push(fontdb::Family::SansSerif),
+                FontFamily::Cursive => query.push(fontdb::Family::Cursive),
+                FontFamily::Fantasy => query.push(fontdb::Family::Fantasy),
+                FontFamily::Monospace => query.push(fontdb::Family::Monospace),
+                FontFamily::Named(name) => {
+                    if let Some(alias) = self.aliases.get(name) {
+                        query.push(fontdb::Family::Name(alias));
+                    }
+                    query.push(fontdb::Family::Name(name));
+                }
+            }
+        }
+        for family in families {
+            if let FontFamily::Named(name) = family {
+                query.push(generic_family(name));
+            }
+        }
+        query.push(fontdb::Family::SansSerif);
+        query
+    }
+
+    fn resolver(&self) -> FontResolver<'_> {
+        FontResolver {
+            select_font: Box::new(move |font, db| {
+                let stretch = match font.stretch() {
+                    FontStretch::UltraCondensed => fontdb::Stretch::UltraCondensed,
+                    FontStretch::ExtraCondensed => fontdb::Stretch::ExtraCondensed,
+                    FontStretch::Condensed => fontdb::Stretch::Condensed,
+                    FontStretch::SemiCondensed => fontdb::Stretch::SemiCondensed,
+                    FontStretch::Normal => fontdb::Stretch::Normal,
+                    FontStretch::SemiExpanded => fontdb::Stretch::SemiExpanded,
+                    FontStretch::Expanded => fontdb::Stretch::Expanded,
+                    FontStretch::ExtraExpanded => fontdb::Stretch::ExtraExpanded,
+                    FontStretch::UltraExpanded => fontdb::Stretch::UltraExpanded,
+                };
+                let style = match font.style() {
+                    FontStyle::Normal => fontdb::Style::Normal,
+                    FontStyle::Italic => fontdb::Style::Italic,
+                    FontStyle::Oblique => fontdb::Style::Oblique,
+                };
+                let id = db.query(&fontdb::Query {
+                    families: &self.query_families(font.families()),
+                    weight: fontdb::Weight(font.weight()),
+                    stretch,
+                    style,
+                });
+                if id.is_none() {
+                    warn!("no font is installed, the texts are not drawn");
+                }
+                id
+            }),
+            select_fallback: FontResolver::default_fallback_selector(),
+        }
+    }
+}
+
+// Generic family of a font family name, e.g. `monospace` for `Courier New`
+fn generic_family(name: &str) -> fontdb::Family<'static> {
+    let name = name.to_ascii_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+    if has(&["mono", "courier", "consol", "fixed"]) {
+        fontdb::Family::Monospace
+    } else if !name.contains("sans") && has(&["serif", "times", "roman", "georgia", "cambria"]) {
+        fontdb::Family::Serif
+    } else if has(&["script", "hand"]) {
+        fontdb::Family::Cursive
+    } else {
+        fontdb::Family::SansSerif
+    }
+}
+
+/// Render a page as a PNG image at `dpi` with the [system fonts](Fonts::system)
+pub fn render_png(page: &Page, dpi: f64) -> DrawrsResult<Vec<u8>> {
+    render_png_with_fonts(page, dpi, &SYSTEM_FONTS)
+}
+
+/// Render a page as a PNG image at `dpi` with `fonts`, see the
+/// [module](self) docs. An empty page gives a single white pixel, a `dpi`
+/// that is not a positive number is an error.
+pub fn render_png_with_fonts(page: &Page, dpi: f64, fonts: &Fonts) -> DrawrsResult<Vec<u8>> {
+    if !(dpi.is_finite() && dpi > 0.0) {
+        return Err(DrawrsError::Raster(format!(
+            "invalid resolution of {dpi} dpi"
+        )));
+    }
+    let options = usvg::Options {
+        font_family: "sans-serif".to_string(),
+        font_resolver: fonts.resolver(),
+        fontdb: fonts.db.clone(),
+        ..Default::default()
+    };
+    let tree = match usvg::Tree::from_str(&page.to_svg(), &options) {
+        Ok(tree) => Some(tree),
+        // The zero view box of an empty page
+        Err(usvg::Error::InvalidSize) => None,
+        Err(e) => return Err(DrawrsError::Raster(e.to_string())),
+    };
+
+    let (width, height) = tree.as_ref().map_or((1.0, 1.0), |tree| {
+        let size = tree.size();
+        (size.width() as f64, size.height() as f64)
+    });
+    let mut scale = dpi / BASE_DPI;
+    if width * height * scale * scale > MAX_PIXELS {
+        scale = (MAX_PIXELS / (width * height)).sqrt();
+        warn!(
+            "PNG of {width}x{height} at {dpi} dpi rendered at {:.0} dpi",
+            scale * BASE_DPI
+        );
+    }
+    let mut pixmap = Pixmap::new(
+        ((width * scale).ceil() as u32).max(1),
+        ((height * scale).ceil() as u32).max(1),
+    )
+    .ok_or_else(|| DrawrsError::Raster(format!("no image of {width}x{height} at {dpi} dpi")))?;
+    pixmap.fill(Color::WHITE);
+    if let Some(tree) = tree {
+        let transform = Transform::from_scale(scale as f32, scale as f32);
+        resvg::render(&tree, transform, &mut pixmap.as_mut());
+    }
+    pixmap
+        .encode_png()
+        .map_err(|e| DrawrsError::Raster(e.to_string()))
+}
+
+impl Page {
+    /// PNG image of the page at `dpi`, see [`render_png`]
+    pub fn to_png(&self, dpi: f64) -> DrawrsResult<Vec<u8>> {
+        render_png(self, dpi)
+    }
+
+    /// PNG image of the page at `dpi` with `fonts`, see [`render_png_with_fonts`]
+    pub fn to_png_with_fonts(&self, dpi: f64, fonts: &Fonts) -> DrawrsResult<Vec<u8>> {
+        render_png_with_fonts(self, dpi, fonts)
+    }
+}
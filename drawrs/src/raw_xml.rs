@@ -0,0 +1,116 @@
+//! Verbatim cells for the draw.io features this crate does not model, e.g.
+//! a stencil shape with custom child elements.
+
+use crate::error::{DrawrsError, DrawrsResult};
+use crate::xml_base::XMLBase;
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+/// A single well-formed XML element written to the page as it is. Its id and
+/// parent are read from the XML: the attributes of the root element, or of
+/// the `mxCell` inside a `UserObject`/`object` wrapper for the parent.
+///
+/// The id and parent of [`Self::base`] are for lookups only, changing them
+/// does not rewrite the XML.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct RawXml {
+    base: XMLBase,
+    xml: String,
+}
+
+impl RawXml {
+    /// Check that `xml` is one well-formed element with an `id`
+    pub fn new(xml: impl Into<String>) -> DrawrsResult<Self> {
+        let xml = xml.into().trim().to_string();
+        let mut reader = Reader::from_str(&xml);
+        reader.trim_text(true);
+
+        let mut id = None;
+        let mut parent = None;
+        let mut depth = 0usize;
+        let mut roots = 0usize;
+        loop {
+            let event = reader.read_event()?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => {
+                    if depth == 0 {
+                        roots += 1;
+                        id = attribute(e, b"id")?;
+                        parent = attribute(e, b"parent")?;
+                    } else if depth == 1 && parent.is_none() && e.name().as_ref() == b"mxCell" {
+                        parent = attribute(e, b"parent")?;
+                    }
+                    if matches!(event, Event::Start(_)) {
+                        depth += 1;
+                    }
+                }
+                Event::End(_) => depth -= 1,
+                Event::Text(_) | Event::CData(_) if depth == 0 => {
+                    return Err(DrawrsError::XmlParse(
+                        "raw XML has text outside of its element".to_string(),
+                    ));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        if depth != 0 {
+            return Err(DrawrsError::XmlParse("raw XML is not closed".to_string()));
+        }
+        if roots != 1 {
+            return Err(DrawrsError::XmlParse(format!(
+                "raw XML must be one element, found {roots}"
+            )));
+        }
+        let Some(id) = id else {
+            return Err(DrawrsError::XmlParse(
+                "raw XML element has no id".to_string(),
+            ));
+        };
+
+        let mut base = XMLBase::new(Some(id));
+        base.xml_class = "raw".to_string();
+        base.xml_parent = parent;
+        Ok(Self { base, xml })
+    }
+
+    pub fn base(&self) -> &XMLBase {
+        &self.base
+    }
+
+    pub fn base_mut(&mut self) -> &mut XMLBase {
+        &mut self.base
+    }
+
+    pub fn id(&self) -> &str {
+        &self.base.id
+    }
+
+    /// The XML as it is written to the page
+    pub fn xml(&self) -> &str {
+        &self.xml
+    }
+}
+
+fn attribute(e: &BytesStart, key: &[u8]) -> DrawrsResult<Option<String>> {
+    match e.try_get_attribute(key)? {
+        Some(attr) => Ok(Some(attr.unescape_value()?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+impl TryFrom<String> for RawXml {
+    type Error = DrawrsError;
+
+    fn try_from(xml: String) -> DrawrsResult<Self> {
+        Self::new(xml)
+    }
+}
+
+impl From<RawXml> for String {
+    fn from(raw: RawXml) -> Self {
+        raw.xml
+    }
+}
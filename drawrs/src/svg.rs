@@ -0,0 +1,312 @@
+//! Plain SVG preview of a [`Page`].
+//!
+//! Only the common subset of the draw.io styles is mapped: rectangles,
+//! ellipses, polygons, arcs, edges with waypoints, text, stroke/fill/opacity,
+//! dashed lines and rotation/flip. Other styles are ignored, the result is a
+//! geometric preview rather than a pixel-perfect rendering.
+
+use crate::diagram::text_format::{JustifyX, JustifyY};
+use crate::diagram::{Edge, Object};
+use crate::page::{DiagramObject, Page};
+use crate::xml_base::XMLBase;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const MARGIN: f64 = 10.0;
+const DEFAULT_FONT_SIZE: f64 = 12.0;
+
+/// Render a page as a standalone SVG document
+pub fn render_svg(page: &Page) -> String {
+    let cells: HashMap<&str, &DiagramObject> =
+        page.objects().iter().map(|obj| (obj.id(), obj)).collect();
+    let mut body = String::new();
+    let mut bounds = Bounds::default();
+    for obj in page.objects() {
+        if is_hidden(obj, &cells) {
+            continue;
+        }
+        let origin = parent_origin(obj, &cells);
+        match page.resolved_object(obj).as_ref() {
+            DiagramObject::XmlBase(_) | DiagramObject::Raw(_) => {}
+            DiagramObject::Object(o) => render_object(o, origin, &mut body, &mut bounds),
+            DiagramObject::Edge(e) => render_edge(e, origin, &cells, &mut body, &mut bounds),
+        }
+    }
+
+    let (min_x, min_y, width, height) = bounds.view_box();
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="{min_x} {min_y} {width} {height}">
+{body}</svg>
+"#
+    )
+}
+
+impl Page {
+    /// Plain SVG preview of the page, see [`render_svg`]
+    pub fn to_svg(&self) -> String {
+        render_svg(self)
+    }
+}
+
+#[derive(Default)]
+struct Bounds {
+    min: Option<[f64; 2]>,
+    max: Option<[f64; 2]>,
+}
+
+impl Bounds {
+    fn add(&mut self, [x, y]: [f64; 2]) {
+        let min = self.min.get_or_insert([x, y]);
+        *min = [min[0].min(x), min[1].min(y)];
+        let max = self.max.get_or_insert([x, y]);
+        *max = [max[0].max(x), max[1].max(y)];
+    }
+
+    fn view_box(&self) -> (f64, f64, f64, f64) {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => (
+                min[0] - MARGIN,
+                min[1] - MARGIN,
+                max[0] - min[0] + 2.0 * MARGIN,
+                max[1] - min[1] + 2.0 * MARGIN,
+            ),
+            _ => (0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Whether the object or one of its ancestors is an invisible layer
+fn is_hidden(obj: &DiagramObject, cells: &HashMap<&str, &DiagramObject>) -> bool {
+    let mut current = Some(obj);
+    // The depth guard protects against parent cycles
+    for _ in 0..cells.len() + 1 {
+        let Some(cell) = current else { break };
+        if cell.base().visible.as_deref() == Some("0") {
+            return true;
+        }
+        current = cell.xml_parent().and_then(|p| cells.get(p).copied());
+    }
+    false
+}
+
+/// Group cells position their children relative to the group origin
+fn parent_origin(obj: &DiagramObject, cells: &HashMap<&str, &DiagramObject>) -> [f64; 2] {
+    let mut origin = [0.0, 0.0];
+    let mut parent = obj.xml_parent().and_then(|p| cells.get(p).copied());
+    for _ in 0..cells.len() {
+        let Some(cell) = parent else { break };
+        if let Some(bbox) = cell.base().group_geometry {
+            origin[0] += bbox.min_x();
+            origin[1] += bbox.min_y();
+        }
+        parent = cell.xml_parent().and_then(|p| cells.get(p).copied());
+    }
+    origin
+}
+
+fn paint_color(color: Option<&String>, default: &str) -> String {
+    XMLBase::xml_ify(color.map(|c| c.as_str()).unwrap_or(default))
+}
+
+fn write_stroke(
+    out: &mut String,
+    stroke: &str,
+    width: Option<f64>,
+    dashed: Option<&str>,
+    opacity: Option<i32>,
+) {
+    let _ = write!(
+        out,
+        r#" stroke="{}" stroke-width="{}""#,
+        stroke,
+        width.unwrap_or(1.0)
+    );
+    if dashed == Some("1") {
+        out.push_str(r#" stroke-dasharray="3 3""#);
+    }
+    if let Some(op) = opacity {
+        let _ = write!(out, r#" opacity="{}""#, op as f64 / 100.0);
+    }
+}
+
+fn render_object(obj: &Object, origin: [f64; 2], out: &mut String, bounds: &mut Bounds) {
+    let geometry = obj.geometry_ref();
+    let x = origin[0] + geometry.x();
+    let y = origin[1] + geometry.y();
+    let (w, h) = (geometry.width(), geometry.height());
+    bounds.add([x, y]);
+    bounds.add([x + w, y + h]);
+
+    let flip_rotation = geometry.flip_rotation();
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    let mut transform = String::new();
+    if let Some(rotation) = flip_rotation.rotation().filter(|r| *r != 0.0) {
+        let _ = write!(transform, "rotate({rotation} {cx} {cy}) ");
+    }
+    let flip_h = flip_rotation.flip_h().unwrap_or(0) != 0;
+    let flip_v = flip_rotation.flip_v().unwrap_or(0) != 0;
+    if flip_h || flip_v {
+        let sx = if flip_h { -1 } else { 1 };
+        let sy = if flip_v { -1 } else { 1 };
+        let _ = write!(
+            transform,
+            "translate({cx} {cy}) scale({sx} {sy}) translate({} {}) ",
+            -cx, -cy
+        );
+    }
+    let transform = transform.trim_end();
+
+    let fill = paint_color(obj.fill_color(), "#FFFFFF");
+    let stroke = paint_color(obj.stroke_color(), "#000000");
+    if fill != "none" || stroke != "none" {
+        let shape = obj.style_property("shape");
+        if shape == Some("ellipse") {
+            let _ = write!(
+                out,
+                r#"  <ellipse cx="{}" cy="{}" rx="{}" ry="{}""#,
+                cx,
+                cy,
+                w / 2.0,
+                h / 2.0
+            );
+        } else if shape == Some("mxgraph.basic.arc") {
+            // Angles are fractions of a turn, clockwise from 12 o'clock
+            let angle = |key: &str, default: f64| {
+                obj.style_property(key)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or(default)
+            };
+            let start = angle("startAngle", 0.0);
+            let end = angle("endAngle", 0.5);
+            let point = |a: f64| {
+                let rad = a * std::f64::consts::TAU;
+                [cx + w / 2.0 * rad.sin(), cy - h / 2.0 * rad.cos()]
+            };
+            let [sx, sy] = point(start);
+            let [ex, ey] = point(end);
+            let large_arc = u8::from((end - start).rem_euclid(1.0) > 0.5);
+            let _ = write!(
+                out,
+                r#"  <path d="M {sx} {sy} A {} {} 0 {large_arc} 1 {ex} {ey}""#,
+                w / 2.0,
+                h / 2.0
+            );
+        } else if !obj.poly_coords().is_empty() {
+            out.push_str(r#"  <polygon points=""#);
+            for (i, p) in obj.poly_coords().iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                let _ = write!(out, "{},{}", x + p[0] * w, y + p[1] * h);
+            }
+            out.push('"');
+        } else {
+            let _ = write!(out, r#"  <rect x="{x}" y="{y}" width="{w}" height="{h}""#);
+            if obj.rounded() == Some(true) {
+                let _ = write!(out, r#" rx="{}""#, w.min(h) * 0.15);
+            }
+        }
+        let _ = write!(out, r#" fill="{fill}""#);
+        write_stroke(
+            out,
+            &stroke,
+            obj.stroke_width(),
+            obj.style_property("dashed"),
+            obj.opacity(),
+        );
+        if !transform.is_empty() {
+            let _ = write!(out, r#" transform="{transform}""#);
+        }
+        out.push_str(" />\n");
+    }
+
+    if let Some(text) = obj.value().filter(|v| !v.is_empty()) {
+        let justify = obj.justify();
+        let (tx, anchor) = match justify.x {
+            JustifyX::Left => (x, "start"),
+            JustifyX::Center => (cx, "middle"),
+            JustifyX::Right => (x + w, "end"),
+        };
+        let (ty, baseline) = match justify.y {
+            JustifyY::Top => (y, "hanging"),
+            JustifyY::Middle => (cy, "central"),
+            JustifyY::Bottom => (y + h, "text-after-edge"),
+        };
+        let _ = write!(
+            out,
+            r#"  <text x="{tx}" y="{ty}" font-size="{}" fill="{}" text-anchor="{anchor}" dominant-baseline="{baseline}""#,
+            obj.font_size().unwrap_or(DEFAULT_FONT_SIZE),
+            paint_color(obj.font_color(), "#000000"),
+        );
+        if let Some(family) = obj.font_family() {
+            let _ = write!(out, r#" font-family="{}""#, XMLBase::xml_ify(family));
+        }
+        if !transform.is_empty() {
+            let _ = write!(out, r#" transform="{transform}""#);
+        }
+        let _ = writeln!(out, ">{}</text>", XMLBase::xml_ify(text));
+    }
+}
+
+fn cell_center(id: Option<&String>, cells: &HashMap<&str, &DiagramObject>) -> Option<[f64; 2]> {
+    let cell = cells.get(id?.as_str())?;
+    let origin = parent_origin(cell, cells);
+    let [x, y] = cell.bounding_box()?.center();
+    Some([origin[0] + x, origin[1] + y])
+}
+
+fn render_edge(
+    edge: &Edge,
+    origin: [f64; 2],
+    cells: &HashMap<&str, &DiagramObject>,
+    out: &mut String,
+    bounds: &mut Bounds,
+) {
+    let geometry = edge.geometry_ref();
+    let offset = |p: [f64; 2]| [origin[0] + p[0], origin[1] + p[1]];
+    let source = geometry
+        .source_point()
+        .map(offset)
+        .or_else(|| cell_center(edge.source(), cells));
+    let target = geometry
+        .target_point()
+        .map(offset)
+        .or_else(|| cell_center(edge.target(), cells));
+    let (Some(source), Some(target)) = (source, target) else {
+        return;
+    };
+    let points: Vec<[f64; 2]> = std::iter::once(source)
+        .chain(geometry.intermediate_points().iter().copied().map(offset))
+        .chain(std::iter::once(target))
+        .collect();
+
+    out.push_str(r#"  <polyline points=""#);
+    for (i, p) in points.iter().enumerate() {
+        bounds.add(*p);
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{},{}", p[0], p[1]);
+    }
+    out.push_str(r#"" fill="none""#);
+    write_stroke(
+        out,
+        &paint_color(edge.stroke_color(), "#000000"),
+        edge.stroke_width(),
+        edge.style_property("dashed"),
+        edge.opacity(),
+    );
+    out.push_str(" />\n");
+
+    if let Some(label) = edge.label().filter(|l| !l.is_empty()) {
+        let mid = points[points.len() / 2];
+        let prev = points[(points.len() - 1) / 2];
+        let _ = writeln!(
+            out,
+            r##"  <text x="{}" y="{}" font-size="{DEFAULT_FONT_SIZE}" fill="#000000" text-anchor="middle" dominant-baseline="central">{}</text>"##,
+            (mid[0] + prev[0]) / 2.0,
+            (mid[1] + prev[1]) / 2.0,
+            XMLBase::xml_ify(label)
+        );
+    }
+}
@@ -3,10 +3,13 @@ use crate::{
     DrawrsError::UnsupportedOrient,
     DrawrsResult,
     diagram::text_format::{Justify, JustifyX},
+    xml_base::{XMLBase, sanitize_id, sanitize_tag},
 };
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Orient {
     R0,
     R90,
@@ -18,12 +21,16 @@ pub enum Orient {
     MXR90,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct BoundingBox {
-    pub min_x: f64,
-    pub min_y: f64,
-    pub width: f64,
-    pub height: f64,
+    #[cfg_attr(feature = "serde", serde(rename = "x"))]
+    min_x: f64,
+    #[cfg_attr(feature = "serde", serde(rename = "y"))]
+    min_y: f64,
+    width: f64,
+    height: f64,
 }
 
 impl BoundingBox {
@@ -36,6 +43,38 @@ impl BoundingBox {
         }
     }
 
+    pub fn min_x(&self) -> f64 {
+        self.min_x
+    }
+
+    pub fn min_y(&self) -> f64 {
+        self.min_y
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn set_min_x(&mut self, min_x: f64) {
+        self.min_x = min_x;
+    }
+
+    pub fn set_min_y(&mut self, min_y: f64) {
+        self.min_y = min_y;
+    }
+
+    pub fn set_width(&mut self, width: f64) {
+        self.width = width;
+    }
+
+    pub fn set_height(&mut self, height: f64) {
+        self.height = height;
+    }
+
     pub fn max_x(&self) -> f64 {
         self.min_x + self.width
     }
@@ -43,6 +82,80 @@ impl BoundingBox {
     pub fn max_y(&self) -> f64 {
         self.min_y + self.height
     }
+
+    pub fn center(&self) -> [f64; 2] {
+        [
+            self.min_x + self.width / 2.0,
+            self.min_y + self.height / 2.0,
+        ]
+    }
+
+    /// Whether the boxes overlap, boxes that only share an edge do not intersect
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min_x < other.max_x()
+            && other.min_x < self.max_x()
+            && self.min_y < other.max_y()
+            && other.min_y < self.max_y()
+    }
+
+    /// Overlapping area, `None` when the boxes do not [`intersect`](Self::intersects)
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let min_x = self.min_x.max(other.min_x);
+        let min_y = self.min_y.max(other.min_y);
+        Some(Self::new(
+            min_x,
+            min_y,
+            self.max_x().min(other.max_x()) - min_x,
+            self.max_y().min(other.max_y()) - min_y,
+        ))
+    }
+
+    /// Whether the point is inside the box or on its border
+    pub fn contains_point(&self, [x, y]: [f64; 2]) -> bool {
+        self.min_x <= x && x <= self.max_x() && self.min_y <= y && y <= self.max_y()
+    }
+
+    /// Whether `other` lies inside the box, borders included
+    pub fn contains(&self, other: &Self) -> bool {
+        self.min_x <= other.min_x
+            && other.max_x() <= self.max_x()
+            && self.min_y <= other.min_y
+            && other.max_y() <= self.max_y()
+    }
+
+    /// Grow the box by `margin` on every side, a negative margin shrinks it
+    pub fn expand(&mut self, margin: f64) {
+        self.min_x -= margin;
+        self.min_y -= margin;
+        self.width += 2.0 * margin;
+        self.height += 2.0 * margin;
+    }
+
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        self.min_x += dx;
+        self.min_y += dy;
+    }
+
+    /// Scale width and height by `factor`, keeping the center in place
+    pub fn scale_about_center(&mut self, factor: f64) {
+        let [center_x, center_y] = self.center();
+        self.width *= factor;
+        self.height *= factor;
+        self.min_x = center_x - self.width / 2.0;
+        self.min_y = center_y - self.height / 2.0;
+    }
+
+    /// Field-wise comparison within `epsilon`
+    pub fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        (self.min_x - other.min_x).abs() <= epsilon
+            && (self.min_y - other.min_y).abs() <= epsilon
+            && (self.width - other.width).abs() <= epsilon
+            && (self.height - other.height).abs() <= epsilon
+    }
+
     pub fn union(bboxs: impl Iterator<Item = Self>) -> Option<Self> {
         let mut min_x = f64::INFINITY;
         let mut min_y = f64::INFINITY;
@@ -72,27 +185,27 @@ pub struct GroupTransform<'a> {
     orient: Orient,
     inst_name: &'a str,
     cell_name: &'a str,
+    substitutions: IndexMap<String, String>,
+    params: IndexMap<String, String>,
+    unknown_placeholder: String,
+    cell_tag: bool,
+    tag_sanitizer: fn(&str) -> String,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
 pub struct FlipRotation {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     flip_h: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     flip_v: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     legacy_anchor_points: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     rotation: Option<f64>,
 }
 
-impl Default for FlipRotation {
-    fn default() -> Self {
-        Self {
-            flip_h: None,
-            flip_v: None,
-            legacy_anchor_points: None,
-            rotation: None,
-        }
-    }
-}
-
 impl FlipRotation {
     pub fn flip_h(&self) -> Option<usize> {
         self.flip_h
@@ -127,6 +240,121 @@ impl FlipRotation {
     }
 }
 
+/// Affine map `(x, y) -> (a·x + b·y + c, d·x + e·y + f)` in page
+/// coordinates (y down), stored as the rows `[[a, b, c], [d, e, f]]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    matrix: [[f64; 3]; 2],
+}
+
+impl Transform2D {
+    pub const IDENTITY: Self = Self {
+        matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+    };
+
+    pub fn new(matrix: [[f64; 3]; 2]) -> Self {
+        Self { matrix }
+    }
+
+    pub fn matrix(&self) -> [[f64; 3]; 2] {
+        self.matrix
+    }
+
+    /// Rotation by `degrees`, clockwise on the page like the draw.io
+    /// `rotation` style
+    pub fn rotation(degrees: f64) -> Self {
+        let (sin, cos) = match degrees.rem_euclid(360.0) {
+            // Exact values for the right angles
+            0.0 => (0.0, 1.0),
+            90.0 => (1.0, 0.0),
+            180.0 => (0.0, -1.0),
+            270.0 => (-1.0, 0.0),
+            _ => degrees.to_radians().sin_cos(),
+        };
+        Self::new([[cos, -sin, 0.0], [sin, cos, 0.0]])
+    }
+
+    pub fn translation(dx: f64, dy: f64) -> Self {
+        Self::new([[1.0, 0.0, dx], [0.0, 1.0, dy]])
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self::new([[sx, 0.0, 0.0], [0.0, sy, 0.0]])
+    }
+
+    /// Placement of a symbol by a Cadence orientation, counterclockwise
+    /// rotations in schematic coordinates and mirroring before rotation
+    pub fn from_orient(orient: Orient) -> Self {
+        match orient {
+            Orient::R0 => Self::IDENTITY,
+            Orient::R90 => Self::rotation(-90.0),
+            Orient::R180 => Self::rotation(180.0),
+            Orient::R270 => Self::rotation(90.0),
+            Orient::MY => Self::scale(-1.0, 1.0),
+            Orient::MX => Self::scale(1.0, -1.0),
+            Orient::MYR90 => Self::scale(-1.0, 1.0).then(Self::rotation(-90.0)),
+            Orient::MXR90 => Self::scale(1.0, -1.0).then(Self::rotation(-90.0)),
+        }
+    }
+
+    /// `self` followed by `next`
+    pub fn then(self, next: Self) -> Self {
+        let [[a, b, c], [d, e, f]] = self.matrix;
+        let [[na, nb, nc], [nd, ne, nf]] = next.matrix;
+        Self::new([
+            [na * a + nb * d, na * b + nb * e, na * c + nb * f + nc],
+            [nd * a + ne * d, nd * b + ne * e, nd * c + ne * f + nf],
+        ])
+    }
+
+    pub fn apply(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let [[a, b, c], [d, e, f]] = self.matrix;
+        [a * x + b * y + c, d * x + e * y + f]
+    }
+
+    /// Whether the transform mirrors the page
+    pub fn is_mirrored(&self) -> bool {
+        let [[a, b, _], [d, e, _]] = self.matrix;
+        a * e - b * d < 0.0
+    }
+
+    /// Rotation and horizontal flip left once the translation and scaling
+    /// are removed, the flip applies first like in draw.io
+    pub fn residual(&self) -> FlipRotation {
+        let [[a, _, _], [d, _, _]] = self.matrix;
+        let mirrored = self.is_mirrored();
+        // Undo the flip: the first column changes sign
+        let (cos, sin) = if mirrored { (-a, -d) } else { (a, d) };
+        let mut degrees = sin.atan2(cos).to_degrees();
+        // In (-180, 180]
+        if degrees <= -180.0 {
+            degrees += 360.0;
+        }
+        FlipRotation {
+            flip_h: mirrored.then_some(1),
+            rotation: (degrees != 0.0).then_some(degrees),
+            ..Default::default()
+        }
+    }
+
+    /// Axis-aligned box of the transformed corners, with the [`residual`](Self::residual)
+    /// rotation and flip of the content
+    pub fn apply_box(&self, bbox: &BoundingBox) -> (BoundingBox, FlipRotation) {
+        let corners = [
+            [bbox.min_x(), bbox.min_y()],
+            [bbox.max_x(), bbox.min_y()],
+            [bbox.min_x(), bbox.max_y()],
+            [bbox.max_x(), bbox.max_y()],
+        ];
+        let aabb = BoundingBox::union(corners.into_iter().map(|corner| {
+            let [x, y] = self.apply(corner);
+            BoundingBox::new(x, y, 0.0, 0.0)
+        }))
+        .unwrap();
+        (aabb, self.residual())
+    }
+}
+
 impl<'a> GroupTransform<'a> {
     pub fn new(
         origin_bounding_box: BoundingBox,
@@ -143,85 +371,190 @@ impl<'a> GroupTransform<'a> {
             orient,
             inst_name,
             cell_name,
+            substitutions: IndexMap::new(),
+            params: IndexMap::new(),
+            unknown_placeholder: String::new(),
+            cell_tag: false,
+            tag_sanitizer: sanitize_tag,
+        }
+    }
+
+    /// Placeholder replacements, e.g. `"[@partName]"` or `"cdsTerm(\"G\")"`,
+    /// taking priority over the built-in ones
+    pub fn with_substitutions(
+        mut self,
+        substitutions: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        self.substitutions.extend(substitutions);
+        self
+    }
+
+    /// Instance parameters used by `cdsParam("name")` and `cdsParam(n)`
+    pub fn with_params(mut self, params: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.params.extend(params);
+        self
+    }
+
+    /// Tag the objects with the cell name after the instance name, e.g.
+    /// `"X42 nch_lvt"`, so draw.io can filter all instances of a cell
+    pub fn with_cell_tag(mut self, cell_tag: bool) -> Self {
+        self.cell_tag = cell_tag;
+        self
+    }
+
+    /// Turn the instance and cell names into single tags, [`sanitize_tag`]
+    /// by default
+    pub fn with_tag_sanitizer(mut self, tag_sanitizer: fn(&str) -> String) -> Self {
+        self.tag_sanitizer = tag_sanitizer;
+        self
+    }
+
+    /// Replacement of unresolved placeholders, empty by default
+    pub fn with_unknown_placeholder(mut self, text: impl Into<String>) -> Self {
+        self.unknown_placeholder = text.into();
+        self
+    }
+
+    /// Length of the placeholder at the start of `text`
+    fn placeholder_len(text: &str) -> Option<usize> {
+        if text.starts_with("[@") {
+            text.find(']').map(|i| i + 1)
+        } else if ["cdsName(", "cdsParam(", "cdsTerm("]
+            .iter()
+            .any(|prefix| text.starts_with(prefix))
+        {
+            text.find(')').map(|i| i + 1)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&self, placeholder: &str) -> String {
+        if let Some(value) = self.substitutions.get(placeholder) {
+            return value.clone();
         }
+        let resolved = match placeholder {
+            "[@instanceName]" | "cdsName()" => Some(self.inst_name.to_owned()),
+            "[@cellName]" | "[@partName]" => Some(self.cell_name.to_owned()),
+            _ => placeholder
+                .strip_prefix("cdsParam(")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|arg| {
+                    let arg = arg.trim().trim_matches(|c| c == '"' || c == '\'');
+                    // Numeric arguments index the parameters, starting from 1
+                    match arg.parse::<usize>() {
+                        Ok(n) => n
+                            .checked_sub(1)
+                            .and_then(|i| self.params.get_index(i))
+                            .map(|(key, value)| format!("{key}={value}")),
+                        Err(_) => self.params.get(arg).cloned(),
+                    }
+                }),
+        };
+        resolved.unwrap_or_else(|| self.unknown_placeholder.clone())
+    }
+
+    /// Expand the `[@...]`, `cdsName()`, `cdsParam(...)` and `cdsTerm(...)` placeholders
+    pub fn substitute(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if let Some(len) = Self::placeholder_len(rest) {
+                out.push_str(&self.resolve(&rest[..len]));
+                rest = &rest[len..];
+            } else {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        out
     }
 
     fn update_text(&self, text: Option<&mut String>) {
         if let Some(t) = text {
-            *t = t.replace("[@cellName]", &self.inst_name);
-            *t = t.replace("cdsName()", &self.cell_name);
+            *t = self.substitute(t);
+        }
+    }
+
+    /// Orientation and offset of the instance. Text justification is only
+    /// defined for R0, R90, R270 and MY so far.
+    fn transform(&self) -> DrawrsResult<Transform2D> {
+        match self.orient {
+            Orient::R0 | Orient::R90 | Orient::R270 | Orient::MY => {
+                Ok(Transform2D::from_orient(self.orient)
+                    .then(Transform2D::translation(self.offset_x, self.offset_y)))
+            }
+            Orient::R180 | Orient::MX | Orient::MYR90 | Orient::MXR90 => {
+                Err(UnsupportedOrient(self.orient))
+            }
         }
     }
 
+    /// Axis-aligned box of the transformed origin bounding box
+    pub fn bounding_box(&self) -> DrawrsResult<BoundingBox> {
+        Ok(self.transform()?.apply_box(&self.origin_bounding_box).0)
+    }
+
+    /// draw.io group cell wrapping the objects of the instance, with the
+    /// instance name as id and the transformed origin bounding box as geometry
+    pub fn group_cell(&self, parent: &str) -> DrawrsResult<XMLBase> {
+        let mut group = XMLBase::new(Some(sanitize_id(self.inst_name)));
+        group.xml_class = "mxCell".to_string();
+        group.xml_parent = Some(parent.to_string());
+        group.group_geometry = Some(self.bounding_box()?);
+        Ok(group)
+    }
+
+    /// Move an object of [`Self::new_obj`] into a [`Self::group_cell`], its
+    /// coordinates become relative to the group. The former parent, e.g. the
+    /// layer cell, is added to the tags so draw.io can still filter by layer.
+    pub fn move_into_group(obj: &mut DiagramObject, group: &XMLBase) {
+        let Some(group_box) = group.group_geometry else {
+            return;
+        };
+        let [dx, dy] = [group_box.min_x(), group_box.min_y()];
+        for point in obj.mut_points() {
+            *point = [point[0] - dx, point[1] - dy];
+        }
+        if let Some((bbox, _)) = obj.mut_box() {
+            bbox.set_min_x(bbox.min_x() - dx);
+            bbox.set_min_y(bbox.min_y() - dy);
+        }
+        if let Some(parent) = obj.xml_parent().map(str::to_string) {
+            obj.add_tag(&parent);
+        }
+        obj.set_xml_parent(Some(group.id.clone()));
+    }
+
     /// Transform points from origin coordinates to group-relative coordinates
-    /// Points remain in their original coordinates (no transform applied)
     fn update_points<'b, I: Iterator<Item = &'b mut [f64; 2]>>(
         &self,
         points: I,
     ) -> DrawrsResult<()> {
-        // Points keep their original coordinates within the group
+        let mut points = points.peekable();
+        if points.peek().is_none() {
+            return Ok(());
+        }
+        let transform = self.transform()?;
         for point in points {
-            match self.orient {
-                Orient::R0 => {}
-                Orient::MY => {
-                    point[0] = -point[0];
-                }
-                Orient::R90 => {
-                    *point = [point[1], -point[0]];
-                }
-                Orient::R180 => return Err(UnsupportedOrient(self.orient)),
-                Orient::R270 => {
-                    *point = [-point[1], point[0]];
-                }
-                Orient::MX => return Err(UnsupportedOrient(self.orient)),
-                Orient::MYR90 => return Err(UnsupportedOrient(self.orient)),
-                Orient::MXR90 => return Err(UnsupportedOrient(self.orient)),
-            }
-            point[0] += self.offset_x;
-            point[1] += self.offset_y;
+            *point = transform.apply(*point);
         }
         Ok(())
     }
 
-    /// Transform bounding boxes from origin coordinates to group-relative coordinates
-    /// Bounding boxes remain in their original coordinates (no transform applied)
+    /// Transform bounding boxes from origin coordinates to group-relative coordinates.
+    /// draw.io keeps the unrotated size around the new center and rotates the
+    /// content with the `rotation` style.
     fn update_box(&self, bbox: Option<(&mut BoundingBox, &mut FlipRotation)>) -> DrawrsResult<()> {
-        // Bounding boxes and flip rotations keep their original values within the group
         if let Some((bbox, flip_rotation)) = bbox {
-            match self.orient {
-                Orient::R0 => {}
-                Orient::R90 => {
-                    [bbox.min_x, bbox.min_y] = [
-                        bbox.min_y - (bbox.width - bbox.height) / 2.0,
-                        -bbox.min_x - bbox.width / 2.0 - bbox.height / 2.0,
-                    ];
-                    flip_rotation.set_rotation(Some(-90.0));
-                }
-                Orient::R180 => {
-                    return Err(UnsupportedOrient(self.orient));
-                }
-                Orient::R270 => {
-                    [bbox.min_x, bbox.min_y] = [
-                        -bbox.min_y - (bbox.width + bbox.height) / 2.0,
-                        bbox.min_x + bbox.width / 2.0 - bbox.height / 2.0,
-                    ];
-                    flip_rotation.set_rotation(Some(90.0));
-                }
-                Orient::MY => {
-                    bbox.min_x = -(bbox.min_x + bbox.width);
-                }
-                Orient::MX => {
-                    return Err(UnsupportedOrient(self.orient));
-                }
-                Orient::MYR90 => {
-                    return Err(UnsupportedOrient(self.orient));
-                }
-                Orient::MXR90 => {
-                    return Err(UnsupportedOrient(self.orient));
-                }
+            let (aabb, residual) = self.transform()?.apply_box(bbox);
+            let [center_x, center_y] = aabb.center();
+            bbox.set_min_x(center_x - bbox.width() / 2.0);
+            bbox.set_min_y(center_y - bbox.height() / 2.0);
+            // Mirrored shapes are not flipped, only moved
+            if let Some(rotation) = residual.rotation() {
+                flip_rotation.set_rotation(Some(rotation));
             }
-            bbox.min_x += self.offset_x;
-            bbox.min_y += self.offset_y;
         }
         Ok(())
     }
@@ -262,9 +595,20 @@ impl<'a> GroupTransform<'a> {
     }
     pub fn new_obj(&self, obj: &DiagramObject) -> DrawrsResult<DiagramObject> {
         let mut new_obj: DiagramObject = obj.clone();
-        new_obj.set_id(format!("{}-{}", self.inst_name, new_obj.id()));
+        let prefix = sanitize_id(self.inst_name);
+        new_obj.set_id(format!("{prefix}-{}", new_obj.id()));
+        // Edges stay attached to the cells of the same instance
+        if let DiagramObject::Edge(edge) = &mut new_obj {
+            let source = edge.source().map(|id| format!("{prefix}-{id}"));
+            edge.set_source(source);
+            let target = edge.target().map(|id| format!("{prefix}-{id}"));
+            edge.set_target(target);
+        }
         self.update_text(new_obj.text_mut());
-        new_obj.set_tag(Some(self.inst_name.to_owned()));
+        new_obj.set_tag(Some((self.tag_sanitizer)(self.inst_name)));
+        if self.cell_tag {
+            new_obj.add_tag(&(self.tag_sanitizer)(self.cell_name));
+        }
 
         if let Some(parent) = new_obj.xml_parent() {
             if parent.starts_with("layer-") {
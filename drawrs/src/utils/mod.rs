@@ -1,6 +1,8 @@
 pub mod color_scheme;
 pub mod page_sizes;
 pub mod standard_colors;
+pub mod stencil;
 
 pub use page_sizes::PageSize;
 pub use standard_colors::StandardColor;
+pub use stencil::Stencil;
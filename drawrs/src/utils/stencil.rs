@@ -0,0 +1,111 @@
+//! Custom shapes given inline in the style, `shape=stencil(...)`.
+//!
+//! draw.io stores the stencil XML URI-encoded, raw-deflated and base64
+//! encoded, see [`compress`].
+
+use crate::xml_base::XmlFloat;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+
+/// Stencil drawn in a `w` x `h` box, stretched to the size of the cell
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stencil {
+    w: f64,
+    h: f64,
+    foreground: String,
+}
+
+impl Stencil {
+    pub fn new(w: f64, h: f64) -> Self {
+        Self {
+            w,
+            h,
+            foreground: String::new(),
+        }
+    }
+
+    /// Add a filled and stroked path made of closed rings. The rings of a path
+    /// are filled together with the non-zero rule, so a ring wound opposite
+    /// to the outline cuts a hole.
+    pub fn add_path(&mut self, rings: &[Vec<[f64; 2]>]) {
+        self.foreground.push_str("<path>");
+        for ring in rings.iter().filter(|ring| !ring.is_empty()) {
+            for (i, [x, y]) in ring.iter().enumerate() {
+                let tag = if i == 0 { "move" } else { "line" };
+                let _ = write!(
+                    self.foreground,
+                    r#"<{tag} x="{}" y="{}"/>"#,
+                    XmlFloat(*x),
+                    XmlFloat(*y)
+                );
+            }
+            self.foreground.push_str("<close/>");
+        }
+        self.foreground.push_str("</path><fillstroke/>");
+    }
+
+    /// The stencil XML
+    pub fn xml(&self) -> String {
+        format!(
+            r#"<shape w="{}" h="{}" aspect="variable" strokewidth="inherit"><foreground>{}</foreground></shape>"#,
+            XmlFloat(self.w),
+            XmlFloat(self.h),
+            self.foreground
+        )
+    }
+
+    /// Value of the `shape` style key
+    pub fn style_value(&self) -> String {
+        format!("stencil({})", compress(&self.xml()))
+    }
+}
+
+/// Compress as draw.io does: URI-encode, raw deflate, then base64
+pub fn compress(data: &str) -> String {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec cannot fail
+    let _ = encoder.write_all(encode_uri_component(data).as_bytes());
+    STANDARD.encode(encoder.finish().unwrap_or_default())
+}
+
+/// Inverse of [`compress`], `None` if the data is not compressed
+pub fn decompress(data: &str) -> Option<String> {
+    let deflated = STANDARD.decode(data.trim()).ok()?;
+    let mut encoded = String::new();
+    DeflateDecoder::new(deflated.as_slice())
+        .read_to_string(&mut encoded)
+        .ok()?;
+    decode_uri_component(&encoded)
+}
+
+/// JavaScript's `encodeURIComponent`
+pub(crate) fn encode_uri_component(data: &str) -> String {
+    let mut encoded = String::with_capacity(data.len());
+    for byte in data.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+fn decode_uri_component(data: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(data.len());
+    let mut iter = data.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -1,43 +1,160 @@
+use crate::error::{DrawrsError, DrawrsResult};
+use crate::utils::stencil::encode_uri_component;
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fmt;
-use uuid::Uuid;
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct XMLBase {
     pub id: String,
     pub xml_class: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "parent", default, skip_serializing_if = "Option::is_none")
+    )]
     pub xml_parent: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "tags", default, skip_serializing_if = "Option::is_none")
+    )]
     pub tag: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub tooltip: Option<String>,
+    /// Hyperlink followed on click in draw.io
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub link: Option<String>,
+    /// Custom data attributes of the `UserObject` wrapper (draw.io "Edit Data")
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "IndexMap::is_empty")
+    )]
+    pub attributes: IndexMap<String, String>,
+    /// Unrecognized mxCell attributes kept from parsing, emitted verbatim
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "IndexMap::is_empty")
+    )]
+    pub preserved_attributes: IndexMap<String, String>,
+    /// Unrecognized mxCell child elements kept from parsing, as raw XML
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub preserved_elements: Vec<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub visible: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub value: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub group_geometry: Option<BoundingBox>,
+    /// Style class of the cell, see [`StyleClass`]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub style_class: Option<StyleClass>,
+}
+
+// The style class is left out when unset, as in the cells read from a file
+impl fmt::Debug for XMLBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("XMLBase");
+        debug
+            .field("id", &self.id)
+            .field("xml_class", &self.xml_class)
+            .field("xml_parent", &self.xml_parent)
+            .field("tag", &self.tag)
+            .field("tooltip", &self.tooltip)
+            .field("link", &self.link)
+            .field("attributes", &self.attributes)
+            .field("preserved_attributes", &self.preserved_attributes)
+            .field("preserved_elements", &self.preserved_elements)
+            .field("visible", &self.visible)
+            .field("value", &self.value)
+            .field("group_geometry", &self.group_geometry);
+        if let Some(style_class) = &self.style_class {
+            debug.field("style_class", style_class);
+        }
+        debug.finish()
+    }
+}
+
+/// A named style shared by cells, written before the own style of each
+/// cell. The style of the page class of the same name is used when the
+/// cell is written as part of a page, see [`Page::define_style`], `style`
+/// otherwise, e.g. by [`Object::xml`].
+///
+/// [`Page::define_style`]: crate::Page::define_style
+/// [`Object::xml`]: crate::Object::xml
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyleClass {
+    pub name: String,
+    pub style: String,
+}
+
+impl StyleClass {
+    pub fn new(name: impl Into<String>, style: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            style: style.into(),
+        }
+    }
 }
 
 impl XMLBase {
     pub fn new(id: Option<String>) -> Self {
         Self {
-            id: id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            id: id.unwrap_or_else(new_id),
             xml_class: "xml_tag".to_string(),
             xml_parent: None,
             tag: None,
             tooltip: None,
+            link: None,
+            attributes: IndexMap::new(),
+            preserved_attributes: IndexMap::new(),
+            preserved_elements: Vec::new(),
             visible: None,
             value: None,
             group_geometry: None,
+            style_class: None,
         }
     }
 
     pub fn with_class(xml_class: String) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: new_id(),
             xml_class,
             xml_parent: None,
             tag: None,
             tooltip: None,
+            link: None,
+            attributes: IndexMap::new(),
+            preserved_attributes: IndexMap::new(),
+            preserved_elements: Vec::new(),
             visible: None,
             value: None,
             group_geometry: None,
+            style_class: None,
         }
     }
 
@@ -73,6 +190,24 @@ impl XMLBase {
     pub fn xml(&self) -> XMLBaseXml<'_> {
         XMLBaseXml(self)
     }
+
+    /// First text of the cell with characters XML 1.0 does not allow, which
+    /// the output drops, see [`sanitize_text`]
+    pub fn invalid_text(&self) -> Option<&str> {
+        [
+            Some(&self.id),
+            self.value.as_ref(),
+            self.tag.as_ref(),
+            self.tooltip.as_ref(),
+            self.link.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.attributes.values())
+        .chain(self.preserved_attributes.values())
+        .map(String::as_str)
+        .find(|text| !text.chars().all(is_xml_char))
+    }
 }
 
 pub struct XMLBaseXml<'a>(&'a XMLBase);
@@ -88,34 +223,44 @@ impl<'a> fmt::Display for XMLBaseXml<'a> {
                 .as_ref()
                 .map(|p| p.as_str())
                 .unwrap_or("1");
+            // The style and connectable flag of a parsed group are kept
+            let preserved = |key: &str, default: &'static str| {
+                self.0
+                    .preserved_attributes
+                    .get(key)
+                    .map_or(default, String::as_str)
+            };
+            write!(
+                f,
+                r#"<mxCell id="{}" connectable="{}" parent="{}" style="{}""#,
+                XMLBase::xml_ify(&self.0.id),
+                XmlAttr(preserved("connectable", "0")),
+                XmlAttr(parent_id),
+                XmlAttr(preserved("style", "group")),
+            )?;
             if let Some(v) = &self.0.value {
-                write!(
-                    f,
-                    r#"<mxCell id="{}" connectable="0" parent="{}" style="group" value="{}" vertex="1">
-          <mxGeometry x="{}" y="{}" width="{}" height="{}" as="geometry" />
-        </mxCell>"#,
-                    XMLBase::xml_ify(&self.0.id),
-                    parent_id,
-                    XMLBase::xml_ify(v),
-                    bbox.min_x,
-                    bbox.min_y,
-                    bbox.width,
-                    bbox.height
-                )
-            } else {
-                write!(
-                    f,
-                    r#"<mxCell id="{}" connectable="0" parent="{}" style="group" vertex="1">
-          <mxGeometry x="{}" y="{}" width="{}" height="{}" as="geometry" />
-        </mxCell>"#,
-                    XMLBase::xml_ify(&self.0.id),
-                    parent_id,
-                    bbox.min_x,
-                    bbox.min_y,
-                    bbox.width,
-                    bbox.height
-                )
+                write!(f, r#" value="{}""#, XMLBase::xml_ify(v))?;
+            }
+            write!(f, r#" vertex="1""#)?;
+            if let Some(visible) = &self.0.visible {
+                write!(f, r#" visible="{}""#, XmlAttr(visible))?;
             }
+            for (key, value) in &self.0.preserved_attributes {
+                if key != "style" && key != "connectable" {
+                    write!(f, r#" {}="{}""#, key, XmlAttr(value))?;
+                }
+            }
+            write!(
+                f,
+                r#">
+          <mxGeometry x="{}" y="{}" width="{}" height="{}" as="geometry" />{}
+        </mxCell>"#,
+                XmlFloat(bbox.min_x()),
+                XmlFloat(bbox.min_y()),
+                XmlFloat(bbox.width()),
+                XmlFloat(bbox.height()),
+                self.0.mx_cell_children()
+            )
         } else {
             // Always output as self-closing tag, value is included in attributes
             write!(f, "<{}", self.0.xml_class)?;
@@ -129,27 +274,105 @@ impl<'a> fmt::Display for XMLBaseXml<'a> {
             if let Some(ref value) = self.0.value {
                 write!(f, r#" value="{}""#, XMLBase::xml_ify(value))?;
             }
+            for (key, value) in &self.0.preserved_attributes {
+                write!(f, r#" {}="{}""#, key, XmlAttr(value))?;
+            }
             write!(f, " />")
         }
     }
 }
 
 impl XMLBase {
+    /// Escape `&`, `<`, `>`, `"` and `'`, the input is taken literally
     pub fn xml_ify(parameter_string: &str) -> String {
-        // First, decode any existing XML entities to avoid double-escaping
-        let decoded = Self::decode_xml_entities(parameter_string);
-        Self::translate_txt(&decoded, &XML_ESCAPE_MAP)
+        let mut escaped = String::with_capacity(parameter_string.len());
+        // Writing to a String cannot fail
+        let _ = write_escaped(&mut escaped, parameter_string);
+        escaped
     }
 
+    /// Decode the predefined XML entities
     pub fn decode_xml_entities(s: &str) -> String {
-        // Decode common XML entities to avoid double-escaping
-        // This ensures that if a string contains &quot; as a literal, it gets decoded to "
-        // before being escaped again
-        s.replace("&amp;", "&")
-            .replace("&lt;", "<")
+        // `&amp;` goes last so "&amp;lt;" decodes to "&lt;"
+        s.replace("&lt;", "<")
             .replace("&gt;", ">")
             .replace("&quot;", "\"")
             .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    }
+
+    /// Entries of the space separated `tags` attribute
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tag.iter().flat_map(|tag| tag.split_whitespace())
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().any(|t| t == tag)
+    }
+
+    /// Add space separated tags, skipping the ones already present
+    pub fn add_tag(&mut self, tag: &str) {
+        let tags = self.tag.take().unwrap_or_default();
+        self.tag = Self::normalize_tags(&format!("{tags} {tag}"));
+    }
+
+    /// Tags separated by single spaces without duplicates, `None` when empty
+    pub fn normalize_tags(tags: &str) -> Option<String> {
+        let mut unique: Vec<&str> = Vec::new();
+        for tag in tags.split_whitespace() {
+            if !unique.contains(&tag) {
+                unique.push(tag);
+            }
+        }
+        (!unique.is_empty()).then(|| unique.join(" "))
+    }
+
+    /// Attributes of the `UserObject` wrapper with a dedicated meaning,
+    /// custom attributes with these keys are not emitted
+    pub const RESERVED_ATTRIBUTES: [&'static str; 5] = ["id", "label", "tags", "tooltip", "link"];
+
+    /// Whether `name` can be written as an XML attribute name: a letter, `_`
+    /// or `:` then letters, digits, `-`, `.`, `_` or `:`
+    pub fn is_xml_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '_' | ':'))
+    }
+
+    /// Set a custom attribute, a key that is not an XML name is rejected,
+    /// see [`Self::is_xml_name`]
+    pub fn set_attribute(&mut self, key: String, value: String) -> DrawrsResult<()> {
+        if !Self::is_xml_name(&key) {
+            return Err(DrawrsError::InvalidAttributeName(key));
+        }
+        self.attributes.insert(key, value);
+        Ok(())
+    }
+
+    /// ` tags="..."`, ` tooltip="..."`, ` link="..."` and the custom attributes for the
+    /// `UserObject` wrapper, `None` when a plain mxCell is enough
+    pub(crate) fn user_object_attributes(&self) -> Option<UserObjectAttrs<'_>> {
+        if self.tag.is_none()
+            && self.tooltip.is_none()
+            && self.link.is_none()
+            && self.attributes.is_empty()
+        {
+            return None;
+        }
+        Some(UserObjectAttrs(self))
+    }
+
+    /// ` visible="..."` and the preserved attributes of the mxCell of an
+    /// object or edge
+    pub(crate) fn mx_cell_attributes(&self) -> MxCellAttrs<'_> {
+        MxCellAttrs(self)
+    }
+
+    /// Preserved child elements of the mxCell, one per line after the geometry
+    pub(crate) fn mx_cell_children(&self) -> MxCellChildren<'_> {
+        MxCellChildren(self)
     }
 
     pub fn translate_txt(string: &str, replacement_dict: &HashMap<char, &str>) -> String {
@@ -165,25 +388,389 @@ impl XMLBase {
     }
 }
 
+pub(crate) struct UserObjectAttrs<'a>(&'a XMLBase);
+
+impl fmt::Display for UserObjectAttrs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(tag) = &self.0.tag {
+            write!(f, r#" tags="{}""#, XmlAttr(tag))?;
+        }
+        if let Some(tooltip) = &self.0.tooltip {
+            write!(f, r#" tooltip="{}""#, XmlAttr(tooltip))?;
+        }
+        if let Some(link) = &self.0.link {
+            write!(f, r#" link="{}""#, XmlAttr(link))?;
+        }
+        for (key, value) in &self.0.attributes {
+            // The keys inserted directly in `attributes` are not checked
+            if !XMLBase::RESERVED_ATTRIBUTES.contains(&key.as_str()) && XMLBase::is_xml_name(key) {
+                write!(f, r#" {}="{}""#, key, XmlAttr(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct MxCellAttrs<'a>(&'a XMLBase);
+
+impl fmt::Display for MxCellAttrs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(visible) = &self.0.visible {
+            write!(f, r#" visible="{}""#, XmlAttr(visible))?;
+        }
+        for (key, value) in &self.0.preserved_attributes {
+            write!(f, r#" {}="{}""#, key, XmlAttr(value))?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct MxCellChildren<'a>(&'a XMLBase);
+
+impl fmt::Display for MxCellChildren<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for element in &self.0.preserved_elements {
+            write!(f, "\n          {element}")?;
+        }
+        Ok(())
+    }
+}
+
 impl Default for XMLBase {
     fn default() -> Self {
         Self::new(None)
     }
 }
 
-use once_cell::sync::Lazy;
-
 use crate::BoundingBox;
 
-static XML_ESCAPE_MAP: Lazy<HashMap<char, &'static str>> = Lazy::new(|| {
-    let mut m = HashMap::new();
-    m.insert('>', "&gt;");
-    m.insert('<', "&lt;");
-    m.insert('&', "&amp;");
-    m.insert('"', "&quot;");
-    m.insert('\'', "&apos;");
-    m.insert('\n', "&#xa;");
-    m.insert('\t', "&#x9;");
-    m.insert('\r', "&#xd;");
-    m
-});
+/// Default maximum number of decimals of the floats written to the XML
+pub const DEFAULT_FLOAT_PRECISION: usize = 4;
+
+thread_local! {
+    static FLOAT_PRECISION: Cell<usize> = const { Cell::new(DEFAULT_FLOAT_PRECISION) };
+}
+
+/// Maximum number of decimals of the floats written to the XML on this thread
+pub fn float_precision() -> usize {
+    FLOAT_PRECISION.with(Cell::get)
+}
+
+/// Set the maximum number of decimals of the floats written to the XML on
+/// this thread. [`Page`](crate::Page) and [`DrawFile`](crate::DrawFile) can
+/// override it for their own output.
+pub fn set_float_precision(decimals: usize) {
+    FLOAT_PRECISION.with(|precision| precision.set(decimals));
+}
+
+/// Run `f` with the float precision temporarily set to `decimals`, if any
+pub(crate) fn with_float_precision<R>(decimals: Option<usize>, f: impl FnOnce() -> R) -> R {
+    let Some(decimals) = decimals else {
+        return f();
+    };
+    let previous = float_precision();
+    set_float_precision(decimals);
+    let result = f();
+    set_float_precision(previous);
+    result
+}
+
+thread_local! {
+    static ID_COUNTER: Cell<Option<u64>> = const { Cell::new(None) };
+    static ID_GENERATOR: Cell<Option<fn() -> String>> = const { Cell::new(None) };
+}
+
+/// Id of a cell created without one: `id-{n}` from the counter of
+/// [`set_id_counter`], or else from the generator of [`set_id_generator`],
+/// or else a random UUID. Without the `uuid` feature the default ids are
+/// `cell-{n}` from a process-wide sequence.
+pub fn new_id() -> String {
+    if let Some(n) = ID_COUNTER.with(Cell::get) {
+        ID_COUNTER.with(|counter| counter.set(Some(n + 1)));
+        return format!("id-{n}");
+    }
+    if let Some(generate) = ID_GENERATOR.with(Cell::get) {
+        return generate();
+    }
+    default_id()
+}
+
+#[cfg(feature = "uuid")]
+fn default_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(not(feature = "uuid"))]
+fn default_id() -> String {
+    static SEQUENCE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let n = SEQUENCE.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("cell-{n}")
+}
+
+/// Generate the ids of the cells created without one on this thread with
+/// `generator`, e.g. `crypto.randomUUID` of a browser. The id counter takes
+/// priority, `None` restores the default ids.
+pub fn set_id_generator(generator: Option<fn() -> String>) {
+    ID_GENERATOR.with(|cell| cell.set(generator));
+}
+
+/// `name` usable in a cell id: the characters other than alphanumerics,
+/// `_` and `-` are replaced by `_`, see [`disambiguate_id`], e.g.
+/// `"I1/I2<3>"` becomes `"I1_I2_3__c92c71b4"`
+pub fn sanitize_id(name: &str) -> String {
+    disambiguate_id(
+        name,
+        name.replace(|c: char| !c.is_alphanumeric() && c != '_' && c != '-', "_"),
+    )
+}
+
+/// `sanitized`, the id made of `name` by replacing characters, followed by
+/// `_` and a hash of `name` when it differs from `name`, so two names made
+/// into the same id, e.g. `"I1/I2"` and `"I1_I2"`, give different ids
+pub fn disambiguate_id(name: &str, sanitized: String) -> String {
+    if sanitized == name {
+        return sanitized;
+    }
+    let hash = name.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    format!("{sanitized}_{hash:08x}")
+}
+
+/// `name` as a single tag, draw.io separates the tags by whitespace: each
+/// whitespace run is replaced by `_`
+pub fn sanitize_tag(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Next value of the id counter of this thread, `None` when the ids are UUIDs
+pub fn id_counter() -> Option<u64> {
+    ID_COUNTER.with(Cell::get)
+}
+
+/// Number the cells created without an id on this thread from `seed`, so two
+/// runs on the same input give the same ids. `None` restores random UUIDs.
+pub fn set_id_counter(seed: Option<u64>) {
+    ID_COUNTER.with(|counter| counter.set(seed));
+}
+
+/// Run `f` with the id counter temporarily set to `seed`
+pub fn with_id_counter<R>(seed: u64, f: impl FnOnce() -> R) -> R {
+    let previous = id_counter();
+    set_id_counter(Some(seed));
+    let result = f();
+    set_id_counter(previous);
+    result
+}
+
+/// What a cell is written with beyond its own fields: the style classes of
+/// the page and the custom fonts of the file being written, if any
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct XmlContext<'a> {
+    pub style_classes: Option<&'a IndexMap<String, String>>,
+    pub font_sources: Option<&'a IndexMap<String, String>>,
+}
+
+/// The style of a cell of `class`: the style of the class in `classes`, the
+/// ones of the page being written, or the own style of the class outside of
+/// a page, with the keys of `own` replaced in place, the other keys of `own`
+/// appended. `own` is returned as is without class.
+pub(crate) fn expand_style_class(
+    class: Option<&StyleClass>,
+    classes: Option<&IndexMap<String, String>>,
+    own: String,
+) -> String {
+    let Some(class) = class else {
+        return own;
+    };
+    let class_style = classes
+        .and_then(|classes| classes.get(&class.name))
+        .unwrap_or(&class.style);
+    merge_styles(class_style, &own)
+}
+
+/// The `fontSource` style value of the CSS font family list `family`: the
+/// URL of its first font in `sources`, the custom fonts of the file being
+/// written, encoded as draw.io does with `encodeURIComponent`
+pub(crate) fn font_source(
+    sources: Option<&IndexMap<String, String>>,
+    family: &str,
+) -> Option<String> {
+    let sources = sources?;
+    family
+        .split(',')
+        .map(|name| name.trim().trim_matches(['"', '\'']))
+        .find_map(|name| sources.get(name))
+        .map(|url| encode_uri_component(url))
+}
+
+/// `base` with the entries of `over` replaced in place or appended, the
+/// entries without `=` (e.g. `ellipse`) are kept once
+pub(crate) fn merge_styles(base: &str, over: &str) -> String {
+    let key = |part: &str| part.split_once('=').map_or(part, |(k, _)| k).to_string();
+    let mut parts: IndexMap<String, &str> = IndexMap::new();
+    for part in base.split(';').chain(over.split(';')) {
+        if !part.is_empty() {
+            parts.insert(key(part), part);
+        }
+    }
+    parts.values().fold(String::new(), |mut style, part| {
+        style.push_str(part);
+        style.push(';');
+        style
+    })
+}
+
+/// A float as written to the XML: rounded to [`float_precision`] decimals,
+/// without trailing zeros, e.g. `40` and `399.9`
+#[derive(Clone, Copy, Debug)]
+pub struct XmlFloat(pub f64);
+
+impl fmt::Display for XmlFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.0.is_finite() {
+            return write!(f, "{}", self.0);
+        }
+        let mut buf = StackBuf::default();
+        let heap;
+        let s =
+            match fmt::Write::write_fmt(&mut buf, format_args!("{:.*}", float_precision(), self.0))
+            {
+                Ok(()) => buf.as_str(),
+                // Too long for the buffer, e.g. 1e300
+                Err(_) => {
+                    heap = format!("{:.*}", float_precision(), self.0);
+                    &heap
+                }
+            };
+        let s = if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            s
+        };
+        // Small negative values round to "-0"
+        if s == "-0" {
+            f.write_str("0")
+        } else {
+            f.write_str(s)
+        }
+    }
+}
+
+// Fixed buffer for formatting a number without allocating
+struct StackBuf {
+    buf: [u8; 64],
+    len: usize,
+}
+
+impl Default for StackBuf {
+    fn default() -> Self {
+        Self {
+            buf: [0; 64],
+            len: 0,
+        }
+    }
+}
+
+impl StackBuf {
+    fn as_str(&self) -> &str {
+        // Only whole `str`s are written
+        std::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for StackBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// A string as written to an attribute value, escaped with [`XMLBase::xml_ify`]
+#[derive(Clone, Copy, Debug)]
+pub struct XmlAttr<'a>(pub &'a str);
+
+impl fmt::Display for XmlAttr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_escaped(f, self.0)
+    }
+}
+
+/// Whether `c` is allowed in an XML 1.0 document, i.e. not a C0 control
+/// other than tab, newline and carriage return, nor U+FFFE/U+FFFF
+pub fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// `text` without the characters XML 1.0 does not allow, the vertical tab and
+/// form feed some exporters use as line separators become newlines
+pub fn sanitize_text(text: &str) -> Cow<'_, str> {
+    if text.chars().all(is_xml_char) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.chars()
+            .filter_map(|c| match c {
+                '\u{b}' | '\u{c}' => Some('\n'),
+                c if is_xml_char(c) => Some(c),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Write `s` with the characters that are not allowed in an attribute value
+/// replaced by their entity, the ones not allowed in XML at all are
+/// sanitized as in [`sanitize_text`]
+fn write_escaped(out: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let entity = match c {
+            '>' => "&gt;",
+            '<' => "&lt;",
+            '&' => "&amp;",
+            '"' => "&quot;",
+            '\'' => "&apos;",
+            '\n' | '\u{b}' | '\u{c}' => "&#xa;",
+            '\t' => "&#x9;",
+            '\r' => "&#xd;",
+            c if !is_xml_char(c) => "",
+            _ => continue,
+        };
+        out.write_str(&s[start..i])?;
+        out.write_str(entity)?;
+        start = i + c.len_utf8();
+    }
+    out.write_str(&s[start..])
+}
+
+// Escapes everything written through it
+struct EscapeWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for EscapeWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_escaped(self.0, s)
+    }
+}
+
+/// The style attribute of a cell: its own `style` escaped as it is
+/// formatted, or expanded with its `class` and the classes of the context,
+/// see [`expand_style_class`]
+pub(crate) struct StyleAttr<'a, T>(pub Option<&'a StyleClass>, pub XmlContext<'a>, pub T);
+
+impl<T: fmt::Display> fmt::Display for StyleAttr<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => fmt::Write::write_fmt(&mut EscapeWriter(f), format_args!("{}", self.2)),
+            class => write_escaped(
+                f,
+                &expand_style_class(class, self.1.style_classes, self.2.to_string()),
+            ),
+        }
+    }
+}
@@ -2,300 +2,394 @@ use crate::BoundingBox;
 use crate::diagram::{Edge, Object};
 use crate::error::{DrawrsError, DrawrsResult};
 use crate::page::DiagramObject;
-use crate::xml_base::XMLBase;
+use crate::xml_base::{XMLBase, new_id};
+use indexmap::IndexMap;
 use quick_xml::Reader;
-use quick_xml::events::Event;
-use uuid;
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, Event};
+
+/// Attribute value with the XML entities decoded, the raw value when it
+/// has an invalid entity
+pub fn attr_value(attr: &Attribute) -> String {
+    attr.unescape_value()
+        .map(|value| value.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).into_owned())
+}
 
-/// Parse XML string to Object or Edge (without transformation)
-pub fn parse_xml_to_object(xml_obj: &str) -> DrawrsResult<DiagramObject> {
-    // Parse XML to extract attributes
-    let mut reader = Reader::from_str(xml_obj);
-    reader.trim_text(true);
+// Start tag re-serialized with the attribute values escaped again
+fn raw_start_tag(e: &BytesStart, self_closing: bool) -> String {
+    let mut tag = format!("<{}", String::from_utf8_lossy(e.name().as_ref()));
+    for attr in e.attributes().flatten() {
+        tag.push_str(&format!(
+            r#" {}="{}""#,
+            String::from_utf8_lossy(attr.key.as_ref()),
+            XMLBase::xml_ify(&attr_value(&attr))
+        ));
+    }
+    tag.push_str(if self_closing { " />" } else { ">" });
+    tag
+}
 
-    let mut buf = Vec::new();
-    let mut obj_id: Option<String> = None;
-    let mut user_object_id: Option<String> = None; // id from UserObject tag
-    let mut user_object_tag: Option<String> = None; // tags from UserObject tag
-    let mut user_object_label: Option<String> = None; // label from UserObject tag (takes priority over mxCell value)
-    let mut parent_id: Option<String> = None;
-    let mut value: Option<String> = None;
-    let mut style: Option<String> = None;
-    let mut edge: Option<i32> = None;
+// Elements with a dedicated meaning, anything else inside a cell is preserved as raw XML
+fn is_known_element(name: &str) -> bool {
+    matches!(
+        name,
+        "UserObject" | "object" | "mxCell" | "mxGeometry" | "mxPoint" | "Array"
+    )
+}
 
-    // Geometry attributes
-    let mut geom_x: Option<f64> = None;
-    let mut geom_y: Option<f64> = None;
-    let mut geom_width: Option<f64> = None;
-    let mut geom_height: Option<f64> = None;
-    let mut geom_relative: Option<bool> = None;
-    let mut source_point: Option<[f64; 2]> = None;
-    let mut target_point: Option<[f64; 2]> = None;
-    let mut intermediate_points: Vec<[f64; 2]> = Vec::new();
-    let mut in_geometry = false;
-    let mut in_array = false;
+/// Incremental builder of one diagram cell from the reader events of its XML.
+///
+/// The events of a `<UserObject>`/`<object>` wrapper or a bare `<mxCell>`,
+/// from its start tag to its end tag, are fed with [`push_event`], so a
+/// whole `.drawio` file can be read in a single pass without re-serializing
+/// each cell. [`parse_xml_to_object`] is the same builder over a string.
+///
+/// [`push_event`]: CellBuilder::push_event
+#[derive(Debug, Default)]
+pub struct CellBuilder {
+    obj_id: Option<String>,
+    // id, tags and label of the UserObject take priority over the mxCell ones
+    user_object_id: Option<String>,
+    user_object_tag: Option<String>,
+    user_object_label: Option<String>,
+    user_object_tooltip: Option<String>,
+    user_object_link: Option<String>,
+    user_object_attributes: IndexMap<String, String>,
+    parent_id: Option<String>,
+    value: Option<String>,
+    style: Option<String>,
+    edge: Option<i32>,
+    visible: Option<String>,
+    source: Option<String>,
+    target: Option<String>,
+    preserved_attributes: IndexMap<String, String>,
+    preserved_elements: Vec<String>,
+    geometry_elements: Vec<String>,
+    // Raw XML of the unrecognized element being captured, with its nesting depth
+    raw_element: Option<(String, usize)>,
+
+    geom_x: Option<f64>,
+    geom_y: Option<f64>,
+    geom_width: Option<f64>,
+    geom_height: Option<f64>,
+    geom_relative: Option<bool>,
+    source_point: Option<[f64; 2]>,
+    target_point: Option<[f64; 2]>,
+    intermediate_points: Vec<[f64; 2]>,
+    in_geometry: bool,
+    // Depth of the open elements of the cell
+    depth: usize,
+}
 
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+impl CellBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-                if name == "UserObject" {
-                    // Parse UserObject to extract id, tags, and label (takes priority over mxCell id and value)
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        match key.as_str() {
-                            "id" => user_object_id = Some(val),
-                            "tags" => user_object_tag = Some(val),
-                            "label" => user_object_label = Some(val),
-                            _ => {}
-                        }
-                    }
-                } else if name == "mxCell" {
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        match key.as_str() {
-                            "id" => obj_id = Some(val),
-                            "parent" => parent_id = Some(val),
-                            "value" => value = Some(val),
-                            "style" => style = Some(val),
-                            "edge" => edge = val.parse().ok(),
-                            _ => {}
-                        }
-                    }
-                } else if name == "mxGeometry" {
-                    in_geometry = true;
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        match key.as_str() {
-                            "x" => geom_x = val.parse().ok(),
-                            "y" => geom_y = val.parse().ok(),
-                            "width" => geom_width = val.parse().ok(),
-                            "height" => geom_height = val.parse().ok(),
-                            "relative" => geom_relative = Some(val == "1"),
-                            _ => {}
-                        }
-                    }
-                    // Default missing x or y to 0.0
-                    if geom_x.is_none() {
-                        geom_x = Some(0.0);
-                    }
-                    if geom_y.is_none() {
-                        geom_y = Some(0.0);
+    /// Feed the next event, returns whether the outermost element of the
+    /// cell is closed and [`finish`](Self::finish) can be called
+    pub fn push_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Start(e) => {
+                self.depth += 1;
+                self.start(e, false);
+                false
+            }
+            Event::Empty(e) => {
+                self.start(e, true);
+                self.depth == 0
+            }
+            Event::Text(t) => {
+                if let Some((raw, _)) = self.raw_element.as_mut() {
+                    raw.push_str(&XMLBase::xml_ify(&t.unescape().unwrap_or_default()));
+                }
+                false
+            }
+            Event::End(e) => {
+                self.depth = self.depth.saturating_sub(1);
+                if let Some((raw, depth)) = self.raw_element.as_mut() {
+                    raw.push_str(&format!(
+                        "</{}>",
+                        String::from_utf8_lossy(e.name().as_ref())
+                    ));
+                    *depth -= 1;
+                    if *depth == 0 {
+                        let (raw, _) = self.raw_element.take().unwrap();
+                        self.push_raw(raw);
                     }
-                } else if name == "mxPoint" && in_geometry {
-                    let mut point_x: Option<f64> = None;
-                    let mut point_y: Option<f64> = None;
-                    let mut point_as: Option<String> = None;
+                } else if e.name().as_ref() == b"mxGeometry" {
+                    self.in_geometry = false;
+                }
+                self.depth == 0
+            }
+            _ => false,
+        }
+    }
 
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        match key.as_str() {
-                            "x" => point_x = val.parse().ok(),
-                            "y" => point_y = val.parse().ok(),
-                            "as" => point_as = Some(val),
-                            _ => {}
-                        }
-                    }
+    fn push_raw(&mut self, raw: String) {
+        if self.in_geometry {
+            self.geometry_elements.push(raw);
+        } else {
+            self.preserved_elements.push(raw);
+        }
+    }
 
-                    // Default missing x or y to 0.0
-                    let x = point_x.unwrap_or(0.0);
-                    let y = point_y.unwrap_or(0.0);
-                    match point_as.as_deref() {
-                        Some("sourcePoint") => source_point = Some([x, y]),
-                        Some("targetPoint") => target_point = Some([x, y]),
-                        _ => {
-                            if in_array {
-                                intermediate_points.push([x, y]);
-                            } else {
-                                intermediate_points.push([x, y]);
-                            }
+    fn start(&mut self, e: &BytesStart, self_closing: bool) {
+        if let Some((raw, depth)) = self.raw_element.as_mut() {
+            raw.push_str(&raw_start_tag(e, self_closing));
+            if !self_closing {
+                *depth += 1;
+            }
+            return;
+        }
+        match e.name().as_ref() {
+            // Newer draw.io versions write `<object>` for the same wrapper
+            b"UserObject" | b"object" => {
+                for attr in e.attributes().flatten() {
+                    let val = attr_value(&attr);
+                    match attr.key.as_ref() {
+                        b"id" => self.user_object_id = Some(val),
+                        b"tags" => self.user_object_tag = Some(val),
+                        b"label" => self.user_object_label = Some(val),
+                        b"tooltip" => self.user_object_tooltip = Some(val),
+                        b"link" => self.user_object_link = Some(val),
+                        key => {
+                            self.user_object_attributes
+                                .insert(String::from_utf8_lossy(key).into_owned(), val);
                         }
                     }
-                } else if name == "Array" && in_geometry {
-                    in_array = true;
                 }
             }
-            Ok(Event::Empty(e)) => {
-                // Handle self-closing tags like <mxGeometry ... />
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                if name == "mxGeometry" {
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        match key.as_str() {
-                            "x" => geom_x = val.parse().ok(),
-                            "y" => geom_y = val.parse().ok(),
-                            "width" => geom_width = val.parse().ok(),
-                            "height" => geom_height = val.parse().ok(),
-                            "relative" => geom_relative = Some(val == "1"),
-                            _ => {}
+            b"mxCell" => {
+                for attr in e.attributes().flatten() {
+                    let val = attr_value(&attr);
+                    match attr.key.as_ref() {
+                        b"id" => self.obj_id = Some(val),
+                        b"parent" => self.parent_id = Some(val),
+                        b"value" => self.value = Some(val),
+                        b"style" => self.style = Some(val),
+                        b"edge" => self.edge = val.parse().ok(),
+                        b"visible" => self.visible = Some(val),
+                        b"source" => self.source = Some(val),
+                        b"target" => self.target = Some(val),
+                        b"vertex" => {}
+                        key => {
+                            self.preserved_attributes
+                                .insert(String::from_utf8_lossy(key).into_owned(), val);
                         }
                     }
-                    // Default missing x or y to 0.0
-                    if geom_x.is_none() {
-                        geom_x = Some(0.0);
-                    }
-                    if geom_y.is_none() {
-                        geom_y = Some(0.0);
-                    }
-                } else if name == "mxPoint" && in_geometry {
-                    let mut point_x: Option<f64> = None;
-                    let mut point_y: Option<f64> = None;
-                    let mut point_as: Option<String> = None;
-
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        match key.as_str() {
-                            "x" => point_x = val.parse().ok(),
-                            "y" => point_y = val.parse().ok(),
-                            "as" => point_as = Some(val),
-                            _ => {}
-                        }
+                }
+            }
+            b"mxGeometry" => {
+                self.in_geometry = !self_closing;
+                for attr in e.attributes().flatten() {
+                    let val = attr_value(&attr);
+                    match attr.key.as_ref() {
+                        b"x" => self.geom_x = val.parse().ok(),
+                        b"y" => self.geom_y = val.parse().ok(),
+                        b"width" => self.geom_width = val.parse().ok(),
+                        b"height" => self.geom_height = val.parse().ok(),
+                        b"relative" => self.geom_relative = Some(val == "1"),
+                        _ => {}
                     }
-
-                    // Default missing x or y to 0.0
-                    let x = point_x.unwrap_or(0.0);
-                    let y = point_y.unwrap_or(0.0);
-                    match point_as.as_deref() {
-                        Some("sourcePoint") => source_point = Some([x, y]),
-                        Some("targetPoint") => target_point = Some([x, y]),
-                        _ => {
-                            intermediate_points.push([x, y]);
-                        }
+                }
+                // Default missing x or y to 0.0
+                self.geom_x.get_or_insert(0.0);
+                self.geom_y.get_or_insert(0.0);
+            }
+            b"mxPoint" if self.in_geometry => {
+                let mut point_x: Option<f64> = None;
+                let mut point_y: Option<f64> = None;
+                let mut point_as: Option<String> = None;
+                for attr in e.attributes().flatten() {
+                    let val = attr_value(&attr);
+                    match attr.key.as_ref() {
+                        b"x" => point_x = val.parse().ok(),
+                        b"y" => point_y = val.parse().ok(),
+                        b"as" => point_as = Some(val),
+                        _ => {}
                     }
                 }
+                // Default missing x or y to 0.0
+                let point = [point_x.unwrap_or(0.0), point_y.unwrap_or(0.0)];
+                match point_as.as_deref() {
+                    Some("sourcePoint") => self.source_point = Some(point),
+                    Some("targetPoint") => self.target_point = Some(point),
+                    _ => self.intermediate_points.push(point),
+                }
             }
-            Ok(Event::End(e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                if name == "mxGeometry" {
-                    in_geometry = false;
-                } else if name == "Array" {
-                    in_array = false;
+            name if !is_known_element(&String::from_utf8_lossy(name)) => {
+                let raw = raw_start_tag(e, self_closing);
+                if self_closing {
+                    self.push_raw(raw);
+                } else {
+                    self.raw_element = Some((raw, 1));
                 }
             }
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(DrawrsError::XmlParsing(e)),
             _ => {}
         }
-        buf.clear();
     }
 
-    // Check if this is a group mxCell (has style="group" or style contains "group")
-    let is_group = style
-        .as_ref()
-        .map(|s| s == "group" || s.contains("group"))
-        .unwrap_or(false);
-
-    // If this is a group mxCell without UserObject, return XMLBase
-    if is_group && user_object_id.is_none() {
-        let final_id = obj_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-        let final_parent_id = parent_id.unwrap_or_else(|| "1".to_string());
+    /// The cell read so far: an [`Edge`], an [`Object`], or an [`XMLBase`]
+    /// for a group cell without a UserObject wrapper
+    pub fn finish(self) -> DiagramObject {
+        // Check if this is a group mxCell (has style="group" or style contains "group")
+        let is_group = self
+            .style
+            .as_ref()
+            .map(|s| s == "group" || s.contains("group"))
+            .unwrap_or(false);
+
+        // If this is a group mxCell without UserObject, return XMLBase
+        if is_group && self.user_object_id.is_none() {
+            let final_id = self.obj_id.unwrap_or_else(new_id);
+            let final_parent_id = self.parent_id.unwrap_or_else(|| "1".to_string());
+
+            let mut xml_base = XMLBase::new(Some(final_id));
+            xml_base.xml_class = "mxCell".to_string();
+            xml_base.xml_parent = Some(final_parent_id);
+            xml_base.value = self.value;
+            xml_base.visible = self.visible;
+            xml_base.preserved_attributes = self.preserved_attributes;
+            xml_base.preserved_elements = self.preserved_elements;
+            // Written as `style="group"` unless it has more than that
+            if let Some(style) = self.style.filter(|style| style != "group") {
+                xml_base
+                    .preserved_attributes
+                    .insert("style".to_string(), style);
+            }
 
-        let mut xml_base = XMLBase::new(Some(final_id));
-        xml_base.xml_class = "mxCell".to_string();
-        xml_base.xml_parent = Some(final_parent_id);
+            // Set group_geometry if geometry is available
+            if let (Some(x), Some(y), Some(w), Some(h)) =
+                (self.geom_x, self.geom_y, self.geom_width, self.geom_height)
+            {
+                xml_base.group_geometry = Some(BoundingBox::new(x, y, w, h));
+            }
 
-        // Set group_geometry if geometry is available
-        if let (Some(x), Some(y), Some(w), Some(h)) = (geom_x, geom_y, geom_width, geom_height) {
-            xml_base.group_geometry = Some(BoundingBox::new(x, y, w, h));
+            return DiagramObject::XmlBase(xml_base);
         }
 
-        return Ok(DiagramObject::XmlBase(xml_base));
-    }
-
-    // Determine if this is an edge or an object
-    let is_edge = edge == Some(1) || source_point.is_some() || target_point.is_some();
-
-    // Use UserObject id if available, then mxCell id, otherwise generate new one
-    let final_id = user_object_id
-        .or(obj_id)
-        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-    let final_parent_id = parent_id.unwrap_or_else(|| "1".to_string());
-
-    if is_edge {
-        // Create Edge
-        let mut edge_obj = Edge::new(Some(final_id));
-
-        if let Some(s) = style {
-            edge_obj.parse_and_set_style(&s);
-        }
+        // Determine if this is an edge or an object
+        let is_edge =
+            self.edge == Some(1) || self.source_point.is_some() || self.target_point.is_some();
 
+        // Use UserObject id if available, then mxCell id, otherwise generate new one
+        let final_id = self.user_object_id.or(self.obj_id).unwrap_or_else(new_id);
+        let final_parent_id = self.parent_id.unwrap_or_else(|| "1".to_string());
         // Use UserObject label if available, otherwise use mxCell value
-        let final_value = user_object_label.or(value);
-        if let Some(v) = final_value {
-            edge_obj.base_mut().value = Some(v);
-        }
+        let final_value = self.user_object_label.or(self.value);
 
-        edge_obj.set_xml_parent(Some(final_parent_id));
+        if is_edge {
+            let mut edge_obj = Edge::new(Some(final_id));
 
-        // Set tag from UserObject if available
-        if let Some(tag) = user_object_tag {
-            edge_obj.base_mut().tag = Some(tag);
-        }
-
-        let geom = edge_obj.geometry();
-
-        if let Some(sp) = source_point {
-            geom.set_source_point(Some(sp));
-        }
-
-        if let Some(tp) = target_point {
-            geom.set_target_point(Some(tp));
-        }
+            if let Some(s) = self.style {
+                edge_obj.parse_and_set_style(&s);
+            }
+            if let Some(v) = final_value {
+                edge_obj.base_mut().value = Some(v);
+            }
 
-        for point in intermediate_points {
-            geom.add_intermediate_point(point);
-        }
+            edge_obj.set_xml_parent(Some(final_parent_id));
 
-        if let Some(w) = geom_width {
-            geom.set_width(w);
-        }
-        if let Some(h) = geom_height {
-            geom.set_height(h);
-        }
-        if let Some(r) = geom_relative {
-            geom.set_relative(Some(r));
-        }
+            // Set tag from UserObject if available
+            if let Some(tag) = self.user_object_tag {
+                edge_obj.base_mut().tag = XMLBase::normalize_tags(&tag);
+            }
+            edge_obj.base_mut().tooltip = self.user_object_tooltip;
+            edge_obj.base_mut().link = self.user_object_link;
+            edge_obj.base_mut().attributes = self.user_object_attributes;
+            edge_obj.base_mut().visible = self.visible;
+            edge_obj.base_mut().preserved_attributes = self.preserved_attributes;
+            edge_obj.base_mut().preserved_elements = self.preserved_elements;
+            edge_obj.set_source(self.source);
+            edge_obj.set_target(self.target);
+
+            let geom = edge_obj.geometry();
+            if let Some(sp) = self.source_point {
+                geom.set_source_point(Some(sp));
+            }
+            if let Some(tp) = self.target_point {
+                geom.set_target_point(Some(tp));
+            }
+            for point in self.intermediate_points {
+                geom.add_intermediate_point(point);
+            }
+            for element in self.geometry_elements {
+                geom.add_preserved_element(element);
+            }
+            if let Some(w) = self.geom_width {
+                geom.set_width(w);
+            }
+            if let Some(h) = self.geom_height {
+                geom.set_height(h);
+            }
+            if let Some(r) = self.geom_relative {
+                geom.set_relative(Some(r));
+            }
 
-        Ok(DiagramObject::Edge(edge_obj))
-    } else {
-        // Create Object
-        let mut obj = Object::new(Some(final_id));
+            DiagramObject::Edge(edge_obj)
+        } else {
+            let mut obj = Object::new(Some(final_id));
 
-        if let Some(s) = style {
-            obj.parse_and_set_style(&s);
-        }
+            if let Some(s) = self.style {
+                obj.parse_and_set_style(&s);
+            }
+            if let Some(v) = final_value {
+                obj.set_value(v);
+            }
 
-        // Use UserObject label if available, otherwise use mxCell value
-        let final_value = user_object_label.or(value);
-        if let Some(v) = final_value {
-            obj.set_value(v);
-        }
+            obj.set_xml_parent(Some(final_parent_id));
 
-        obj.set_xml_parent(Some(final_parent_id));
+            // Set tag from UserObject if available
+            if let Some(tag) = self.user_object_tag {
+                obj.base_mut().tag = XMLBase::normalize_tags(&tag);
+            }
+            obj.base_mut().tooltip = self.user_object_tooltip;
+            obj.base_mut().link = self.user_object_link;
+            obj.base_mut().attributes = self.user_object_attributes;
+            obj.base_mut().visible = self.visible;
+            obj.base_mut().preserved_attributes = self.preserved_attributes;
+            obj.base_mut().preserved_elements = self.preserved_elements;
+
+            if let (Some(x), Some(y)) = (self.geom_x, self.geom_y) {
+                obj.set_position([x, y]);
+            }
+            if let Some(w) = self.geom_width {
+                obj.set_width(w);
+            }
+            if let Some(h) = self.geom_height {
+                obj.set_height(h);
+            }
+            for element in self.geometry_elements {
+                obj.geometry_mut().add_preserved_element(element);
+            }
 
-        // Set tag from UserObject if available
-        if let Some(tag) = user_object_tag {
-            obj.base_mut().tag = Some(tag);
+            DiagramObject::Object(obj)
         }
+    }
+}
 
-        if let (Some(x), Some(y)) = (geom_x, geom_y) {
-            obj.set_position([x, y]);
-        }
+/// Parse XML string to Object or Edge (without transformation).
+///
+/// Unrecognized mxCell attributes and child elements are kept on the cell
+/// (and unrecognized `<mxGeometry>` children on the geometry) so they are
+/// emitted again when the cell is serialized.
+pub fn parse_xml_to_object(xml_obj: &str) -> DrawrsResult<DiagramObject> {
+    let mut reader = Reader::from_str(xml_obj);
+    reader.trim_text(true);
 
-        if let Some(w) = geom_width {
-            obj.set_width(w);
-        }
-        if let Some(h) = geom_height {
-            obj.set_height(h);
+    let mut buf = Vec::new();
+    let mut cell = CellBuilder::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(event) => {
+                cell.push_event(&event);
+            }
+            Err(e) => return Err(DrawrsError::XmlParsing(e)),
         }
-
-        Ok(DiagramObject::Object(obj))
+        buf.clear();
     }
+    Ok(cell.finish())
 }
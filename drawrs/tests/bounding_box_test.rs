@@ -0,0 +1,96 @@
+use drawrs::BoundingBox;
+
+const EPSILON: f64 = 1e-9;
+
+#[test]
+fn test_center() {
+    let bbox = BoundingBox::new(10.0, 20.0, 30.0, 40.0);
+    assert_eq!(bbox.center(), [25.0, 40.0]);
+}
+
+#[test]
+fn test_intersection() {
+    let a = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+    let b = BoundingBox::new(5.0, -5.0, 10.0, 10.0);
+    assert!(a.intersects(&b));
+    assert_eq!(
+        a.intersection(&b),
+        Some(BoundingBox::new(5.0, 0.0, 5.0, 5.0))
+    );
+    assert_eq!(a.intersection(&b), b.intersection(&a));
+
+    let far = BoundingBox::new(20.0, 20.0, 1.0, 1.0);
+    assert!(!a.intersects(&far));
+    assert_eq!(a.intersection(&far), None);
+}
+
+#[test]
+fn test_touching_boxes_do_not_intersect() {
+    let a = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+    let b = BoundingBox::new(10.0, 0.0, 10.0, 10.0);
+    assert!(!a.intersects(&b));
+    assert_eq!(a.intersection(&b), None);
+}
+
+#[test]
+fn test_contains() {
+    let outer = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+    assert!(outer.contains(&BoundingBox::new(2.0, 2.0, 3.0, 3.0)));
+    assert!(outer.contains(&outer));
+    assert!(!outer.contains(&BoundingBox::new(8.0, 8.0, 3.0, 3.0)));
+
+    assert!(outer.contains_point([0.0, 10.0]));
+    assert!(outer.contains_point([5.0, 5.0]));
+    assert!(!outer.contains_point([10.5, 5.0]));
+}
+
+#[test]
+fn test_degenerate_boxes() {
+    let point = BoundingBox::new(5.0, 5.0, 0.0, 0.0);
+    let line = BoundingBox::new(0.0, 5.0, 10.0, 0.0);
+    let square = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+
+    // Strictly inside a box, the intersection is the degenerate box itself
+    assert!(point.intersects(&square));
+    assert_eq!(point.intersection(&square), Some(point));
+    assert_eq!(line.intersection(&square), Some(line));
+    // On the border or against another degenerate box
+    assert!(!BoundingBox::new(0.0, 5.0, 0.0, 0.0).intersects(&square));
+    assert!(!point.intersects(&point));
+
+    assert!(square.contains(&point));
+    // A corner of the box, unlike `intersects` the border counts
+    assert!(square.contains(&BoundingBox::new(10.0, 10.0, 0.0, 0.0)));
+    assert!(square.contains(&line));
+    assert!(point.contains_point([5.0, 5.0]));
+    assert!(point.contains(&point));
+
+    let mut grown = point;
+    grown.expand(1.0);
+    assert_eq!(grown, BoundingBox::new(4.0, 4.0, 2.0, 2.0));
+    let mut scaled = point;
+    scaled.scale_about_center(3.0);
+    assert_eq!(scaled, point);
+}
+
+#[test]
+fn test_expand_and_translate() {
+    let mut bbox = BoundingBox::new(0.0, 0.0, 10.0, 4.0);
+    bbox.expand(2.0);
+    assert_eq!(bbox, BoundingBox::new(-2.0, -2.0, 14.0, 8.0));
+    bbox.expand(-2.0);
+    assert_eq!(bbox, BoundingBox::new(0.0, 0.0, 10.0, 4.0));
+    bbox.translate(3.0, -1.0);
+    assert_eq!(bbox, BoundingBox::new(3.0, -1.0, 10.0, 4.0));
+}
+
+#[test]
+fn test_scale_about_center() {
+    let mut bbox = BoundingBox::new(0.0, 0.0, 0.3, 0.6);
+    let center = bbox.center();
+    bbox.scale_about_center(1.0 / 3.0);
+    assert!(bbox.approx_eq(&BoundingBox::new(0.1, 0.2, 0.1, 0.2), EPSILON));
+    assert!((bbox.center()[0] - center[0]).abs() < EPSILON);
+    assert!((bbox.center()[1] - center[1]).abs() < EPSILON);
+    assert!(!bbox.approx_eq(&BoundingBox::new(0.1, 0.2, 0.1, 0.3), EPSILON));
+}
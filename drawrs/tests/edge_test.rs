@@ -7,6 +7,13 @@ fn test_default_values() {
     assert_eq!(edge.connection(), "line");
     assert_eq!(edge.pattern(), "solid");
     assert_eq!(edge.edge(), 1);
+    // Nothing is written, draw.io draws its default arrow
+    assert_eq!(edge.line_end_target(), None);
+    assert_eq!(edge.style().to_string(), "");
+    assert_eq!(
+        Edge::new_wire(None).style().to_string(),
+        "endArrow=none;rounded=0;"
+    );
 }
 
 #[test]
@@ -87,3 +94,167 @@ fn test_xml_generation() {
     assert!(xml.contains("mxCell"));
     assert!(xml.contains("edge"));
 }
+
+#[test]
+fn test_tooltip_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut edge = Edge::new(Some("e".to_string()));
+    edge.set_tag(Some("wire".to_string()));
+    edge.set_tooltip(Some("net VDD".to_string()));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    let xml = edge.xml().to_string();
+    assert!(xml.starts_with(r#"<UserObject label="" tags="wire" tooltip="net VDD" id="e">"#));
+
+    let DiagramObject::Edge(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an edge");
+    };
+    assert_eq!(parsed.tag().map(String::as_str), Some("wire"));
+    assert_eq!(parsed.tooltip().map(String::as_str), Some("net VDD"));
+}
+
+#[test]
+fn test_link_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut edge = Edge::new(Some("e".to_string()));
+    edge.set_tooltip(Some("net VDD".to_string()));
+    edge.set_link(Some("https://example.com/nets/VDD".to_string()));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    let xml = edge.xml().to_string();
+    assert!(xml.starts_with(
+        r#"<UserObject label="" tooltip="net VDD" link="https://example.com/nets/VDD" id="e">"#
+    ));
+
+    let DiagramObject::Edge(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an edge");
+    };
+    assert_eq!(
+        parsed.link().map(String::as_str),
+        Some("https://example.com/nets/VDD")
+    );
+}
+
+#[test]
+fn test_attributes_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut edge = Edge::new(Some("e".to_string()));
+    edge.set_attribute("net", "VDD").unwrap();
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    let xml = edge.xml().to_string();
+    assert!(xml.starts_with(r#"<UserObject label="" net="VDD" id="e">"#));
+
+    let DiagramObject::Edge(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an edge");
+    };
+    assert_eq!(parsed.attribute("net").map(String::as_str), Some("VDD"));
+    assert_eq!(parsed.tag(), None);
+}
+
+#[test]
+fn test_line_join_round_trip() {
+    use drawrs::{DiagramObject, LineJoin, parse_xml_to_object};
+
+    let mut edge = Edge::new(Some("e".to_string()));
+    edge.set_line_join(Some(LineJoin::Round));
+    edge.set_opacity(Some(50));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    let style = edge.style().to_string();
+    assert!(style.contains("opacity=50;jointStyle=round;"));
+
+    let DiagramObject::Edge(parsed) = parse_xml_to_object(&edge.xml().to_string()).unwrap() else {
+        panic!("expected an edge");
+    };
+    assert_eq!(parsed.line_join(), Some(LineJoin::Round));
+    assert_eq!(parsed.style_property("jointStyle"), None);
+}
+
+#[test]
+fn test_text_format_round_trip() {
+    use drawrs::diagram::text_format::{Justify, JustifyX, JustifyY};
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut edge = Edge::new(Some("e".to_string()));
+    edge.set_label(Some("net1".to_string()));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    // The centered default is not written
+    assert!(!edge.style().to_string().contains("align="));
+
+    edge.set_font_size(Some(8.5));
+    edge.set_font_color(Some("#FF0000".to_string()));
+    edge.set_font_family(Some("Helvetica".to_string())).unwrap();
+    edge.set_label_background_color(Some("#FFFFFF".to_string()));
+    let justify = Justify::new()
+        .with_x(JustifyX::Left)
+        .with_y(JustifyY::Bottom);
+    edge.set_justify(justify);
+    let style = edge.style().to_string();
+    assert!(style.contains(
+        "fontColor=#FF0000;fontSize=8.5;fontFamily=Helvetica;labelBackgroundColor=#FFFFFF;align=left;verticalAlign=bottom;"
+    ));
+
+    let DiagramObject::Edge(parsed) = parse_xml_to_object(&edge.xml().to_string()).unwrap() else {
+        panic!("expected an edge");
+    };
+    assert_eq!(parsed.font_size(), Some(8.5));
+    assert_eq!(parsed.font_color().map(String::as_str), Some("#FF0000"));
+    assert_eq!(parsed.font_family().map(String::as_str), Some("Helvetica"));
+    assert_eq!(
+        parsed.label_background_color().map(String::as_str),
+        Some("#FFFFFF")
+    );
+    assert_eq!(*parsed.justify(), justify);
+    assert_eq!(parsed.style_property("align"), None);
+    assert_eq!(parsed.style().to_string(), style);
+}
+
+#[test]
+fn test_remove_style_property() {
+    let mut edge = Edge::new(None);
+    edge.parse_and_set_style("shape=link;dashed=1;dashPattern=8 4;strokeWidth=2;");
+    edge.remove_style_property("shape");
+    edge.remove_style_property("dashPattern");
+    edge.remove_style_property("strokeWidth");
+    let style = edge.style().to_string();
+    assert_eq!(style, "dashed=1;");
+    assert_eq!(edge.style_property("dashPattern"), None);
+    assert_eq!(edge.stroke_width(), None);
+
+    let mut wire = Edge::new_wire(None);
+    wire.remove_style_property("endArrow");
+    assert_eq!(wire.style().to_string(), "rounded=0;");
+
+    edge.parse_and_set_style("shape=link;startArrow=oval;fontSize=8;");
+    edge.clear_style();
+    assert_eq!(
+        edge.style().to_string(),
+        Edge::new(None).style().to_string()
+    );
+}
+
+#[test]
+fn test_arrowless_style_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    for style in [
+        "strokeColor=#000000;",
+        "edgeStyle=orthogonalEdgeStyle;html=1;",
+        "strokeWidth=2;endArrow=none;",
+        "startArrow=oval;rounded=1;",
+    ] {
+        let mut edge = Edge::new(Some("e".to_string()));
+        edge.parse_and_set_style(style);
+        assert_eq!(edge.style().to_string(), style);
+        let DiagramObject::Edge(parsed) = parse_xml_to_object(&edge.xml().to_string()).unwrap()
+        else {
+            panic!("expected an edge");
+        };
+        assert_eq!(parsed.style().to_string(), style);
+    }
+}
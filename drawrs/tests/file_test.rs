@@ -43,8 +43,95 @@ fn test_write_basic() {
     let page = Page::new(None, true);
     file.add_page(page);
 
-    let xml_content = file.xml().to_string();
+    let xml_content = file.xml().unwrap().to_string();
     assert!(!xml_content.is_empty());
     assert!(xml_content.contains("<mxfile"));
     assert!(xml_content.contains("</mxfile>"));
 }
+
+#[test]
+fn test_invalid_text() {
+    use drawrs::{DrawrsError, Object};
+    let mut object = Object::new(Some("label".to_string()));
+    object.set_value("A\u{b}B".to_string());
+    let mut page = Page::new(None, true);
+    page.add_object(object.into());
+    let mut file = DrawFile::new();
+    file.add_page(page);
+
+    // The output is sanitized by default
+    assert!(
+        file.xml()
+            .unwrap()
+            .to_string()
+            .contains(r#"value="A&#xa;B""#)
+    );
+    file.validate_xml().unwrap();
+    // The strict check reports the cell
+    assert!(matches!(
+        file.check_text(),
+        Err(DrawrsError::InvalidText { id, value }) if id == "label" && value == "A\u{b}B"
+    ));
+}
+
+#[test]
+fn test_validate_xml() {
+    use drawrs::file::validate_xml;
+    validate_xml(r#"<a b="&amp;"><c/></a>"#).unwrap();
+    assert!(validate_xml("<a><b></b>").is_err());
+    assert!(validate_xml("<a></b>").is_err());
+    assert!(validate_xml(r#"<a b="&bogus;"/>"#).is_err());
+    assert!(validate_xml("<a>\u{1}</a>").is_err());
+}
+
+#[test]
+fn test_custom_font() {
+    use drawrs::Object;
+    let mut page = Page::new(None, true);
+    for (id, style) in [
+        ("corp", "fontFamily=Verdana, 'Corp Sans', sans-serif;"),
+        (
+            "own",
+            "fontFamily=Corp Sans;fontSource=https%3A%2F%2Fother.css;",
+        ),
+        ("plain", "fontFamily=Verdana;"),
+    ] {
+        let mut object = Object::new(Some(id.to_string()));
+        object.parse_and_set_style(style);
+        page.add_object(object.into());
+    }
+    let mut file = DrawFile::new();
+    file.add_page(page);
+    file.add_custom_font(
+        "Corp Sans",
+        "https://fonts.example.com/css?family=Corp+Sans",
+    );
+    let xml = file.xml().unwrap().to_string();
+
+    let source = "fontSource=https%3A%2F%2Ffonts.example.com%2Fcss%3Ffamily%3DCorp%2BSans;";
+    assert_eq!(xml.matches(source).count(), 1, "{xml}");
+    assert!(xml.contains("fontFamily=Verdana, &apos;Corp Sans&apos;, sans-serif;fontSource="));
+    // A cell keeps its own source
+    assert_eq!(xml.matches("fontSource=").count(), 2);
+    file.validate_xml().unwrap();
+    // The fonts belong to the file, a page written on its own has none
+    let page_xml = file.pages[0].xml().unwrap().to_string();
+    assert_eq!(page_xml.matches("fontSource=").count(), 1);
+}
+
+#[test]
+fn test_file_timestamp() {
+    let mut file = DrawFile::new();
+    file.timestamp = Some("2024-01-01T00:00:00".to_string());
+    drawrs::file::set_fixed_timestamp(Some("2000-01-01T00:00:00".to_string()));
+    // The timestamp of the file wins over the thread one
+    assert!(
+        file.xml()
+            .unwrap()
+            .to_string()
+            .contains(r#"modified="2024-01-01T00:00:00""#)
+    );
+    file.timestamp = None;
+    assert_eq!(file.modified(), "2000-01-01T00:00:00");
+    drawrs::file::set_fixed_timestamp(None);
+}
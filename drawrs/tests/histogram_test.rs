@@ -0,0 +1,115 @@
+use drawrs::DiagramObject;
+use drawrs::DrawrsError;
+use drawrs::diagram_types::histogram::{BinSpec, Histogram};
+
+#[test]
+fn test_initialization_empty_data_raises_error() {
+    let result = Histogram::new(Vec::new(), BinSpec::Auto);
+    assert!(matches!(result, Err(DrawrsError::EmptyData)));
+}
+
+#[test]
+fn test_nan_sample_raises_error() {
+    let result = Histogram::new(vec![1.0, f64::NAN, 2.0], BinSpec::Auto);
+    match result {
+        Err(DrawrsError::InvalidValue(key, _)) => assert_eq!(key, "sample[1]"),
+        _ => panic!("expected InvalidValue"),
+    }
+}
+
+#[test]
+fn test_all_equal_samples_raise_error() {
+    let result = Histogram::new(vec![3.0; 5], BinSpec::Count(4));
+    assert!(matches!(result, Err(DrawrsError::DegenerateData(_))));
+}
+
+#[test]
+fn test_invalid_bin_spec() {
+    assert!(Histogram::new(vec![1.0, 2.0], BinSpec::Count(0)).is_err());
+    assert!(Histogram::new(vec![1.0, 2.0], BinSpec::Width(-1.0)).is_err());
+}
+
+#[test]
+fn test_too_many_bins_raise_error() {
+    let result = Histogram::new(vec![0.0, 1.0], BinSpec::Width(1e-12));
+    match result {
+        Err(DrawrsError::InvalidValue(key, _)) => assert_eq!(key, "bins"),
+        _ => panic!("expected InvalidValue"),
+    }
+    let result = Histogram::new(vec![0.0, 1.0], BinSpec::Count(Histogram::MAX_BINS + 1));
+    assert!(matches!(result, Err(DrawrsError::InvalidValue(..))));
+}
+
+#[test]
+fn test_bin_count() {
+    let samples = (1..=10).map(|x| x as f64).collect();
+    let histogram = Histogram::new(samples, BinSpec::Count(3)).unwrap();
+    assert_eq!(histogram.len(), 3);
+    assert_eq!(histogram.edges(), &[1.0, 4.0, 7.0, 10.0]);
+    assert_eq!(histogram.counts(), &[3, 3, 4]);
+}
+
+#[test]
+fn test_bin_width() {
+    let samples = (0..=10).map(|x| x as f64).collect();
+    let histogram = Histogram::new(samples, BinSpec::Width(2.5)).unwrap();
+    assert_eq!(histogram.edges(), &[0.0, 2.5, 5.0, 7.5, 10.0]);
+    assert_eq!(histogram.counts(), &[3, 2, 3, 3]);
+}
+
+#[test]
+fn test_bin_auto() {
+    // IQR = 6.25 - 2.75 = 3.5, width = 2 * 3.5 / 8^(1/3) = 3.5
+    let samples = (1..=8).map(|x| x as f64).collect();
+    let histogram = Histogram::new(samples, BinSpec::Auto).unwrap();
+    assert_eq!(histogram.edges(), &[1.0, 4.5, 8.0]);
+    assert_eq!(histogram.counts(), &[4, 4]);
+}
+
+#[test]
+fn test_bars_have_no_spacing() {
+    let samples = vec![0.0, 1.0, 1.0, 2.0, 2.0, 2.0, 3.0];
+    let histogram = Histogram::new(samples, BinSpec::Count(3)).unwrap();
+    let bars: Vec<_> = histogram.objects[..histogram.len()]
+        .iter()
+        .map(|obj| obj.bounding_box().unwrap())
+        .collect();
+    for pair in bars.windows(2) {
+        assert_eq!(pair[0].max_x(), pair[1].min_x());
+    }
+    // Tallest bin reaches the full height
+    assert_eq!(bars[2].height(), Histogram::DEFAULT_MAX_BAR_HEIGHT);
+}
+
+#[test]
+fn test_mean_sigma_lines() {
+    let samples = vec![0.0, 2.0, 4.0, 6.0, 8.0];
+    let mut histogram = Histogram::new(samples, BinSpec::Count(4)).unwrap();
+    histogram.set_show_mean(true);
+    histogram.set_show_sigma(true);
+    assert_eq!(histogram.mean(), 4.0);
+
+    let lines: Vec<_> = histogram
+        .objects
+        .iter()
+        .filter_map(|obj| match obj {
+            DiagramObject::Edge(edge) => Some(edge),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(lines.len(), 3);
+    // Mean is in the middle of the 4 bins
+    let x = 2.0 * Histogram::DEFAULT_BAR_WIDTH;
+    assert_eq!(lines[0].geometry_ref().source_point().unwrap()[0], x);
+    assert_eq!(lines[0].geometry_ref().target_point().unwrap()[0], x);
+    assert!(lines[0].style().to_string().contains("dashed=1"));
+}
+
+#[test]
+fn test_move() {
+    let samples = vec![0.0, 1.0, 2.0];
+    let mut histogram = Histogram::new(samples, BinSpec::Count(2)).unwrap();
+    histogram.move_to([100.0, 200.0]);
+    assert_eq!(histogram.position(), [100.0, 200.0]);
+    assert_eq!(histogram.objects[0].bounding_box().unwrap().min_x(), 100.0);
+}
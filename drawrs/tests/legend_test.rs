@@ -42,3 +42,29 @@ fn test_update_mapping() {
     assert!(result.is_ok());
     assert_eq!(legend.items(), 1);
 }
+
+#[test]
+fn test_title_and_border() {
+    let mapping = [
+        ("Alpha".to_string(), "#ff0000".to_string()),
+        ("Beta".to_string(), "#00ff00".to_string()),
+    ];
+    let mut legend = Legend::new(mapping).unwrap();
+    assert_eq!(legend.objects().len(), 4);
+    assert_eq!(legend.size(), [125.0, 45.0]);
+
+    legend.set_title(Some("Layers".to_string()));
+    legend.set_border(true);
+    let objects = legend.objects();
+    assert_eq!(objects.len(), 6);
+    assert_eq!(legend.size(), [135.0, 80.0]);
+    // Border behind, then the title, then the rows in mapping order
+    assert_eq!([objects[0].width(), objects[0].height()], legend.size());
+    assert_eq!(objects[1].value().map(String::as_str), Some("Layers"));
+    assert_eq!(objects[3].value().map(String::as_str), Some("Alpha"));
+    assert_eq!(objects[3].position(), [30.0, 30.0]);
+    assert_eq!(objects[5].value().map(String::as_str), Some("Beta"));
+
+    legend.move_to([100.0, 0.0]);
+    assert_eq!(legend.objects()[0].position(), [100.0, 0.0]);
+}
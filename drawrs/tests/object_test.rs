@@ -79,3 +79,199 @@ fn test_xml_generation() {
     assert!(xml.contains("Test"));
     assert!(xml.contains("mxGeometry"));
 }
+
+#[test]
+fn test_tooltip_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_value("R1".to_string());
+    obj.set_tooltip(Some("X1 <res> w=\"2u\"".to_string()));
+    let xml = obj.xml().to_string();
+    // A tooltip alone forces the UserObject form
+    assert!(xml.starts_with(
+        r#"<UserObject label="R1" tooltip="X1 &lt;res&gt; w=&quot;2u&quot;" id="obj">"#
+    ));
+    assert!(!xml.contains("tags="));
+
+    let DiagramObject::Object(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an object");
+    };
+    assert_eq!(parsed.id(), "obj");
+    assert_eq!(parsed.value().map(String::as_str), Some("R1"));
+    assert_eq!(
+        parsed.tooltip().map(String::as_str),
+        Some("X1 <res> w=\"2u\"")
+    );
+    assert_eq!(parsed.tag(), None);
+}
+
+#[test]
+fn test_link_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_link(Some("https://pdk.example.com/a?b=1&c=2".to_string()));
+    let xml = obj.xml().to_string();
+    // A link alone forces the UserObject form
+    assert!(xml.starts_with(
+        r#"<UserObject label="" link="https://pdk.example.com/a?b=1&amp;c=2" id="obj">"#
+    ));
+
+    let DiagramObject::Object(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an object");
+    };
+    assert_eq!(
+        parsed.link().map(String::as_str),
+        Some("https://pdk.example.com/a?b=1&c=2")
+    );
+    assert!(parsed.base().attributes.is_empty());
+}
+
+#[test]
+fn test_attributes_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_attribute("device", "nch_lvt").unwrap();
+    obj.set_attribute("w", "2u & \"wide\"").unwrap();
+    // Reserved keys are not emitted twice
+    obj.set_attribute("id", "other").unwrap();
+    // Keys that would break the XML are rejected
+    for key in ["", "a b", "a\"", "1w", "a=b"] {
+        assert!(obj.set_attribute(key, "x").is_err());
+    }
+    let xml = obj.xml().to_string();
+    assert!(xml.starts_with(
+        r#"<UserObject label="" device="nch_lvt" w="2u &amp; &quot;wide&quot;" id="obj">"#
+    ));
+
+    let DiagramObject::Object(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an object");
+    };
+    assert_eq!(parsed.id(), "obj");
+    assert_eq!(
+        parsed.attribute("device").map(String::as_str),
+        Some("nch_lvt")
+    );
+    assert_eq!(
+        parsed.attribute("w").map(String::as_str),
+        Some("2u & \"wide\"")
+    );
+    assert_eq!(parsed.base().attributes.len(), 2);
+}
+
+#[test]
+fn test_multiple_tags_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_tag(Some("X42".to_string()));
+    obj.add_tag("nch_lvt");
+    obj.add_tag("X42");
+    let xml = obj.xml().to_string();
+    assert!(xml.contains(r#"tags="X42 nch_lvt""#));
+
+    let DiagramObject::Object(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an object");
+    };
+    assert_eq!(parsed.tags().collect::<Vec<_>>(), ["X42", "nch_lvt"]);
+
+    // Extra whitespace and duplicates from hand-edited files are dropped
+    let xml = xml.replace(r#"tags="X42 nch_lvt""#, r#"tags=" X42  nch_lvt X42 ""#);
+    let parsed = parse_xml_to_object(&xml).unwrap();
+    assert_eq!(parsed.tag().map(String::as_str), Some("X42 nch_lvt"));
+    assert!(parsed.base().has_tag("nch_lvt"));
+
+    // set_tag replaces the whole set
+    obj.set_tag(Some("X1".to_string()));
+    assert_eq!(obj.tags().collect::<Vec<_>>(), ["X1"]);
+}
+
+#[test]
+fn test_connection_points_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_connection_points(vec![[0.0, 0.5], [1.0, 0.25]]);
+    let xml = obj.xml().to_string();
+    assert!(xml.contains("points=[[0,0.5,0],[1,0.25,0]];"));
+
+    let DiagramObject::Object(parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an object");
+    };
+    assert_eq!(parsed.connection_points(), [[0.0, 0.5], [1.0, 0.25]]);
+
+    // Perimeter flags and offsets written by draw.io are kept verbatim
+    let points = "[[0,0,0,0,0],[0.5,1,1,2,-2]]";
+    let xml = xml.replace(
+        "points=[[0,0.5,0],[1,0.25,0]];",
+        &format!("points={points};"),
+    );
+    let DiagramObject::Object(mut parsed) = parse_xml_to_object(&xml).unwrap() else {
+        panic!("expected an object");
+    };
+    assert!(parsed.connection_points().is_empty());
+    assert_eq!(parsed.style_property("points"), Some(points));
+    assert!(
+        parsed
+            .xml()
+            .to_string()
+            .contains(&format!("points={points};"))
+    );
+
+    // Setting the points replaces the verbatim value
+    parsed.set_connection_points(vec![[1.0, 1.0]]);
+    let xml = parsed.xml().to_string();
+    assert!(xml.contains("points=[[1,1,0]];"));
+    assert!(!xml.contains(points));
+}
+
+#[test]
+fn test_label_background_round_trip() {
+    use drawrs::{DiagramObject, parse_xml_to_object};
+
+    let mut obj = Object::new(Some("o".to_string()));
+    obj.set_value("text".to_string());
+    obj.set_label_background_color(Some("#FFFFFF".to_string()));
+    assert!(
+        obj.style()
+            .to_string()
+            .contains("labelBackgroundColor=#FFFFFF;")
+    );
+    let DiagramObject::Object(parsed) = parse_xml_to_object(&obj.xml().to_string()).unwrap() else {
+        panic!("expected an object");
+    };
+    assert_eq!(
+        parsed.label_background_color().map(String::as_str),
+        Some("#FFFFFF")
+    );
+    assert_eq!(parsed.style_property("labelBackgroundColor"), None);
+}
+
+#[test]
+fn test_remove_style_property() {
+    let mut obj = Object::new(None);
+    obj.parse_and_set_style("shape=hexagon;dashPattern=8 4;fillColor=#FF0000;rotation=90;");
+    obj.remove_style_property("shape");
+    obj.remove_style_property("dashPattern");
+    obj.remove_style_property("fillColor");
+    obj.remove_style_property("rotation");
+    // Removing a missing key does nothing
+    obj.remove_style_property("perimeter");
+    let style = obj.style().to_string();
+    assert_eq!(style, "align=center;verticalAlign=middle;");
+    assert_eq!(obj.style_property("shape"), None);
+    assert_eq!(obj.fill_color(), None);
+
+    obj.parse_and_set_style("shape=hexagon;dashPattern=8 4;strokeWidth=2;align=left;");
+    obj.set_value("kept".to_string());
+    obj.set_width(42.0);
+    obj.clear_style();
+    assert_eq!(
+        obj.style().to_string(),
+        Object::new(None).style().to_string()
+    );
+    assert_eq!(obj.value().map(String::as_str), Some("kept"));
+    assert_eq!(obj.width(), 42.0);
+}
@@ -31,3 +31,356 @@ fn test_add_object() {
     page.add_object(obj.into());
     assert_eq!(page.objects().len(), initial_count + 1);
 }
+
+#[test]
+fn test_background_cell() {
+    use drawrs::diagram::Object;
+    let mut page = Page::new(None, true);
+    assert!(page.has_background());
+    page.add_object(Object::new(Some("a".to_string())).into());
+    assert_eq!(page.objects()[2].xml_parent(), None);
+    assert!(page.check_parents().is_empty());
+
+    // Without the background cell, parentless objects go on the first layer
+    let mut page = Page::new(None, false);
+    assert!(!page.has_background());
+    page.add_object(Object::new(Some("a".to_string())).into());
+    assert_eq!(page.check_parents(), ["a"]);
+    page.add_layer_cell(true, "layer-1".to_string(), "Layer 1".to_string());
+    page.add_layer_cell(true, "layer-2".to_string(), "Layer 2".to_string());
+    page.add_object(Object::new(Some("b".to_string())).into());
+    assert_eq!(page.objects()[4].xml_parent(), Some("layer-1"));
+    let mut dangling = Object::new(Some("c".to_string()));
+    dangling.set_xml_parent(Some("missing".to_string()));
+    page.add_object(dangling.into());
+    assert_eq!(page.check_parents(), ["a", "c"]);
+    assert!(page.xml().unwrap().to_string().contains(
+        r#"id="b" value="" style="align=center;verticalAlign=middle;" vertex="1" parent="layer-1""#
+    ));
+}
+
+#[test]
+fn test_layers() {
+    use drawrs::diagram::Object;
+    use drawrs::{BoundingBox, XMLBase};
+
+    let mut page = Page::new(None, true);
+    page.add_layer_cell(false, "layer-1".to_string(), "Layer 1".to_string());
+    page.add_object(Object::new(Some("plain".to_string())).into());
+    let mut group = XMLBase::new(Some("group".to_string()));
+    group.xml_class = "mxCell".to_string();
+    group.xml_parent = Some("layer-1".to_string());
+    group.group_geometry = Some(BoundingBox::new(0.0, 0.0, 10.0, 10.0));
+    page.add_object(group.into());
+    for id in ["member", "other"] {
+        let mut obj = Object::new(Some(id.to_string()));
+        obj.set_xml_parent(Some(
+            if id == "member" { "group" } else { "layer-1" }.to_string(),
+        ));
+        page.add_object(obj.into());
+    }
+
+    let layers: Vec<_> = page.layers().collect();
+    let summary: Vec<(&str, &str, bool, Vec<&str>)> = layers
+        .iter()
+        .map(|layer| {
+            let ids = layer.objects().map(|obj| obj.id()).collect();
+            (layer.id(), layer.name(), layer.visible(), ids)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("1", "", true, vec!["plain"]),
+            ("layer-1", "Layer 1", false, vec!["group", "other"]),
+        ]
+    );
+    assert!(!layers[1].locked());
+    let members: Vec<&str> = page.children_of("group").map(|obj| obj.id()).collect();
+    assert_eq!(members, ["member"]);
+}
+
+#[test]
+fn test_dedup_ids() {
+    use drawrs::diagram::Object;
+    let mut page = Page::new(None, false);
+    for id in ["a", "a", "a-1", "b", "a"] {
+        page.add_object(Object::new(Some(id.to_string())).into());
+    }
+    assert_eq!(page.dedup_ids(), 3);
+    let ids: Vec<&str> = page.objects().iter().map(|obj| obj.id()).collect();
+    assert_eq!(ids[1..], ["a", "a-1", "a-1-1", "b", "a-2"]);
+    assert_eq!(page.dedup_ids(), 0);
+}
+
+#[test]
+fn test_parents_emitted_before_children() {
+    use drawrs::diagram::Object;
+
+    let mut page = Page::new(None, true);
+    let mut child = Object::new(Some("child".to_string()));
+    child.set_xml_parent(Some("container".to_string()));
+    page.add_object(child.into());
+    let mut container = Object::new(Some("container".to_string()));
+    container.set_xml_parent(Some("layer".to_string()));
+    page.add_object(container.into());
+    page.add_object(Object::new(Some("plain".to_string())).into());
+    page.add_layer_cell(true, "layer".to_string(), "annotations".to_string());
+
+    let ids: Vec<&str> = page
+        .ordered_objects()
+        .unwrap()
+        .into_iter()
+        .map(|obj| obj.id())
+        .collect();
+    assert_eq!(ids, ["0", "1", "layer", "container", "child", "plain"]);
+
+    let xml = page.xml().unwrap().to_string();
+    let position = |id: &str| xml.find(&format!(r#"id="{id}""#)).unwrap();
+    assert!(position("layer") < position("container"));
+    assert!(position("container") < position("child"));
+}
+
+#[test]
+fn test_parent_cycle_is_an_error() {
+    use drawrs::DrawrsError;
+    use drawrs::diagram::Object;
+
+    let mut page = Page::new(None, true);
+    let mut a = Object::new(Some("a".to_string()));
+    a.set_xml_parent(Some("b".to_string()));
+    let mut b = Object::new(Some("b".to_string()));
+    b.set_xml_parent(Some("a".to_string()));
+    page.add_object(a.into());
+    page.add_object(b.into());
+
+    assert!(matches!(
+        page.ordered_objects(),
+        Err(DrawrsError::ParentCycle(_))
+    ));
+    // draw.io cannot load the page, serializing it fails
+    assert!(matches!(page.xml(), Err(DrawrsError::ParentCycle(_))));
+    let mut file = drawrs::DrawFile::new();
+    file.add_page(page);
+    assert!(matches!(file.xml(), Err(DrawrsError::ParentCycle(_))));
+}
+
+#[test]
+fn test_float_precision_override() {
+    use drawrs::DrawFile;
+    use drawrs::diagram::Object;
+
+    let mut obj = Object::new(Some("box".to_string()));
+    obj.set_position([1.0 / 3.0, 0.0]);
+    let mut page = Page::new(None, true);
+    page.add_object(obj.into());
+    assert!(page.xml().unwrap().to_string().contains(r#"x="0.3333""#));
+
+    page.set_float_precision(Some(1));
+    assert!(page.xml().unwrap().to_string().contains(r#"x="0.3""#));
+
+    page.set_float_precision(None);
+    let mut file = DrawFile::new();
+    file.float_precision = Some(2);
+    file.add_page(page);
+    assert!(file.xml().unwrap().to_string().contains(r#"x="0.33""#));
+    // The override only lasts for the serialization
+    assert_eq!(drawrs::xml_base::float_precision(), 4);
+}
+
+fn replace_fixture() -> Page {
+    use drawrs::diagram::{Edge, Object};
+
+    let mut page = Page::new(None, true);
+    page.add_layer_cell(true, "layer-net1".to_string(), "net1".to_string());
+    let mut tagged = Object::new(Some("net1-label".to_string()));
+    tagged.set_value("net1 / net10".to_string());
+    tagged.set_font_family(Some("net1".to_string())).unwrap();
+    tagged.base_mut().tag = Some("net1".to_string());
+    page.add_object(tagged.into());
+    let mut edge = Edge::new(Some("wire-net1-1".to_string()));
+    edge.base_mut().value = Some("net1".to_string());
+    edge.set_xml_parent(Some("layer-net1".to_string()));
+    page.add_object(edge.into());
+    page
+}
+
+#[test]
+fn test_replace_text() {
+    use drawrs::DrawFile;
+
+    let mut page = replace_fixture();
+    let before = page.xml().unwrap().to_string();
+    assert_eq!(page.replace_text("net1", "VDD"), 3);
+    let xml = page.xml().unwrap().to_string();
+    assert!(xml.contains(r#"<UserObject label="VDD / VDD0" tags="net1" id="net1-label">"#));
+    assert!(xml.contains(r#"value="VDD""#));
+    // Ids, styles, tags and layer names are untouched
+    assert!(xml.contains("fontFamily=net1;"));
+    assert!(xml.contains(r#"id="wire-net1-1""#));
+    assert!(xml.contains(r#"parent="layer-net1""#));
+    assert!(xml.contains(r#"value="net1""#));
+    assert_eq!(
+        before.matches("net1").count() - xml.matches("net1").count(),
+        3
+    );
+    assert_eq!(page.replace_text("net1", "VDD"), 0);
+    assert_eq!(page.replace_text("", "VDD"), 0);
+
+    let mut file = DrawFile::new();
+    file.add_page(replace_fixture());
+    file.add_page(replace_fixture());
+    assert_eq!(file.replace_text("net1", "VDD"), 6);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_replace_text_regex() {
+    let mut page = replace_fixture();
+    let pattern = regex::Regex::new(r"net(\d+)").unwrap();
+    assert_eq!(page.replace_text_regex(&pattern, "N$1"), 3);
+    let xml = page.xml().unwrap().to_string();
+    assert!(xml.contains(r#"label="N1 / N10""#));
+    assert!(xml.contains("fontFamily=net1;"));
+}
+
+#[test]
+fn test_style_classes() {
+    use drawrs::diagram::{Edge, Object};
+    use drawrs::{DiagramObject, StyleClass};
+
+    let styled_edge = |id: &str, color: &str| {
+        let mut edge = Edge::new_wire(Some(id.to_string()));
+        edge.set_stroke_color(Some(color.to_string()));
+        edge.set_stroke_width(Some(2.0));
+        edge.set_opacity(Some(50));
+        edge
+    };
+    let mut page = Page::new(None, true);
+    page.define_style("wire", styled_edge("", "#000000").style().to_string());
+    page.define_style("box", "ellipse;fillColor=#FF0000;strokeColor=#00FF00;");
+
+    // The page classes replace the styles of the classes of the cells
+    let wire = StyleClass::new("wire", "strokeColor=#0000FF;");
+    let mut classed = Edge::new_wire(Some("classed".to_string()));
+    classed.set_style_class(Some(wire.clone()));
+    let mut colored = Edge::new_wire(Some("colored".to_string()));
+    colored.set_style_class(Some(wire));
+    colored.set_stroke_color(Some("#FF0000".to_string()));
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_style_class(Some(StyleClass::new("box", "")));
+    obj.set_fill_color(Some("none".to_string()));
+    let mut unknown = Edge::new_wire(Some("unknown".to_string()));
+    unknown.set_style_class(Some(StyleClass::new("missing", "dashed=1;")));
+    let standalone = unknown.xml().to_string();
+    for obj in [classed.into(), colored.into(), obj.into(), unknown.into()] {
+        page.add_object(obj);
+    }
+
+    // Expansion gives the style of a fully styled cell, keys in the same order
+    let xml = page.xml().unwrap().to_string();
+    let style = |edge: Edge| format!(r#"style="{}""#, edge.style());
+    assert!(xml.contains(&style(styled_edge("", "#000000"))));
+    assert!(xml.contains(&style(styled_edge("", "#FF0000"))));
+    assert!(xml.contains(
+        r#"style="ellipse;fillColor=none;strokeColor=#00FF00;align=center;verticalAlign=middle;""#
+    ));
+    // A class the page does not define, or a cell outside of a page, is
+    // written with the style of its class
+    let unknown_cell = r#"<mxCell id="unknown" value="" style="dashed=1;endArrow=none;rounded=0;""#;
+    assert!(xml.contains(unknown_cell));
+    assert!(standalone.contains(unknown_cell));
+
+    // The resolved objects carry the class properties
+    let resolved = |id: &str| {
+        let obj = page.objects().iter().find(|obj| obj.id() == id).unwrap();
+        page.resolved_object(obj).into_owned()
+    };
+    let DiagramObject::Edge(edge) = resolved("classed") else {
+        panic!("expected an edge");
+    };
+    assert_eq!(edge.stroke_color().map(String::as_str), Some("#000000"));
+    assert_eq!(edge.opacity(), Some(50));
+    assert_eq!(edge.style_class(), None);
+    let DiagramObject::Object(obj) = resolved("obj") else {
+        panic!("expected an object");
+    };
+    assert_eq!(obj.fill_color().map(String::as_str), Some("none"));
+    assert_eq!(obj.stroke_color().map(String::as_str), Some("#00FF00"));
+}
+
+fn pie_page() -> (Page, String) {
+    use drawrs::diagram_types::pie_chart::PieChart;
+    let data = std::collections::HashMap::from([("A".to_string(), 10.0)]);
+    let chart = PieChart::new(data).unwrap();
+    let group = chart.objects[0].id().to_string();
+    let mut page = Page::new(None, true);
+    for obj in chart.objects {
+        page.add_object(obj.into());
+    }
+    (page, group)
+}
+
+fn position(page: &Page, id: &str) -> [f64; 2] {
+    let obj = page.objects().iter().find(|obj| obj.id() == id).unwrap();
+    let bbox = obj.bounding_box().unwrap();
+    [bbox.min_x(), bbox.min_y()]
+}
+
+#[test]
+fn test_duplicate_grouped_slice() {
+    let (mut page, group) = pie_page();
+    let count = page.objects().len();
+    let copy = page.duplicate_object(&group, "-copy").unwrap();
+    assert_eq!(copy, format!("{group}-copy"));
+    // The group with its slice and label
+    assert_eq!(page.objects().len(), count + 3);
+    let children: Vec<&str> = page.children_of(&copy).map(|obj| obj.id()).collect();
+    assert_eq!(children.len(), 2);
+    assert!(children.iter().all(|id| id.ends_with("-copy")));
+    assert!(page.check_parents().is_empty());
+
+    // The copies move on their own
+    page.translate_objects(&[copy.as_str()], 100.0, 50.0);
+    let [x, y] = position(&page, &group);
+    assert_eq!(position(&page, &copy), [x + 100.0, y + 50.0]);
+    for child in page.children_of(&group) {
+        assert_eq!(
+            position(&page, child.id()),
+            position(&page, &format!("{}-copy", child.id()))
+        );
+    }
+
+    page.scale_objects(&[copy.as_str()], 2.0, [x, y]);
+    let bbox = |id: &str| {
+        let obj = page.objects().iter().find(|obj| obj.id() == id).unwrap();
+        obj.bounding_box().unwrap()
+    };
+    assert_eq!(bbox(&copy).min_x(), x + 200.0);
+    assert_eq!(bbox(&copy).width(), 2.0 * bbox(&group).width());
+
+    // The ids are checked
+    assert!(matches!(
+        page.duplicate_object(&group, "-copy"),
+        Err(drawrs::DrawrsError::DuplicateId(id)) if id == copy
+    ));
+    assert!(matches!(
+        page.duplicate_object("missing", "-copy"),
+        Err(drawrs::DrawrsError::UnknownId(_))
+    ));
+}
+
+#[test]
+fn test_translate_edge_points() {
+    let mut edge = drawrs::Edge::new(Some("wire".to_string()));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    let mut page = Page::new(None, true);
+    page.add_object(edge.into());
+    page.translate_objects(&["wire"], 5.0, -5.0);
+    page.scale_objects(&["wire"], 2.0, [5.0, -5.0]);
+    let obj = page.objects_mut().last_mut().unwrap();
+    let mut points: Vec<[f64; 2]> = obj.mut_points().map(|p| *p).collect();
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    assert_eq!(points, [[5.0, -5.0], [25.0, -5.0]]);
+}
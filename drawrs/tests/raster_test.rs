@@ -0,0 +1,83 @@
+#![cfg(feature = "raster")]
+
+use drawrs::raster::Fonts;
+use drawrs::{DrawrsError, Object, Page};
+use resvg::tiny_skia::Pixmap;
+
+fn tiny_page(font_family: Option<&str>) -> Page {
+    let mut page = Page::new(Some("page-1".to_string()), true);
+    let mut rect = Object::new(Some("rect".to_string()));
+    rect.set_position([0.0, 0.0]);
+    rect.set_width(40.0);
+    rect.set_height(20.0);
+    rect.set_fill_color(Some("#FF0000".to_string()));
+    rect.set_stroke_color(Some("none".to_string()));
+    rect.set_value("R1".to_string());
+    rect.set_font_family(font_family.map(str::to_string))
+        .unwrap();
+    page.add_object(rect.into());
+    page
+}
+
+fn pixel(png: &Pixmap, x: u32, y: u32) -> [u8; 3] {
+    let color = png.pixel(x, y).unwrap();
+    [color.red(), color.green(), color.blue()]
+}
+
+// Whether the black text is drawn in the middle of the box
+fn has_text(png: &Pixmap) -> bool {
+    (10..50).any(|x| (15..35).any(|y| pixel(png, x, y)[0] < 0x80))
+}
+
+#[test]
+fn page_to_png() {
+    let page = tiny_page(None);
+    let png = Pixmap::decode_png(&page.to_png(96.0).unwrap()).unwrap();
+    // The box and a margin of 10 on each side
+    assert_eq!((png.width(), png.height()), (60, 40));
+    assert_eq!(pixel(&png, 0, 0), [0xFF, 0xFF, 0xFF]);
+    assert_eq!(pixel(&png, 59, 39), [0xFF, 0xFF, 0xFF]);
+    assert_eq!(pixel(&png, 12, 12), [0xFF, 0x00, 0x00]);
+    assert!(has_text(&png));
+
+    let png = Pixmap::decode_png(&page.to_png(192.0).unwrap()).unwrap();
+    assert_eq!((png.width(), png.height()), (120, 80));
+    assert_eq!(pixel(&png, 1, 1), [0xFF, 0xFF, 0xFF]);
+    assert_eq!(pixel(&png, 24, 24), [0xFF, 0x00, 0x00]);
+}
+
+#[test]
+fn empty_page_to_png() {
+    let page = Page::new(Some("empty".to_string()), true);
+    let png = Pixmap::decode_png(&page.to_png(96.0).unwrap()).unwrap();
+    assert_eq!((png.width(), png.height()), (1, 1));
+    assert_eq!(pixel(&png, 0, 0), [0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn invalid_dpi() {
+    let page = tiny_page(None);
+    for dpi in [0.0, -96.0, f64::NAN, f64::INFINITY] {
+        assert!(
+            matches!(page.to_png(dpi), Err(DrawrsError::Raster(_))),
+            "{dpi} dpi"
+        );
+    }
+}
+
+#[test]
+fn font_fallback() {
+    let fonts = Fonts::system();
+    if fonts.has_family("DejaVu Sans") {
+        // A family that is not installed falls back to an installed font
+        let page = tiny_page(Some("Not An Installed Font"));
+        let png = Pixmap::decode_png(&page.to_png_with_fonts(96.0, &fonts).unwrap()).unwrap();
+        assert!(has_text(&png));
+    }
+
+    // Without fonts the texts are left out
+    let no_fonts = Fonts::default();
+    let png =
+        Pixmap::decode_png(&tiny_page(None).to_png_with_fonts(96.0, &no_fonts).unwrap()).unwrap();
+    assert!(!has_text(&png));
+}
@@ -0,0 +1,68 @@
+use drawrs::{DrawrsError, Page, RawXml};
+
+const STENCIL: &str = r#"<mxCell id="stencil" parent="1" style="shape=stencil(abc);" vertex="1">
+          <mxGeometry x="10" y="20" width="30" height="40" as="geometry" />
+          <Array as="points"><mxPoint x="1" y="2" /></Array>
+        </mxCell>"#;
+
+#[test]
+fn test_raw_xml_round_trip() {
+    let raw = RawXml::new(STENCIL).unwrap();
+    assert_eq!(raw.id(), "stencil");
+    assert_eq!(raw.base().xml_parent.as_deref(), Some("1"));
+
+    let mut page = Page::new(None, true);
+    page.add_object(raw.into());
+    assert!(page.xml().unwrap().to_string().contains(STENCIL));
+    assert!(page.check_parents().is_empty());
+}
+
+#[test]
+fn test_raw_xml_user_object_parent() {
+    let raw = RawXml::new(
+        r#"<UserObject label="a" id="u1"><mxCell parent="layer-1" vertex="1" /></UserObject>"#,
+    )
+    .unwrap();
+    assert_eq!(raw.id(), "u1");
+    assert_eq!(raw.base().xml_parent.as_deref(), Some("layer-1"));
+}
+
+#[test]
+fn test_raw_xml_validation() {
+    for xml in [
+        // Not closed
+        r#"<mxCell id="a"><mxGeometry as="geometry" />"#,
+        // Mismatched end tag
+        r#"<mxCell id="a"></mxPoint>"#,
+        // No id
+        r#"<mxCell parent="1" />"#,
+        // Two elements
+        r#"<mxCell id="a" /><mxCell id="b" />"#,
+        // Text outside of the element
+        r#"text <mxCell id="a" />"#,
+        "",
+    ] {
+        assert!(
+            matches!(
+                RawXml::new(xml),
+                Err(DrawrsError::XmlParse(_) | DrawrsError::XmlParsing(_))
+            ),
+            "{xml}"
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_raw_xml_json() {
+    use drawrs::DiagramObject;
+
+    let obj = DiagramObject::Raw(RawXml::new(STENCIL).unwrap());
+    let json = serde_json::to_value(&obj).unwrap();
+    assert_eq!(json["raw"], STENCIL);
+    let DiagramObject::Raw(raw) = serde_json::from_value(json).unwrap() else {
+        panic!("expected a raw object");
+    };
+    assert_eq!(raw.xml(), STENCIL);
+    assert!(serde_json::from_value::<DiagramObject>(serde_json::json!({"raw": "<a>"})).is_err());
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "serde")]
+
+use drawrs::{BoundingBox, DiagramObject, Edge, FillStyle, Object, Page};
+
+#[test]
+fn test_object_json_round_trip() {
+    let mut obj = Object::new(Some("obj-1".to_string()));
+    obj.set_value("R1".to_string());
+    obj.set_position([10.0, 20.0]);
+    obj.set_fill_color(Some("#FF0000".to_string()));
+    obj.set_fill_style(Some(FillStyle::CrossHatch));
+    obj.set_font_size(Some(14.0));
+    obj.set_xml_parent(Some("layer-1".to_string()));
+    obj.set_shape("ellipse".to_string());
+    obj.apply_style_property("customKey", "customValue");
+
+    let text = serde_json::to_string(&obj).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(json["id"], "obj-1");
+    assert_eq!(json["parent"], "layer-1");
+    assert_eq!(json["fillColor"], "#FF0000");
+    assert_eq!(json["fillStyle"], "cross-hatch");
+    assert_eq!(json["textFormat"]["fontSize"], 14.0);
+    assert_eq!(json["geometry"]["x"], 10.0);
+    assert_eq!(json["style"]["shape"], "ellipse");
+    assert_eq!(json["style"]["customKey"], "customValue");
+
+    // Deserialize from the text, which keeps the style key order
+    let back: Object = serde_json::from_str(&text).unwrap();
+    assert_eq!(back.xml().to_string(), obj.xml().to_string());
+}
+
+#[test]
+fn test_edge_json_round_trip() {
+    let mut edge = Edge::new_wire(Some("edge-1".to_string()));
+    edge.set_stroke_color(Some("#0000FF".to_string()));
+    edge.set_end_fill_target(true);
+    edge.set_tag(Some("net1".to_string()));
+    edge.geometry().set_relative(Some(true));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().add_intermediate_point([50.0, 0.0]);
+    edge.geometry().add_intermediate_point([50.0, 50.0]);
+    edge.geometry().set_target_point(Some([100.0, 50.0]));
+
+    let json = serde_json::to_string(&edge).unwrap();
+    assert!(json.contains(r#""endArrow":"none""#));
+    assert!(json.contains(r#""endFill":true"#));
+    assert!(json.contains(r#""tags":"net1""#));
+    assert!(json.contains(r#""intermediatePoints":[[50.0,0.0],[50.0,50.0]]"#));
+
+    let back: Edge = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.geometry_ref().intermediate_points().len(), 2);
+    assert_eq!(back.xml().to_string(), edge.xml().to_string());
+}
+
+#[test]
+fn test_page_json_round_trip() {
+    let mut page = Page::new(Some("page-1".to_string()), true);
+    page.set_name("Schematic".to_string());
+    page.add_layer_cell(true, "layer-wire".to_string(), "wire".to_string());
+    let mut obj = Object::new(Some("obj-1".to_string()));
+    obj.set_xml_parent(Some("layer-wire".to_string()));
+    page.add_object(obj.into());
+    let mut edge = Edge::new(Some("edge-1".to_string()));
+    edge.geometry().set_source_point(Some([0.0, 0.0]));
+    edge.geometry().set_target_point(Some([10.0, 0.0]));
+    page.add_object(edge.into());
+
+    let json = serde_json::to_string_pretty(&page).unwrap();
+    let back: Page = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.id(), "page-1");
+    assert_eq!(back.name(), "Schematic");
+    assert!(matches!(back.objects()[2], DiagramObject::XmlBase(_)));
+    assert!(matches!(back.objects()[3], DiagramObject::Object(_)));
+    assert!(matches!(back.objects()[4], DiagramObject::Edge(_)));
+    assert_eq!(
+        back.xml().unwrap().to_string(),
+        page.xml().unwrap().to_string()
+    );
+}
+
+#[test]
+fn test_bounding_box_json_round_trip() {
+    let mut bbox = BoundingBox::new(1.0, 2.0, 3.0, 4.0);
+    bbox.set_min_x(5.0);
+    bbox.set_height(6.0);
+    let json = serde_json::to_value(bbox).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"x": 5.0, "y": 2.0, "width": 3.0, "height": 6.0})
+    );
+    let back: BoundingBox = serde_json::from_value(json).unwrap();
+    assert_eq!(back, bbox);
+    assert_eq!([back.min_x(), back.max_y()], [5.0, 8.0]);
+}
@@ -0,0 +1,31 @@
+use drawrs::Object;
+use drawrs::utils::Stencil;
+use drawrs::utils::stencil::{compress, decompress};
+
+#[test]
+fn test_compress_round_trip() {
+    let xml = r#"<shape w="10" h="10"><foreground><rect x="0" y="0" w="10" h="10"/></foreground></shape>"#;
+    let compressed = compress(xml);
+    assert!(!compressed.contains('<'));
+    assert_eq!(decompress(&compressed).as_deref(), Some(xml));
+    assert_eq!(decompress("not base64"), None);
+}
+
+#[test]
+fn test_stencil_path() {
+    let mut stencil = Stencil::new(20.0, 10.0);
+    stencil.add_path(&[vec![[0.0, 0.0], [20.0, 0.0], [10.0, 10.0]]]);
+    assert_eq!(
+        stencil.xml(),
+        r#"<shape w="20" h="10" aspect="variable" strokewidth="inherit"><foreground><path><move x="0" y="0"/><line x="20" y="0"/><line x="10" y="10"/><close/></path><fillstroke/></foreground></shape>"#
+    );
+
+    let mut obj = Object::new(None);
+    obj.set_stencil(&stencil);
+    let shape = obj.style_property("shape").unwrap();
+    let compressed = shape
+        .strip_prefix("stencil(")
+        .and_then(|s| s.strip_suffix(')'))
+        .unwrap();
+    assert_eq!(decompress(compressed), Some(stencil.xml()));
+}
@@ -0,0 +1,89 @@
+use drawrs::diagram::text_format::{Justify, JustifyX, JustifyY};
+use drawrs::{Edge, Object, Page};
+
+fn sample_page() -> Page {
+    let mut page = Page::new(Some("page-1".to_string()), true);
+
+    let mut rect = Object::new(Some("rect".to_string()));
+    rect.set_position([0.0, 0.0]);
+    rect.set_width(100.0);
+    rect.set_height(50.0);
+    rect.set_fill_color(Some("#66ccff".to_string()));
+    rect.set_opacity(Some(50));
+    rect.set_value("R1 <1k>".to_string());
+    page.add_object(rect.into());
+
+    let mut ellipse = Object::new(Some("ellipse".to_string()));
+    ellipse.set_position([150.0, 0.0]);
+    ellipse.set_width(40.0);
+    ellipse.set_height(20.0);
+    ellipse.set_shape("ellipse".to_string());
+    ellipse.set_fill_color(Some("none".to_string()));
+    ellipse.apply_style_property("rotation", "90");
+    page.add_object(ellipse.into());
+
+    let mut polygon = Object::new(Some("polygon".to_string()));
+    polygon.set_position([0.0, 100.0]);
+    polygon.set_width(20.0);
+    polygon.set_height(20.0);
+    polygon.set_shape("mxgraph.basic.polygon".to_string());
+    polygon.set_poly_coords(vec![[0.0, 1.0], [0.5, 0.0], [1.0, 1.0]]);
+    page.add_object(polygon.into());
+
+    let mut label = Object::new(Some("label".to_string()));
+    label.set_position([150.0, 100.0]);
+    label.set_width(60.0);
+    label.set_height(10.0);
+    label.set_fill_color(Some("none".to_string()));
+    label.set_stroke_color(Some("none".to_string()));
+    label.set_font_size(Some(10.0));
+    label.set_font_color(Some("#FF0000".to_string()));
+    label.set_justify(Justify {
+        x: JustifyX::Right,
+        y: JustifyY::Bottom,
+    });
+    label.set_value("VDD".to_string());
+    page.add_object(label.into());
+
+    let mut edge = Edge::new(Some("edge".to_string()));
+    edge.set_stroke_color(Some("#0000FF".to_string()));
+    edge.set_stroke_width(Some(2.0));
+    edge.apply_style_property("dashed", "1");
+    edge.geometry().set_source_point(Some([100.0, 25.0]));
+    edge.geometry().add_intermediate_point([120.0, 25.0]);
+    edge.geometry().add_intermediate_point([120.0, 110.0]);
+    edge.geometry().set_target_point(Some([20.0, 110.0]));
+    page.add_object(edge.into());
+
+    page
+}
+
+#[test]
+fn test_page_to_svg_snapshot() {
+    insta::assert_snapshot!(sample_page().to_svg());
+}
+
+#[test]
+fn test_hidden_layer_is_skipped() {
+    let mut page = Page::new(None, false);
+    page.add_layer_cell(false, "hidden".to_string(), "hidden".to_string());
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_xml_parent(Some("hidden".to_string()));
+    page.add_object(obj.into());
+    let svg = page.to_svg();
+    assert!(!svg.contains("<rect"));
+}
+
+#[test]
+fn test_group_children_are_offset() {
+    let mut page = Page::new(None, true);
+    let mut group = drawrs::XMLBase::new(Some("group".to_string()));
+    group.xml_class = "mxCell".to_string();
+    group.group_geometry = Some(drawrs::BoundingBox::new(100.0, 200.0, 10.0, 10.0));
+    page.add_object(group.into());
+    let mut obj = Object::new(Some("obj".to_string()));
+    obj.set_position([1.0, 2.0]);
+    obj.set_xml_parent(Some("group".to_string()));
+    page.add_object(obj.into());
+    assert!(page.to_svg().contains(r#"<rect x="101" y="202""#));
+}
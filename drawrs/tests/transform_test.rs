@@ -0,0 +1,255 @@
+use drawrs::{BoundingBox, DiagramObject, GroupTransform, Orient, parse_xml_to_object};
+
+fn transform<'a>(inst_name: &'a str, cell_name: &'a str) -> GroupTransform<'a> {
+    GroupTransform::new(
+        BoundingBox::new(0.0, 0.0, 10.0, 10.0),
+        0.0,
+        0.0,
+        Orient::R0,
+        inst_name,
+        cell_name,
+    )
+}
+
+#[test]
+fn test_substitute_builtin_placeholders() {
+    let t = transform("M1", "nch");
+    assert_eq!(t.substitute("[@instanceName]:[@partName]"), "M1:nch");
+    assert_eq!(t.substitute("cdsName() [@cellName]"), "M1 nch");
+    // Unterminated placeholders are left as is
+    assert_eq!(t.substitute("[@instanceName"), "[@instanceName");
+}
+
+#[test]
+fn test_substitute_params_and_unknown() {
+    let t = transform("M1", "nch")
+        .with_params([
+            ("w".to_string(), "1u".to_string()),
+            ("l".to_string(), "30n".to_string()),
+        ])
+        .with_substitutions([("cdsTerm(\"G\")".to_string(), "VIN".to_string())]);
+    assert_eq!(t.substitute("cdsParam(\"w\")"), "1u");
+    assert_eq!(t.substitute("cdsParam(2)"), "l=30n");
+    assert_eq!(t.substitute("cdsTerm(\"G\")"), "VIN");
+    assert_eq!(t.substitute("cdsTerm(\"D\")|cdsParam(3)|[@foo]"), "||");
+
+    let t = t.with_unknown_placeholder("?");
+    assert_eq!(t.substitute("cdsTerm(\"D\") [@foo]"), "? ?");
+}
+
+#[test]
+fn test_substitute_user_object_label() {
+    let xml = r#"<UserObject label="[@instanceName] cdsParam(&quot;w&quot;) cdsTerm(&quot;G&quot;)" tags="sym" id="label-0">
+  <mxCell parent="layer-device-label" style="text;" vertex="1">
+    <mxGeometry x="0" y="0" width="40" height="10" as="geometry" />
+  </mxCell>
+</UserObject>"#;
+    let obj = parse_xml_to_object(xml).unwrap();
+    let t = transform("M3", "pch")
+        .with_params([("w".to_string(), "2u".to_string())])
+        .with_unknown_placeholder("?");
+    let new_obj = t.new_obj(&obj).unwrap();
+    assert_eq!(new_obj.text().map(String::as_str), Some("M3 2u ?"));
+    assert_eq!(new_obj.id(), "M3-label-0");
+}
+
+// The per-orient formulas GroupTransform used before Transform2D
+fn reference_point(orient: Orient, [x, y]: [f64; 2]) -> [f64; 2] {
+    match orient {
+        Orient::R0 => [x, y],
+        Orient::R90 => [y, -x],
+        Orient::R270 => [-y, x],
+        Orient::MY => [-x, y],
+        _ => unreachable!(),
+    }
+}
+
+fn reference_box(orient: Orient, b: BoundingBox) -> (BoundingBox, Option<f64>) {
+    match orient {
+        Orient::R0 => (b, None),
+        Orient::R90 => (
+            BoundingBox::new(
+                b.min_y() - (b.width() - b.height()) / 2.0,
+                -b.min_x() - b.width() / 2.0 - b.height() / 2.0,
+                b.width(),
+                b.height(),
+            ),
+            Some(-90.0),
+        ),
+        Orient::R270 => (
+            BoundingBox::new(
+                -b.min_y() - (b.width() + b.height()) / 2.0,
+                b.min_x() + b.width() / 2.0 - b.height() / 2.0,
+                b.width(),
+                b.height(),
+            ),
+            Some(90.0),
+        ),
+        Orient::MY => (
+            BoundingBox::new(-(b.min_x() + b.width()), b.min_y(), b.width(), b.height()),
+            None,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_matrix_path_matches_orient_formulas() {
+    use drawrs::diagram::{Edge, Object};
+
+    let (offset_x, offset_y) = (200.0, -400.0);
+    let values = [-37.5, -1.0, 0.0, 0.25, 12.0, 80.0];
+    for orient in [Orient::R0, Orient::R90, Orient::R270, Orient::MY] {
+        let transform = GroupTransform::new(
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0),
+            offset_x,
+            offset_y,
+            orient,
+            "X1",
+            "res",
+        );
+        for &x in &values {
+            for &y in &values {
+                let mut edge = Edge::new(Some("e".to_string()));
+                edge.set_xml_parent(Some("layer-device".to_string()));
+                edge.geometry().set_source_point(Some([x, y]));
+                edge.geometry().set_target_point(Some([y, x]));
+                let DiagramObject::Edge(mut moved) = transform.new_obj(&edge.into()).unwrap()
+                else {
+                    unreachable!()
+                };
+                let [rx, ry] = reference_point(orient, [x, y]);
+                assert_eq!(
+                    moved.geometry().source_point(),
+                    Some([rx + offset_x, ry + offset_y]),
+                    "{orient:?} ({x}, {y})"
+                );
+
+                for &width in &[0.0, 3.0, 25.0] {
+                    for &height in &[0.0, 7.5, 40.0] {
+                        let mut obj = Object::new(Some("o".to_string()));
+                        obj.set_xml_parent(Some("layer-device".to_string()));
+                        obj.set_position([x, y]);
+                        obj.set_width(width);
+                        obj.set_height(height);
+                        let moved = transform.new_obj(&obj.into()).unwrap();
+                        let DiagramObject::Object(moved) = moved else {
+                            unreachable!()
+                        };
+                        let (mut expected, rotation) =
+                            reference_box(orient, BoundingBox::new(x, y, width, height));
+                        expected.translate(offset_x, offset_y);
+                        let actual = moved.geometry_ref().bounding_box();
+                        assert!(
+                            actual.approx_eq(&expected, 1e-9),
+                            "{orient:?} {actual:?} != {expected:?}"
+                        );
+                        assert_eq!(
+                            moved.geometry_ref().flip_rotation().rotation(),
+                            rotation,
+                            "{orient:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_transform2d_orients() {
+    use drawrs::Transform2D;
+
+    let p = [3.0, 5.0];
+    let expected = [
+        (Orient::R0, [3.0, 5.0]),
+        (Orient::R90, [5.0, -3.0]),
+        (Orient::R180, [-3.0, -5.0]),
+        (Orient::R270, [-5.0, 3.0]),
+        (Orient::MY, [-3.0, 5.0]),
+        (Orient::MX, [3.0, -5.0]),
+        (Orient::MYR90, [5.0, 3.0]),
+        (Orient::MXR90, [-5.0, -3.0]),
+    ];
+    for (orient, mapped) in expected {
+        let transform = Transform2D::from_orient(orient);
+        assert_eq!(transform.apply(p), mapped, "{orient:?}");
+        let mirrored = matches!(
+            orient,
+            Orient::MY | Orient::MX | Orient::MYR90 | Orient::MXR90
+        );
+        assert_eq!(transform.is_mirrored(), mirrored, "{orient:?}");
+    }
+
+    let residual = Transform2D::from_orient(Orient::MX).residual();
+    assert_eq!(residual.flip_h(), Some(1));
+    assert_eq!(residual.rotation(), Some(180.0));
+}
+
+#[test]
+fn test_transform2d_composition() {
+    use drawrs::Transform2D;
+
+    let transform = Transform2D::scale(2.0, 3.0)
+        .then(Transform2D::rotation(90.0))
+        .then(Transform2D::translation(10.0, 20.0));
+    // (1, 1) -> (2, 3) -> (-3, 2) -> (7, 22)
+    assert_eq!(transform.apply([1.0, 1.0]), [7.0, 22.0]);
+    assert_eq!(
+        Transform2D::IDENTITY.then(transform).matrix(),
+        transform.matrix()
+    );
+
+    let (aabb, residual) =
+        Transform2D::rotation(90.0).apply_box(&BoundingBox::new(0.0, 0.0, 4.0, 2.0));
+    assert_eq!(aabb, BoundingBox::new(-2.0, 0.0, 2.0, 4.0));
+    assert_eq!(residual.rotation(), Some(90.0));
+    assert_eq!(residual.flip_h(), None);
+
+    let [x, y] = Transform2D::rotation(45.0).apply([1.0, 0.0]);
+    assert!((x - y).abs() < 1e-12 && (x - 0.5f64.sqrt()).abs() < 1e-12);
+}
+
+#[test]
+fn test_tag_sanitizer() {
+    use drawrs::{DiagramObject, Object};
+
+    let obj: DiagramObject = Object::new(Some("o".to_string())).into();
+    let new_obj = transform("I0 I3", "nch lvt")
+        .with_cell_tag(true)
+        .new_obj(&obj)
+        .unwrap();
+    assert_eq!(new_obj.id(), "I0_I3_3aec453c-o");
+    assert_eq!(new_obj.tags().collect::<Vec<_>>(), ["I0_I3", "nch_lvt"]);
+
+    let new_obj = transform("I0 I3", "nch")
+        .with_tag_sanitizer(|name| name.replace(' ', "."))
+        .new_obj(&obj)
+        .unwrap();
+    assert_eq!(new_obj.tag().map(String::as_str), Some("I0.I3"));
+}
+
+#[test]
+fn test_connected_edge_placed_twice() {
+    use drawrs::{Edge, Object};
+
+    let mut vertex = Object::new(Some("pin".to_string()));
+    vertex.set_xml_parent(Some("layer-pin".to_string()));
+    let vertex: DiagramObject = vertex.into();
+    let mut edge = Edge::new(Some("e".to_string()));
+    edge.set_xml_parent(Some("layer-device".to_string()));
+    edge.set_source(Some("pin".to_string()));
+    edge.set_target(Some("pin".to_string()));
+    let edge: DiagramObject = edge.into();
+
+    for inst_name in ["I0", "I1"] {
+        let t = transform(inst_name, "nch");
+        let new_vertex = t.new_obj(&vertex).unwrap();
+        let DiagramObject::Edge(new_edge) = t.new_obj(&edge).unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(new_vertex.id(), format!("{inst_name}-pin"));
+        assert_eq!(new_edge.source().map(String::as_str), Some(new_vertex.id()));
+        assert_eq!(new_edge.target().map(String::as_str), Some(new_vertex.id()));
+    }
+}
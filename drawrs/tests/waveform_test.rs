@@ -0,0 +1,126 @@
+use drawrs::DrawrsError;
+use drawrs::diagram_types::waveform::{Level, Waveform};
+use drawrs::{DiagramObject, Edge, Object};
+
+fn edges(waveform: &Waveform) -> Vec<&Edge> {
+    waveform
+        .objects()
+        .iter()
+        .filter_map(|obj| match obj {
+            DiagramObject::Edge(edge) => Some(edge),
+            _ => None,
+        })
+        .collect()
+}
+
+fn boxes(waveform: &Waveform) -> Vec<&Object> {
+    waveform
+        .objects()
+        .iter()
+        .filter_map(|obj| match obj {
+            DiagramObject::Object(obj) if obj.position()[0] >= waveform.label_width() => Some(obj),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_empty_signal_raises_error() {
+    let mut waveform = Waveform::new();
+    let result = waveform.add_signal("a", Vec::new());
+    assert!(matches!(result, Err(DrawrsError::EmptyData)));
+    assert!(waveform.is_empty());
+}
+
+#[test]
+fn test_invalid_clock_period() {
+    let mut waveform = Waveform::new();
+    assert!(waveform.add_clock("clk", 1).is_err());
+}
+
+#[test]
+fn test_clock_step_geometry() {
+    let mut waveform = Waveform::new();
+    waveform
+        .add_signal("en", vec![Level::Low, Level::Low, Level::Low, Level::Low])
+        .unwrap();
+    waveform.add_clock("clk", 2).unwrap();
+    assert_eq!(waveform.len(), 2);
+    assert_eq!(waveform.steps(), 4);
+
+    let w = Waveform::DEFAULT_STEP_WIDTH;
+    let h = Waveform::DEFAULT_LANE_HEIGHT;
+    let x0 = Waveform::DEFAULT_LABEL_WIDTH;
+    let top = h + Waveform::DEFAULT_LANE_SPACING;
+
+    let edges = edges(&waveform);
+    assert_eq!(edges.len(), 2);
+    let clk = edges[1].geometry_ref();
+    assert_eq!(clk.source_point(), Some([x0, top]));
+    assert_eq!(
+        clk.intermediate_points(),
+        &[
+            [x0 + w, top],
+            [x0 + w, top + h],
+            [x0 + 2.0 * w, top + h],
+            [x0 + 2.0 * w, top],
+            [x0 + 3.0 * w, top],
+            [x0 + 3.0 * w, top + h],
+        ]
+    );
+    assert_eq!(clk.target_point(), Some([x0 + 4.0 * w, top + h]));
+}
+
+#[test]
+fn test_data_lane_transition() {
+    let mut waveform = Waveform::new();
+    waveform
+        .add_signal(
+            "bus",
+            vec![
+                Level::Data("A".to_string()),
+                Level::Data("A".to_string()),
+                Level::Data("B".to_string()),
+                Level::X,
+                Level::Z,
+            ],
+        )
+        .unwrap();
+
+    let w = Waveform::DEFAULT_STEP_WIDTH;
+    let x0 = Waveform::DEFAULT_LABEL_WIDTH;
+    let boxes = boxes(&waveform);
+    assert_eq!(boxes.len(), 3);
+    assert_eq!(boxes[0].value().unwrap(), "A");
+    assert_eq!(boxes[0].position(), [x0, 0.0]);
+    assert_eq!(boxes[0].width(), 2.0 * w);
+    assert_eq!(boxes[1].value().unwrap(), "B");
+    assert_eq!(boxes[1].position(), [x0 + 2.0 * w, 0.0]);
+    assert_eq!(boxes[1].width(), w);
+    assert!(boxes[0].style().to_string().contains("shape=hexagon"));
+    assert!(boxes[2].style().to_string().contains("fillStyle=hatch"));
+
+    let edges = edges(&waveform);
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].stroke_color().unwrap(), Waveform::Z_COLOR);
+    assert_eq!(
+        edges[0].geometry_ref().source_point(),
+        Some([x0 + 4.0 * w, Waveform::DEFAULT_LANE_HEIGHT / 2.0])
+    );
+}
+
+#[test]
+fn test_objects_grouped_under_container() {
+    let mut waveform = Waveform::new();
+    waveform.add_clock("clk", 4).unwrap();
+    waveform.set_xml_parent(Some("layer-annotate-your-drawing".to_string()));
+    waveform.move_to([100.0, 200.0]);
+    assert_eq!(waveform.position(), [100.0, 200.0]);
+
+    let objects = waveform.objects();
+    assert_eq!(objects[0].xml_parent(), Some("layer-annotate-your-drawing"));
+    assert!(objects[0].xml().to_string().contains(r#"x="100" y="200""#));
+    for obj in &objects[1..] {
+        assert_eq!(obj.xml_parent(), Some(waveform.container_id()));
+    }
+}
@@ -0,0 +1,59 @@
+//! Writing the XML of a page allocates per page, not per cell
+use drawrs::{Edge, Page};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// Counts the written bytes without storing them
+struct Sink(usize);
+
+impl Write for Sink {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_edge_xml_does_not_allocate_per_edge() {
+    const EDGES: usize = 100_000;
+    let mut page = Page::new(Some("page".to_string()), true);
+    for i in 0..EDGES {
+        let mut edge = Edge::new(Some(format!("wire-{i}")));
+        edge.set_stroke_color(Some("#000000".to_string()));
+        edge.set_stroke_width(Some(1.5));
+        edge.set_label(Some("a<b".to_string()));
+        edge.geometry()
+            .set_source_point(Some([i as f64 * 0.25, -1.0]));
+        edge.geometry()
+            .set_target_point(Some([i as f64 * 0.25, 10.0]));
+        edge.geometry().add_intermediate_point([0.5, 0.5]);
+        page.add_object(edge.into());
+    }
+
+    let mut sink = Sink(0);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    write!(sink, "{}", page.xml().unwrap()).unwrap();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert!(sink.0 > EDGES * 300);
+    // The cell ordering of the page, not the cells themselves
+    assert!(allocations < 100, "{allocations} allocations");
+}
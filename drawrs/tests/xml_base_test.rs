@@ -1,3 +1,4 @@
+use drawrs::DiagramObject;
 use drawrs::xml_base::XMLBase;
 
 #[test]
@@ -44,3 +45,240 @@ fn test_translate_txt() {
     replacements.insert('c', "Z");
     assert_eq!(XMLBase::translate_txt("abc", &replacements), "XbZ");
 }
+
+#[test]
+fn test_xml_ify_literal_entities() {
+    assert_eq!(XMLBase::xml_ify("&amp;"), "&amp;amp;");
+    assert_eq!(XMLBase::xml_ify("&quot;"), "&amp;quot;");
+    assert_eq!(XMLBase::xml_ify("R&D"), "R&amp;D");
+    assert_eq!(
+        XMLBase::decode_xml_entities("&amp;amp; &amp;lt;"),
+        "&amp; &lt;"
+    );
+}
+
+#[test]
+fn test_entities_stable_across_round_trips() {
+    use drawrs::diagram::Object;
+    use drawrs::parse_xml_to_object;
+
+    for value in ["&amp;", "&quot;", "R&D", "a < b & \"c\""] {
+        let mut obj = Object::new(Some("obj".to_string()));
+        obj.set_value(value.to_string());
+        let mut xml = drawrs::DiagramObject::from(obj).xml().to_string();
+        for _ in 0..2 {
+            let parsed = parse_xml_to_object(&xml).unwrap();
+            assert_eq!(parsed.text().map(String::as_str), Some(value));
+            xml = parsed.xml().to_string();
+        }
+    }
+}
+
+#[test]
+fn test_unknown_attributes_and_elements_preserved() {
+    use drawrs::parse_xml_to_object;
+
+    let fixture = r#"<mxCell id="obj" value="doc" style="rounded=1;" vertex="1" connectable="0" visible="0" link="https://example.com/?a=1&amp;b=2" parent="1">
+          <mxGeometry x="10" y="20" width="30" height="40" as="geometry">
+            <mxRectangle x="1" y="2" width="3" height="4" as="alternateBounds" />
+          </mxGeometry>
+          <mxExtra note="kept"><child /></mxExtra>
+        </mxCell>"#;
+    let parsed = parse_xml_to_object(fixture).unwrap();
+    let xml = parsed.xml().to_string();
+    assert!(xml.contains(r#"link="https://example.com/?a=1&amp;b=2""#));
+    assert!(xml.contains(r#"visible="0""#));
+    assert!(xml.contains(r#"connectable="0""#));
+    assert!(
+        xml.contains(r#"<mxRectangle x="1" y="2" width="3" height="4" as="alternateBounds" />"#)
+    );
+    assert!(xml.contains(r#"<mxExtra note="kept"><child /></mxExtra>"#));
+
+    // Stable over a second cycle
+    let reparsed = parse_xml_to_object(&xml).unwrap();
+    assert_eq!(reparsed.xml().to_string(), xml);
+}
+
+#[test]
+fn test_group_unknown_attributes_and_elements_preserved() {
+    use drawrs::parse_xml_to_object;
+
+    let fixture = r#"<mxCell id="grp" connectable="0" parent="1" style="group;locked=1;" vertex="1" collapsed="1">
+          <mxGeometry x="10" y="20" width="30" height="40" as="geometry" />
+          <mxExtra note="kept"><child /></mxExtra>
+        </mxCell>"#;
+    let parsed = parse_xml_to_object(fixture).unwrap();
+    assert!(matches!(&parsed, DiagramObject::XmlBase(base) if base.group_geometry.is_some()));
+    let xml = parsed.xml().to_string();
+    assert!(xml.contains(r#"style="group;locked=1;""#));
+    assert!(xml.contains(r#"collapsed="1""#));
+    assert!(xml.contains(r#"<mxExtra note="kept"><child /></mxExtra>"#));
+    assert_eq!(xml.matches("connectable=").count(), 1);
+
+    // Stable over a second cycle
+    let reparsed = parse_xml_to_object(&xml).unwrap();
+    assert_eq!(reparsed.xml().to_string(), xml);
+}
+
+#[test]
+fn test_object_element_parsed_as_user_object() {
+    use drawrs::parse_xml_to_object;
+
+    let xml = r#"<object label="Note" tags="annotate" id="obj">
+          <mxCell style="rounded=1;" vertex="1" parent="1">
+            <mxGeometry x="10" y="20" width="30" height="40" as="geometry" />
+          </mxCell>
+        </object>"#;
+    let parsed = parse_xml_to_object(xml).unwrap();
+    assert_eq!(parsed.id(), "obj");
+    assert_eq!(parsed.tag().map(String::as_str), Some("annotate"));
+    assert_eq!(parsed.text().map(String::as_str), Some("Note"));
+    assert!(
+        parsed
+            .xml()
+            .to_string()
+            .starts_with(r#"<UserObject label="Note""#)
+    );
+}
+
+#[test]
+fn test_xml_float_precision() {
+    use drawrs::xml_base::XmlFloat;
+
+    assert_eq!(XmlFloat(40.0).to_string(), "40");
+    assert_eq!(XmlFloat(-120.0).to_string(), "-120");
+    assert_eq!(XmlFloat(399.99999999999994).to_string(), "400");
+    assert_eq!(XmlFloat(0.30000000000000004).to_string(), "0.3");
+    assert_eq!(XmlFloat(12.34567).to_string(), "12.3457");
+    assert_eq!(XmlFloat(-0.00001).to_string(), "0");
+}
+
+#[test]
+fn test_float_precision_round_trip() {
+    use drawrs::diagram::{Edge, Object};
+    use drawrs::parse_xml_to_object;
+
+    let mut obj = Object::new(Some("box".to_string()));
+    obj.set_position([399.99999999999994, 1.0 / 3.0]);
+    obj.set_width(40.0);
+    obj.set_height(20.000000000000004);
+    let xml = obj.xml().to_string();
+    assert!(xml.contains(r#"x="400" y="0.3333" width="40" height="20""#));
+    let parsed = parse_xml_to_object(&xml).unwrap();
+    let bbox = parsed.bounding_box().unwrap();
+    assert!(bbox.approx_eq(&obj.geometry_ref().bounding_box(), 1e-4));
+
+    let mut edge = Edge::new(Some("wire".to_string()));
+    edge.geometry().set_source_point(Some([0.1 + 0.2, 10.0]));
+    edge.geometry().set_target_point(Some([2.0 / 3.0, 10.0]));
+    edge.geometry().add_intermediate_point([1e-9, 5.5]);
+    let xml = edge.xml().to_string();
+    assert!(xml.contains(r#"<mxPoint x="0.3" y="10" as="sourcePoint" />"#));
+    assert!(xml.contains(r#"<mxPoint x="0.6667" y="10" as="targetPoint" />"#));
+    assert!(xml.contains(r#"<mxPoint x="0" y="5.5" />"#));
+    let parsed = parse_xml_to_object(&xml).unwrap();
+    let DiagramObject::Edge(parsed) = parsed else {
+        panic!("expected an edge");
+    };
+    let [x, y] = parsed.geometry_ref().target_point().unwrap();
+    assert!((x - 2.0 / 3.0).abs() < 1e-4 && y == 10.0);
+}
+
+#[test]
+fn test_malicious_ids_round_trip() {
+    use drawrs::diagram::{Edge, Object};
+    use drawrs::{DrawFile, Page, parse_xml_to_object};
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let id = r#"a"<b>&'c"#;
+    let mut obj = Object::new(Some(id.to_string()));
+    obj.set_xml_parent(Some(format!("{id}-parent")));
+    obj.apply_style_property("fontFamily", r#"Say "hi""#);
+    let mut tagged = Object::new(Some(format!("{id}-tagged")));
+    tagged.base_mut().tag = Some(id.to_string());
+    let mut edge = Edge::new(Some(format!("{id}-edge")));
+    edge.set_source(Some(id.to_string()));
+    edge.set_target(Some(format!("{id}-tagged")));
+
+    let objects: Vec<DiagramObject> = vec![obj.into(), tagged.into(), edge.into()];
+    for cell in &objects {
+        let parsed = parse_xml_to_object(&cell.xml().to_string()).unwrap();
+        assert_eq!(parsed.id(), cell.id());
+        assert_eq!(parsed.xml().to_string(), cell.xml().to_string());
+    }
+    let DiagramObject::Edge(edge) = parse_xml_to_object(&objects[2].xml().to_string()).unwrap()
+    else {
+        panic!("expected an edge");
+    };
+    assert_eq!(edge.source().map(String::as_str), Some(id));
+
+    let mut page = Page::new(Some(id.to_string()), false);
+    page.set_name(id.to_string());
+    for cell in objects {
+        page.add_object(cell);
+    }
+    let mut file = DrawFile::new();
+    file.add_page(page);
+    let xml = file.xml().unwrap().to_string();
+    let mut reader = Reader::from_str(&xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => panic!("malformed XML: {e}\n{xml}"),
+        }
+    }
+}
+
+#[test]
+fn test_id_counter() {
+    let ids =
+        drawrs::xml_base::with_id_counter(3, || [XMLBase::new(None).id, XMLBase::default().id]);
+    assert_eq!(ids, ["id-3", "id-4"]);
+    assert!(drawrs::xml_base::id_counter().is_none());
+    assert_ne!(XMLBase::new(None).id, "id-5");
+}
+
+#[test]
+fn test_invalid_xml_chars() {
+    use drawrs::xml_base::{is_xml_char, sanitize_text};
+    assert!(is_xml_char('\t') && is_xml_char('\n') && is_xml_char('é'));
+    assert!(!is_xml_char('\u{b}') && !is_xml_char('\u{1}') && !is_xml_char('\u{FFFF}'));
+    // Vertical tab and form feed are line separators, other controls are dropped
+    assert_eq!(sanitize_text("A\u{b}B\u{c}C\u{1}D"), "A\nB\nCD");
+    assert!(matches!(
+        sanitize_text("plain"),
+        std::borrow::Cow::Borrowed(_)
+    ));
+    assert_eq!(XMLBase::xml_ify("A\u{b}B\u{1}"), "A&#xa;B");
+
+    let mut base = XMLBase::new(Some("cell".to_string()));
+    assert_eq!(base.invalid_text(), None);
+    base.value = Some("A\u{b}B".to_string());
+    assert_eq!(base.invalid_text(), Some("A\u{b}B"));
+}
+
+#[test]
+fn test_id_generator() {
+    use drawrs::xml_base::{set_id_generator, with_id_counter};
+    set_id_generator(Some(|| "generated".to_string()));
+    assert_eq!(XMLBase::new(None).id, "generated");
+    // The counter takes priority
+    assert_eq!(with_id_counter(7, || XMLBase::new(None).id), "id-7");
+    set_id_generator(None);
+    assert_ne!(XMLBase::new(None).id, "generated");
+}
+
+#[test]
+fn test_sanitize_id_collisions() {
+    use drawrs::xml_base::sanitize_id;
+
+    assert_eq!(sanitize_id("I1_I2"), "I1_I2");
+    assert_eq!(sanitize_id("net-1"), "net-1");
+    let ids = ["I1/I2", "I1_I2", "I1 I2", "I1<I2"].map(sanitize_id);
+    assert!(ids[0].starts_with("I1_I2_"));
+    for (i, id) in ids.iter().enumerate() {
+        assert!(ids[i + 1..].iter().all(|other| other != id), "{ids:?}");
+    }
+}